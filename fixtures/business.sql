@@ -1,5 +1,5 @@
-INSERT INTO business (id)
-VALUES ('business-1');
+INSERT INTO business (id, name)
+VALUES ('business-1', 'Business 1');
 
 INSERT INTO user_business (user_id, business_id)
 VALUES ('user-1', 'business-1');
@@ -104,4 +104,8 @@ VALUES ('event-1',
               }
             ]
           }
-        ]'::jsonb);
\ No newline at end of file
+        ]'::jsonb);
+INSERT INTO event_lifecycle (event_id, starts, ends)
+VALUES ('event-1', '2023-06-15 09:30:00 +00:00', '2023-06-15 10:30:00 +00:00'),
+       ('event-2', null, null),
+       ('event-3', null, null);
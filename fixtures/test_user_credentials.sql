@@ -42,8 +42,8 @@ VALUES ('user-manager');
 INSERT INTO any_business_user (user_id)
 VALUES ('any-business');
 
-INSERT INTO business (id)
-VALUES ('business-1');
+INSERT INTO business (id, name)
+VALUES ('business-1', 'Business 1');
 INSERT INTO user_business (user_id, business_id)
 VALUES ('business-1-user', 'business-1');
 
@@ -0,0 +1,37 @@
+create table audit_log
+(
+    id                bigint generated always as identity
+        constraint audit_log_pk primary key,
+    created_date_time timestamptz not null default now(),
+    actor             text        not null,
+    roles             jsonb       not null,
+    operation         text        not null,
+    object_type       text        not null,
+    object_id         text        not null,
+    before            jsonb,
+    after             jsonb
+);
+
+create index audit_log_object_index
+    on audit_log (object_type, object_id);
+
+create index audit_log_actor_index
+    on audit_log (actor);
+
+create index audit_log_created_date_time_index
+    on audit_log (created_date_time);
+
+-- the audit log is append-only, entries must never be changed or removed
+create function audit_log_append_only() returns trigger
+    language plpgsql as
+$$
+begin
+    raise exception 'audit_log is append-only';
+end;
+$$;
+
+create trigger audit_log_append_only
+    before update or delete
+    on audit_log
+    for each row
+execute function audit_log_append_only();
@@ -0,0 +1,125 @@
+-- Every version of programs and events is kept, such that their state at any point in time can be retrieved.
+-- A version is valid from its `modification_date_time` until `valid_until`,
+-- which is `null` for the current version of existing objects.
+
+create table program_version
+(
+    id                     text        not null,
+    version                bigint      not null,
+    created_date_time      timestamptz not null,
+    modification_date_time timestamptz not null,
+    valid_until            timestamptz,
+
+    program_name           text        not null,
+    program_long_name      text,
+    retailer_name          text,
+    retailer_long_name     text,
+    program_type           text,
+    country                text,
+    principal_subdivision  text,
+    interval_period        jsonb,
+    program_descriptions   jsonb,
+    binding_events         boolean,
+    local_price            boolean,
+    payload_descriptors    jsonb,
+    targets                jsonb,
+    business_id            text,
+    constraint program_version_pk primary key (id, version)
+);
+
+create table event_version
+(
+    id                     text        not null,
+    version                bigint      not null,
+    created_date_time      timestamptz not null,
+    modification_date_time timestamptz not null,
+    valid_until            timestamptz,
+
+    program_id             text        not null,
+    event_name             text,
+    priority               bigint,
+    report_descriptors     jsonb,
+    payload_descriptors    jsonb,
+    interval_period        jsonb,
+    intervals              jsonb       not null,
+    targets                jsonb,
+    constraint event_version_pk primary key (id, version)
+);
+
+create index event_version_program_id_index
+    on event_version (program_id);
+
+create function program_version_trigger() returns trigger
+    language plpgsql as
+$$
+begin
+    if tg_op in ('UPDATE', 'DELETE') then
+        update program_version set valid_until = now() where id = old.id and valid_until is null;
+    end if;
+
+    if tg_op = 'DELETE' then
+        return old;
+    end if;
+
+    insert into program_version (id, version, created_date_time, modification_date_time, program_name,
+                                 program_long_name, retailer_name, retailer_long_name, program_type, country,
+                                 principal_subdivision, interval_period, program_descriptions, binding_events,
+                                 local_price, payload_descriptors, targets, business_id)
+    values (new.id, coalesce((select max(version) from program_version where id = new.id), 0) + 1,
+            new.created_date_time, new.modification_date_time, new.program_name, new.program_long_name,
+            new.retailer_name, new.retailer_long_name, new.program_type, new.country, new.principal_subdivision,
+            new.interval_period, new.program_descriptions, new.binding_events, new.local_price,
+            new.payload_descriptors, new.targets, new.business_id);
+    return new;
+end;
+$$;
+
+create function event_version_trigger() returns trigger
+    language plpgsql as
+$$
+begin
+    if tg_op in ('UPDATE', 'DELETE') then
+        update event_version set valid_until = now() where id = old.id and valid_until is null;
+    end if;
+
+    if tg_op = 'DELETE' then
+        return old;
+    end if;
+
+    insert into event_version (id, version, created_date_time, modification_date_time, program_id, event_name,
+                               priority, report_descriptors, payload_descriptors, interval_period, intervals,
+                               targets)
+    values (new.id, coalesce((select max(version) from event_version where id = new.id), 0) + 1,
+            new.created_date_time, new.modification_date_time, new.program_id, new.event_name, new.priority,
+            new.report_descriptors, new.payload_descriptors, new.interval_period, new.intervals, new.targets);
+    return new;
+end;
+$$;
+
+create trigger program_version
+    after insert or update or delete
+    on program
+    for each row
+execute function program_version_trigger();
+
+create trigger event_version
+    after insert or update or delete
+    on event
+    for each row
+execute function event_version_trigger();
+
+-- the first version of all objects already present
+insert into program_version (id, version, created_date_time, modification_date_time, program_name, program_long_name,
+                             retailer_name, retailer_long_name, program_type, country, principal_subdivision,
+                             interval_period, program_descriptions, binding_events, local_price,
+                             payload_descriptors, targets, business_id)
+select id, 1, created_date_time, modification_date_time, program_name, program_long_name, retailer_name,
+       retailer_long_name, program_type, country, principal_subdivision, interval_period, program_descriptions,
+       binding_events, local_price, payload_descriptors, targets, business_id
+from program;
+
+insert into event_version (id, version, created_date_time, modification_date_time, program_id, event_name, priority,
+                           report_descriptors, payload_descriptors, interval_period, intervals, targets)
+select id, 1, created_date_time, modification_date_time, program_id, event_name, priority, report_descriptors,
+       payload_descriptors, interval_period, intervals, targets
+from event;
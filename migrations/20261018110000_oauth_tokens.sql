@@ -0,0 +1,30 @@
+-- Refresh tokens issued by the internal OAuth provider.
+-- Only the SHA-256 hash of a token is stored, such that a leaked database does not leak usable tokens.
+create table refresh_token
+(
+    token_hash text primary key,
+    client_id  text        not null references user_credentials (client_id) on delete cascade,
+    scope      text        not null,
+    created    timestamptz not null,
+    expires    timestamptz not null
+);
+
+create index refresh_token_client_id_idx on refresh_token (client_id);
+
+-- Access tokens revoked before they expire, identified by their `jti` claim.
+-- Entries can be removed once the token expired.
+create table revoked_token
+(
+    jti     text primary key,
+    expires timestamptz not null
+);
+
+create index revoked_token_expires_idx on revoked_token (expires);
+
+-- All access tokens of a client issued up to `revoked_at` are revoked,
+-- e.g., because its credentials got removed or the roles of its user changed.
+create table revoked_client
+(
+    client_id  text primary key,
+    revoked_at timestamptz not null
+);
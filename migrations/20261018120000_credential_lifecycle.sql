@@ -0,0 +1,5 @@
+-- Metadata of client credentials. Credentials without expiry date are valid until removed.
+alter table user_credentials
+    add column created   timestamptz not null default now(),
+    add column expires   timestamptz,
+    add column last_used timestamptz;
@@ -0,0 +1,12 @@
+-- Businesses are the tenants of the VTN, they used to consist of an id only
+alter table business
+    add column name        text,
+    add column description text,
+    add column created     timestamptz not null default now(),
+    add column modified    timestamptz not null default now();
+
+update business
+set name = id;
+
+alter table business
+    alter column name set not null;
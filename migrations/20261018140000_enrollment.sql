@@ -0,0 +1,6 @@
+-- VENs are enrolled in programs via the API now, instead of only through the targets of a program
+alter table ven_program
+    add column created_date_time timestamptz not null default now();
+
+create index ven_program_ven_id_index
+    on ven_program (ven_id);
@@ -0,0 +1,24 @@
+-- One-time tokens VENs use to register themselves via `POST /vens`.
+-- Only the SHA-256 hash of a token is stored, like for refresh tokens.
+create table registration_token
+(
+    token_hash  text primary key,
+    description text,
+    -- the `sub` claim of the VEN manager that issued the token
+    created_by  text        not null,
+    created     timestamptz not null,
+    expires     timestamptz not null
+);
+
+-- VENs that registered themselves, along with the user created for them.
+-- VENs created by VEN managers do not have an entry and are approved implicitly.
+create table ven_registration
+(
+    ven_id         text primary key references ven (id) on delete cascade,
+    user_id        text references "user" (id) on delete set null,
+    approval_state text        not null check (approval_state in ('PENDING', 'APPROVED', 'REJECTED')),
+    created        timestamptz not null,
+    modified       timestamptz not null
+);
+
+create index ven_registration_approval_state_idx on ven_registration (approval_state);
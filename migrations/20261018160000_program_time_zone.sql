@@ -0,0 +1,3 @@
+alter table program
+    add column time_zone_offset text,
+    add column time_zone        text;
@@ -0,0 +1,25 @@
+-- Recurring events, the VTN creates the events of the upcoming occurrences ahead of time
+create table event_template
+(
+    id                     text primary key,
+    created_date_time      timestamptz not null,
+    modification_date_time timestamptz not null,
+    program_id             text        not null references program (id) on delete cascade,
+    content                jsonb       not null
+);
+
+create index event_template_program_id_index
+    on event_template (program_id);
+
+-- The occurrences events were created for.
+-- The occurrence is kept if its event gets deleted, such that the event is not created again.
+create table event_template_occurrence
+(
+    template_id text        not null references event_template (id) on delete cascade,
+    occurrence  timestamptz not null,
+    event_id    text references event (id) on delete set null,
+    primary key (template_id, occurrence)
+);
+
+create index event_template_occurrence_event_id_index
+    on event_template_occurrence (event_id);
@@ -0,0 +1,42 @@
+-- Cancelled events are kept, such that VENs get notified about the cancellation.
+-- The start and end of the intervals are stored to filter the events by their state.
+create table event_lifecycle
+(
+    event_id            text primary key references event (id) on delete cascade,
+    starts              timestamptz,
+    ends                timestamptz,
+    cancelled_date_time timestamptz,
+    superseded_by       text references event (id) on delete set null
+);
+
+create index event_lifecycle_superseded_by_index
+    on event_lifecycle (superseded_by);
+
+-- The VTN computes the time span of new events in the time zone of their program.
+-- For existing events, it is approximated from the interval period of the event.
+insert into event_lifecycle (event_id, starts, ends)
+select id,
+       starts,
+       case
+           when duration * jsonb_array_length(intervals) < interval '1000 years'
+               then starts + duration * jsonb_array_length(intervals)
+           end
+from (select id,
+             intervals,
+             (interval_period ->> 'start')::timestamptz  as starts,
+             (interval_period ->> 'duration')::interval as duration
+      from event) existing;
+
+-- The state depends on the current time, hence it is computed when queried
+create view event_state as
+select e.id as event_id,
+       case
+           when l.superseded_by is not null then 'SUPERSEDED'
+           when l.cancelled_date_time is not null then 'CANCELLED'
+           when l.starts > now() then 'SCHEDULED'
+           when l.ends <= now() then 'COMPLETED'
+           else 'ACTIVE'
+           end as state,
+       l.superseded_by
+from event e
+         left join event_lifecycle l on l.event_id = e.id;
@@ -0,0 +1,26 @@
+-- Reports removed by the retention job, unless it purges them
+create table report_archive
+(
+    id                     text        not null
+        constraint report_archive_pk
+            primary key,
+    created_date_time      timestamptz not null,
+    modification_date_time timestamptz not null,
+    archived_date_time     timestamptz not null,
+
+    program_id             text        not null,
+    event_id               text        not null,
+    client_name            text        not null,
+    report_name            text,
+    payload_descriptors    jsonb,
+    resources              jsonb       not null
+);
+
+create index report_archive_event_id_index
+    on report_archive (event_id);
+
+create index report_modification_date_time_index
+    on report (modification_date_time);
+
+create index event_lifecycle_ends_index
+    on event_lifecycle (ends);
@@ -0,0 +1,22 @@
+-- Whether VENs, or their resources, opt in or out of (some intervals of) an event.
+-- A VEN has at most one status per event and resource, a new status replaces the previous one.
+create table event_opt
+(
+    id                     text primary key,
+    created_date_time      timestamptz not null,
+    modification_date_time timestamptz not null,
+    event_id               text        not null references event (id) on delete cascade,
+    ven_id                 text        not null references ven (id) on delete cascade,
+    resource_id            text references resource (id) on delete cascade,
+    opt_type               text        not null,
+    interval_first         integer,
+    interval_last          integer,
+    reason                 text,
+    check ((interval_first IS NULL) = (interval_last IS NULL))
+);
+
+create unique index event_opt_event_id_ven_id_resource_id_uindex
+    on event_opt (event_id, ven_id, resource_id) nulls not distinct;
+
+create index event_opt_ven_id_index
+    on event_opt (ven_id);
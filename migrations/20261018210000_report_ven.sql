@@ -0,0 +1,9 @@
+-- The VEN that created a report, reports created before are not attributed to a VEN
+create table report_ven
+(
+    report_id text primary key references report (id) on delete cascade,
+    ven_id    text not null references ven (id) on delete cascade
+);
+
+create index report_ven_ven_id_index
+    on report_ven (ven_id);
@@ -0,0 +1,39 @@
+-- the time zone of a program is part of its version history,
+-- such that events retrieved as of some point in time can be expanded in the time zone of that time
+alter table program_version
+    add column time_zone_offset text,
+    add column time_zone        text;
+
+create or replace function program_version_trigger() returns trigger
+    language plpgsql as
+$$
+begin
+    if tg_op in ('UPDATE', 'DELETE') then
+        update program_version set valid_until = now() where id = old.id and valid_until is null;
+    end if;
+
+    if tg_op = 'DELETE' then
+        return old;
+    end if;
+
+    insert into program_version (id, version, created_date_time, modification_date_time, program_name,
+                                 program_long_name, retailer_name, retailer_long_name, program_type, country,
+                                 principal_subdivision, time_zone_offset, time_zone, interval_period,
+                                 program_descriptions, binding_events, local_price, payload_descriptors, targets,
+                                 business_id)
+    values (new.id, coalesce((select max(version) from program_version where id = new.id), 0) + 1,
+            new.created_date_time, new.modification_date_time, new.program_name, new.program_long_name,
+            new.retailer_name, new.retailer_long_name, new.program_type, new.country, new.principal_subdivision,
+            new.time_zone_offset, new.time_zone, new.interval_period, new.program_descriptions, new.binding_events,
+            new.local_price, new.payload_descriptors, new.targets, new.business_id);
+    return new;
+end;
+$$;
+
+-- older versions were stored before the time zone was recorded, the current one is known
+update program_version v
+set time_zone_offset = p.time_zone_offset,
+    time_zone        = p.time_zone
+from program p
+where v.id = p.id
+  and v.valid_until is null;
@@ -0,0 +1,20 @@
+-- Attribute the reports created before the VTN recorded the reporting VEN,
+-- the same way new reports of users with several VENs are attributed:
+-- to the VEN identified by the client name, if it may report in the program
+insert into report_ven (report_id, ven_id)
+select distinct on (r.id) r.id, v.id
+from report r
+  join ven v on v.id = r.client_name or v.ven_name = r.client_name
+where not exists (select 1 from report_ven rv where rv.report_id = r.id)
+  and (not exists (select 1 from ven_program vp where vp.program_id = r.program_id)
+       or exists (select 1 from ven_program vp where vp.program_id = r.program_id and vp.ven_id = v.id))
+order by r.id, v.id = r.client_name desc;
+
+-- or else to the only VEN enrolled in the program, which is the only one that may report in it
+insert into report_ven (report_id, ven_id)
+select r.id, min(vp.ven_id)
+from report r
+  join ven_program vp on vp.program_id = r.program_id
+where not exists (select 1 from report_ven rv where rv.report_id = r.id)
+group by r.id
+having count(*) = 1;
@@ -0,0 +1,4 @@
+-- The VEN that created an archived report, as the report_ven row is removed together with the report.
+-- No foreign key, as the archive outlives the VEN
+alter table report_archive
+    add column ven_id text;
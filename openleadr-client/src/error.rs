@@ -8,6 +8,10 @@ pub enum Error {
     Serde(serde_json::Error),
     UrlParseError(url::ParseError),
     Problem(openleadr_wire::problem::Problem),
+    /// The VTN rejected a batch request.
+    /// Contains one problem for each item in the batch, in the order of the request.
+    /// Items that were valid on their own are reported with `424 Failed Dependency`.
+    BatchProblem(Vec<openleadr_wire::problem::Problem>),
     AuthProblem(openleadr_wire::oauth::OAuthError),
     OAuthTokenNotBearer,
     ObjectNotFound,
@@ -82,6 +86,7 @@ impl std::fmt::Display for Error {
             Error::Serde(err) => write!(f, "Serde error: {}", err),
             Error::UrlParseError(err) => write!(f, "URL parse error: {}", err),
             Error::Problem(err) => write!(f, "OpenADR Problem: {:?}", err),
+            Error::BatchProblem(errs) => write!(f, "OpenADR batch Problems: {:?}", errs),
             Error::AuthProblem(err) => write!(f, "Authentication problem: {:?}", err),
             Error::ObjectNotFound => write!(f, "Object not found"),
             Error::DuplicateObject => write!(f, "Found more than one object matching the filter"),
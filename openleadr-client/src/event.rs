@@ -1,10 +1,23 @@
+//! Client for a specific event, see [`EventClient`]
+//!
+//! The state and cancellation of events, opts, report summaries, and delivered reductions
+//! are extensions of this implementation and not part of the OpenADR 3.0 specification.
+
 use std::sync::Arc;
 
 use crate::{
     error::{Error, Result},
     ClientRef, ReportClient,
 };
-use openleadr_wire::{event::EventContent, report::ReportContent, Event, Report};
+use openleadr_wire::{
+    baseline::{BaselineMethod, DeliveredReduction},
+    event::{EventContent, EventId, EventState},
+    opt::{Opt, OptContent},
+    report::ReportContent,
+    report_summary::ReportSummary,
+    ven::VenId,
+    Event, Report,
+};
 
 /// Client to manage the data of a specific event and the reports contained in that event
 ///
@@ -39,6 +52,10 @@ impl EventClient {
         }
     }
 
+    pub(crate) fn into_event(self) -> Event {
+        self.data
+    }
+
     /// Get the id of the event
     pub fn id(&self) -> &openleadr_wire::event::EventId {
         &self.data.id
@@ -54,6 +71,16 @@ impl EventClient {
         self.data.modification_date_time
     }
 
+    /// Get the lifecycle state of the event as determined by the VTN.
+    pub fn state(&self) -> Option<EventState> {
+        self.data.state
+    }
+
+    /// Get the event replacing this one, if it got superseded
+    pub fn superseded_by(&self) -> Option<&EventId> {
+        self.data.superseded_by.as_ref()
+    }
+
     /// Read the data of the event
     pub fn content(&self) -> &EventContent {
         &self.data.content
@@ -76,6 +103,53 @@ impl EventClient {
         Ok(())
     }
 
+    /// Cancel the event on the VTN, optionally superseded by another event,
+    /// and refreshes the locally stored data with the returned VTN data.
+    ///
+    /// In contrast to [`delete`](Self::delete), the event stays available,
+    /// such that VENs learn about the cancellation.
+    pub async fn cancel(&mut self, superseded_by: Option<&EventId>) -> Result<()> {
+        #[derive(serde::Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Cancellation<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            superseded_by: Option<&'a EventId>,
+        }
+
+        self.data = self
+            .client
+            .post(
+                &format!("events/{}/cancel", self.id()),
+                &Cancellation { superseded_by },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Opt the VEN out of all intervals of the event.
+    /// Fails with a conflict if the events of the program are binding.
+    pub async fn opt_out(&self, ven_id: &VenId, reason: Option<&str>) -> Result<Opt> {
+        let mut content = OptContent::opt_out(ven_id.clone());
+        content.reason = reason.map(ToString::to_string);
+        self.set_opt(content).await
+    }
+
+    /// Set the opt status of the VEN, or one of its resources, for the event,
+    /// replacing the previous status of the same VEN and resource.
+    pub async fn set_opt(&self, content: OptContent) -> Result<Opt> {
+        self.client
+            .post(&format!("events/{}/opts", self.id()), &content)
+            .await
+    }
+
+    /// Get the opt statuses for the event.
+    /// VENs only see their own statuses, business users those of all VENs.
+    pub async fn get_opts(&self) -> Result<Vec<Opt>> {
+        self.client
+            .get(&format!("events/{}/opts", self.id()), &[])
+            .await
+    }
+
     /// Delete the event from the VTN
     pub async fn delete(self) -> Result<Event> {
         self.client.delete(&format!("events/{}", self.id())).await
@@ -136,6 +210,37 @@ impl EventClient {
             .collect())
     }
 
+    /// Get the reports of the event aggregated per interval, for business users only.
+    pub async fn get_report_summary(&self) -> Result<ReportSummary> {
+        self.client
+            .get(&format!("events/{}/report-summary", self.id()), &[])
+            .await
+    }
+
+    /// Get the reduction each resource delivered during the event relative to its baseline,
+    /// for business users only.
+    pub async fn get_delivered_reduction(
+        &self,
+        method: &BaselineMethod,
+    ) -> Result<DeliveredReduction> {
+        let x = method.x.to_string();
+        let y = method.y.to_string();
+        let adjustment_hours = method.adjustment_hours.map(|hours| hours.to_string());
+        let adjustment_cap = method.adjustment_cap.map(|cap| cap.to_string());
+
+        let mut query = vec![("x", x.as_str()), ("y", y.as_str())];
+        if let Some(adjustment_hours) = &adjustment_hours {
+            query.push(("adjustmentHours", adjustment_hours));
+        }
+        if let Some(adjustment_cap) = &adjustment_cap {
+            query.push(("adjustmentCap", adjustment_cap));
+        }
+
+        self.client
+            .get(&format!("events/{}/delivered-reduction", self.id()), &query)
+            .await
+    }
+
     /// Get all reports from the VTN, possibly filtered by `client_name`, trying to paginate whenever possible
     pub async fn get_report_list(&self, client_name: Option<&str>) -> Result<Vec<ReportClient>> {
         self.client
@@ -57,10 +57,13 @@ mod report;
 mod resource;
 mod target;
 mod timeline;
+mod tls;
 mod ven;
 
 use axum::async_trait;
-use openleadr_wire::{event::EventId, Event, Ven};
+use chrono::{DateTime, SecondsFormat, Utc};
+use openleadr_wire::{batch::BatchUpdate, event::EventId, Event, Ven};
+use opentelemetry::trace::TraceContextExt;
 use std::{
     fmt::Debug,
     future::Future,
@@ -68,6 +71,9 @@ use std::{
     time::{Duration, Instant},
 };
 use tokio::sync::RwLock;
+use tracing::trace;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
 
 use reqwest::{Method, RequestBuilder, Response};
 use url::Url;
@@ -79,6 +85,7 @@ pub use report::*;
 pub use resource::*;
 pub use target::*;
 pub use timeline::*;
+pub use tls::*;
 pub use ven::*;
 
 use crate::error::Result;
@@ -264,11 +271,7 @@ impl ClientRef {
         Ok(())
     }
 
-    async fn request<T: serde::de::DeserializeOwned>(
-        &self,
-        mut request: RequestBuilder,
-        query: &[(&str, &str)],
-    ) -> Result<T> {
+    async fn send(&self, mut request: RequestBuilder, query: &[(&str, &str)]) -> Result<Response> {
         self.ensure_auth().await?;
         request = request.header("Accept", "application/json");
         if !query.is_empty() {
@@ -282,7 +285,23 @@ impl ClientRef {
                 request = request.bearer_auth(&token.token);
             }
         }
-        let res = self.client.send(request).await?;
+
+        let request_id = Uuid::new_v4().to_string();
+        trace!(request_id, "sending request to VTN");
+        request = request.header("X-Request-Id", request_id);
+        for (name, value) in trace_context_headers() {
+            request = request.header(name, value);
+        }
+
+        Ok(self.client.send(request).await?)
+    }
+
+    async fn request<T: serde::de::DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        query: &[(&str, &str)],
+    ) -> Result<T> {
+        let res = self.send(request, query).await?;
 
         // handle any errors returned by the server
         if !res.status().is_success() {
@@ -293,6 +312,34 @@ impl ClientRef {
         Ok(res.json().await?)
     }
 
+    /// Sends a request to one of the batch endpoints of the VTN.
+    ///
+    /// If the VTN rejects the batch, it responds with a problem for each item of the batch.
+    /// Errors that concern the request as a whole, e.g., a missing authorization,
+    /// are still reported as a single problem.
+    async fn batch<S, T>(&self, method: Method, path: &str, body: &S) -> Result<T>
+    where
+        S: serde::ser::Serialize + Sync,
+        T: serde::de::DeserializeOwned,
+    {
+        let url = self.vtn_base_url.join(path)?;
+        let request = self.client.request_builder(method, url).json(body);
+        let res = self.send(request, &[]).await?;
+
+        if !res.status().is_success() {
+            let body = res.bytes().await?;
+            if let Ok(problems) =
+                serde_json::from_slice::<Vec<openleadr_wire::problem::Problem>>(&body)
+            {
+                return Err(Error::BatchProblem(problems));
+            }
+            let problem = serde_json::from_slice::<openleadr_wire::problem::Problem>(&body)?;
+            return Err(Error::from(problem));
+        }
+
+        Ok(res.json().await?)
+    }
+
     async fn get<T: serde::de::DeserializeOwned>(
         &self,
         path: &str,
@@ -365,6 +412,35 @@ impl ClientRef {
     }
 }
 
+/// Propagates the trace of the current [`tracing::Span`] to the VTN
+/// via the W3C `traceparent` and `tracestate` headers.
+///
+/// The headers are only set if the application installed a
+/// [`tracing_opentelemetry`] layer, otherwise there is no trace to propagate.
+fn trace_context_headers() -> Vec<(&'static str, String)> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return vec![];
+    }
+
+    let mut headers = vec![(
+        "traceparent",
+        format!(
+            "00-{}-{}-{:02x}",
+            span_context.trace_id(),
+            span_context.span_id(),
+            span_context.trace_flags().to_u8()
+        ),
+    )];
+    let trace_state = span_context.trace_state().header();
+    if !trace_state.is_empty() {
+        headers.push(("tracestate", trace_state));
+    }
+    headers
+}
+
 #[derive(Debug)]
 struct ReqwestClientRef {
     client: reqwest::Client,
@@ -450,6 +526,21 @@ impl Client {
         Self::with_details(base_url, oauth_base_url, client, auth)
     }
 
+    /// Create a new client for a VTN located at the specified URL with custom TLS settings,
+    /// e.g., a client certificate or additional root certificates.
+    ///
+    /// Like [`Self::with_url`], this assumes that the VTN also works as an OAuth provider.
+    /// If the VTN authenticates the VEN by its client certificate, `auth` can be `None`.
+    pub fn with_tls(
+        base_url: Url,
+        auth: Option<ClientCredentials>,
+        tls: TlsConfig,
+    ) -> Result<Self> {
+        let client = tls.apply(reqwest::Client::builder()).build()?;
+        let oauth_base_url = base_url.join("/auth/token")?;
+        Ok(Self::with_details(base_url, oauth_base_url, client, auth))
+    }
+
     /// Create a new client with more details than [`Self::with_url`].
     ///
     /// It allows specifying a [`reqwest::Client`] instead of the default one.
@@ -551,6 +642,18 @@ impl Client {
         Ok(ProgramClient::from_program(self.clone(), program))
     }
 
+    /// Get a program by id as it was stored at the point in time `as_of`
+    pub async fn get_program_by_id_as_of(
+        &self,
+        id: &ProgramId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Program> {
+        let as_of = as_of.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        self.client_ref
+            .get(&format!("programs/{}", id.as_str()), &[("asOf", &as_of)])
+            .await
+    }
+
     /// Low-level operation that gets a list of events from the VTN with the given query parameters
     ///
     /// To automatically iterate pages, use [`self.get_event_list`]
@@ -560,9 +663,26 @@ impl Client {
         filter: Filter<'_, impl AsRef<str>>,
         pagination: PaginationOptions,
     ) -> Result<Vec<EventClient>> {
+        let events = self
+            .get_event_page(program_id, filter, pagination, None)
+            .await?;
+        Ok(events
+            .into_iter()
+            .map(|event| EventClient::from_event(self.client_ref.clone(), event))
+            .collect())
+    }
+
+    async fn get_event_page(
+        &self,
+        program_id: Option<&ProgramId>,
+        filter: Filter<'_, impl AsRef<str>>,
+        pagination: PaginationOptions,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Event>> {
         // convert query params
         let skip_str = pagination.skip.to_string();
         let limit_str = pagination.limit.to_string();
+        let as_of_str = as_of.map(|as_of| as_of.to_rfc3339_opts(SecondsFormat::AutoSi, true));
         // insert into query params
         let mut query: Vec<(&str, &str)> = vec![("skip", &skip_str), ("limit", &limit_str)];
 
@@ -572,12 +692,12 @@ impl Client {
             query.push(("programID", program_id.as_str()));
         }
 
+        if let Some(as_of) = as_of_str.as_deref() {
+            query.push(("asOf", as_of));
+        }
+
         // send request and return response
-        let events: Vec<Event> = self.client_ref.get("events", &query).await?;
-        Ok(events
-            .into_iter()
-            .map(|event| EventClient::from_event(self.client_ref.clone(), event))
-            .collect())
+        self.client_ref.get("events", &query).await
     }
 
     /// Get all events from the VTN with the given query parameters.
@@ -609,6 +729,82 @@ impl Client {
         Ok(EventClient::from_event(self.client_ref.clone(), event))
     }
 
+    /// Get all events from the VTN with the given query parameters,
+    /// as they were stored at the point in time `as_of`.
+    ///
+    /// This includes events that got updated or deleted since then.
+    /// It automatically tries to iterate pages where necessary.
+    pub async fn get_event_list_as_of(
+        &self,
+        program_id: Option<&ProgramId>,
+        filter: Filter<'_, impl AsRef<str> + Clone>,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<Event>> {
+        self.client_ref
+            .iterate_pages(|skip, limit| {
+                self.get_event_page(
+                    program_id,
+                    filter.clone(),
+                    PaginationOptions { skip, limit },
+                    Some(as_of),
+                )
+            })
+            .await
+    }
+
+    /// Get an event by id as it was stored at the point in time `as_of`
+    pub async fn get_event_by_id_as_of(&self, id: &EventId, as_of: DateTime<Utc>) -> Result<Event> {
+        let as_of = as_of.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        self.client_ref
+            .get(&format!("events/{}", id.as_str()), &[("asOf", &as_of)])
+            .await
+    }
+
+    /// Get all versions of an event, ordered from the oldest to the most recent one
+    pub async fn get_event_versions(&self, id: &EventId) -> Result<Vec<Event>> {
+        self.client_ref
+            .get(&format!("events/{}/versions", id.as_str()), &[])
+            .await
+    }
+
+    /// Create multiple events on the VTN at once.
+    ///
+    /// The VTN stores either all or none of the events.
+    /// If any of them is rejected, [`Error::BatchProblem`] contains a problem for each of the events,
+    /// in the same order as they were passed to this function.
+    pub async fn create_events(&self, events: Vec<EventContent>) -> Result<Vec<EventClient>> {
+        let events: Vec<Event> = self
+            .client_ref
+            .batch(Method::POST, "events/batch", &events)
+            .await?;
+        Ok(events
+            .into_iter()
+            .map(|event| EventClient::from_event(self.client_ref.clone(), event))
+            .collect())
+    }
+
+    /// Stores any modifications made to the content of multiple events at once
+    /// and refreshes the locally stored data with the returned VTN data.
+    ///
+    /// The VTN updates either all or none of the events.
+    /// If any of them is rejected, [`Error::BatchProblem`] contains a problem for each of the events,
+    /// and the locally stored data is left untouched.
+    pub async fn update_events(&self, events: &mut [EventClient]) -> Result<()> {
+        let updates = events
+            .iter()
+            .map(|event| BatchUpdate::new(event.id().clone(), event.content().clone()))
+            .collect::<Vec<_>>();
+        let updated: Vec<Event> = self
+            .client_ref
+            .batch(Method::PUT, "events/batch", &updates)
+            .await?;
+
+        for (event, data) in events.iter_mut().zip(updated) {
+            *event = EventClient::from_event(self.client_ref.clone(), data);
+        }
+        Ok(())
+    }
+
     /// Create a new VEN entity at the VTN. The content should be created with [`VenContent::new`].
     pub async fn create_ven(&self, ven: VenContent) -> Result<VenClient> {
         let ven = self.client_ref.post("vens", &ven).await?;
@@ -667,3 +863,42 @@ impl Client {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider as _;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn trace_context_without_trace() {
+        assert!(trace_context_headers().is_empty());
+    }
+
+    #[test]
+    fn trace_context_of_current_span() {
+        let tracer = opentelemetry_sdk::trace::TracerProvider::default().tracer("test");
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request");
+            let _guard = span.enter();
+
+            let headers = trace_context_headers();
+            assert_eq!(headers.len(), 1);
+            let (name, value) = &headers[0];
+            assert_eq!(*name, "traceparent");
+
+            let span_context = span.context().span().span_context().clone();
+            assert_eq!(
+                value,
+                &format!(
+                    "00-{}-{}-01",
+                    span_context.trace_id(),
+                    span_context.span_id()
+                )
+            );
+        });
+    }
+}
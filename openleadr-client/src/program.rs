@@ -1,10 +1,17 @@
+//! Client for a specific program, see [`ProgramClient`]
+//!
+//! Enrollments are an extension of this implementation and not part of the OpenADR 3.0 specification.
+
 use crate::{
     error::{Error, Result},
     Client, EventClient, EventContent, Filter, PaginationOptions, ProgramContent, ProgramId,
     Timeline,
 };
+use chrono::{DateTime, Utc};
 use openleadr_wire::{
+    enrollment::{Enrollment, NewEnrollment},
     event::{EventInterval, Priority},
+    ven::VenId,
     Program,
 };
 
@@ -70,6 +77,51 @@ impl ProgramClient {
             .await
     }
 
+    /// Enroll a VEN in the program.
+    ///
+    /// Once any VEN is enrolled in a program, only the enrolled VENs can see the program and its events.
+    pub async fn enroll_ven(&self, ven_id: &VenId) -> Result<Enrollment> {
+        self.client
+            .client_ref
+            .post(
+                &format!("programs/{}/vens", self.id()),
+                &NewEnrollment {
+                    ven_id: ven_id.clone(),
+                },
+            )
+            .await
+    }
+
+    /// Enroll all VENs whose targets match the targets of the program.
+    /// Returns the new enrollments only, VENs already enrolled are skipped.
+    pub async fn enroll_matching_vens(&self) -> Result<Vec<Enrollment>> {
+        self.client
+            .client_ref
+            .post(
+                &format!("programs/{}/vens/match", self.id()),
+                &serde_json::json!({}),
+            )
+            .await
+    }
+
+    /// Remove the enrollment of a VEN in the program.
+    ///
+    /// Removing the last enrollment makes the program and its events visible to all VENs again.
+    pub async fn unenroll_ven(&self, ven_id: &VenId) -> Result<Enrollment> {
+        self.client
+            .client_ref
+            .delete(&format!("programs/{}/vens/{}", self.id(), ven_id))
+            .await
+    }
+
+    /// Get the enrollments of all VENs in the program
+    pub async fn get_enrollments(&self) -> Result<Vec<Enrollment>> {
+        self.client
+            .client_ref
+            .get(&format!("programs/{}/vens", self.id()), &[])
+            .await
+    }
+
     /// Create a new event on the VTN.
     /// The content should be created with [`ProgramClient::new_event`]
     /// to automatically insert the correct program ID
@@ -121,12 +173,39 @@ impl ProgramClient {
     }
 
     /// Retrieves the events for this program from the VTN and tries to build a [`Timeline`] from it.
+    /// Cancelled events are left out, see [`Timeline::cancelled_events`].
     pub async fn get_timeline(
         &self,
         filter: Filter<'_, impl AsRef<str> + Clone>,
     ) -> Result<Timeline> {
-        let events = self.get_event_list(filter).await?;
-        let events = events.iter().map(|e| e.content()).collect();
-        Timeline::from_events(&self.data, events).ok_or(Error::InvalidInterval)
+        let events: Vec<_> = self
+            .get_event_list(filter)
+            .await?
+            .into_iter()
+            .map(EventClient::into_event)
+            .collect();
+        Timeline::from_vtn_events(&self.data, &events).ok_or(Error::InvalidInterval)
+    }
+
+    /// Builds the [`Timeline`] of this program from the events as they were stored on the VTN
+    /// at the point in time `as_of`.
+    ///
+    /// This allows to reconstruct what a VEN could have known at that time,
+    /// even if the events got updated or deleted since then.
+    /// The events are expanded in the time zone the program had at that time.
+    pub async fn get_timeline_as_of(
+        &self,
+        filter: Filter<'_, impl AsRef<str> + Clone>,
+        as_of: DateTime<Utc>,
+    ) -> Result<Timeline> {
+        let program = self
+            .client
+            .get_program_by_id_as_of(self.id(), as_of)
+            .await?;
+        let events = self
+            .client
+            .get_event_list_as_of(Some(self.id()), filter, as_of)
+            .await?;
+        Timeline::from_vtn_events(&program, &events).ok_or(Error::InvalidInterval)
     }
 }
@@ -4,9 +4,9 @@ use chrono::{DateTime, Utc};
 use tracing::warn;
 
 use openleadr_wire::{
-    event::{EventContent, EventValuesMap, Priority},
+    event::{EventContent, EventId, EventValuesMap, Priority},
     interval::IntervalPeriod,
-    Program,
+    Event, Program,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -29,6 +29,8 @@ struct InternalInterval {
 #[derive(Clone, Default, Debug)]
 pub struct Timeline {
     data: rangemap::RangeMap<DateTime<Utc>, InternalInterval>,
+    /// Events left out of the timeline, because the VTN cancelled them
+    cancelled: Vec<EventId>,
 }
 
 impl Timeline {
@@ -36,6 +38,7 @@ impl Timeline {
     pub fn new() -> Self {
         Self {
             data: rangemap::RangeMap::new(),
+            cancelled: Vec::new(),
         }
     }
 
@@ -71,8 +74,14 @@ impl Timeline {
     /// If both are specified, the individual period takes precedence over the one specified in the event.
     /// If for an interval, there is no period present, and none specified in the event,
     /// then this function will return [`None`]
+    ///
+    /// Days, weeks, months, and years of the interval durations are evaluated in the
+    /// [time zone of the program](openleadr_wire::program::ProgramContent::effective_time_zone).
+    /// For example, an interval of `P1D` starting at noon ends at noon the next day
+    /// in the local time of the program, even across a daylight saving time transition.
     pub fn from_events(program: &Program, mut events: Vec<&EventContent>) -> Option<Self> {
         let mut data = Self::default();
+        let time_zone = program.content.effective_time_zone();
 
         events.sort_by_key(|e| e.priority);
 
@@ -103,7 +112,13 @@ impl Timeline {
                     };
 
                 let range = match duration {
-                    Some(duration) => *start..*start + duration.to_chrono_at_datetime(*start),
+                    // the infinite duration, or one exceeding the representable range
+                    Some(duration) => {
+                        *start
+                            ..time_zone
+                                .checked_add(start, duration)
+                                .unwrap_or(DateTime::<Utc>::MAX_UTC)
+                    }
                     None => *start..DateTime::<Utc>::MAX_UTC,
                 };
 
@@ -113,7 +128,7 @@ impl Timeline {
                     id: id as u32,
                     randomize_start: randomize_start
                         .as_ref()
-                        .map(|d| d.to_chrono_at_datetime(*start)),
+                        .map(|d| time_zone.to_chrono_at_datetime(start, d)),
                     value_map: event_interval.payloads.clone(),
                     priority: event.priority,
                 };
@@ -131,6 +146,30 @@ impl Timeline {
         Some(data)
     }
 
+    /// Creates a [`Timeline`] like [`from_events`](Self::from_events) from the [`Event`]s as
+    /// retrieved from the VTN.
+    ///
+    /// Events the VTN reports as [cancelled](openleadr_wire::event::EventState::is_cancelled)
+    /// are left out of the timeline.
+    /// Their ids are available via [`cancelled_events`](Self::cancelled_events),
+    /// such that a VEN can react to the cancellation.
+    pub fn from_vtn_events(program: &Program, events: &[Event]) -> Option<Self> {
+        let (cancelled, events): (Vec<_>, Vec<_>) = events
+            .iter()
+            .partition(|event| event.state.is_some_and(|state| state.is_cancelled()));
+
+        let mut timeline =
+            Self::from_events(program, events.into_iter().map(|e| &e.content).collect())?;
+        timeline.cancelled = cancelled.into_iter().map(|e| e.id.clone()).collect();
+
+        Some(timeline)
+    }
+
+    /// The events left out of this timeline, because the VTN cancelled them
+    pub fn cancelled_events(&self) -> &[EventId] {
+        &self.cancelled
+    }
+
     /// Get an iterator over the [`Interval`]s in this [`Timeline`]
     pub fn iter(&self) -> Iter<'_> {
         Iter {
@@ -251,7 +290,7 @@ mod test {
 
     use super::*;
     use openleadr_wire::{
-        event::EventInterval,
+        event::{EventInterval, EventState},
         program::{ProgramContent, ProgramId},
         values_map::Value,
     };
@@ -425,7 +464,7 @@ mod test {
 
         event.interval_period = Some(IntervalPeriod {
             start: DateTime::UNIX_EPOCH,
-            duration: Some(openleadr_wire::Duration::hours(5.)),
+            duration: Some(openleadr_wire::Duration::hours(5)),
             randomize_start: None,
         });
 
@@ -464,7 +503,7 @@ mod test {
                         duration: Some(openleadr_wire::Duration::hours(
                             (range.end - range.start) as _,
                         )),
-                        randomize_start: Some(openleadr_wire::Duration::hours(5.0)),
+                        randomize_start: Some(openleadr_wire::Duration::hours(5)),
                     }),
                     payloads: vec![EventValuesMap {
                         value_type: openleadr_wire::event::EventType::Price,
@@ -503,4 +542,124 @@ mod test {
             "when an event is split, only the first interval should retain `randomize_start`",
         );
     }
+
+    #[test]
+    fn daily_intervals_across_daylight_saving_time() {
+        let mut program = test_program("p");
+        program.content.time_zone = Some(chrono_tz::Europe::Amsterdam);
+
+        let intervals = (0..3)
+            .map(|id| {
+                EventInterval::new(
+                    id,
+                    vec![EventValuesMap {
+                        value_type: openleadr_wire::event::EventType::Price,
+                        values: vec![Value::Integer(id.into())],
+                    }],
+                )
+            })
+            .collect();
+        let mut event = EventContent::new(program.id.clone(), intervals);
+        // noon in Amsterdam, the day before the clocks are set forward
+        event.interval_period = Some(IntervalPeriod {
+            start: "2024-03-30T11:00:00Z".parse().unwrap(),
+            duration: Some("P1D".parse().unwrap()),
+            randomize_start: None,
+        });
+
+        let ranges = |program: &Program| {
+            Timeline::from_events(program, vec![&event])
+                .unwrap()
+                .iter()
+                .map(|(range, _)| range.clone())
+                .collect::<Vec<_>>()
+        };
+        let utc = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(
+            ranges(&program),
+            vec![
+                utc("2024-03-30T11:00:00Z")..utc("2024-03-31T10:00:00Z"),
+                utc("2024-03-31T10:00:00Z")..utc("2024-04-01T10:00:00Z"),
+                utc("2024-04-01T10:00:00Z")..utc("2024-04-02T10:00:00Z"),
+            ],
+            "every interval starts at noon local time",
+        );
+
+        // standard time only
+        program.content.time_zone = None;
+        program.content.time_zone_offset = Some(openleadr_wire::Duration::PT1H.into());
+        assert_eq!(
+            ranges(&program),
+            vec![
+                utc("2024-03-30T11:00:00Z")..utc("2024-03-31T11:00:00Z"),
+                utc("2024-03-31T11:00:00Z")..utc("2024-04-01T11:00:00Z"),
+                utc("2024-04-01T11:00:00Z")..utc("2024-04-02T11:00:00Z"),
+            ],
+        );
+    }
+
+    #[test]
+    fn monthly_interval_in_time_zone() {
+        let mut program = test_program("p");
+        program.content.time_zone = Some(chrono_tz::America::New_York);
+
+        let mut event = test_event_content(0..1, 42);
+        // midnight in New York, the clocks are set back on 2024-11-03
+        event.intervals[0].interval_period = Some(IntervalPeriod {
+            start: "2024-10-01T04:00:00Z".parse().unwrap(),
+            duration: Some("P1M".parse().unwrap()),
+            randomize_start: Some("P1D".parse().unwrap()),
+        });
+
+        let timeline = Timeline::from_events(&program, vec![&event]).unwrap();
+        let (range, interval) = timeline.iter().next().unwrap();
+        assert_eq!(
+            range.end,
+            "2024-11-01T04:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(interval.randomize_start(), Some(Duration::hours(24)));
+
+        event.intervals[0].interval_period.as_mut().unwrap().start =
+            "2024-11-01T04:00:00Z".parse().unwrap();
+        let timeline = Timeline::from_events(&program, vec![&event]).unwrap();
+        let (range, _) = timeline.iter().next().unwrap();
+        assert_eq!(
+            range.end,
+            "2024-12-01T05:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn cancelled_events_are_excluded() {
+        let program = test_program("p");
+        let event = |id: &str, value, state| Event {
+            id: id.parse().unwrap(),
+            created_date_time: Default::default(),
+            modification_date_time: Default::default(),
+            state,
+            superseded_by: None,
+            content: test_event_content(0..2, value),
+        };
+        let events = [
+            event("event-1", 42, Some(EventState::Active)),
+            event("event-2", 21, Some(EventState::Cancelled)),
+            event("event-3", 12, Some(EventState::Superseded)),
+        ];
+
+        let timeline = Timeline::from_vtn_events(&program, &events).unwrap();
+        assert_eq!(
+            timeline.data.into_iter().collect::<Vec<_>>(),
+            vec![interval_with_value(0, 0..2, 42, Priority::UNSPECIFIED)]
+        );
+        assert_eq!(
+            timeline.cancelled,
+            vec!["event-2".parse().unwrap(), "event-3".parse().unwrap()]
+        );
+
+        // without a state, for example when retrieved as of a past point in time
+        let timeline = Timeline::from_vtn_events(&program, &[event("event-4", 7, None)]).unwrap();
+        assert!(timeline.cancelled_events().is_empty());
+        assert_eq!(timeline.iter().count(), 1);
+    }
 }
@@ -0,0 +1,76 @@
+use reqwest::{Certificate, ClientBuilder, Identity};
+
+use crate::error::Result;
+
+/// TLS settings for connecting to a VTN, see [`Client::with_tls`](crate::Client::with_tls).
+///
+/// ```no_run
+/// # use openleadr_client::{Client, TlsConfig};
+/// # fn main() -> Result<(), openleadr_client::Error> {
+/// let tls = TlsConfig::new()
+///     .add_root_certificate_pem(&std::fs::read("ca.pem").unwrap())?
+///     .client_certificate_pem(&std::fs::read("ven-1.pem").unwrap())?;
+/// // the VTN identifies the VEN by its client certificate, so no OAuth credentials are needed
+/// let client = Client::with_tls("https://your-vtn.com".parse().unwrap(), None, tls)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    identity: Option<Identity>,
+    root_certificates: Vec<Certificate>,
+    built_in_roots: bool,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            identity: None,
+            root_certificates: vec![],
+            built_in_roots: true,
+        }
+    }
+}
+
+impl TlsConfig {
+    /// Trusts the root certificates of the operating system only and uses no client certificate
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Presents the client certificate to the VTN during the TLS handshake.
+    ///
+    /// The PEM must contain the certificate chain, starting with the client certificate,
+    /// and the private key in PKCS#8, PKCS#1, or SEC1 format.
+    pub fn client_certificate_pem(mut self, pem: &[u8]) -> Result<Self> {
+        self.identity = Some(Identity::from_pem(pem)?);
+        Ok(self)
+    }
+
+    /// Additionally trusts all certificates in the PEM, e.g., the CA of a test VTN
+    pub fn add_root_certificate_pem(mut self, pem: &[u8]) -> Result<Self> {
+        self.root_certificates
+            .extend(Certificate::from_pem_bundle(pem)?);
+        Ok(self)
+    }
+
+    /// Trusts only the root certificates added via [`Self::add_root_certificate_pem`],
+    /// but not the ones of the operating system
+    pub fn only_custom_roots(mut self) -> Self {
+        self.built_in_roots = false;
+        self
+    }
+
+    /// Applies the settings to a [`reqwest::ClientBuilder`],
+    /// in case you want to configure further details of the HTTP client.
+    pub fn apply(self, mut builder: ClientBuilder) -> ClientBuilder {
+        builder = builder.tls_built_in_root_certs(self.built_in_roots);
+        for certificate in self.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+        if let Some(identity) = self.identity {
+            builder = builder.identity(identity);
+        }
+        builder
+    }
+}
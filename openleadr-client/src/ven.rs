@@ -1,10 +1,13 @@
 use crate::{resource::ResourceClient, ClientRef, Error, Result};
 use chrono::{DateTime, Utc};
 use openleadr_wire::{
+    batch::BatchUpdate,
+    enrollment::Enrollment,
     resource::{Resource, ResourceContent, ResourceId},
     ven::{VenContent, VenId},
     Ven,
 };
+use reqwest::Method;
 use std::sync::Arc;
 
 /// A client for interacting with the data in a specific VEN and the resources contained in the VEN.
@@ -64,6 +67,13 @@ impl VenClient {
         self.client.delete(&format!("vens/{}", self.id())).await
     }
 
+    /// Get the enrollments of this VEN in programs
+    pub async fn get_enrollments(&self) -> Result<Vec<Enrollment>> {
+        self.client
+            .get(&format!("vens/{}/programs", self.id()), &[])
+            .await
+    }
+
     /// Create a resource as a child of this VEN
     pub async fn create_resource(&self, resource: ResourceContent) -> Result<ResourceClient> {
         let resource = self
@@ -77,6 +87,58 @@ impl VenClient {
         ))
     }
 
+    /// Create multiple resources as children of this VEN at once.
+    ///
+    /// The VTN stores either all or none of the resources.
+    /// If any of them is rejected, [`Error::BatchProblem`] contains a problem for each of the resources,
+    /// in the same order as they were passed to this function.
+    pub async fn create_resources(
+        &self,
+        resources: Vec<ResourceContent>,
+    ) -> Result<Vec<ResourceClient>> {
+        let resources: Vec<Resource> = self
+            .client
+            .batch(
+                Method::POST,
+                &format!("vens/{}/resources/batch", self.id()),
+                &resources,
+            )
+            .await?;
+        Ok(resources
+            .into_iter()
+            .map(|resource| {
+                ResourceClient::from_resource(Arc::clone(&self.client), self.id().clone(), resource)
+            })
+            .collect())
+    }
+
+    /// Stores any modifications made to the content of multiple resources of this VEN at once
+    /// and refreshes the locally stored data with the returned VTN data.
+    ///
+    /// The VTN updates either all or none of the resources.
+    /// If any of them is rejected, [`Error::BatchProblem`] contains a problem for each of the resources,
+    /// and the locally stored data is left untouched.
+    pub async fn update_resources(&self, resources: &mut [ResourceClient]) -> Result<()> {
+        let updates = resources
+            .iter()
+            .map(|resource| BatchUpdate::new(resource.id().clone(), resource.content().clone()))
+            .collect::<Vec<_>>();
+        let updated: Vec<Resource> = self
+            .client
+            .batch(
+                Method::PUT,
+                &format!("vens/{}/resources/batch", self.id()),
+                &updates,
+            )
+            .await?;
+
+        for (resource, data) in resources.iter_mut().zip(updated) {
+            *resource =
+                ResourceClient::from_resource(Arc::clone(&self.client), self.id().clone(), data);
+        }
+        Ok(())
+    }
+
     async fn get_resources_req(
         &self,
         resource_name: Option<&str>,
@@ -158,6 +158,7 @@ pub async fn setup_program_client(program_name: impl ToString, db: PgPool) -> Pr
         country: None,
         principal_subdivision: None,
         time_zone_offset: None,
+        time_zone: None,
         interval_period: None,
         program_descriptions: None,
         binding_events: None,
@@ -1,7 +1,9 @@
 use axum::http::StatusCode;
+use chrono::{TimeZone, Utc};
 use openleadr_client::{Error, Filter, PaginationOptions};
 use openleadr_wire::{
     event::{EventContent, EventInterval, EventType, EventValuesMap, Priority},
+    interval::IntervalPeriod,
     program::{ProgramContent, ProgramId},
     target::{TargetEntry, TargetMap, TargetType},
     values_map::Value,
@@ -338,3 +340,107 @@ async fn filter_constraint_violation(db: PgPool) {
     };
     assert_eq!(problem.status, StatusCode::BAD_REQUEST);
 }
+
+#[sqlx::test(fixtures("users"))]
+async fn batch_create_update(db: PgPool) {
+    let client = common::setup_client(db).await;
+    let program = client
+        .create_program(ProgramContent::new("program"))
+        .await
+        .unwrap();
+
+    let contents = vec![
+        EventContent {
+            event_name: Some("event1".to_string()),
+            ..default_content(program.id())
+        },
+        EventContent {
+            event_name: Some("event2".to_string()),
+            ..default_content(program.id())
+        },
+    ];
+
+    let mut events = client.create_events(contents.clone()).await.unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].content(), &contents[0]);
+    assert_eq!(events[1].content(), &contents[1]);
+
+    for event in events.iter_mut() {
+        event.content_mut().priority = Priority::MIN;
+    }
+    client.update_events(&mut events).await.unwrap();
+    assert!(events.iter().all(|e| e.content().priority == Priority::MIN));
+
+    let event = client.get_event_by_id(events[1].id()).await.unwrap();
+    assert_eq!(event.content().priority, Priority::MIN);
+
+    let err = client
+        .create_events(vec![
+            default_content(program.id()),
+            default_content(&ProgramId::new("not-existing").unwrap()),
+        ])
+        .await
+        .unwrap_err();
+    let Error::BatchProblem(problems) = err else {
+        unreachable!()
+    };
+    assert_eq!(problems.len(), 2);
+    assert_eq!(problems[0].status, StatusCode::FAILED_DEPENDENCY);
+    assert_eq!(problems[1].status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(fixtures("users"))]
+async fn versions_and_timeline_as_of(db: PgPool) {
+    let client = common::setup_client(db).await;
+    let program = client
+        .create_program(ProgramContent::new("program"))
+        .await
+        .unwrap();
+
+    let start = Utc.with_ymd_and_hms(2024, 8, 1, 12, 0, 0).unwrap();
+    let content = EventContent {
+        interval_period: Some(IntervalPeriod {
+            start,
+            duration: Some(openleadr_wire::Duration::hours(1)),
+            randomize_start: None,
+        }),
+        ..default_content(program.id())
+    };
+    let mut event = program.create_event(content).await.unwrap();
+    let created = event.modification_date_time();
+
+    event.content_mut().intervals[0].payloads[0].values = vec![Value::Number(42.0)];
+    event.update().await.unwrap();
+
+    let versions = client.get_event_versions(event.id()).await.unwrap();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0].modification_date_time, created);
+    assert_eq!(&versions[1].content, event.content());
+
+    let old = client
+        .get_event_by_id_as_of(event.id(), created)
+        .await
+        .unwrap();
+    assert_eq!(old, versions[0]);
+
+    let value_at = |timeline: &openleadr_client::Timeline| {
+        let (_, interval) = timeline.at_datetime(&start).unwrap();
+        interval.value_map()[0].values.clone()
+    };
+
+    let timeline = program
+        .get_timeline_as_of(Filter::none(), created)
+        .await
+        .unwrap();
+    assert_eq!(value_at(&timeline), vec![Value::Number(123.4)]);
+
+    let timeline = program.get_timeline(Filter::none()).await.unwrap();
+    assert_eq!(value_at(&timeline), vec![Value::Number(42.0)]);
+
+    event.delete().await.unwrap();
+    let timeline = program
+        .get_timeline_as_of(Filter::none(), created)
+        .await
+        .unwrap();
+    assert_eq!(value_at(&timeline), vec![Value::Number(123.4)]);
+}
@@ -3,6 +3,7 @@ use openleadr_client::{Error, Filter, PaginationOptions};
 use openleadr_wire::{
     program::ProgramContent,
     target::{TargetEntry, TargetMap, TargetType},
+    ven::VenContent,
 };
 use sqlx::PgPool;
 
@@ -18,6 +19,7 @@ fn default_content() -> ProgramContent {
         country: None,
         principal_subdivision: None,
         time_zone_offset: None,
+        time_zone: None,
         interval_period: None,
         program_descriptions: None,
         binding_events: None,
@@ -261,3 +263,54 @@ async fn retrieve_all_with_filter(db: PgPool) {
         .unwrap();
     assert_eq!(programs.len(), 0);
 }
+
+#[sqlx::test(fixtures("users"))]
+async fn enrollments(db: PgPool) {
+    let client = common::setup_client(db).await;
+
+    let program = client
+        .create_program(ProgramContent {
+            targets: Some(TargetMap(vec![TargetEntry {
+                label: TargetType::Group,
+                values: ["Group 1".to_string()],
+            }])),
+            ..default_content()
+        })
+        .await
+        .unwrap();
+    let ven1 = client
+        .create_ven(VenContent::new("ven-1".to_string(), None, None, None))
+        .await
+        .unwrap();
+    let ven2 = client
+        .create_ven(VenContent::new(
+            "ven-2".to_string(),
+            None,
+            Some(TargetMap(vec![TargetEntry {
+                label: TargetType::Group,
+                values: ["Group 1".to_string()],
+            }])),
+            None,
+        ))
+        .await
+        .unwrap();
+
+    let enrollment = program.enroll_ven(ven1.id()).await.unwrap();
+    assert_eq!(&enrollment.ven_id, ven1.id());
+    assert!(program
+        .enroll_ven(ven1.id())
+        .await
+        .unwrap_err()
+        .is_conflict());
+
+    let matching = program.enroll_matching_vens().await.unwrap();
+    assert_eq!(matching.len(), 1);
+    assert_eq!(&matching[0].ven_id, ven2.id());
+
+    assert_eq!(program.get_enrollments().await.unwrap().len(), 2);
+    assert_eq!(ven2.get_enrollments().await.unwrap(), matching);
+
+    let removed = program.unenroll_ven(ven1.id()).await.unwrap();
+    assert_eq!(removed, enrollment);
+    assert!(ven1.get_enrollments().await.unwrap().is_empty());
+}
@@ -0,0 +1,72 @@
+use openleadr_client::{Client, TlsConfig};
+use openleadr_vtn::{
+    config::{ClientCertificateMapping, TlsConfig as VtnTlsConfig},
+    data_source::PostgresStorage,
+    state::AppState,
+    tls,
+};
+use sqlx::PgPool;
+use std::path::{Path, PathBuf};
+use tokio::{net::TcpListener, sync::oneshot};
+
+fn asset(name: &str) -> PathBuf {
+    Path::new("../openleadr-vtn/tests/assets/tls").join(name)
+}
+
+#[sqlx::test(fixtures("users", "vens"))]
+async fn client_certificate(db: PgPool) {
+    let vtn_config = VtnTlsConfig {
+        client_ca: Some(asset("ca.pem")),
+        require_client_cert: true,
+        client_certificates: vec![ClientCertificateMapping {
+            fingerprint: None,
+            subject: Some("O=OpenLEADR, CN=ven-1".to_string()),
+            ven_id: "ven-1".parse().unwrap(),
+        }],
+        ..VtnTlsConfig::new(asset("server.pem"), asset("server.key"))
+    };
+    let router = AppState::new(PostgresStorage::new(db).unwrap()).into_router();
+    let listener = TcpListener::bind("localhost:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (shutdown, shutdown_receiver) = oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        tls::serve(listener, router, &vtn_config, async {
+            shutdown_receiver.await.ok();
+        })
+        .await
+        .unwrap();
+    });
+
+    let ca = std::fs::read(asset("ca.pem")).unwrap();
+    let mut identity = std::fs::read(asset("client.pem")).unwrap();
+    identity.extend(std::fs::read(asset("client.key")).unwrap());
+    let url = format!("https://localhost:{port}/").parse().unwrap();
+
+    let tls = TlsConfig::new()
+        .add_root_certificate_pem(&ca)
+        .unwrap()
+        .only_custom_roots()
+        .client_certificate_pem(&identity)
+        .unwrap();
+    let client = Client::with_tls(url, None, tls).unwrap();
+    let ven = client
+        .get_ven_by_id(&"ven-1".parse().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(ven.content().ven_name, "ven-1-name");
+
+    // the server certificate is not trusted without the custom root
+    let url = format!("https://localhost:{port}/").parse().unwrap();
+    let tls = TlsConfig::new()
+        .only_custom_roots()
+        .client_certificate_pem(&identity)
+        .unwrap();
+    let client = Client::with_tls(url, None, tls).unwrap();
+    assert!(client
+        .get_ven_by_id(&"ven-1".parse().unwrap())
+        .await
+        .is_err());
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap();
+}
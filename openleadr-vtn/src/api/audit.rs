@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tracing::trace;
+use validator::Validate;
+
+use crate::{
+    api::{AppResponse, ValidatedQuery},
+    data_source::{AuditEntry, AuditLog, AuditObjectType, AuditOperation},
+    jwt::UserManagerUser,
+};
+
+pub async fn get_all(
+    State(audit_log): State<Arc<dyn AuditLog>>,
+    ValidatedQuery(query_params): ValidatedQuery<QueryParams>,
+    UserManagerUser(_): UserManagerUser,
+) -> AppResponse<Vec<AuditEntry>> {
+    trace!(?query_params);
+
+    let entries = audit_log.retrieve_all(&query_params).await?;
+    trace!("retrieved {} audit log entries", entries.len());
+
+    Ok(Json(entries))
+}
+
+#[derive(Deserialize, Validate, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryParams {
+    pub(crate) actor: Option<String>,
+    pub(crate) operation: Option<AuditOperation>,
+    pub(crate) object_type: Option<AuditObjectType>,
+    #[serde(rename = "objectID")]
+    pub(crate) object_id: Option<String>,
+    /// Only entries recorded at or after this point in time
+    pub(crate) since: Option<DateTime<Utc>>,
+    /// Only entries recorded before this point in time
+    pub(crate) until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub(crate) skip: i64,
+    #[validate(range(min = 1, max = 50))]
+    #[serde(default = "get_50")]
+    pub(crate) limit: i64,
+}
+
+fn get_50() -> i64 {
+    50
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod test {
+    use super::*;
+    use crate::{api::test::ApiTest, jwt::AuthRole};
+    use axum::{body::Body, http::StatusCode};
+    use openleadr_wire::{problem::Problem, Program};
+    use reqwest::Method;
+    use sqlx::PgPool;
+
+    #[sqlx::test(fixtures("users"))]
+    async fn records_mutations(db: PgPool) {
+        let test = ApiTest::new(
+            db.clone(),
+            vec![AuthRole::AnyBusiness, AuthRole::UserManager],
+        );
+
+        let (status, program) = test
+            .request::<Program>(
+                Method::POST,
+                "/programs",
+                Body::from(r#"{"programName":"program-audit"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, updated) = test
+            .request::<Program>(
+                Method::PUT,
+                &format!("/programs/{}", program.id),
+                Body::from(r#"{"programName":"program-audit-updated"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = test
+            .request::<Program>(
+                Method::DELETE,
+                &format!("/programs/{}", program.id),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, entries) = test
+            .request::<Vec<AuditEntry>>(
+                Method::GET,
+                &format!("/audit?objectType=PROGRAM&objectID={}", program.id),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(entries.len(), 3);
+
+        let [create, update, delete] = &entries[..] else {
+            unreachable!()
+        };
+        assert!(entries.iter().all(|entry| entry.actor == "test_admin"
+            && entry.roles == vec![AuthRole::AnyBusiness, AuthRole::UserManager]));
+
+        assert_eq!(create.operation, AuditOperation::Create);
+        assert_eq!(create.before, None);
+        assert_eq!(create.after, Some(serde_json::to_value(&program).unwrap()));
+
+        assert_eq!(update.operation, AuditOperation::Update);
+        assert_eq!(update.before, create.after);
+        assert_eq!(update.after, Some(serde_json::to_value(&updated).unwrap()));
+
+        assert_eq!(delete.operation, AuditOperation::Delete);
+        assert_eq!(delete.before, update.after);
+        assert_eq!(delete.after, None);
+
+        // failed mutations are not recorded
+        let (status, _) = test
+            .request::<Problem>(Method::DELETE, "/programs/not-existing", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, entries) = test
+            .request::<Vec<AuditEntry>>(Method::GET, "/audit?operation=DELETE", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn records_user_management(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::UserManager]);
+
+        let (status, _) = test
+            .request::<serde_json::Value>(
+                Method::POST,
+                "/users/user-1",
+                Body::from(r#"{"client_id":"new-client","client_secret":"new-client-secret"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, entries) = test
+            .request::<Vec<AuditEntry>>(
+                Method::GET,
+                "/audit?objectType=USER&objectID=user-1",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, AuditOperation::Update);
+        assert_eq!(
+            entries[0].after.as_ref().unwrap()["client_ids"],
+            serde_json::json!(["new-client", "user-1-client-id"])
+        );
+        // credentials secrets must never end up in the audit log
+        assert!(!serde_json::to_string(&entries[0])
+            .unwrap()
+            .contains("new-client-secret"));
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn filters(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::UserManager]);
+
+        let (status, entries) = test
+            .request::<Vec<AuditEntry>>(
+                Method::GET,
+                "/audit?actor=someone-else&since=2024-01-01T00:00:00Z&until=2124-01-01T00:00:00Z",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(entries.is_empty());
+
+        let (status, _) = test
+            .request::<Problem>(Method::GET, "/audit?objectType=NONSENSE", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = test
+            .request::<Problem>(Method::GET, "/audit?limit=51", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn only_user_managers(db: PgPool) {
+        let test = ApiTest::new(
+            db,
+            vec![
+                AuthRole::AnyBusiness,
+                AuthRole::VenManager,
+                AuthRole::VEN("ven-1".parse().unwrap()),
+            ],
+        );
+
+        let (status, _) = test
+            .request::<Problem>(Method::GET, "/audit", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
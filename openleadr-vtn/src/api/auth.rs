@@ -1,5 +1,10 @@
 #[cfg(feature = "internal-oauth")]
-use crate::{api::ValidatedForm, data_source::AuthSource, jwt::JwtManager};
+use crate::{
+    api::ValidatedForm,
+    data_source::{AuthInfo, AuthSource, RefreshToken, TokenSource},
+    jwt::{allowed_scopes, JwtManager},
+    rate_limit::RateLimits,
+};
 #[cfg(feature = "internal-oauth")]
 use axum::extract::State;
 #[cfg(feature = "internal-oauth")]
@@ -8,9 +13,21 @@ use axum_extra::{
     TypedHeader,
 };
 #[cfg(feature = "internal-oauth")]
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+#[cfg(feature = "internal-oauth")]
+use chrono::Utc;
+#[cfg(feature = "internal-oauth")]
+use jsonwebtoken::jwk::JwkSet;
+#[cfg(feature = "internal-oauth")]
+use openleadr_wire::oauth::Scope;
+#[cfg(feature = "internal-oauth")]
 use serde::Deserialize;
 #[cfg(feature = "internal-oauth")]
-use std::sync::Arc;
+use sha2::{Digest, Sha256};
+#[cfg(feature = "internal-oauth")]
+use std::{sync::Arc, time::Duration};
+#[cfg(feature = "internal-oauth")]
+use tracing::{error, info};
 #[cfg(feature = "internal-oauth")]
 use validator::Validate;
 
@@ -24,40 +41,65 @@ use axum::{
 use openleadr_wire::oauth::{OAuthError, OAuthErrorType};
 use reqwest::header;
 
+#[cfg(feature = "internal-oauth")]
+const ACCESS_TOKEN_EXPIRATION: Duration = Duration::from_secs(3600 * 24 * 30);
+#[cfg(feature = "internal-oauth")]
+const REFRESH_TOKEN_EXPIRATION: Duration = Duration::from_secs(3600 * 24 * 90);
+
 #[derive(Debug, Deserialize, Validate)]
 #[cfg(feature = "internal-oauth")]
 pub struct AccessTokenRequest {
     grant_type: String,
-    // TODO: handle scope
-    // scope: Option<String>,
+    scope: Option<String>,
+    /// Required for the `refresh_token` grant type
+    refresh_token: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// Request to the revocation (RFC 7009) and introspection (RFC 7662) endpoints.
+/// The optional `token_type_hint` is ignored, as the kind of token is determined by its format.
+#[derive(Debug, Deserialize, Validate)]
+#[cfg(feature = "internal-oauth")]
+pub struct TokenRequest {
+    token: String,
     client_id: Option<String>,
     client_secret: Option<String>,
 }
 
 #[derive(Debug)]
-pub struct ResponseOAuthError(pub OAuthError);
+pub enum ResponseOAuthError {
+    OAuth(OAuthError),
+    /// Rejected before the OAuth request got processed, rendered as problem.
+    /// Used if a rate limit or lockout got exceeded.
+    Problem(AppError),
+}
 
 impl IntoResponse for ResponseOAuthError {
     fn into_response(self) -> Response<axum::body::Body> {
-        match self.0.error {
+        let err = match self {
+            Self::OAuth(err) => err,
+            Self::Problem(err) => return err.into_response(),
+        };
+        match err.error {
             OAuthErrorType::InvalidClient => (
                 StatusCode::UNAUTHORIZED,
                 [(header::WWW_AUTHENTICATE, r#"Basic realm="VTN""#)],
-                Json(self.0),
+                Json(err),
             )
                 .into_response(),
             OAuthErrorType::ServerError => {
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(self.0)).into_response()
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(err)).into_response()
             }
             OAuthErrorType::OAuthNotEnabled => AppError::NotFound.into_response(),
-            _ => (StatusCode::BAD_REQUEST, Json(self.0)).into_response(),
+            _ => (StatusCode::BAD_REQUEST, Json(err)).into_response(),
         }
     }
 }
 
 impl From<jsonwebtoken::errors::Error> for ResponseOAuthError {
     fn from(_: jsonwebtoken::errors::Error) -> Self {
-        ResponseOAuthError(
+        ResponseOAuthError::OAuth(
             OAuthError::new(OAuthErrorType::ServerError)
                 .with_description("Could not issue a new token".to_string()),
         )
@@ -66,7 +108,21 @@ impl From<jsonwebtoken::errors::Error> for ResponseOAuthError {
 
 impl From<OAuthError> for ResponseOAuthError {
     fn from(err: OAuthError) -> Self {
-        ResponseOAuthError(err)
+        ResponseOAuthError::OAuth(err)
+    }
+}
+
+#[cfg(feature = "internal-oauth")]
+impl From<AppError> for ResponseOAuthError {
+    fn from(err: AppError) -> Self {
+        if let AppError::TooManyRequests(_) = err {
+            return ResponseOAuthError::Problem(err);
+        }
+        error!(%err, "OAuth request failed");
+        ResponseOAuthError::OAuth(
+            OAuthError::new(OAuthErrorType::ServerError)
+                .with_description("An internal error occurred".to_string()),
+        )
     }
 }
 
@@ -76,6 +132,8 @@ pub struct AccessTokenResponse {
     token_type: &'static str,
     expires_in: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     scope: Option<String>,
 }
 
@@ -85,34 +143,260 @@ impl IntoResponse for AccessTokenResponse {
     }
 }
 
-/// RFC 6749 client credentials grant flow
+/// RFC 6749 client credentials grant flow (section 4.4) and refreshing an access token (section 6)
 #[cfg(feature = "internal-oauth")]
 pub(crate) async fn token(
     State(auth_source): State<Arc<dyn AuthSource>>,
+    State(token_source): State<Arc<dyn TokenSource>>,
     State(jwt_manager): State<Arc<JwtManager>>,
+    State(rate_limits): State<Arc<RateLimits>>,
     authorization: Option<TypedHeader<Authorization<Basic>>>,
     ValidatedForm(request): ValidatedForm<AccessTokenRequest>,
 ) -> Result<AccessTokenResponse, ResponseOAuthError> {
-    if request.grant_type != "client_credentials" {
+    if !["client_credentials", "refresh_token"].contains(&request.grant_type.as_str()) {
         return Err(OAuthError::new(OAuthErrorType::UnsupportedGrantType)
-            .with_description("Only client_credentials grant type is supported".to_string())
+            .with_description(
+                "Only the client_credentials and refresh_token grant types are supported"
+                    .to_string(),
+            )
             .into());
     }
 
-    let auth_header = authorization
-        .as_ref()
-        .map(|TypedHeader(auth)| (auth.username(), auth.password()));
+    let client = authenticate_client(
+        auth_source.as_ref(),
+        &rate_limits,
+        authorization.as_ref().map(|TypedHeader(auth)| auth),
+        request.client_id.as_deref(),
+        request.client_secret.as_deref(),
+    )
+    .await?;
 
-    let auth_body = request
-        .client_id
-        .as_ref()
-        .map(|client_id| {
-            (
-                client_id.as_str(),
-                request.client_secret.as_deref().unwrap_or(""),
-            )
+    let permitted = if request.grant_type == "refresh_token" {
+        let refresh_token = request.refresh_token.as_deref().ok_or_else(|| {
+            OAuthError::new(OAuthErrorType::InvalidRequest)
+                .with_description("The refresh_token parameter is required".to_string())
+        })?;
+        // a refresh token can be used only once, the response contains a new one
+        let original = token_source
+            .remove_refresh_token(&hash_token(refresh_token), &client.client_id)
+            .await?
+            .ok_or_else(|| {
+                OAuthError::new(OAuthErrorType::InvalidGrant)
+                    .with_description("Invalid or expired refresh token".to_string())
+            })?;
+        // the current roles of the client may permit less than originally granted
+        allowed_scopes(&client.roles)
+            .into_iter()
+            .filter(|scope| {
+                original
+                    .scope
+                    .split_whitespace()
+                    .any(|original| original == scope.as_str())
+            })
+            .collect()
+    } else {
+        allowed_scopes(&client.roles)
+    };
+    let scopes = granted_scopes(request.scope.as_deref(), permitted)?;
+
+    // tokens must not outlive the credential
+    let now = Utc::now();
+    let lifetime = |lifetime: Duration| match client.expires {
+        Some(expires) => (expires - now).to_std().unwrap_or_default().min(lifetime),
+        None => lifetime,
+    };
+    let access_token_expiration = lifetime(ACCESS_TOKEN_EXPIRATION);
+    let refresh_token_expiration = lifetime(REFRESH_TOKEN_EXPIRATION);
+
+    let access_token = jwt_manager.create(
+        access_token_expiration,
+        client.client_id.clone(),
+        client.roles,
+        &scopes,
+    )?;
+
+    let refresh_token = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>());
+    token_source
+        .add_refresh_token(&RefreshToken {
+            token_hash: hash_token(&refresh_token),
+            client_id: client.client_id,
+            scope: Scope::join(&scopes),
+            created: now,
+            expires: now + refresh_token_expiration,
         })
-        .or_else(|| request.client_secret.as_ref().map(|cr| ("", cr.as_str())));
+        .await?;
+
+    Ok(AccessTokenResponse {
+        access_token,
+        token_type: "Bearer",
+        expires_in: access_token_expiration.as_secs(),
+        refresh_token: Some(refresh_token),
+        scope: Some(Scope::join(&scopes)),
+    })
+}
+
+/// RFC 7009 token revocation, for both access and refresh tokens.
+/// Clients may only revoke their own tokens.
+#[cfg(feature = "internal-oauth")]
+pub(crate) async fn revoke(
+    State(auth_source): State<Arc<dyn AuthSource>>,
+    State(token_source): State<Arc<dyn TokenSource>>,
+    State(jwt_manager): State<Arc<JwtManager>>,
+    State(rate_limits): State<Arc<RateLimits>>,
+    authorization: Option<TypedHeader<Authorization<Basic>>>,
+    ValidatedForm(request): ValidatedForm<TokenRequest>,
+) -> Result<StatusCode, ResponseOAuthError> {
+    let client = authenticate_client(
+        auth_source.as_ref(),
+        &rate_limits,
+        authorization.as_ref().map(|TypedHeader(auth)| auth),
+        request.client_id.as_deref(),
+        request.client_secret.as_deref(),
+    )
+    .await?;
+    let foreign_token = || {
+        ResponseOAuthError::OAuth(
+            OAuthError::new(OAuthErrorType::UnauthorizedClient)
+                .with_description("The token was not issued to this client".to_string()),
+        )
+    };
+
+    let token_hash = hash_token(&request.token);
+    if let Some(refresh_token) = token_source.get_refresh_token(&token_hash).await? {
+        if refresh_token.client_id != client.client_id {
+            return Err(foreign_token());
+        }
+        token_source
+            .remove_refresh_token(&token_hash, &client.client_id)
+            .await?;
+        info!(client_id = client.client_id, "refresh token revoked");
+        return Ok(StatusCode::OK);
+    }
+
+    match jwt_manager.decode_and_validate(&request.token).await {
+        Ok(claims) => {
+            if claims.sub != client.client_id {
+                return Err(foreign_token());
+            }
+            if let Some(jti) = &claims.jti {
+                let expires = chrono::DateTime::from_timestamp(claims.exp as i64, 0)
+                    .unwrap_or_else(|| Utc::now() + ACCESS_TOKEN_EXPIRATION);
+                token_source.revoke_access_token(jti, expires).await?;
+                info!(client_id = client.client_id, jti, "access token revoked");
+            }
+        }
+        // invalid, expired, or already revoked tokens are not an error, see RFC 7009, section 2.2
+        Err(AppError::Forbidden(_)) => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+#[cfg(feature = "internal-oauth")]
+pub struct IntrospectionResponse {
+    active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nbf: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jti: Option<String>,
+}
+
+/// RFC 7662 token introspection.
+/// Clients may introspect their own tokens, user managers the tokens of any client.
+#[cfg(feature = "internal-oauth")]
+pub(crate) async fn introspect(
+    State(auth_source): State<Arc<dyn AuthSource>>,
+    State(token_source): State<Arc<dyn TokenSource>>,
+    State(jwt_manager): State<Arc<JwtManager>>,
+    State(rate_limits): State<Arc<RateLimits>>,
+    authorization: Option<TypedHeader<Authorization<Basic>>>,
+    ValidatedForm(request): ValidatedForm<TokenRequest>,
+) -> Result<Json<IntrospectionResponse>, ResponseOAuthError> {
+    let client = authenticate_client(
+        auth_source.as_ref(),
+        &rate_limits,
+        authorization.as_ref().map(|TypedHeader(auth)| auth),
+        request.client_id.as_deref(),
+        request.client_secret.as_deref(),
+    )
+    .await?;
+    let may_introspect = |client_id: &str| {
+        client_id == client.client_id || client.roles.iter().any(|role| role.is_user_manager())
+    };
+
+    if let Some(refresh_token) = token_source
+        .get_refresh_token(&hash_token(&request.token))
+        .await?
+    {
+        if refresh_token.expires > Utc::now() && may_introspect(&refresh_token.client_id) {
+            return Ok(Json(IntrospectionResponse {
+                active: true,
+                scope: Some(refresh_token.scope),
+                client_id: Some(refresh_token.client_id.clone()),
+                exp: Some(refresh_token.expires.timestamp()),
+                iat: Some(refresh_token.created.timestamp()),
+                sub: Some(refresh_token.client_id),
+                ..Default::default()
+            }));
+        }
+        return Ok(Json(IntrospectionResponse::default()));
+    }
+
+    let claims = match jwt_manager.decode_and_validate(&request.token).await {
+        Ok(claims) if may_introspect(&claims.sub) => claims,
+        Ok(_) | Err(AppError::Forbidden(_)) => return Ok(Json(IntrospectionResponse::default())),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(Json(IntrospectionResponse {
+        active: true,
+        scope: claims.scope,
+        client_id: Some(claims.sub.clone()),
+        token_type: Some("Bearer"),
+        exp: Some(claims.exp as i64),
+        iat: Some(claims.iat as i64),
+        nbf: Some(claims.nbf as i64),
+        sub: Some(claims.sub),
+        jti: claims.jti,
+    }))
+}
+
+/// The public keys of the internal OAuth provider as JSON Web Key Set (RFC 7517),
+/// such that other services can validate the tokens it issues
+#[cfg(feature = "internal-oauth")]
+pub(crate) async fn jwks(State(jwt_manager): State<Arc<JwtManager>>) -> Json<JwkSet> {
+    Json(jwt_manager.jwk_set())
+}
+
+/// Authenticates the client via HTTP Basic authentication or the `client_id` and `client_secret`
+/// parameters in the request body, see RFC 6749, section 2.3.1
+#[cfg(feature = "internal-oauth")]
+async fn authenticate_client(
+    auth_source: &dyn AuthSource,
+    rate_limits: &RateLimits,
+    authorization: Option<&Authorization<Basic>>,
+    client_id: Option<&str>,
+    client_secret: Option<&str>,
+) -> Result<AuthInfo, ResponseOAuthError> {
+    let auth_header = authorization.map(|auth| (auth.username(), auth.password()));
+
+    let auth_body = client_id
+        .map(|client_id| (client_id, client_secret.unwrap_or("")))
+        .or_else(|| client_secret.map(|cr| ("", cr)));
 
     if auth_header.is_some() && auth_body.is_some() {
         return Err(OAuthError::new(OAuthErrorType::InvalidRequest)
@@ -129,23 +413,442 @@ pub(crate) async fn token(
             .into());
     };
 
+    rate_limits.check_auth(client_id)?;
+
     // check that the client_id and client_secret are valid
-    let Some(user) = auth_source
+    let client = auth_source
         .check_credentials(client_id, client_secret)
-        .await
-    else {
-        return Err(OAuthError::new(OAuthErrorType::InvalidClient)
+        .await;
+    rate_limits.credentials_checked(client_id, client.is_some());
+    client.ok_or_else(|| {
+        OAuthError::new(OAuthErrorType::InvalidClient)
             .with_description("Invalid client_id or client_secret".to_string())
+            .into()
+    })
+}
+
+/// Refresh and registration tokens are stored as hash only
+#[cfg(feature = "internal-oauth")]
+pub(crate) fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The requested scopes the client is permitted to, or all permitted scopes if none are requested.
+/// See RFC 6749, section 3.3
+#[cfg(feature = "internal-oauth")]
+fn granted_scopes(
+    requested: Option<&str>,
+    permitted: Vec<Scope>,
+) -> Result<Vec<Scope>, ResponseOAuthError> {
+    let Some(requested) = requested.filter(|requested| !requested.trim().is_empty()) else {
+        return Ok(permitted);
+    };
+
+    let requested = requested
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<Scope>, _>>()
+        .map_err(|err| {
+            OAuthError::new(OAuthErrorType::InvalidScope).with_description(err.to_string())
+        })?;
+
+    let granted = permitted
+        .into_iter()
+        .filter(|scope| requested.contains(scope))
+        .collect::<Vec<_>>();
+    if granted.is_empty() {
+        return Err(OAuthError::new(OAuthErrorType::InvalidScope)
+            .with_description(
+                "The client is not permitted to any of the requested scopes".to_string(),
+            )
             .into());
+    }
+
+    Ok(granted)
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+#[cfg(feature = "internal-oauth")]
+mod test {
+    use crate::{
+        api::test::state,
+        config::{Config, RateLimit},
+        data_source::PostgresStorage,
+        state::AppState,
+    };
+    use axum::{
+        body::Body,
+        http::{self, header, Request, Response, StatusCode},
+        Router,
     };
+    use http_body_util::BodyExt;
+    use openleadr_wire::problem::Problem;
+    use serde_json::Value;
+    use sqlx::PgPool;
+    use tower::ServiceExt;
 
-    let expiration = std::time::Duration::from_secs(3600 * 24 * 30);
-    let token = jwt_manager.create(expiration, user.client_id, user.roles)?;
+    async fn login(
+        app: &Router,
+        client_id: &str,
+        client_secret: &str,
+        scope: Option<&str>,
+    ) -> Response<Body> {
+        let mut form = format!(
+            "client_id={client_id}&client_secret={client_secret}&grant_type=client_credentials"
+        );
+        if let Some(scope) = scope {
+            form.push_str(&format!("&scope={scope}"));
+        }
+        post_form(app, "/auth/token", form).await
+    }
 
-    Ok(AccessTokenResponse {
-        access_token: token,
-        token_type: "Bearer",
-        expires_in: expiration.as_secs(),
-        scope: None,
-    })
+    async fn post_form(app: &Router, path: &str, form: String) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri(path)
+                    .header(
+                        header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from(form))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    async fn active(app: &Router, token: &str) -> bool {
+        let response = post_form(
+            app,
+            "/auth/introspect",
+            format!("token={token}&client_id=admin&client_secret=admin"),
+        )
+        .await;
+        json(response).await["active"].as_bool().unwrap()
+    }
+
+    async fn tokens(response: Response<Body>) -> (String, String) {
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json(response).await;
+        (
+            body["access_token"].as_str().unwrap().to_string(),
+            body["refresh_token"].as_str().unwrap().to_string(),
+        )
+    }
+
+    async fn json(response: Response<Body>) -> Value {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    async fn request(
+        app: &Router,
+        method: http::Method,
+        path: &str,
+        token: &str,
+    ) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(path)
+                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                    .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(Body::from(r#"{"programName": "scoped"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn grant_permitted_scopes(db: PgPool) {
+        let app = state(db).await.into_router();
+
+        let response = login(&app, "admin", "admin", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            json(response).await["scope"],
+            "read_all write_programs write_events write_reports write_subscriptions write_vens"
+        );
+
+        // the order of the requested scopes does not matter
+        let response = login(&app, "admin", "admin", Some("write_events+read_all")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json(response).await["scope"], "read_all write_events");
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn invalid_scope(db: PgPool) {
+        let app = state(db).await.into_router();
+
+        let response = login(&app, "admin", "admin", Some("read_all+write_everything")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error = json(response).await;
+        assert_eq!(error["error"], "invalid_scope");
+        assert_eq!(
+            error["error_description"],
+            "unknown scope: write_everything"
+        );
+
+        // user-1 does not have any roles
+        let response = login(&app, "user-1-client-id", "user-1", Some("read_all")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json(response).await["error"], "invalid_scope");
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn insufficient_scope(db: PgPool) {
+        let app = state(db).await.into_router();
+
+        let response = login(&app, "admin", "admin", Some("read_all")).await;
+        let token = json(response).await["access_token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        // passes the scope check, but the program does not exist
+        let response = request(&app, http::Method::GET, "/programs/program-1", &token).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = request(&app, http::Method::POST, "/programs", &token).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer error="insufficient_scope", scope="write_programs""#
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem.status, StatusCode::FORBIDDEN);
+
+        // event templates create events
+        let response = request(&app, http::Method::POST, "/event-templates", &token).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer error="insufficient_scope", scope="write_events""#
+        );
+
+        // routes outside the specification do not require a scope
+        let response = request(&app, http::Method::GET, "/users", &token).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn refresh_token_rotation(db: PgPool) {
+        let app = state(db).await.into_router();
+
+        let (_, refresh_token) =
+            tokens(login(&app, "admin", "admin", Some("read_all+write_events")).await).await;
+        let refresh = |refresh_token: &str, scope: &str| {
+            post_form(
+                &app,
+                "/auth/token",
+                format!("client_id=admin&client_secret=admin&grant_type=refresh_token&refresh_token={refresh_token}{scope}"),
+            )
+        };
+
+        // the scope can only be narrowed
+        let response = refresh(&refresh_token, "&scope=read_all+write_programs").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json(response).await;
+        assert_eq!(body["scope"], "read_all");
+        let new_refresh_token = body["refresh_token"].as_str().unwrap().to_string();
+        assert_ne!(new_refresh_token, refresh_token);
+
+        // refresh tokens can be used once only
+        let response = refresh(&refresh_token, "").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json(response).await["error"], "invalid_grant");
+
+        // and only by the client they were issued to
+        let response = post_form(
+            &app,
+            "/auth/token",
+            format!("client_id=user-1-client-id&client_secret=user-1&grant_type=refresh_token&refresh_token={new_refresh_token}"),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json(response).await["error"], "invalid_grant");
+
+        // which can still use it after the attempt of the other client
+        let response = refresh(&new_refresh_token, "").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = post_form(
+            &app,
+            "/auth/token",
+            "grant_type=refresh_token&refresh_token=abc".to_string(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(json(response).await["error"], "invalid_client");
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn revoke(db: PgPool) {
+        let app = state(db).await.into_router();
+
+        let (access_token, refresh_token) = tokens(login(&app, "admin", "admin", None).await).await;
+        let revoke = |token: &str, client_id: &str, client_secret: &str| {
+            post_form(
+                &app,
+                "/auth/revoke",
+                format!("token={token}&client_id={client_id}&client_secret={client_secret}"),
+            )
+        };
+
+        let response = revoke(&access_token, "user-1-client-id", "user-1").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json(response).await["error"], "unauthorized_client");
+        let response = request(&app, http::Method::GET, "/users", &access_token).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = revoke(&access_token, "admin", "admin").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = request(&app, http::Method::GET, "/users", &access_token).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // revoking twice, or revoking an unknown token, is not an error
+        let response = revoke(&access_token, "admin", "admin").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = revoke("not-a-token", "admin", "admin").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = revoke(&refresh_token, "admin", "admin").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = post_form(
+            &app,
+            "/auth/token",
+            format!("client_id=admin&client_secret=admin&grant_type=refresh_token&refresh_token={refresh_token}"),
+        )
+        .await;
+        assert_eq!(json(response).await["error"], "invalid_grant");
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn introspect(db: PgPool) {
+        let app = state(db).await.into_router();
+
+        let (access_token, refresh_token) =
+            tokens(login(&app, "admin", "admin", Some("read_all")).await).await;
+        let introspect = |token: &str, client_id: &str, client_secret: &str| {
+            post_form(
+                &app,
+                "/auth/introspect",
+                format!("token={token}&client_id={client_id}&client_secret={client_secret}"),
+            )
+        };
+
+        let response = introspect(&access_token, "admin", "admin").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json(response).await;
+        assert_eq!(body["active"], true);
+        assert_eq!(body["scope"], "read_all");
+        assert_eq!(body["sub"], "admin");
+        assert_eq!(body["token_type"], "Bearer");
+        assert!(body["jti"].is_string());
+
+        let body = json(introspect(&refresh_token, "admin", "admin").await).await;
+        assert_eq!(body["active"], true);
+        assert_eq!(body["client_id"], "admin");
+
+        // other clients do not learn anything about the token
+        let body = json(introspect(&access_token, "user-1-client-id", "user-1").await).await;
+        assert_eq!(body, serde_json::json!({"active": false}));
+
+        let body = json(introspect("not-a-token", "admin", "admin").await).await;
+        assert_eq!(body, serde_json::json!({"active": false}));
+
+        let response = introspect(&access_token, "admin", "wrong").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn removing_credentials_revokes_tokens(db: PgPool) {
+        let app = state(db).await.into_router();
+
+        let (admin_token, _) = tokens(login(&app, "admin", "admin", None).await).await;
+        let (access_token, refresh_token) =
+            tokens(login(&app, "user-1-client-id", "user-1", None).await).await;
+        assert!(active(&app, &access_token).await);
+        assert!(active(&app, &refresh_token).await);
+
+        let response = request(
+            &app,
+            http::Method::DELETE,
+            "/users/user-1/user-1-client-id",
+            &admin_token,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert!(!active(&app, &access_token).await);
+        assert!(!active(&app, &refresh_token).await);
+        // tokens of other clients remain valid
+        assert!(active(&app, &admin_token).await);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn lockout(db: PgPool) {
+        let app = state(db).await.into_router();
+
+        for _ in 0..5 {
+            let response = login(&app, "admin", "guessed", None).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        // locked out, even with the correct secret
+        let response = login(&app, "admin", "admin", None).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "300");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem.status, StatusCode::TOO_MANY_REQUESTS);
+
+        let response = post_form(
+            &app,
+            "/auth/introspect",
+            "token=abc&client_id=admin&client_secret=admin".to_string(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // other clients are not affected
+        let response = login(&app, "user-1-client-id", "user-1", Some("read_all")).await;
+        assert_eq!(json(response).await["error"], "invalid_scope");
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn rate_limit(db: PgPool) {
+        let mut config = Config::default();
+        config.apply_env().unwrap();
+        config.rate_limit.auth = Some(RateLimit {
+            requests_per_minute: 6,
+            burst: 2,
+        });
+        let app = AppState::from_config(PostgresStorage::new(db).unwrap(), &config)
+            .unwrap()
+            .into_router();
+
+        for _ in 0..2 {
+            let response = login(&app, "admin", "admin", None).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = login(&app, "admin", "admin", None).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        // one request per 10 seconds, part of which passed during the previous logins
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=10).contains(&retry_after));
+    }
 }
@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tracing::{info, trace};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    api::{AppResponse, ValidatedJson},
+    data_source::{Business, BusinessSource},
+    error::AppError,
+    jwt::{BusinessIds, Claims, User, UserManagerUser},
+};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct NewBusiness {
+    /// Generated by the VTN if missing
+    #[validate(length(min = 1, max = 128))]
+    id: Option<String>,
+    #[serde(flatten)]
+    #[validate(nested)]
+    content: BusinessContent,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct BusinessContent {
+    #[validate(length(min = 1, max = 128))]
+    name: String,
+    description: Option<String>,
+}
+
+/// The businesses the user may see, `None` meaning all of them.
+/// Business users only see their own businesses, such that tenants do not learn about each other.
+fn visible_businesses(user: &Claims) -> Result<Option<Vec<String>>, AppError> {
+    if user.is_user_manager() {
+        return Ok(None);
+    }
+
+    match user.business_ids() {
+        BusinessIds::Any => Ok(None),
+        BusinessIds::Specific(ids) if !ids.is_empty() => Ok(Some(ids)),
+        BusinessIds::Specific(_) => {
+            Err(AppError::Forbidden("User does not have the required role"))
+        }
+    }
+}
+
+pub async fn get_all(
+    State(business_source): State<Arc<dyn BusinessSource>>,
+    User(user): User,
+) -> AppResponse<Vec<Business>> {
+    let ids = visible_businesses(&user)?;
+    let businesses = business_source.get_all_businesses(ids.as_deref()).await?;
+
+    trace!("received {} businesses", businesses.len());
+    Ok(Json(businesses))
+}
+
+pub async fn get(
+    State(business_source): State<Arc<dyn BusinessSource>>,
+    Path(id): Path<String>,
+    User(user): User,
+) -> AppResponse<Business> {
+    if visible_businesses(&user)?.is_some_and(|ids| !ids.contains(&id)) {
+        return Err(AppError::NotFound);
+    }
+
+    let business = business_source.get_business(&id).await?;
+    trace!(business_id = business.id(), "received business");
+    Ok(Json(business))
+}
+
+pub async fn add(
+    State(business_source): State<Arc<dyn BusinessSource>>,
+    UserManagerUser(manager): UserManagerUser,
+    ValidatedJson(new): ValidatedJson<NewBusiness>,
+) -> Result<(StatusCode, Json<Business>), AppError> {
+    let id = new.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let business = business_source
+        .add_business(
+            &id,
+            &new.content.name,
+            new.content.description.as_deref(),
+            &User(manager),
+        )
+        .await?;
+
+    info!(business_id = business.id(), "created new business");
+    Ok((StatusCode::CREATED, Json(business)))
+}
+
+pub async fn edit(
+    State(business_source): State<Arc<dyn BusinessSource>>,
+    Path(id): Path<String>,
+    UserManagerUser(manager): UserManagerUser,
+    ValidatedJson(content): ValidatedJson<BusinessContent>,
+) -> AppResponse<Business> {
+    let business = business_source
+        .edit_business(
+            &id,
+            &content.name,
+            content.description.as_deref(),
+            &User(manager),
+        )
+        .await?;
+
+    info!(business_id = business.id(), "updated business");
+    Ok(Json(business))
+}
+
+pub async fn delete(
+    State(business_source): State<Arc<dyn BusinessSource>>,
+    Path(id): Path<String>,
+    UserManagerUser(manager): UserManagerUser,
+) -> AppResponse<Business> {
+    let business = business_source.remove_business(&id, &User(manager)).await?;
+
+    info!(business_id = business.id(), "deleted business");
+    Ok(Json(business))
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod test {
+    use super::*;
+    use crate::{api::test::ApiTest, jwt::AuthRole};
+    use axum::body::Body;
+    use openleadr_wire::problem::Problem;
+    use reqwest::Method;
+    use sqlx::PgPool;
+
+    #[sqlx::test(fixtures("users", "programs", "business"))]
+    async fn crud(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::UserManager]);
+
+        let (status, business) = test
+            .request::<Business>(
+                Method::POST,
+                "/businesses",
+                Body::from(r#"{"id":"business-2","name":"Retailer 2"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(business.id, "business-2");
+        assert_eq!(business.description, None);
+
+        let (status, generated) = test
+            .request::<Business>(
+                Method::POST,
+                "/businesses",
+                Body::from(r#"{"name":"Retailer 3","description":"generated id"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(Uuid::parse_str(&generated.id).is_ok());
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/businesses",
+                Body::from(r#"{"id":"business-2","name":"Duplicate"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, _) = test
+            .request::<Problem>(Method::POST, "/businesses", Body::from(r#"{"name":""}"#))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, edited) = test
+            .request::<Business>(
+                Method::PUT,
+                "/businesses/business-2",
+                Body::from(r#"{"name":"Retailer 2","description":"edited"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(edited.description.as_deref(), Some("edited"));
+        assert_eq!(edited.created, business.created);
+
+        let (status, businesses) = test
+            .request::<Vec<Business>>(Method::GET, "/businesses", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(businesses.len(), 3);
+
+        let (status, deleted) = test
+            .request::<Business>(Method::DELETE, "/businesses/business-2", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(deleted, edited);
+
+        let (status, _) = test
+            .request::<Problem>(Method::GET, "/businesses/business-2", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // business-1 owns program-3
+        let (status, _) = test
+            .request::<Problem>(Method::DELETE, "/businesses/business-1", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business"))]
+    async fn business_users_see_own_businesses_only(db: PgPool) {
+        let manager = ApiTest::new(db.clone(), vec![AuthRole::UserManager]);
+        let (status, _) = manager
+            .request::<Business>(
+                Method::POST,
+                "/businesses",
+                Body::from(r#"{"id":"business-2","name":"Retailer 2"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let test = ApiTest::new(db.clone(), vec![AuthRole::Business("business-1".into())]);
+        let (status, businesses) = test
+            .request::<Vec<Business>>(Method::GET, "/businesses", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(businesses.len(), 1);
+        assert_eq!(businesses[0].id, "business-1");
+
+        let (status, _) = test
+            .request::<Business>(Method::GET, "/businesses/business-1", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = test
+            .request::<Problem>(Method::GET, "/businesses/business-2", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let test = ApiTest::new(db.clone(), vec![AuthRole::AnyBusiness]);
+        let (status, businesses) = test
+            .request::<Vec<Business>>(Method::GET, "/businesses", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(businesses.len(), 2);
+
+        let test = ApiTest::new(db, vec![AuthRole::VenManager]);
+        let (status, _) = test
+            .request::<Problem>(Method::GET, "/businesses", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business"))]
+    async fn only_user_managers_manage_businesses(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/businesses",
+                Body::from(r#"{"name":"Retailer 2"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::PUT,
+                "/businesses/business-1",
+                Body::from(r#"{"name":"Renamed"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = test
+            .request::<Problem>(Method::DELETE, "/businesses/business-1", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,321 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use reqwest::StatusCode;
+use tracing::{info, trace};
+
+use openleadr_wire::{
+    enrollment::{Enrollment, NewEnrollment},
+    program::ProgramId,
+    ven::VenId,
+};
+
+use crate::{
+    api::{AppResponse, ValidatedJson},
+    data_source::EnrollmentSource,
+    error::AppError,
+    jwt::{BusinessUser, User},
+};
+
+pub async fn get_by_program(
+    State(enrollment_source): State<Arc<dyn EnrollmentSource>>,
+    Path(program_id): Path<ProgramId>,
+    user: User,
+) -> AppResponse<Vec<Enrollment>> {
+    let enrollments = enrollment_source
+        .retrieve_by_program(&program_id, &user)
+        .await?;
+
+    trace!(%program_id, "retrieved {} enrollments", enrollments.len());
+
+    Ok(Json(enrollments))
+}
+
+pub async fn get_by_ven(
+    State(enrollment_source): State<Arc<dyn EnrollmentSource>>,
+    Path(ven_id): Path<VenId>,
+    user: User,
+) -> AppResponse<Vec<Enrollment>> {
+    let enrollments = enrollment_source.retrieve_by_ven(&ven_id, &user).await?;
+
+    trace!(%ven_id, "retrieved {} enrollments", enrollments.len());
+
+    Ok(Json(enrollments))
+}
+
+pub async fn add(
+    State(enrollment_source): State<Arc<dyn EnrollmentSource>>,
+    Path(program_id): Path<ProgramId>,
+    BusinessUser(user): BusinessUser,
+    ValidatedJson(new): ValidatedJson<NewEnrollment>,
+) -> Result<(StatusCode, Json<Enrollment>), AppError> {
+    let enrollment = enrollment_source
+        .enroll(&program_id, &new.ven_id, &User(user))
+        .await?;
+
+    info!(%program_id, %enrollment.ven_id, "VEN enrolled");
+
+    Ok((StatusCode::CREATED, Json(enrollment)))
+}
+
+pub async fn add_matching(
+    State(enrollment_source): State<Arc<dyn EnrollmentSource>>,
+    Path(program_id): Path<ProgramId>,
+    BusinessUser(user): BusinessUser,
+) -> AppResponse<Vec<Enrollment>> {
+    let enrollments = enrollment_source
+        .enroll_matching(&program_id, &User(user))
+        .await?;
+
+    info!(%program_id, "enrolled {} matching VENs", enrollments.len());
+
+    Ok(Json(enrollments))
+}
+
+pub async fn delete(
+    State(enrollment_source): State<Arc<dyn EnrollmentSource>>,
+    Path((program_id, ven_id)): Path<(ProgramId, VenId)>,
+    BusinessUser(user): BusinessUser,
+) -> AppResponse<Enrollment> {
+    let enrollment = enrollment_source
+        .unenroll(&program_id, &ven_id, &User(user))
+        .await?;
+
+    info!(%program_id, %ven_id, "VEN unenrolled");
+
+    Ok(Json(enrollment))
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod test {
+    use super::*;
+    use crate::{api::test::ApiTest, jwt::AuthRole};
+    use axum::body::Body;
+    use openleadr_wire::{problem::Problem, Program};
+    use reqwest::Method;
+    use sqlx::PgPool;
+
+    fn enroll_body(ven_id: &str) -> Body {
+        Body::from(format!(r#"{{"venID":"{ven_id}"}}"#))
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business", "vens", "vens-programs"))]
+    async fn enroll_and_unenroll(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::AnyBusiness]);
+
+        let (status, enrollments) = test
+            .request::<Vec<Enrollment>>(Method::GET, "/programs/program-1/vens", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(enrollments.len(), 2);
+
+        let (status, enrollment) = test
+            .request::<Enrollment>(
+                Method::POST,
+                "/programs/program-2/vens",
+                enroll_body("ven-1"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(enrollment.program_id.as_str(), "program-2");
+        assert_eq!(enrollment.ven_name, "ven-1-name");
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/programs/program-2/vens",
+                enroll_body("ven-1"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        // the VEN now sees the program
+        let ven = ApiTest::new(db.clone(), vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+        let (status, _) = ven
+            .request::<Program>(Method::GET, "/programs/program-2", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, enrollments) = ven
+            .request::<Vec<Enrollment>>(Method::GET, "/vens/ven-1/programs", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(enrollments.len(), 3);
+
+        let (status, deleted) = test
+            .request::<Enrollment>(
+                Method::DELETE,
+                "/programs/program-2/vens/ven-1",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(deleted, enrollment);
+
+        let (status, _) = ven
+            .request::<Problem>(Method::GET, "/programs/program-2", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::DELETE,
+                "/programs/program-2/vens/ven-1",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business", "vens", "vens-programs"))]
+    async fn unenroll_last_ven(db: PgPool) {
+        // only ven-1 is enrolled in program-3
+        let other_ven = ApiTest::new(db.clone(), vec![AuthRole::VEN("ven-2".parse().unwrap())]);
+        let (status, _) = other_ven
+            .request::<Problem>(Method::GET, "/programs/program-3", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let test = ApiTest::new(db.clone(), vec![AuthRole::AnyBusiness]);
+        let (status, _) = test
+            .request::<Enrollment>(
+                Method::DELETE,
+                "/programs/program-3/vens/ven-1",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // without enrollments, the program is visible to all VENs
+        let (status, _) = other_ven
+            .request::<Program>(Method::GET, "/programs/program-3", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business", "vens", "vens-programs"))]
+    async fn permissions(db: PgPool) {
+        // program-3 belongs to business-1
+        let other_business =
+            ApiTest::new(db.clone(), vec![AuthRole::Business("business-2".into())]);
+        let (status, _) = other_business
+            .request::<Problem>(Method::GET, "/programs/program-3/vens", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = other_business
+            .request::<Problem>(
+                Method::POST,
+                "/programs/program-3/vens",
+                enroll_body("ven-2"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, enrollments) = other_business
+            .request::<Vec<Enrollment>>(Method::GET, "/vens/ven-1/programs", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(enrollments.is_empty());
+
+        let business = ApiTest::new(db.clone(), vec![AuthRole::Business("business-1".into())]);
+        let (status, _) = business
+            .request::<Enrollment>(
+                Method::POST,
+                "/programs/program-3/vens",
+                enroll_body("ven-2"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, enrollments) = business
+            .request::<Vec<Enrollment>>(Method::GET, "/vens/ven-1/programs", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(enrollments.len(), 1);
+
+        // VENs neither manage enrollments nor see the other VENs of a program
+        let ven = ApiTest::new(db.clone(), vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+        let (status, _) = ven
+            .request::<Problem>(Method::GET, "/programs/program-1/vens", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = ven
+            .request::<Problem>(
+                Method::DELETE,
+                "/programs/program-1/vens/ven-1",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, enrollments) = ven
+            .request::<Vec<Enrollment>>(Method::GET, "/vens/ven-2/programs", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(enrollments.is_empty());
+
+        let ven_manager = ApiTest::new(db, vec![AuthRole::VenManager]);
+        let (status, enrollments) = ven_manager
+            .request::<Vec<Enrollment>>(Method::GET, "/programs/program-1/vens", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(enrollments.len(), 2);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "vens"))]
+    async fn enroll_matching(db: PgPool) {
+        sqlx::query!(
+            r#"
+            UPDATE program
+            SET targets = '[{"type": "GROUP", "values": ["group-1"]}, {"type": "SERVICE_AREA", "values": ["area-1"]}]'
+            WHERE id = 'program-2'
+            "#
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            UPDATE ven
+            SET targets = '[{"type": "SERVICE_AREA", "values": ["area-1"]}, {"type": "GROUP", "values": ["group-1"]}]'
+            WHERE id = 'ven-2'
+            "#
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+
+        let (status, enrollments) = test
+            .request::<Vec<Enrollment>>(
+                Method::POST,
+                "/programs/program-2/vens/match",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(enrollments.len(), 1);
+        assert_eq!(enrollments[0].ven_id.as_str(), "ven-2");
+
+        // already enrolled VENs are skipped
+        let (status, enrollments) = test
+            .request::<Vec<Enrollment>>(
+                Method::POST,
+                "/programs/program-2/vens/match",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(enrollments.is_empty());
+
+        // ven-1 matches the group, but not the service area
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/programs/program-3/vens/match",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -5,19 +5,21 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use tracing::{info, trace};
 use validator::{Validate, ValidationError};
 
 use openleadr_wire::{
-    event::{EventContent, EventId},
+    batch::BatchUpdate,
+    event::{EventContent, EventId, EventState},
     program::ProgramId,
     target::TargetType,
     Event,
 };
 
 use crate::{
-    api::{AppResponse, ValidatedJson, ValidatedQuery},
+    api::{AppResponse, ValidatedBatchJson, ValidatedJson, ValidatedQuery},
     data_source::EventCrud,
     error::AppError,
     jwt::{BusinessUser, User},
@@ -39,14 +41,29 @@ pub async fn get_all(
 pub async fn get(
     State(event_source): State<Arc<dyn EventCrud>>,
     Path(id): Path<EventId>,
+    ValidatedQuery(query_params): ValidatedQuery<AsOfQueryParams>,
     user: User,
 ) -> AppResponse<Event> {
-    let event = event_source.retrieve(&id, &user).await?;
-    trace!(%event.id, event.event_name=event.content.event_name, "retrieved event");
+    let event = match query_params.as_of {
+        Some(as_of) => event_source.retrieve_as_of(&id, as_of, &user).await?,
+        None => event_source.retrieve(&id, &user).await?,
+    };
+    trace!(%event.id, event.event_name=event.content.event_name, as_of=?query_params.as_of, "retrieved event");
 
     Ok(Json(event))
 }
 
+pub async fn get_versions(
+    State(event_source): State<Arc<dyn EventCrud>>,
+    Path(id): Path<EventId>,
+    user: User,
+) -> AppResponse<Vec<Event>> {
+    let versions = event_source.retrieve_versions(&id, &user).await?;
+    trace!(%id, "retrieved {} versions of event", versions.len());
+
+    Ok(Json(versions))
+}
+
 pub async fn add(
     State(event_source): State<Arc<dyn EventCrud>>,
     BusinessUser(user): BusinessUser,
@@ -72,6 +89,30 @@ pub async fn edit(
     Ok(Json(event))
 }
 
+pub async fn add_batch(
+    State(event_source): State<Arc<dyn EventCrud>>,
+    BusinessUser(user): BusinessUser,
+    ValidatedBatchJson(new_events): ValidatedBatchJson<EventContent>,
+) -> Result<(StatusCode, Json<Vec<Event>>), AppError> {
+    let events = event_source.create_batch(new_events, &User(user)).await?;
+
+    info!(count = events.len(), "events created in batch");
+
+    Ok((StatusCode::CREATED, Json(events)))
+}
+
+pub async fn edit_batch(
+    State(event_source): State<Arc<dyn EventCrud>>,
+    BusinessUser(user): BusinessUser,
+    ValidatedBatchJson(updates): ValidatedBatchJson<BatchUpdate<EventId, EventContent>>,
+) -> AppResponse<Vec<Event>> {
+    let events = event_source.update_batch(updates, &User(user)).await?;
+
+    info!(count = events.len(), "events updated in batch");
+
+    Ok(Json(events))
+}
+
 pub async fn delete(
     State(event_source): State<Arc<dyn EventCrud>>,
     Path(id): Path<EventId>,
@@ -82,8 +123,32 @@ pub async fn delete(
     Ok(Json(event))
 }
 
+pub async fn cancel(
+    State(event_source): State<Arc<dyn EventCrud>>,
+    Path(id): Path<EventId>,
+    BusinessUser(user): BusinessUser,
+    ValidatedJson(cancellation): ValidatedJson<Cancellation>,
+) -> AppResponse<Event> {
+    let event = event_source
+        .cancel(&id, cancellation.superseded_by.as_ref(), &User(user))
+        .await?;
+
+    info!(%event.id, superseded_by=?event.superseded_by, "cancelled event");
+
+    Ok(Json(event))
+}
+
+#[derive(Deserialize, Validate, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Cancellation {
+    /// The event replacing the cancelled one
+    #[serde(default)]
+    superseded_by: Option<EventId>,
+}
+
 #[derive(Deserialize, Validate, Debug)]
 #[validate(schema(function = "validate_target_type_value_pair"))]
+#[validate(schema(function = "validate_state_not_as_of"))]
 #[serde(rename_all = "camelCase")]
 pub struct QueryParams {
     #[serde(rename = "programID")]
@@ -97,6 +162,18 @@ pub struct QueryParams {
     #[validate(range(min = 1, max = 50))]
     #[serde(default = "get_50")]
     pub(crate) limit: i64,
+    /// Retrieve the events as they were stored at this point in time
+    #[serde(rename = "asOf")]
+    pub(crate) as_of: Option<DateTime<Utc>>,
+    /// Only retrieve the events currently in this lifecycle state
+    pub(crate) state: Option<EventState>,
+}
+
+#[derive(Deserialize, Validate, Debug)]
+pub struct AsOfQueryParams {
+    /// Retrieve the program or event as it was stored at this point in time
+    #[serde(rename = "asOf")]
+    pub(crate) as_of: Option<DateTime<Utc>>,
 }
 
 fn validate_target_type_value_pair(query: &QueryParams) -> Result<(), ValidationError> {
@@ -107,6 +184,16 @@ fn validate_target_type_value_pair(query: &QueryParams) -> Result<(), Validation
     }
 }
 
+fn validate_state_not_as_of(query: &QueryParams) -> Result<(), ValidationError> {
+    if query.state.is_some() && query.as_of.is_some() {
+        Err(ValidationError::new(
+            "The state query parameter cannot be combined with asOf.",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 fn get_50() -> i64 {
     50
 }
@@ -129,7 +216,9 @@ mod test {
     };
     use http_body_util::BodyExt;
     use openleadr_wire::{
-        event::{EventInterval, EventPayloadDescriptor, EventType, EventValuesMap, Priority},
+        event::{
+            EventInterval, EventPayloadDescriptor, EventState, EventType, EventValuesMap, Priority,
+        },
         problem::Problem,
         target::{TargetEntry, TargetMap},
         values_map::Value,
@@ -594,6 +683,350 @@ mod test {
         }
     }
 
+    #[sqlx::test(fixtures("programs"))]
+    async fn batch_create_update(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+
+        let new_events = vec![
+            EventContent {
+                event_name: Some("event1".to_string()),
+                ..default_event_content()
+            },
+            EventContent {
+                program_id: ProgramId::new("program-2").unwrap(),
+                event_name: Some("event2".to_string()),
+                ..default_event_content()
+            },
+        ];
+
+        let (status, events) = test
+            .request::<Vec<Event>>(
+                Method::POST,
+                "/events/batch",
+                Body::from(serde_json::to_vec(&new_events).unwrap()),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].content, new_events[0]);
+        assert_eq!(events[1].content, new_events[1]);
+
+        let updates = events
+            .iter()
+            .map(|event| {
+                BatchUpdate::new(
+                    event.id.clone(),
+                    EventContent {
+                        priority: Priority::MIN,
+                        ..event.content.clone()
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let (status, updated) = test
+            .request::<Vec<Event>>(
+                Method::PUT,
+                "/events/batch",
+                Body::from(serde_json::to_vec(&updates).unwrap()),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        for (event, update) in updated.iter().zip(&updates) {
+            assert_eq!(event.id, update.id);
+            assert_eq!(event.content, update.content);
+        }
+
+        let (status, event) = test
+            .request::<Event>(
+                Method::GET,
+                &format!("/events/{}", events[1].id),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(event.content.priority, Priority::MIN);
+    }
+
+    #[sqlx::test(fixtures("programs"))]
+    async fn batch_is_atomic(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+
+        let (_, event) = test
+            .request::<Event>(
+                Method::POST,
+                "/events",
+                Body::from(serde_json::to_vec(&default_event_content()).unwrap()),
+            )
+            .await;
+
+        let new_events = vec![
+            default_event_content(),
+            EventContent {
+                program_id: ProgramId::new("not-existing").unwrap(),
+                ..default_event_content()
+            },
+        ];
+
+        let (status, problems) = test
+            .request::<Vec<Problem>>(
+                Method::POST,
+                "/events/batch",
+                Body::from(serde_json::to_vec(&new_events).unwrap()),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].status, StatusCode::FAILED_DEPENDENCY);
+        assert_eq!(problems[1].status, StatusCode::BAD_REQUEST);
+
+        let updates = vec![
+            BatchUpdate::new(
+                event.id.clone(),
+                EventContent {
+                    priority: Priority::MIN,
+                    ..default_event_content()
+                },
+            ),
+            BatchUpdate::new(
+                "not-existing".parse::<EventId>().unwrap(),
+                default_event_content(),
+            ),
+        ];
+
+        let (status, problems) = test
+            .request::<Vec<Problem>>(
+                Method::PUT,
+                "/events/batch",
+                Body::from(serde_json::to_vec(&updates).unwrap()),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(problems[0].status, StatusCode::FAILED_DEPENDENCY);
+        assert_eq!(problems[1].status, StatusCode::NOT_FOUND);
+
+        // the valid update got rolled back as well
+        let (status, db_event) = test
+            .request::<Event>(Method::GET, &format!("/events/{}", event.id), Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(db_event, event);
+
+        // validation errors are reported per item, too
+        let invalid = vec![
+            default_event_content(),
+            EventContent {
+                intervals: vec![],
+                ..default_event_content()
+            },
+        ];
+        let (status, problems) = test
+            .request::<Vec<Problem>>(
+                Method::POST,
+                "/events/batch",
+                Body::from(serde_json::to_vec(&invalid).unwrap()),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(problems[0].status, StatusCode::FAILED_DEPENDENCY);
+        assert_eq!(problems[1].status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = test
+            .request::<Problem>(Method::POST, "/events/batch", Body::from("[]"))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(fixtures("programs"))]
+    async fn versions(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+        let as_of = |time: DateTime<Utc>| time.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+        // past versions have no lifecycle state
+        let version = |event: &Event| Event {
+            state: None,
+            ..event.clone()
+        };
+
+        let (status, created) = test
+            .request::<Event>(
+                Method::POST,
+                "/events",
+                Body::from(serde_json::to_vec(&default_event_content()).unwrap()),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, updated) = test
+            .request::<Event>(
+                Method::PUT,
+                &format!("/events/{}", created.id),
+                Body::from(
+                    serde_json::to_vec(&EventContent {
+                        priority: Priority::MIN,
+                        ..default_event_content()
+                    })
+                    .unwrap(),
+                ),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, versions) = test
+            .request::<Vec<Event>>(
+                Method::GET,
+                &format!("/events/{}/versions", created.id),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(versions, vec![version(&created), version(&updated)]);
+
+        let (status, event) = test
+            .request::<Event>(
+                Method::GET,
+                &format!(
+                    "/events/{}?asOf={}",
+                    created.id,
+                    as_of(created.modification_date_time)
+                ),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(event, version(&created));
+
+        let (status, events) = test
+            .request::<Vec<Event>>(
+                Method::GET,
+                &format!(
+                    "/events?programID=program-1&asOf={}",
+                    as_of(created.modification_date_time)
+                ),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(events, vec![version(&created)]);
+
+        let (status, _) = test
+            .request::<Event>(
+                Method::DELETE,
+                &format!("/events/{}", created.id),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // deleted events can still be retrieved as of a point in time before the deletion
+        let (status, event) = test
+            .request::<Event>(
+                Method::GET,
+                &format!(
+                    "/events/{}?asOf={}",
+                    created.id,
+                    as_of(updated.modification_date_time)
+                ),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(event, version(&updated));
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::GET,
+                &format!("/events/{}?asOf={}", created.id, as_of(Utc::now())),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::GET,
+                &format!(
+                    "/events/{}?asOf={}",
+                    created.id,
+                    as_of(created.created_date_time - chrono::Duration::seconds(1))
+                ),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = test
+            .request::<Problem>(Method::GET, "/events/not-existing/versions", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("programs"))]
+    async fn cancel(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+
+        let (status, events) = test
+            .request::<Vec<Event>>(
+                Method::POST,
+                "/events/batch",
+                Body::from(
+                    serde_json::to_vec(&vec![default_event_content(), default_event_content()])
+                        .unwrap(),
+                ),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(events[0].state, Some(EventState::Active));
+
+        let (status, cancelled) = test
+            .request::<Event>(
+                Method::POST,
+                &format!("/events/{}/cancel", events[0].id),
+                Body::from(format!(r#"{{"supersededBy": "{}"}}"#, events[1].id)),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cancelled.state, Some(EventState::Superseded));
+        assert_eq!(cancelled.superseded_by, Some(events[1].id.clone()));
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                &format!("/events/{}/cancel", events[0].id),
+                Body::from("{}"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, event) = test
+            .request::<Event>(
+                Method::GET,
+                &format!("/events/{}", events[0].id),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(event, cancelled);
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::GET,
+                "/events?state=CANCELLED&asOf=2024-01-01T00:00:00Z",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "events", "vens"))]
+    async fn ven_cannot_cancel(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+
+        let (status, _) = test
+            .request::<Problem>(Method::POST, "/events/event-1/cancel", Body::from("{}"))
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
     mod permissions {
         use super::*;
 
@@ -613,7 +1046,7 @@ mod test {
 
             let token = jwt_test_token(&state, vec![AuthRole::Business("business-2".to_string())]);
             let response = help_create_event(&mut app, &content, &token).await;
-            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
             let token = jwt_test_token(
                 &state,
@@ -638,7 +1071,7 @@ mod test {
                 )
                 .await
                 .unwrap();
-            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
             let token = jwt_test_token(&state, vec![AuthRole::Business("business-2".to_string())]);
             let response = app
@@ -654,7 +1087,7 @@ mod test {
                 )
                 .await
                 .unwrap();
-            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
             let token = jwt_test_token(&state, vec![AuthRole::Business("business-1".to_string())]);
             let response = app
@@ -686,6 +1119,19 @@ mod test {
                 .await
                 .unwrap();
             assert_eq!(response.status(), StatusCode::OK);
+
+            // programs of no business are reserved to users of any business
+            let content = EventContent {
+                program_id: "program-1".parse().unwrap(),
+                ..default_event_content()
+            };
+            let token = jwt_test_token(&state, vec![AuthRole::Business("business-1".to_string())]);
+            let response = help_create_event(&mut app, &content, &token).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            let token = jwt_test_token(&state, vec![AuthRole::AnyBusiness]);
+            let response = help_create_event(&mut app, &content, &token).await;
+            assert_eq!(response.status(), StatusCode::CREATED);
         }
 
         #[sqlx::test(fixtures("users", "programs", "business", "events"))]
@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tracing::{info, trace};
+use validator::Validate;
+
+use openleadr_wire::{
+    event_template::{EventTemplate, EventTemplateContent, EventTemplateId},
+    program::ProgramId,
+};
+
+use crate::{
+    api::{AppResponse, ValidatedJson, ValidatedQuery},
+    config::EventTemplatesConfig,
+    data_source::EventTemplateSource,
+    error::AppError,
+    jwt::{BusinessUser, User},
+};
+
+#[derive(Deserialize, Validate, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryParams {
+    #[serde(rename = "programID")]
+    program_id: Option<ProgramId>,
+}
+
+/// The events of the occurrences until then are created right away
+fn materialize_until(config: &EventTemplatesConfig) -> DateTime<Utc> {
+    Utc::now() + config.lookahead()
+}
+
+pub async fn get_all(
+    State(template_source): State<Arc<dyn EventTemplateSource>>,
+    ValidatedQuery(query_params): ValidatedQuery<QueryParams>,
+    BusinessUser(user): BusinessUser,
+) -> AppResponse<Vec<EventTemplate>> {
+    let templates = template_source
+        .retrieve_all(query_params.program_id.as_ref(), &User(user))
+        .await?;
+    trace!("retrieved {} event templates", templates.len());
+
+    Ok(Json(templates))
+}
+
+pub async fn get(
+    State(template_source): State<Arc<dyn EventTemplateSource>>,
+    Path(id): Path<EventTemplateId>,
+    BusinessUser(user): BusinessUser,
+) -> AppResponse<EventTemplate> {
+    let template = template_source.retrieve(&id, &User(user)).await?;
+    trace!(%template.id, template.template_name=template.content.template_name, "retrieved event template");
+
+    Ok(Json(template))
+}
+
+pub async fn add(
+    State(template_source): State<Arc<dyn EventTemplateSource>>,
+    State(config): State<EventTemplatesConfig>,
+    BusinessUser(user): BusinessUser,
+    ValidatedJson(new): ValidatedJson<EventTemplateContent>,
+) -> Result<(StatusCode, Json<EventTemplate>), AppError> {
+    let template = template_source
+        .create(new, materialize_until(&config), &User(user))
+        .await?;
+
+    info!(%template.id, template_name=template.content.template_name, "event template created");
+
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+pub async fn edit(
+    State(template_source): State<Arc<dyn EventTemplateSource>>,
+    State(config): State<EventTemplatesConfig>,
+    Path(id): Path<EventTemplateId>,
+    BusinessUser(user): BusinessUser,
+    ValidatedJson(content): ValidatedJson<EventTemplateContent>,
+) -> AppResponse<EventTemplate> {
+    let template = template_source
+        .update(&id, content, materialize_until(&config), &User(user))
+        .await?;
+
+    info!(%template.id, template_name=template.content.template_name, "event template updated");
+
+    Ok(Json(template))
+}
+
+pub async fn delete(
+    State(template_source): State<Arc<dyn EventTemplateSource>>,
+    Path(id): Path<EventTemplateId>,
+    BusinessUser(user): BusinessUser,
+) -> AppResponse<EventTemplate> {
+    let template = template_source.delete(&id, &User(user)).await?;
+
+    info!(%template.id, template_name=template.content.template_name, "deleted event template");
+
+    Ok(Json(template))
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod test {
+    use super::*;
+    use crate::{api::test::ApiTest, jwt::AuthRole};
+    use axum::body::Body;
+    use openleadr_wire::problem::Problem;
+    use reqwest::Method;
+    use sqlx::PgPool;
+
+    fn template_body(program_id: &str, frequency: &str) -> Body {
+        let start_date = Utc::now().date_naive();
+        Body::from(format!(
+            r#"{{
+                "programID": "{program_id}",
+                "templateName": "peak prices",
+                "recurrence": {{"frequency": "{frequency}", "startTime": "17:00:00", "startDate": "{start_date}"}},
+                "intervalDuration": "PT1H",
+                "intervals": [{{"id": 0, "payloads": [{{"type": "PRICE", "values": [0.42]}}]}}]
+            }}"#
+        ))
+    }
+
+    #[sqlx::test(fixtures("users", "programs"))]
+    async fn crud(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::AnyBusiness]);
+
+        let (status, template) = test
+            .request::<EventTemplate>(
+                Method::POST,
+                "/event-templates",
+                template_body("program-1", "DAILY"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // the events of the next seven days are created right away
+        let events = sqlx::query_scalar!(
+            "SELECT count(*) AS \"count!\" FROM event WHERE program_id = 'program-1'"
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(events, 7);
+
+        let path = format!("/event-templates/{}", template.id);
+        let (status, updated) = test
+            .request::<EventTemplate>(Method::PUT, &path, template_body("program-1", "WEEKDAYS"))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            updated.content.recurrence.frequency,
+            openleadr_wire::event_template::Frequency::Weekdays
+        );
+
+        let (status, templates) = test
+            .request::<Vec<EventTemplate>>(
+                Method::GET,
+                "/event-templates?programID=program-1",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(templates, vec![updated]);
+
+        let (status, _) = test
+            .request::<EventTemplate>(Method::DELETE, &path, Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = test
+            .request::<Problem>(Method::GET, &path, Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("users", "programs"))]
+    async fn invalid_recurrence(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/event-templates",
+                template_body("program-1", "WEEKLY"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "vens"))]
+    async fn vens_cannot_access_templates(db: PgPool) {
+        let test = ApiTest::new(db, vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+
+        let (status, _) = test
+            .request::<Problem>(Method::GET, "/event-templates", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/event-templates",
+                template_body("program-1", "DAILY"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
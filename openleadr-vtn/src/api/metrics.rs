@@ -0,0 +1,96 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderValue},
+    response::IntoResponse,
+};
+use metrics::gauge;
+
+use crate::{
+    error::AppError,
+    metrics::{prometheus_handle, DB_POOL_CONNECTIONS, DB_POOL_MAX_CONNECTIONS, OBJECTS},
+    state::AppState,
+};
+
+/// Renders all metrics in the Prometheus text format.
+///
+/// The storage related gauges are collected on each scrape,
+/// all other metrics are recorded while handling the requests.
+pub async fn get(State(app_state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let storage = app_state.storage.metrics();
+
+    let pool = storage.connection_pool();
+    gauge!(DB_POOL_CONNECTIONS, "state" => "idle").set(pool.idle as f64);
+    gauge!(DB_POOL_CONNECTIONS, "state" => "in_use")
+        .set(pool.size.saturating_sub(pool.idle) as f64);
+    gauge!(DB_POOL_MAX_CONNECTIONS).set(pool.max as f64);
+
+    let counts = storage.object_counts().await?;
+    for (object_type, count) in [
+        ("PROGRAM", counts.programs),
+        ("EVENT", counts.events),
+        ("REPORT", counts.reports),
+        ("VEN", counts.vens),
+        ("RESOURCE", counts.resources),
+        ("USER", counts.users),
+    ] {
+        gauge!(OBJECTS, "object_type" => object_type).set(count as f64);
+    }
+
+    let handle = prometheus_handle();
+    handle.run_upkeep();
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4"),
+        )],
+        handle.render(),
+    ))
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod test {
+    use crate::{api::test::ApiTest, jwt::AuthRole};
+    use axum::{body::Body, http::StatusCode};
+    use openleadr_wire::{problem::Problem, Program};
+    use reqwest::Method;
+    use sqlx::PgPool;
+
+    #[sqlx::test(fixtures("users"))]
+    async fn metrics(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::AnyBusiness]);
+
+        let (status, _) = test
+            .request::<Program>(
+                Method::POST,
+                "/programs",
+                Body::from(r#"{"programName":"program-metrics"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let unauthenticated = ApiTest::new(db, vec![]);
+        let (status, _) = unauthenticated
+            .request::<Problem>(Method::GET, "/vens", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, body) = test.text_request(Method::GET, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+
+        // the metrics are global, so other tests running in parallel can increase the counters
+        assert!(body.contains(
+            r#"openleadr_http_requests_total{method="POST",route="/programs",status="201"}"#
+        ));
+        assert!(body.contains(
+            r#"openleadr_http_request_duration_seconds_bucket{method="POST",route="/programs",status="201",le="0.005"}"#
+        ));
+        assert!(body.contains(
+            r#"openleadr_auth_failures_total{kind="forbidden",reason="User does not have the required role"}"#
+        ));
+        assert!(body.contains(r#"openleadr_db_pool_connections{state="idle"}"#));
+        assert!(body.contains("openleadr_db_pool_max_connections"));
+        assert!(body.contains(r#"openleadr_objects{object_type="PROGRAM"}"#));
+    }
+}
@@ -13,9 +13,17 @@ use reqwest::StatusCode;
 use serde::de::DeserializeOwned;
 use validator::Validate;
 
+pub(crate) mod audit;
 pub(crate) mod auth;
+pub(crate) mod business;
+pub(crate) mod enrollment;
 pub(crate) mod event;
+pub(crate) mod event_template;
+pub(crate) mod metrics;
+pub(crate) mod opt;
 pub(crate) mod program;
+#[cfg(feature = "internal-oauth")]
+pub(crate) mod registration;
 pub(crate) mod report;
 pub(crate) mod resource;
 #[cfg(feature = "internal-oauth")]
@@ -24,6 +32,22 @@ pub(crate) mod ven;
 
 pub(crate) type AppResponse<T> = Result<Json<T>, AppError>;
 
+// a literal, such that the error message can be built from it
+macro_rules! max_batch_size {
+    () => {
+        500
+    };
+}
+
+/// Maximum number of items accepted in a single batch request
+pub(crate) const MAX_BATCH_SIZE: usize = max_batch_size!();
+
+const BATCH_SIZE_ERROR: &str = concat!(
+    "A batch must contain at least 1 and at most ",
+    max_batch_size!(),
+    " items"
+);
+
 #[derive(Debug, Clone)]
 pub(crate) struct ValidatedForm<T>(T);
 
@@ -33,6 +57,11 @@ pub(crate) struct ValidatedQuery<T>(pub T);
 #[derive(Debug, Clone)]
 pub(crate) struct ValidatedJson<T>(pub T);
 
+/// A JSON list of items for a batch request.
+/// Each item is validated on its own, such that validation errors can be reported per item.
+#[derive(Debug, Clone)]
+pub(crate) struct ValidatedBatchJson<T>(pub Vec<T>);
+
 #[async_trait]
 impl<T, S> FromRequest<S> for ValidatedJson<T>
 where
@@ -49,6 +78,33 @@ where
     }
 }
 
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedBatchJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+    Json<Vec<T>>: FromRequest<S, Rejection = JsonRejection>,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(items) = Json::<Vec<T>>::from_request(req, state).await?;
+        if !(1..=MAX_BATCH_SIZE).contains(&items.len()) {
+            return Err(AppError::BadRequest(BATCH_SIZE_ERROR));
+        }
+
+        let errors = items
+            .iter()
+            .map(|item| item.validate().err().map(AppError::from))
+            .collect::<Vec<_>>();
+        if errors.iter().any(Option::is_some) {
+            return Err(AppError::Batch(errors));
+        }
+
+        Ok(ValidatedBatchJson(items))
+    }
+}
+
 #[async_trait]
 impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
 where
@@ -95,9 +151,15 @@ pub async fn healthcheck(State(app_state): State<AppState>) -> Result<impl IntoR
 #[cfg(test)]
 #[cfg(feature = "live-db-test")]
 mod test {
-    use crate::{data_source::PostgresStorage, jwt::AuthRole, state::AppState};
+    use crate::{
+        config::{Config, RateLimit},
+        data_source::PostgresStorage,
+        jwt::{allowed_scopes, AuthRole},
+        state::AppState,
+    };
     use axum::{
         body::Body,
+        extract::ConnectInfo,
         http::{self, Request, StatusCode},
         Router,
     };
@@ -106,6 +168,7 @@ mod test {
     use reqwest::Method;
     use serde::de::DeserializeOwned;
     use sqlx::PgPool;
+    use std::net::SocketAddr;
     use tower::ServiceExt;
 
     pub(crate) struct ApiTest {
@@ -123,7 +186,8 @@ mod test {
                 .create(
                     std::time::Duration::from_secs(60),
                     "test_admin".to_string(),
-                    roles,
+                    roles.clone(),
+                    &allowed_scopes(&roles),
                 )
                 .unwrap();
 
@@ -132,6 +196,14 @@ mod test {
             Self { router, token }
         }
 
+        /// Sends the requests with another bearer token, e.g., a registration token
+        pub(crate) fn with_token(&self, token: &str) -> Self {
+            Self {
+                router: self.router.clone(),
+                token: token.to_string(),
+            }
+        }
+
         pub(crate) async fn request<T: DeserializeOwned>(
             &self,
             method: Method,
@@ -183,6 +255,34 @@ mod test {
 
             response.status()
         }
+
+        pub(crate) async fn text_request(
+            &self,
+            method: Method,
+            path: &str,
+        ) -> (StatusCode, String) {
+            let response = self
+                .router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(path)
+                        .header(
+                            http::header::AUTHORIZATION,
+                            format!("Bearer {}", self.token),
+                        )
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        }
     }
 
     #[cfg(feature = "internal-oauth")]
@@ -192,7 +292,8 @@ mod test {
             .create(
                 std::time::Duration::from_secs(60),
                 "test_admin".to_string(),
-                roles,
+                roles.clone(),
+                &allowed_scopes(&roles),
             )
             .unwrap()
     }
@@ -254,6 +355,102 @@ mod test {
         assert_eq!(status, StatusCode::NOT_FOUND);
     }
 
+    #[sqlx::test]
+    async fn request_id(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::VenManager]);
+
+        let response = test
+            .router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/not-existent")
+                    .header("X-Request-Id", "ven-request-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["X-Request-Id"], "ven-request-1");
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem.instance.as_deref(), Some("ven-request-1"));
+
+        // a request id is generated if the client did not provide one
+        let response = test
+            .router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/not-existent")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let request_id = response.headers()["X-Request-Id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem.instance, Some(request_id));
+    }
+
+    #[sqlx::test]
+    async fn rate_limits(db: PgPool) {
+        let mut config = Config::default();
+        config.apply_env().unwrap();
+        config.rate_limit.per_ip = Some(RateLimit {
+            requests_per_minute: 60,
+            burst: 2,
+        });
+        config.rate_limit.per_client = Some(RateLimit {
+            requests_per_minute: 60,
+            burst: 1,
+        });
+        let state = AppState::from_config(PostgresStorage::new(db).unwrap(), &config).unwrap();
+        let token = jwt_test_token(&state, vec![AuthRole::UserManager]);
+        let router = state.into_router();
+
+        let request = |path: &str, ip: [u8; 4], token: Option<&str>| {
+            let mut request = Request::builder().method(Method::GET).uri(path);
+            if let Some(token) = token {
+                request = request.header(http::header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            let mut request = request.body(Body::empty()).unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((ip, 50000))));
+            router.clone().oneshot(request)
+        };
+
+        for _ in 0..2 {
+            let response = request("/health", [192, 0, 2, 1], None).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = request("/health", [192, 0, 2, 1], None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[http::header::RETRY_AFTER], "1");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let problem: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem.status, StatusCode::TOO_MANY_REQUESTS);
+
+        // the per client limit applies across IP addresses
+        let response = request("/users", [192, 0, 2, 2], Some(&token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = request("/users", [192, 0, 2, 3], Some(&token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
     #[sqlx::test]
     async fn healthcheck(db: PgPool) {
         let test = ApiTest::new(db.clone(), vec![]);
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use tracing::{info, trace};
+
+use openleadr_wire::{
+    event::EventId,
+    opt::{Opt, OptContent},
+};
+
+use crate::{
+    api::{AppResponse, ValidatedJson},
+    data_source::OptSource,
+    error::AppError,
+    jwt::{User, VENUser},
+};
+
+pub async fn get_all(
+    State(opt_source): State<Arc<dyn OptSource>>,
+    Path(event_id): Path<EventId>,
+    user: User,
+) -> AppResponse<Vec<Opt>> {
+    let opts = opt_source.retrieve_all(&event_id, &user).await?;
+    trace!(%event_id, "retrieved {} opt statuses", opts.len());
+
+    Ok(Json(opts))
+}
+
+pub async fn set(
+    State(opt_source): State<Arc<dyn OptSource>>,
+    Path(event_id): Path<EventId>,
+    VENUser(user): VENUser,
+    ValidatedJson(new): ValidatedJson<OptContent>,
+) -> Result<(StatusCode, Json<Opt>), AppError> {
+    let (opt, previous) = opt_source.set(&event_id, new, &User(user)).await?;
+
+    info!(%opt.id, %event_id, ven_id = %opt.content.ven_id, opt_type = ?opt.content.opt_type, "opt status set");
+
+    let status = if previous.is_some() {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(opt)))
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod test {
+    use super::*;
+    use crate::{api::test::ApiTest, jwt::AuthRole};
+    use axum::body::Body;
+    use openleadr_wire::{opt::OptType, problem::Problem};
+    use reqwest::Method;
+    use sqlx::PgPool;
+
+    fn opt_body(ven_id: &str, opt_type: &str) -> Body {
+        Body::from(format!(
+            r#"{{"venID": "{ven_id}", "optType": "{opt_type}", "reason": "maintenance"}}"#
+        ))
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business", "events", "vens", "vens-programs"))]
+    async fn set_and_get(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+
+        let (status, opt) = test
+            .request::<Opt>(
+                Method::POST,
+                "/events/event-3/opts",
+                opt_body("ven-1", "OPT_OUT"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(opt.content.opt_type, OptType::OptOut);
+
+        let (status, replaced) = test
+            .request::<Opt>(
+                Method::POST,
+                "/events/event-3/opts",
+                opt_body("ven-1", "OPT_IN"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(replaced.id, opt.id);
+
+        // the business owning the program sees the status
+        let test = ApiTest::new(db, vec![AuthRole::Business("business-1".to_string())]);
+        let (status, opts) = test
+            .request::<Vec<Opt>>(Method::GET, "/events/event-3/opts", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(opts, vec![replaced]);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "events", "vens", "vens-programs"))]
+    async fn only_vens_set_their_own_status(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/events/event-1/opts",
+                opt_body("ven-2", "OPT_OUT"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/events/event-1/opts",
+                opt_body("ven-1", "OPT_OUT"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "events", "vens", "vens-programs"))]
+    async fn binding_events_cannot_be_opted_out_of(db: PgPool) {
+        sqlx::query!("UPDATE program SET binding_events = true WHERE id = 'program-1'")
+            .execute(&db)
+            .await
+            .unwrap();
+        let test = ApiTest::new(db, vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/events/event-1/opts",
+                opt_body("ven-1", "OPT_OUT"),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+}
@@ -16,7 +16,7 @@ use openleadr_wire::{
 };
 
 use crate::{
-    api::{AppResponse, ValidatedJson, ValidatedQuery},
+    api::{event::AsOfQueryParams, AppResponse, ValidatedJson, ValidatedQuery},
     data_source::ProgramCrud,
     error::AppError,
     jwt::{BusinessUser, User},
@@ -37,21 +37,34 @@ pub async fn get_all(
 pub async fn get(
     State(program_source): State<Arc<dyn ProgramCrud>>,
     Path(id): Path<ProgramId>,
+    ValidatedQuery(query_params): ValidatedQuery<AsOfQueryParams>,
     user: User,
 ) -> AppResponse<Program> {
-    let program = program_source.retrieve(&id, &user).await?;
+    let program = match query_params.as_of {
+        Some(as_of) => program_source.retrieve_as_of(&id, as_of, &user).await?,
+        None => program_source.retrieve(&id, &user).await?,
+    };
 
-    trace!(%program.id, program.program_name=program.content.program_name, "program retrieved");
+    trace!(%program.id, program.program_name=program.content.program_name, as_of=?query_params.as_of, "program retrieved");
 
     Ok(Json(program))
 }
 
 pub async fn add(
     State(program_source): State<Arc<dyn ProgramCrud>>,
+    ValidatedQuery(query_params): ValidatedQuery<AddQueryParams>,
     BusinessUser(user): BusinessUser,
     ValidatedJson(new_program): ValidatedJson<ProgramContent>,
 ) -> Result<(StatusCode, Json<Program>), AppError> {
-    let program = program_source.create(new_program, &User(user)).await?;
+    let user = User(user);
+    let program = match query_params.business_id {
+        Some(business_id) => {
+            program_source
+                .create_for_business(new_program, &business_id, &user)
+                .await?
+        }
+        None => program_source.create(new_program, &user).await?,
+    };
 
     info!(%program.id, program.program_name=program.content.program_name, "program added");
 
@@ -96,6 +109,15 @@ pub struct QueryParams {
     pub(crate) limit: i64,
 }
 
+#[derive(Deserialize, Validate, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddQueryParams {
+    /// The business owning the new program.
+    /// Inferred from the user if missing, which fails for users of multiple businesses.
+    #[validate(length(min = 1))]
+    pub(crate) business_id: Option<String>,
+}
+
 fn validate_target_type_value_pair(query: &QueryParams) -> Result<(), ValidationError> {
     if query.target_type.is_some() == query.target_values.is_some() {
         Ok(())
@@ -126,6 +148,7 @@ mod test {
         http::{self, Request, Response, StatusCode},
         Router,
     };
+    use chrono::{DateTime, Utc};
     use http_body_util::BodyExt;
     use openleadr_wire::{
         problem::Problem,
@@ -146,6 +169,7 @@ mod test {
             country: None,
             principal_subdivision: None,
             time_zone_offset: None,
+            time_zone: None,
             interval_period: None,
             program_descriptions: None,
             binding_events: None,
@@ -296,6 +320,77 @@ mod test {
         assert!(program.modification_date_time < db_program.modification_date_time);
     }
 
+    #[sqlx::test(fixtures("users"))]
+    async fn versions_keep_time_zone(db: PgPool) {
+        let amsterdam = ProgramContent {
+            time_zone: Some(chrono_tz::Europe::Amsterdam),
+            ..default_content()
+        };
+        let new_york = ProgramContent {
+            time_zone: Some(chrono_tz::America::New_York),
+            time_zone_offset: Some("-PT5H".parse().unwrap()),
+            ..default_content()
+        };
+        let (state, mut programs) = state_with_programs(vec![amsterdam.clone()], db).await;
+        let created = programs.remove(0);
+        let token = jwt_test_token(&state, vec![AuthRole::AnyBusiness]);
+        let mut app = state.into_router();
+        let as_of = |time: DateTime<Utc>| time.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+
+        let response = app
+            .clone()
+            .oneshot(program_request(
+                http::Method::PUT,
+                new_york.clone(),
+                created.id.as_str(),
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let updated: Program = serde_json::from_slice(&body).unwrap();
+
+        let response = get_help(
+            &mut app,
+            &token,
+            &format!(
+                "{}?asOf={}",
+                created.id,
+                as_of(created.modification_date_time)
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let program: Program = serde_json::from_slice(&body).unwrap();
+        assert_eq!(program, created);
+
+        let response = get_help(
+            &mut app,
+            &token,
+            &format!("{}?asOf={}", created.id, as_of(Utc::now())),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let program: Program = serde_json::from_slice(&body).unwrap();
+        assert_eq!(program, updated);
+        assert_eq!(program.content, new_york);
+
+        let response = get_help(
+            &mut app,
+            &token,
+            &format!(
+                "{}?asOf={}",
+                created.id,
+                as_of(created.created_date_time - chrono::Duration::seconds(1))
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[sqlx::test(fixtures("users"))]
     async fn update_same_name(db: PgPool) {
         let program1 = ProgramContent {
@@ -571,7 +666,7 @@ mod test {
         }
 
         #[sqlx::test(fixtures("users", "business"))]
-        async fn businesses_can_read_any_program(db: PgPool) {
+        async fn businesses_read_own_programs_only(db: PgPool) {
             let (state, _) = state_with_programs(vec![], db).await;
             let token = jwt_test_token(&state, vec![AuthRole::Business("business-1".to_string())]);
             let mut app = state.clone().into_router();
@@ -587,7 +682,7 @@ mod test {
 
             let token = jwt_test_token(&state, vec![AuthRole::Business("business-2".to_string())]);
             let response = get_help(&mut app, &token, program.id.as_str()).await;
-            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
             let token = jwt_test_token(
                 &state,
@@ -602,6 +697,78 @@ mod test {
             let token = jwt_test_token(&state, vec![AuthRole::AnyBusiness]);
             let response = get_help(&mut app, &token, program.id.as_str()).await;
             assert_eq!(response.status(), StatusCode::OK);
+
+            // programs without business are visible to users of any business only
+            let token = jwt_test_token(&state, vec![AuthRole::AnyBusiness]);
+            let content = ProgramContent {
+                program_name: "without-business".to_string(),
+                ..default_content()
+            };
+            let response = help_create_program(&mut app, &token, &content).await;
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let program: Program = serde_json::from_slice(&body).unwrap();
+
+            let token = jwt_test_token(&state, vec![AuthRole::Business("business-1".to_string())]);
+            let response = get_help(&mut app, &token, program.id.as_str()).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+
+        #[sqlx::test(fixtures("users", "business"))]
+        async fn create_for_business(db: PgPool) {
+            sqlx::query!("INSERT INTO business (id, name) VALUES ('business-2', 'Business 2')")
+                .execute(&db)
+                .await
+                .unwrap();
+            let (state, _) = state_with_programs(vec![], db).await;
+            let app = state.clone().into_router();
+
+            let create = |token: String, business_id: &str| {
+                let content = ProgramContent {
+                    program_name: format!("program-{business_id}"),
+                    ..default_content()
+                };
+                let request = Request::builder()
+                    .method(http::Method::POST)
+                    .uri(format!("/programs?businessId={business_id}"))
+                    .header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(Body::from(serde_json::to_vec(&content).unwrap()))
+                    .unwrap();
+                app.clone().oneshot(request)
+            };
+
+            let token = jwt_test_token(
+                &state,
+                vec![
+                    AuthRole::Business("business-1".to_string()),
+                    AuthRole::Business("business-2".to_string()),
+                ],
+            );
+            let response = create(token.clone(), "business-2").await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let program: Program = serde_json::from_slice(&body).unwrap();
+
+            let business_2 =
+                jwt_test_token(&state, vec![AuthRole::Business("business-2".to_string())]);
+            let response = get_help(&mut app.clone(), &business_2, program.id.as_str()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let business_1 =
+                jwt_test_token(&state, vec![AuthRole::Business("business-1".to_string())]);
+            let response = get_help(&mut app.clone(), &business_1, program.id.as_str()).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            // business users cannot create programs for other businesses
+            let response = create(business_1, "business-2").await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            let token = jwt_test_token(&state, vec![AuthRole::AnyBusiness]);
+            let response = create(token.clone(), "business-1").await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+
+            let response = create(token, "not-existing").await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
         }
 
         #[sqlx::test(fixtures("users", "business", "programs", "vens"))]
@@ -0,0 +1,370 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::{info, trace};
+use uuid::Uuid;
+use validator::Validate;
+
+use openleadr_wire::ven::{Ven, VenContent, VenId};
+
+use crate::{
+    api::{auth::hash_token, AppResponse, ValidatedJson, ValidatedQuery},
+    data_source::{ApprovalState, RegistrationSource, RegistrationToken, VenRegistration},
+    error::AppError,
+    jwt::{User, VenManagerUser},
+};
+
+/// Distinguishes registration tokens from access tokens in the `Authorization` header
+pub(crate) const REGISTRATION_TOKEN_PREFIX: &str = "venreg_";
+
+const REGISTRATION_TOKEN_EXPIRATION: Duration = Duration::days(7);
+
+#[derive(Deserialize, Debug, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct NewRegistrationToken {
+    #[validate(length(max = 128))]
+    description: Option<String>,
+    /// Defaults to seven days from now
+    expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(Deserialize))]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedRegistrationToken {
+    /// Returned once, the VTN only stores its hash
+    registration_token: String,
+    #[serde(with = "openleadr_wire::serde_rfc3339")]
+    expires: DateTime<Utc>,
+}
+
+/// Response to a VEN registering itself via `POST /vens`
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(Deserialize))]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredVen {
+    #[serde(flatten)]
+    ven: Ven,
+    approval_state: ApprovalState,
+    /// The credential of the user created for the VEN.
+    /// The secret is returned once and cannot be retrieved later.
+    #[serde(rename = "clientID")]
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryParams {
+    approval_state: Option<ApprovalState>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalUpdate {
+    approval_state: ApprovalState,
+}
+
+pub async fn add_token(
+    State(registration_source): State<Arc<dyn RegistrationSource>>,
+    VenManagerUser(manager): VenManagerUser,
+    ValidatedJson(new): ValidatedJson<NewRegistrationToken>,
+) -> Result<(StatusCode, Json<CreatedRegistrationToken>), AppError> {
+    let now = Utc::now();
+    let expires = new
+        .expires
+        .unwrap_or_else(|| now + REGISTRATION_TOKEN_EXPIRATION);
+    if expires <= now {
+        return Err(AppError::BadRequest(
+            "The expiry date of a registration token must be in the future",
+        ));
+    }
+
+    let registration_token = format!(
+        "{REGISTRATION_TOKEN_PREFIX}{}",
+        URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+    );
+    registration_source
+        .add_token(&RegistrationToken {
+            token_hash: hash_token(&registration_token),
+            description: new.description,
+            created_by: manager.sub.clone(),
+            created: now,
+            expires,
+        })
+        .await?;
+
+    info!(created_by = manager.sub, %expires, "issued registration token");
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedRegistrationToken {
+            registration_token,
+            expires,
+        }),
+    ))
+}
+
+/// Creates the VEN, along with a user and a credential for it.
+/// Called by `POST /vens` if authorized by a registration token instead of an access token.
+pub(crate) async fn register(
+    registration_source: &dyn RegistrationSource,
+    registration_token: &str,
+    new: VenContent,
+) -> Result<RegisteredVen, AppError> {
+    let client_id = Uuid::new_v4().to_string();
+    let client_secret = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>());
+
+    let (ven, registration) = registration_source
+        .register(
+            &hash_token(registration_token),
+            new,
+            &client_id,
+            &client_secret,
+        )
+        .await?;
+
+    info!(%ven.id, ven.ven_name=ven.content.ven_name, "VEN registered itself");
+
+    Ok(RegisteredVen {
+        ven,
+        approval_state: registration.approval_state,
+        client_id,
+        client_secret,
+    })
+}
+
+pub async fn get_all(
+    State(registration_source): State<Arc<dyn RegistrationSource>>,
+    ValidatedQuery(query_params): ValidatedQuery<QueryParams>,
+    VenManagerUser(_): VenManagerUser,
+) -> AppResponse<Vec<VenRegistration>> {
+    let registrations = registration_source
+        .retrieve_all(query_params.approval_state)
+        .await?;
+
+    trace!("retrieved {} VEN registrations", registrations.len());
+    Ok(Json(registrations))
+}
+
+pub async fn get(
+    State(registration_source): State<Arc<dyn RegistrationSource>>,
+    Path(ven_id): Path<VenId>,
+    user: User,
+) -> AppResponse<VenRegistration> {
+    let registration = registration_source.retrieve(&ven_id, &user).await?;
+
+    trace!(%ven_id, "retrieved VEN registration");
+    Ok(Json(registration))
+}
+
+pub async fn edit(
+    State(registration_source): State<Arc<dyn RegistrationSource>>,
+    Path(ven_id): Path<VenId>,
+    VenManagerUser(manager): VenManagerUser,
+    ValidatedJson(update): ValidatedJson<ApprovalUpdate>,
+) -> AppResponse<VenRegistration> {
+    let registration = registration_source
+        .set_approval_state(&ven_id, update.approval_state, &User(manager))
+        .await?;
+
+    info!(%ven_id, approval_state = ?registration.approval_state, "VEN registration reviewed");
+    Ok(Json(registration))
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod test {
+    use super::*;
+    use crate::{
+        api::test::ApiTest,
+        config::{Config, RateLimit},
+        data_source::PostgresStorage,
+        jwt::AuthRole,
+        state::AppState,
+    };
+    use axum::{
+        body::Body,
+        extract::ConnectInfo,
+        http::{self, Request},
+    };
+    use openleadr_wire::problem::Problem;
+    use reqwest::Method;
+    use sqlx::PgPool;
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    fn ven_body(name: &str) -> Body {
+        Body::from(format!(r#"{{"objectType":"VEN","venName":"{name}"}}"#))
+    }
+
+    async fn registration_token(manager: &ApiTest) -> String {
+        let (status, token) = manager
+            .request::<CreatedRegistrationToken>(
+                Method::POST,
+                "/registration-tokens",
+                Body::from(r#"{"description":"test"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(token
+            .registration_token
+            .starts_with(REGISTRATION_TOKEN_PREFIX));
+        token.registration_token
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn register_and_approve(db: PgPool) {
+        let manager = ApiTest::new(db, vec![AuthRole::VenManager]);
+        let token = registration_token(&manager).await;
+
+        let ven = manager.with_token(&token);
+        let (status, registered) = ven
+            .request::<RegisteredVen>(Method::POST, "/vens", ven_body("self-registered"))
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(registered.approval_state, ApprovalState::Pending);
+        assert_eq!(registered.ven.content.ven_name, "self-registered");
+
+        // the token can be used only once
+        let (status, _) = ven
+            .request::<Problem>(Method::POST, "/vens", ven_body("another"))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, pending) = manager
+            .request::<Vec<VenRegistration>>(
+                Method::GET,
+                "/registrations?approvalState=PENDING",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].ven_id, registered.ven.id);
+        assert!(pending[0].user_id.is_some());
+
+        let path = format!("/registrations/{}", registered.ven.id);
+        let (status, approved) = manager
+            .request::<VenRegistration>(
+                Method::PUT,
+                &path,
+                Body::from(r#"{"approvalState":"APPROVED"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(approved.approval_state, ApprovalState::Approved);
+
+        let (status, pending) = manager
+            .request::<Vec<VenRegistration>>(
+                Method::GET,
+                "/registrations?approvalState=PENDING",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(pending.is_empty());
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn invalid_registration_token(db: PgPool) {
+        let manager = ApiTest::new(db, vec![AuthRole::VenManager]);
+
+        let (status, _) = manager
+            .with_token(&format!("{REGISTRATION_TOKEN_PREFIX}unknown"))
+            .request::<Problem>(Method::POST, "/vens", ven_body("self-registered"))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = manager
+            .request::<Problem>(
+                Method::POST,
+                "/registration-tokens",
+                Body::from(r#"{"expires":"2020-01-01T00:00:00Z"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn registrations_limited_per_ip(db: PgPool) {
+        let mut config = Config::default();
+        config.apply_env().unwrap();
+        config.rate_limit.auth = Some(RateLimit {
+            requests_per_minute: 60,
+            burst: 2,
+        });
+        let router = AppState::from_config(PostgresStorage::new(db).unwrap(), &config)
+            .unwrap()
+            .into_router();
+
+        let register = |ip: [u8; 4]| {
+            let mut request = Request::builder()
+                .method(Method::POST)
+                .uri("/vens")
+                .header(
+                    http::header::AUTHORIZATION,
+                    format!("Bearer {REGISTRATION_TOKEN_PREFIX}unknown"),
+                )
+                .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(ven_body("self-registered"))
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((ip, 50000))));
+            router.clone().oneshot(request)
+        };
+
+        for _ in 0..2 {
+            let response = register([192, 0, 2, 1]).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = register([192, 0, 2, 1]).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let response = register([192, 0, 2, 2]).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn only_ven_managers_review_registrations(db: PgPool) {
+        let manager = ApiTest::new(db.clone(), vec![AuthRole::VenManager]);
+        let token = registration_token(&manager).await;
+        let (_, registered) = manager
+            .with_token(&token)
+            .request::<RegisteredVen>(Method::POST, "/vens", ven_body("self-registered"))
+            .await;
+
+        let business = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+        let (status, _) = business
+            .request::<Problem>(Method::POST, "/registration-tokens", Body::from("{}"))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = business
+            .request::<Problem>(Method::GET, "/registrations", Body::empty())
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = business
+            .request::<Problem>(
+                Method::PUT,
+                &format!("/registrations/{}", registered.ven.id),
+                Body::from(r#"{"approvalState":"APPROVED"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = business
+            .request::<Problem>(
+                Method::GET,
+                &format!("/registrations/{}", registered.ven.id),
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
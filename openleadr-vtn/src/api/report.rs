@@ -5,22 +5,27 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use metrics::counter;
 use serde::{Deserialize, Serialize};
 use tracing::{info, instrument};
 use validator::Validate;
 
 use openleadr_wire::{
+    baseline::{BaselineMethod, DeliveredReduction},
     event::EventId,
     program::ProgramId,
     report::{ReportContent, ReportId},
+    report_summary::ReportSummary,
     Report,
 };
 
 use crate::{
     api::{AppResponse, ValidatedJson, ValidatedQuery},
+    baseline,
     data_source::ReportCrud,
     error::AppError,
     jwt::{BusinessUser, User, VENUser},
+    metrics::REPORTS_TOTAL,
 };
 
 #[instrument(skip(user, report_source))]
@@ -53,6 +58,7 @@ pub async fn add(
     let report = report_source.create(new_report, &User(user)).await?;
 
     info!(%report.id, report_name=?report.content.report_name, "report created");
+    counter!(REPORTS_TOTAL, "operation" => "create").increment(1);
 
     Ok((StatusCode::CREATED, Json(report)))
 }
@@ -67,6 +73,7 @@ pub async fn edit(
     let report = report_source.update(&id, content, &User(user)).await?;
 
     info!(%report.id, report_name=?report.content.report_name, "report updated");
+    counter!(REPORTS_TOTAL, "operation" => "update").increment(1);
 
     Ok(Json(report))
 }
@@ -83,6 +90,29 @@ pub async fn delete(
     Ok(Json(report))
 }
 
+#[instrument(skip(user, report_source))]
+pub async fn get_summary(
+    State(report_source): State<Arc<dyn ReportCrud>>,
+    Path(event_id): Path<EventId>,
+    BusinessUser(user): BusinessUser,
+) -> AppResponse<ReportSummary> {
+    let summary = report_source.summarize(&event_id, &User(user)).await?;
+    Ok(Json(summary))
+}
+
+#[instrument(skip(user, report_source))]
+pub async fn get_delivered_reduction(
+    State(report_source): State<Arc<dyn ReportCrud>>,
+    Path(event_id): Path<EventId>,
+    ValidatedQuery(method): ValidatedQuery<BaselineMethod>,
+    BusinessUser(user): BusinessUser,
+) -> AppResponse<DeliveredReduction> {
+    let history = report_source
+        .usage_history(&event_id, baseline::lookback_days(&method), &User(user))
+        .await?;
+    Ok(Json(baseline::delivered_reduction(&history, &method)))
+}
+
 #[derive(Serialize, Deserialize, Validate, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryParams {
@@ -110,8 +140,11 @@ mod test {
     use crate::{api::test::ApiTest, jwt::AuthRole};
     use axum::{body::Body, http, http::StatusCode};
     use openleadr_wire::{
+        baseline::DeliveredReduction,
         problem::Problem,
         report::{ReportContent, ReportPayloadDescriptor, ReportType},
+        report_summary::ReportSummary,
+        Report,
     };
     use sqlx::PgPool;
 
@@ -181,4 +214,148 @@ mod test {
                 .contains("outside of allowed range 1..=128"))
         }
     }
+
+    #[sqlx::test(fixtures("users", "programs", "business", "events", "vens", "vens-programs"))]
+    async fn summary(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+        let report = r#"{
+            "programID": "program-3",
+            "eventID": "event-3",
+            "clientName": "ven-1-client",
+            "resources": [{
+                "resourceName": "resource-1",
+                "intervals": [{"id": 3, "payloads": [{"type": "USAGE", "values": [2.5]}, {"type": "BASELINE", "values": [4]}]}]
+            }]
+        }"#;
+        let (status, _) = test
+            .request::<Report>(http::Method::POST, "/reports", Body::from(report))
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // the summary is for business users only
+        let (status, _) = test
+            .request::<Problem>(
+                http::Method::GET,
+                "/events/event-3/report-summary",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let test = ApiTest::new(db, vec![AuthRole::Business("business-1".to_string())]);
+        let (status, summary) = test
+            .request::<ReportSummary>(
+                http::Method::GET,
+                "/events/event-3/report-summary",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(summary.report_count, 1);
+        assert_eq!(summary.reporting_ven_ids, vec!["ven-1".parse().unwrap()]);
+        assert!(summary.silent_ven_ids.is_empty());
+        assert_eq!(summary.intervals.len(), 1);
+        let interval = &summary.intervals[0];
+        assert_eq!(interval.id, 3);
+        assert_eq!(interval.usage.unwrap().total, 2.5);
+        assert_eq!(interval.usage_delta_to_baseline, Some(-1.5));
+
+        // event-1 belongs to a program of another business
+        let (status, _) = test
+            .request::<Problem>(
+                http::Method::GET,
+                "/events/event-1/report-summary",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business", "events", "vens", "vens-programs"))]
+    async fn summary_attributes_reports_by_client_name(db: PgPool) {
+        // both VENs are enrolled in program-1
+        let test = ApiTest::new(
+            db.clone(),
+            vec![
+                AuthRole::VEN("ven-1".parse().unwrap()),
+                AuthRole::VEN("ven-2".parse().unwrap()),
+            ],
+        );
+        for client_name in ["ven-2-name", "unknown-client"] {
+            let report = ReportContent {
+                program_id: "program-1".parse().unwrap(),
+                event_id: "event-1".parse().unwrap(),
+                client_name: client_name.to_string(),
+                ..default()
+            };
+            let (status, _) = test
+                .request::<Report>(
+                    http::Method::POST,
+                    "/reports",
+                    Body::from(serde_json::to_vec(&report).unwrap()),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+
+        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+        let (status, summary) = test
+            .request::<ReportSummary>(
+                http::Method::GET,
+                "/events/event-1/report-summary",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(summary.report_count, 2);
+        // the report of the unknown client cannot be attributed to one of the VENs of the user
+        assert_eq!(summary.reporting_ven_ids, vec!["ven-2".parse().unwrap()]);
+        assert_eq!(summary.silent_ven_ids, vec!["ven-1".parse().unwrap()]);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business", "events", "vens", "vens-programs"))]
+    async fn delivered_reduction(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+        let (status, _) = test
+            .request::<Problem>(
+                http::Method::GET,
+                "/events/event-3/delivered-reduction",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let test = ApiTest::new(db, vec![AuthRole::Business("business-1".to_string())]);
+        let (status, reduction) = test
+            .request::<DeliveredReduction>(
+                http::Method::GET,
+                "/events/event-3/delivered-reduction?x=3&y=5&adjustmentHours=2",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(reduction.program_id.as_str(), "program-3");
+        assert_eq!(reduction.method.x, 3);
+        assert_eq!(reduction.method.adjustment_hours, Some(2));
+        // event-3 has no interval period and therefore no baseline
+        assert!(reduction.resources.is_empty());
+
+        let (status, _) = test
+            .request::<Problem>(
+                http::Method::GET,
+                "/events/event-3/delivered-reduction?x=6&y=5",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = test
+            .request::<Problem>(
+                http::Method::GET,
+                "/events/event-1/delivered-reduction",
+                Body::empty(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
 }
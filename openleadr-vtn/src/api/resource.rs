@@ -11,12 +11,13 @@ use tracing::{info, trace};
 use validator::{Validate, ValidationError};
 
 use openleadr_wire::{
+    batch::BatchUpdate,
     resource::{Resource, ResourceContent, ResourceId},
     target::TargetType,
 };
 
 use crate::{
-    api::{AppResponse, ValidatedJson, ValidatedQuery},
+    api::{AppResponse, ValidatedBatchJson, ValidatedJson, ValidatedQuery},
     data_source::ResourceCrud,
     error::AppError,
     jwt::User,
@@ -70,9 +71,9 @@ pub async fn add(
     ValidatedJson(new_resource): ValidatedJson<ResourceContent>,
 ) -> Result<(StatusCode, Json<Resource>), AppError> {
     has_write_permission(&user, &ven_id)?;
-    let ven = resource_source.create(new_resource, ven_id, &user).await?;
+    let resource = resource_source.create(new_resource, ven_id, &user).await?;
 
-    Ok((StatusCode::CREATED, Json(ven)))
+    Ok((StatusCode::CREATED, Json(resource)))
 }
 
 pub async fn edit(
@@ -89,6 +90,36 @@ pub async fn edit(
     Ok(Json(resource))
 }
 
+pub async fn add_batch(
+    State(resource_source): State<Arc<dyn ResourceCrud>>,
+    user: User,
+    Path(ven_id): Path<VenId>,
+    ValidatedBatchJson(new_resources): ValidatedBatchJson<ResourceContent>,
+) -> Result<(StatusCode, Json<Vec<Resource>>), AppError> {
+    has_write_permission(&user, &ven_id)?;
+    let resources = resource_source
+        .create_batch(new_resources, ven_id, &user)
+        .await?;
+
+    info!(count = resources.len(), "resources created in batch");
+
+    Ok((StatusCode::CREATED, Json(resources)))
+}
+
+pub async fn edit_batch(
+    State(resource_source): State<Arc<dyn ResourceCrud>>,
+    Path(ven_id): Path<VenId>,
+    user: User,
+    ValidatedBatchJson(updates): ValidatedBatchJson<BatchUpdate<ResourceId, ResourceContent>>,
+) -> AppResponse<Vec<Resource>> {
+    has_write_permission(&user, &ven_id)?;
+    let resources = resource_source.update_batch(updates, ven_id, &user).await?;
+
+    info!(count = resources.len(), "resources updated in batch");
+
+    Ok(Json(resources))
+}
+
 pub async fn delete(
     State(resource_source): State<Arc<dyn ResourceCrud>>,
     Path((ven_id, id)): Path<(VenId, ResourceId)>,
@@ -311,9 +342,74 @@ mod test {
         assert_eq!(status, StatusCode::NOT_FOUND);
     }
 
+    #[sqlx::test(fixtures("users", "vens", "resources"))]
+    async fn batch_add_edit(db: PgPool) {
+        let test = ApiTest::new(db.clone(), vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+
+        let (status, resources) = test
+            .request::<Vec<Resource>>(
+                Method::POST,
+                "/vens/ven-1/resources/batch",
+                Body::from(
+                    r#"[{"resourceName":"new-resource-1"},{"resourceName":"new-resource-2"}]"#,
+                ),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(resources.len(), 2);
+        assert_eq!(resources[0].content.resource_name, "new-resource-1");
+        assert_eq!(resources[1].content.resource_name, "new-resource-2");
+
+        let updates = format!(
+            r#"[{{"id":"{}","resourceName":"updated-1"}},{{"id":"{}","resourceName":"updated-2"}}]"#,
+            resources[0].id, resources[1].id
+        );
+        let (status, updated) = test
+            .request::<Vec<Resource>>(
+                Method::PUT,
+                "/vens/ven-1/resources/batch",
+                Body::from(updates),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated[0].id, resources[0].id);
+        assert_eq!(updated[0].content.resource_name, "updated-1");
+        assert_eq!(updated[1].content.resource_name, "updated-2");
+
+        // a name conflict within the batch rejects the whole batch
+        let (status, problems) = test
+            .request::<Vec<Problem>>(
+                Method::POST,
+                "/vens/ven-1/resources/batch",
+                Body::from(r#"[{"resourceName":"new-resource-3"},{"resourceName":"updated-1"}]"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(problems[0].status, StatusCode::FAILED_DEPENDENCY);
+        assert_eq!(problems[1].status, StatusCode::CONFLICT);
+
+        let (status, _) = test
+            .request::<Resource>(
+                Method::POST,
+                "/vens/ven-1/resources",
+                Body::from(r#"{"resourceName":"new-resource-3"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, _) = test
+            .request::<Problem>(
+                Method::POST,
+                "/vens/ven-2/resources/batch",
+                Body::from(r#"[{"resourceName":"new-resource-1"}]"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
     #[sqlx::test(fixtures("users", "vens"))]
     async fn name_constraint_validation(db: PgPool) {
-        let test = ApiTest::new(db, vec![AuthRole::AnyBusiness]);
+        let test = ApiTest::new(db, vec![AuthRole::VenManager]);
 
         let resources = [
             ResourceContent{resource_name: "".to_string(), targets: None, attributes:None},
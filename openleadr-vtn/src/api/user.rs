@@ -1,20 +1,22 @@
 use crate::{
     api::{AppResponse, ValidatedJson},
+    config::CredentialsConfig,
     data_source::{AuthSource, UserDetails},
     error::AppError,
-    jwt::{AuthRole, UserManagerUser},
+    jwt::{AuthRole, User, UserManagerUser},
 };
 use axum::{
     extract::{Path, State},
     Json,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
 use reqwest::StatusCode;
-#[cfg(test)]
 use serde::Serialize;
 use serde_with::serde_derive::Deserialize;
-use std::sync::Arc;
+use std::{borrow::Cow, sync::Arc};
 use tracing::{info, trace};
-use validator::Validate;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 #[derive(Deserialize, Debug, Validate)]
 #[cfg_attr(test, derive(Serialize))]
@@ -28,7 +30,20 @@ pub struct NewUser {
 #[cfg_attr(test, derive(Serialize, Default))]
 pub struct NewCredential {
     client_id: String,
-    client_secret: String,
+    /// Generated by the VTN if missing
+    client_secret: Option<String>,
+    /// Defaults to the configured lifetime, if any
+    expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct CreatedCredential {
+    #[serde(flatten)]
+    user: UserDetails,
+    /// The secret generated by the VTN. It is returned once and cannot be retrieved later.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_secret: Option<String>,
 }
 
 pub async fn get_all(
@@ -53,7 +68,7 @@ pub async fn get(
 
 pub async fn add_user(
     State(auth_source): State<Arc<dyn AuthSource>>,
-    UserManagerUser(_): UserManagerUser,
+    UserManagerUser(manager): UserManagerUser,
     ValidatedJson(new_user): ValidatedJson<NewUser>,
 ) -> Result<(StatusCode, Json<UserDetails>), AppError> {
     let user = auth_source
@@ -61,6 +76,7 @@ pub async fn add_user(
             &new_user.reference,
             new_user.description.as_deref(),
             &new_user.roles,
+            &User(manager),
         )
         .await?;
     info!(user_id = user.id(), "created new user");
@@ -69,25 +85,66 @@ pub async fn add_user(
 
 pub async fn add_credential(
     State(auth_source): State<Arc<dyn AuthSource>>,
+    State(policy): State<CredentialsConfig>,
     Path(id): Path<String>,
-    UserManagerUser(_): UserManagerUser,
+    UserManagerUser(manager): UserManagerUser,
     ValidatedJson(new): ValidatedJson<NewCredential>,
-) -> AppResponse<UserDetails> {
+) -> AppResponse<CreatedCredential> {
+    let generated = new.client_secret.is_none();
+    let client_secret = match new.client_secret {
+        Some(client_secret) => {
+            check_secret_strength(&client_secret, &policy)?;
+            client_secret
+        }
+        None => URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>()),
+    };
+    let expires = new.expires.or_else(|| {
+        policy
+            .default_lifetime()
+            .map(|lifetime| Utc::now() + lifetime)
+    });
+    if expires.is_some_and(|expires| expires <= Utc::now()) {
+        return Err(AppError::BadRequest(
+            "The expiry date of a credential must be in the future",
+        ));
+    }
+
     let user = auth_source
-        .add_credential(&id, &new.client_id, &new.client_secret)
+        .add_credential(&id, &new.client_id, &client_secret, expires, &User(manager))
         .await?;
     info!(
         user_id = id,
         client_id = new.client_id,
+        ?expires,
+        generated,
         "created new credential for user"
     );
-    Ok(Json(user))
+    Ok(Json(CreatedCredential {
+        user,
+        client_secret: generated.then_some(client_secret),
+    }))
+}
+
+fn check_secret_strength(client_secret: &str, policy: &CredentialsConfig) -> Result<(), AppError> {
+    if client_secret.chars().count() >= policy.min_secret_length {
+        return Ok(());
+    }
+
+    let mut errors = ValidationErrors::new();
+    errors.add(
+        "client_secret",
+        ValidationError::new("length").with_message(Cow::Owned(format!(
+            "must have at least {} characters",
+            policy.min_secret_length
+        ))),
+    );
+    Err(errors.into())
 }
 
 pub async fn edit(
     State(auth_source): State<Arc<dyn AuthSource>>,
     Path(id): Path<String>,
-    UserManagerUser(_): UserManagerUser,
+    UserManagerUser(manager): UserManagerUser,
     ValidatedJson(modified): ValidatedJson<NewUser>,
 ) -> AppResponse<UserDetails> {
     let user = auth_source
@@ -96,6 +153,7 @@ pub async fn edit(
             &modified.reference,
             modified.description.as_deref(),
             &modified.roles,
+            &User(manager),
         )
         .await?;
 
@@ -106,9 +164,9 @@ pub async fn edit(
 pub async fn delete_user(
     State(auth_source): State<Arc<dyn AuthSource>>,
     Path(id): Path<String>,
-    UserManagerUser(_): UserManagerUser,
+    UserManagerUser(manager): UserManagerUser,
 ) -> AppResponse<UserDetails> {
-    let user = auth_source.remove_user(&id).await?;
+    let user = auth_source.remove_user(&id, &User(manager)).await?;
     info!(user_id = user.id(), "deleted user");
     Ok(Json(user))
 }
@@ -116,9 +174,11 @@ pub async fn delete_user(
 pub async fn delete_credential(
     State(auth_source): State<Arc<dyn AuthSource>>,
     Path((user_id, client_id)): Path<(String, String)>,
-    UserManagerUser(_): UserManagerUser,
+    UserManagerUser(manager): UserManagerUser,
 ) -> AppResponse<UserDetails> {
-    let user = auth_source.remove_credentials(&user_id, &client_id).await?;
+    let user = auth_source
+        .remove_credentials(&user_id, &client_id, &User(manager))
+        .await?;
     info!(user_id = user.id(), client_id, "deleted credential");
     Ok(Json(user))
 }
@@ -127,7 +187,10 @@ pub async fn delete_credential(
 #[cfg(feature = "live-db-test")]
 mod test {
     use super::*;
-    use crate::api::test::{jwt_test_token, state};
+    use crate::{
+        api::test::{jwt_test_token, state},
+        data_source::CredentialDetails,
+    };
     use axum::{
         body::Body,
         http,
@@ -138,6 +201,15 @@ mod test {
     use sqlx::PgPool;
     use tower::ServiceExt;
 
+    fn credential(client_id: &str) -> CredentialDetails {
+        CredentialDetails {
+            client_id: client_id.to_string(),
+            created: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
+            expires: None,
+            last_used: None,
+        }
+    }
+
     fn user_1() -> UserDetails {
         UserDetails {
             id: "user-1".to_string(),
@@ -145,6 +217,7 @@ mod test {
             description: Some("desc".to_string()),
             roles: vec![],
             client_ids: vec!["user-1-client-id".to_string()],
+            credentials: vec![credential("user-1-client-id")],
             created: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
             modified: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
         }
@@ -161,6 +234,7 @@ mod test {
                 AuthRole::AnyBusiness,
             ],
             client_ids: vec!["admin".to_string()],
+            credentials: vec![credential("admin")],
             created: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
             modified: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
         }
@@ -453,7 +527,8 @@ mod test {
 
         let new_credential = NewCredential {
             client_id: "test".to_string(),
-            client_secret: "test".to_string(),
+            client_secret: Some("a-long-enough-secret".to_string()),
+            expires: None,
         };
 
         let response = help_add_credential(&mut app, &token, "admin", &new_credential).await;
@@ -465,10 +540,107 @@ mod test {
         let response = help_login(
             &mut app,
             &new_credential.client_id,
-            &new_credential.client_secret,
+            new_credential.client_secret.as_deref().unwrap(),
         )
         .await;
         assert_eq!(response.status(), StatusCode::OK);
+
+        // the secret is never returned
+        let response = help_get(&mut app, &token, "admin").await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(!String::from_utf8_lossy(&body).contains("a-long-enough-secret"));
+        let user: UserDetails = serde_json::from_slice(&body).unwrap();
+        let credential = user
+            .credentials
+            .iter()
+            .find(|credential| credential.client_id == "test")
+            .unwrap();
+        assert!(credential.last_used.is_some());
+        assert!(credential.expires.is_none());
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn generate_secret(db: PgPool) {
+        let state = state(db).await;
+        let token = jwt_test_token(&state, vec![AuthRole::UserManager]);
+        let mut app = state.into_router();
+
+        let new_credential = NewCredential {
+            client_id: "generated".to_string(),
+            ..Default::default()
+        };
+        let response = help_add_credential(&mut app, &token, "user-1", &new_credential).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let created: CreatedCredential = serde_json::from_slice(&body).unwrap();
+        let client_secret = created.client_secret.unwrap();
+        assert_eq!(client_secret.len(), 43);
+        assert!(created.user.client_ids.contains(&"generated".to_string()));
+
+        let response = help_login(&mut app, "generated", &client_secret).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn weak_secret(db: PgPool) {
+        let state = state(db).await;
+        let token = jwt_test_token(&state, vec![AuthRole::UserManager]);
+        let mut app = state.into_router();
+
+        let new_credential = NewCredential {
+            client_id: "weak".to_string(),
+            client_secret: Some("short".to_string()),
+            ..Default::default()
+        };
+        let response = help_add_credential(&mut app, &token, "user-1", &new_credential).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = help_login(&mut app, "weak", "short").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn credential_expiry(db: PgPool) {
+        let state = state(db.clone()).await;
+        let token = jwt_test_token(&state, vec![AuthRole::UserManager]);
+        let mut app = state.into_router();
+
+        let new_credential = NewCredential {
+            client_id: "expired".to_string(),
+            client_secret: Some("a-long-enough-secret".to_string()),
+            expires: Some(Utc::now() - chrono::Duration::minutes(1)),
+        };
+        let response = help_add_credential(&mut app, &token, "user-1", &new_credential).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let expires = Utc::now() + chrono::Duration::days(1);
+        let new_credential = NewCredential {
+            expires: Some(expires),
+            ..new_credential
+        };
+        let response = help_add_credential(&mut app, &token, "user-1", &new_credential).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let user = UserDetails::from(response).await;
+        assert_eq!(
+            user.credentials[0].expires.unwrap().timestamp_millis(),
+            expires.timestamp_millis()
+        );
+
+        // tokens do not outlive the credential
+        let response = help_login(&mut app, "expired", "a-long-enough-secret").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["expires_in"].as_u64().unwrap() <= 24 * 3600);
+
+        sqlx::query!(
+            "UPDATE user_credentials SET expires = now() - interval '1 second' WHERE client_id = 'expired'"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        let response = help_login(&mut app, "expired", "a-long-enough-secret").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
     #[sqlx::test(fixtures("users"))]
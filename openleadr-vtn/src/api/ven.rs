@@ -1,9 +1,22 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
+    async_trait,
+    extract::{FromRef, FromRequestParts, Path, State},
+    http::request::Parts,
+    response::{IntoResponse, Response},
     Json,
 };
+#[cfg(feature = "internal-oauth")]
+use std::net::SocketAddr;
+
+#[cfg(feature = "internal-oauth")]
+use axum::extract::ConnectInfo;
+#[cfg(feature = "internal-oauth")]
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
 use reqwest::StatusCode;
 use serde::Deserialize;
 use tracing::{info, trace};
@@ -14,13 +27,55 @@ use openleadr_wire::{
     ven::{Ven, VenContent, VenId},
 };
 
+#[cfg(feature = "internal-oauth")]
+use crate::{
+    api::registration::{self, REGISTRATION_TOKEN_PREFIX},
+    data_source::RegistrationSource,
+    rate_limit::RateLimits,
+};
 use crate::{
     api::{AppResponse, ValidatedJson, ValidatedQuery},
     data_source::VenCrud,
     error::AppError,
-    jwt::{User, VenManagerUser},
+    jwt::{Claims, JwtManager, User, VenManagerUser},
 };
 
+/// Either a VEN manager, or a VEN registering itself with a registration token
+pub enum VenCreator {
+    Manager(Claims),
+    #[cfg(feature = "internal-oauth")]
+    Registration(String),
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for VenCreator
+where
+    Arc<JwtManager>: FromRef<S>,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        #[cfg(feature = "internal-oauth")]
+        if let Ok(TypedHeader(Authorization(bearer))) =
+            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await
+        {
+            if bearer.token().starts_with(REGISTRATION_TOKEN_PREFIX) {
+                if let Some(rate_limits) = parts.extensions.get::<Arc<RateLimits>>() {
+                    let ip = parts
+                        .extensions
+                        .get::<ConnectInfo<SocketAddr>>()
+                        .map(|ConnectInfo(addr)| addr.ip());
+                    rate_limits.check_registration(ip)?;
+                }
+                return Ok(VenCreator::Registration(bearer.token().to_string()));
+            }
+        }
+
+        let VenManagerUser(user) = VenManagerUser::from_request_parts(parts, state).await?;
+        Ok(VenCreator::Manager(user))
+    }
+}
+
 pub async fn get_all(
     State(ven_source): State<Arc<dyn VenCrud>>,
     ValidatedQuery(query_params): ValidatedQuery<QueryParams>,
@@ -28,9 +83,7 @@ pub async fn get_all(
 ) -> AppResponse<Vec<Ven>> {
     trace!(?query_params);
 
-    let vens = ven_source
-        .retrieve_all(&query_params, &user.try_into()?)
-        .await?;
+    let vens = ven_source.retrieve_all(&query_params, &User(user)).await?;
 
     trace!("retrieved {} VENs", vens.len());
 
@@ -40,25 +93,42 @@ pub async fn get_all(
 pub async fn get(
     State(ven_source): State<Arc<dyn VenCrud>>,
     Path(id): Path<VenId>,
-    User(user): User,
+    user: User,
 ) -> AppResponse<Ven> {
-    let ven = ven_source.retrieve(&id, &user.try_into()?).await?;
+    let ven = ven_source.retrieve(&id, &user).await?;
 
     trace!(%ven.id, ven.ven_name=ven.content.ven_name, "VEN retrieved");
 
     Ok(Json(ven))
 }
 
+#[cfg_attr(
+    not(feature = "internal-oauth"),
+    allow(clippy::infallible_destructuring_match)
+)]
 pub async fn add(
     State(ven_source): State<Arc<dyn VenCrud>>,
-    VenManagerUser(user): VenManagerUser,
+    #[cfg(feature = "internal-oauth")] State(registration_source): State<
+        Arc<dyn RegistrationSource>,
+    >,
+    creator: VenCreator,
     ValidatedJson(new_ven): ValidatedJson<VenContent>,
-) -> Result<(StatusCode, Json<Ven>), AppError> {
-    let ven = ven_source.create(new_ven, &user.try_into()?).await?;
+) -> Result<Response, AppError> {
+    let user = match creator {
+        VenCreator::Manager(user) => user,
+        #[cfg(feature = "internal-oauth")]
+        VenCreator::Registration(token) => {
+            let registered =
+                registration::register(registration_source.as_ref(), &token, new_ven).await?;
+            return Ok((StatusCode::CREATED, Json(registered)).into_response());
+        }
+    };
+
+    let ven = ven_source.create(new_ven, &User(user)).await?;
 
     info!(%ven.id, ven.ven_name=ven.content.ven_name, "VEN added");
 
-    Ok((StatusCode::CREATED, Json(ven)))
+    Ok((StatusCode::CREATED, Json(ven)).into_response())
 }
 
 pub async fn edit(
@@ -67,7 +137,7 @@ pub async fn edit(
     VenManagerUser(user): VenManagerUser,
     ValidatedJson(content): ValidatedJson<VenContent>,
 ) -> AppResponse<Ven> {
-    let ven = ven_source.update(&id, content, &user.try_into()?).await?;
+    let ven = ven_source.update(&id, content, &User(user)).await?;
 
     info!(%ven.id, ven.ven_name=ven.content.ven_name, "VEN updated");
 
@@ -79,7 +149,7 @@ pub async fn delete(
     Path(id): Path<VenId>,
     VenManagerUser(user): VenManagerUser,
 ) -> AppResponse<Ven> {
-    let ven = ven_source.delete(&id, &user.try_into()?).await?;
+    let ven = ven_source.delete(&id, &User(user)).await?;
     info!(%ven.id, ven.ven_name=ven.content.ven_name, "VEN deleted");
     Ok(Json(ven))
 }
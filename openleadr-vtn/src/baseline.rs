@@ -0,0 +1,492 @@
+//! Customer baselines and the reductions resources delivered during events, see [`BaselineMethod`]
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeDelta, Utc, Weekday};
+use openleadr_wire::{
+    baseline::{BaselineMethod, DeliveredReduction, IntervalReduction, ResourceReduction},
+    program::ProgramTimeZone,
+    report::ResourceName,
+    values_map::Value,
+    ven::VenId,
+};
+
+use crate::data_source::UsageHistory;
+
+/// Number of days before the event the usage history has to cover,
+/// enough for `y` weekend days besides days with other events
+pub(crate) fn lookback_days(method: &BaselineMethod) -> u32 {
+    4 * u32::from(method.y) + 7
+}
+
+/// The reported usage of a resource by the start of the reported interval
+type Usage = BTreeMap<DateTime<Utc>, f64>;
+
+/// The reported `USAGE` per resource.
+/// For intervals reported more than once, the last modified report wins.
+fn usage_by_resource(history: &UsageHistory) -> HashMap<(VenId, ResourceName), Usage> {
+    let mut usage: HashMap<(VenId, ResourceName), Usage> = HashMap::new();
+
+    for (ven_id, report) in &history.reports {
+        for resource in &report.content.resources {
+            let entry = usage
+                .entry((ven_id.clone(), resource.resource_name.clone()))
+                .or_default();
+            let spans = resource.interval_spans(&history.time_zone);
+
+            for interval in &resource.intervals {
+                let Some((_, start, _)) = spans.iter().find(|(id, ..)| *id == interval.id) else {
+                    continue;
+                };
+                let value = interval
+                    .payloads
+                    .iter()
+                    .filter(|payload| payload.value_type.0 == "USAGE")
+                    .find_map(|payload| payload.values.first().and_then(Value::as_number));
+                if let Some(value) = value {
+                    entry.insert(*start, value);
+                }
+            }
+        }
+    }
+
+    usage
+}
+
+/// Sum of the usage reported for the intervals starting within `start..end`, `None` if there are none
+fn usage_within(usage: &Usage, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<f64> {
+    if start >= end {
+        return None;
+    }
+    let mut values = usage.range(start..end).map(|(_, value)| *value).peekable();
+    values.peek()?;
+    Some(values.sum())
+}
+
+/// The same local time `days` days earlier
+fn days_earlier(
+    time_zone: &ProgramTimeZone,
+    datetime: &DateTime<Utc>,
+    days: u64,
+) -> Option<DateTime<Utc>> {
+    let local = time_zone
+        .to_local(datetime)
+        .checked_sub_days(Days::new(days))?;
+    time_zone.from_local(&local)
+}
+
+fn is_weekend(day: NaiveDate) -> bool {
+    matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// The mean of the values, `None` if there are none
+fn mean(values: impl ExactSizeIterator<Item = f64>) -> Option<f64> {
+    let count = values.len() as f64;
+    (count > 0.0).then(|| values.sum::<f64>() / count)
+}
+
+/// An eligible day before the event, `days` days earlier, along with its usage during each interval
+struct Day {
+    date: NaiveDate,
+    days: u64,
+    usage: Vec<f64>,
+}
+
+impl Day {
+    fn total(&self) -> f64 {
+        self.usage.iter().sum()
+    }
+}
+
+struct Calculation<'a> {
+    method: &'a BaselineMethod,
+    time_zone: &'a ProgramTimeZone,
+    /// Start and end of the intervals of the event
+    windows: Vec<(i32, DateTime<Utc>, DateTime<Utc>)>,
+    /// Local days with other events of the program
+    event_days: HashSet<NaiveDate>,
+}
+
+impl Calculation<'_> {
+    /// The usage within `start..end` the given number of days earlier
+    fn usage_earlier(
+        &self,
+        usage: &Usage,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+        days: u64,
+    ) -> Option<f64> {
+        usage_within(
+            usage,
+            days_earlier(self.time_zone, start, days)?,
+            days_earlier(self.time_zone, end, days)?,
+        )
+    }
+
+    /// The `y` most recent eligible days, most recent first
+    fn eligible_days(&self, usage: &Usage, event_day: NaiveDate) -> Vec<Day> {
+        let mut eligible = Vec::new();
+
+        for days in 1..=u64::from(lookback_days(self.method)) {
+            if eligible.len() == usize::from(self.method.y) {
+                break;
+            }
+            let Some(date) = event_day.checked_sub_days(Days::new(days)) else {
+                break;
+            };
+            if is_weekend(date) != is_weekend(event_day) || self.event_days.contains(&date) {
+                continue;
+            }
+            let day_usage = self
+                .windows
+                .iter()
+                .map(|(_, start, end)| self.usage_earlier(usage, start, end, days))
+                .collect::<Option<Vec<_>>>();
+            if let Some(day_usage) = day_usage {
+                eligible.push(Day {
+                    date,
+                    days,
+                    usage: day_usage,
+                });
+            }
+        }
+
+        eligible
+    }
+
+    /// Usage during the adjustment hours before the event relative to the baseline of these hours
+    fn adjustment_factor(&self, usage: &Usage, baseline_days: &[Day]) -> Option<f64> {
+        let hours = self.method.adjustment_hours?;
+        let (_, end, _) = self.windows.first()?;
+        let start = *end - TimeDelta::hours(hours.into());
+
+        let actual = usage_within(usage, start, *end)?;
+        let baseline = baseline_days
+            .iter()
+            .map(|day| self.usage_earlier(usage, &start, end, day.days))
+            .collect::<Option<Vec<_>>>()?;
+        // no baseline without enough eligible days
+        let baseline = mean(baseline.into_iter())?;
+        if baseline <= 0.0 {
+            return None;
+        }
+
+        let factor = actual / baseline;
+        Some(match self.method.adjustment_cap {
+            Some(cap) => factor.clamp((1.0 - cap).max(0.0), 1.0 + cap),
+            None => factor,
+        })
+    }
+
+    fn resource(
+        &self,
+        ven_id: VenId,
+        resource_name: ResourceName,
+        usage: &Usage,
+    ) -> ResourceReduction {
+        let mut baseline_days = match self.windows.first() {
+            Some((_, start, _)) => self.eligible_days(usage, self.time_zone.to_local(start).date()),
+            None => vec![],
+        };
+        // the most recent day comes first among days with the same usage, as the sort is stable
+        baseline_days.sort_by(|a, b| b.total().total_cmp(&a.total()));
+        baseline_days.truncate(self.method.x.into());
+        if baseline_days.len() < usize::from(self.method.x) {
+            baseline_days.clear();
+        }
+
+        let adjustment_factor = self.adjustment_factor(usage, &baseline_days);
+        let intervals: Vec<IntervalReduction> = self
+            .windows
+            .iter()
+            .enumerate()
+            .map(|(index, (id, start, end))| {
+                let baseline = mean(baseline_days.iter().map(|day| day.usage[index]))
+                    .map(|baseline| baseline * adjustment_factor.unwrap_or(1.0));
+                let usage = usage_within(usage, *start, *end);
+                IntervalReduction {
+                    id: *id,
+                    start: *start,
+                    end: *end,
+                    baseline,
+                    usage,
+                    reduction: baseline
+                        .zip(usage)
+                        .map(|(baseline, usage)| baseline - usage),
+                }
+            })
+            .collect();
+
+        let reductions: Vec<f64> = intervals.iter().filter_map(|i| i.reduction).collect();
+        let mut days: Vec<NaiveDate> = baseline_days.iter().map(|day| day.date).collect();
+        days.sort();
+
+        ResourceReduction {
+            ven_id,
+            resource_name,
+            baseline_days: days,
+            adjustment_factor,
+            intervals,
+            total_reduction: (!reductions.is_empty()).then(|| reductions.iter().sum()),
+        }
+    }
+}
+
+fn resource_name_as_str(name: &ResourceName) -> &str {
+    match name {
+        ResourceName::AggregatedReport => "AGGREGATED_REPORT",
+        ResourceName::Private(name) => name,
+    }
+}
+
+/// The baseline and reduction of each resource the VENs reported `USAGE` for,
+/// for the intervals of the event that have a start and an end
+pub(crate) fn delivered_reduction(
+    history: &UsageHistory,
+    method: &BaselineMethod,
+) -> DeliveredReduction {
+    let time_zone = &history.time_zone;
+    let calculation = Calculation {
+        method,
+        time_zone,
+        windows: history
+            .event
+            .content
+            .interval_spans(time_zone)
+            .into_iter()
+            .filter_map(|(id, start, end)| Some((id, start, end?)))
+            .collect(),
+        event_days: history
+            .program_event_starts
+            .iter()
+            .map(|start| time_zone.to_local(start).date())
+            .collect(),
+    };
+
+    let mut resources: Vec<ResourceReduction> = usage_by_resource(history)
+        .into_iter()
+        .map(|((ven_id, resource_name), usage)| calculation.resource(ven_id, resource_name, &usage))
+        .collect();
+    resources.sort_by(|a, b| {
+        (a.ven_id.as_str(), resource_name_as_str(&a.resource_name))
+            .cmp(&(b.ven_id.as_str(), resource_name_as_str(&b.resource_name)))
+    });
+
+    DeliveredReduction {
+        event_id: history.event.id.clone(),
+        program_id: history.event.content.program_id.clone(),
+        method: method.clone(),
+        resources,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openleadr_wire::{
+        event::{EventContent, EventInterval},
+        interval::{Interval, IntervalPeriod},
+        report::{ReportContent, ReportResource},
+        values_map::{ValueType, ValuesMap},
+        Duration, Event, Report,
+    };
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    /// An event on Wednesday, 2024-03-13, from 14:00 to 16:00 UTC in two intervals
+    fn event() -> Event {
+        let interval = |id| EventInterval {
+            id,
+            interval_period: None,
+            payloads: vec![],
+        };
+        Event {
+            id: "event-1".parse().unwrap(),
+            created_date_time: at("2024-03-01T00:00:00Z"),
+            modification_date_time: at("2024-03-01T00:00:00Z"),
+            state: None,
+            superseded_by: None,
+            content: EventContent::new(
+                "program-1".parse().unwrap(),
+                vec![interval(0), interval(1)],
+            )
+            .with_interval_period(IntervalPeriod {
+                start: at("2024-03-13T14:00:00Z"),
+                duration: Some(Duration::PT1H),
+                randomize_start: None,
+            }),
+        }
+    }
+
+    /// A report of hourly usage of resource-1 from the given start on
+    fn report(start: &str, usage: &[f64]) -> Report {
+        Report {
+            id: "report-1".parse().unwrap(),
+            created_date_time: at("2024-03-14T00:00:00Z"),
+            modification_date_time: at("2024-03-14T00:00:00Z"),
+            content: ReportContent {
+                program_id: "program-1".parse().unwrap(),
+                event_id: "event-1".parse().unwrap(),
+                client_name: "client".to_string(),
+                report_name: None,
+                payload_descriptors: None,
+                resources: vec![ReportResource {
+                    resource_name: ResourceName::Private("resource-1".to_string()),
+                    interval_period: Some(IntervalPeriod {
+                        start: at(start),
+                        duration: Some(Duration::PT1H),
+                        randomize_start: None,
+                    }),
+                    intervals: usage
+                        .iter()
+                        .enumerate()
+                        .map(|(id, value)| {
+                            Interval::new(
+                                id as i32,
+                                vec![ValuesMap {
+                                    value_type: ValueType("USAGE".to_string()),
+                                    values: vec![Value::Number(*value)],
+                                }],
+                            )
+                        })
+                        .collect(),
+                }],
+            },
+        }
+    }
+
+    fn history(reports: Vec<Report>, program_event_starts: Vec<DateTime<Utc>>) -> UsageHistory {
+        UsageHistory {
+            event: event(),
+            time_zone: ProgramTimeZone::default(),
+            program_event_starts,
+            reports: reports
+                .into_iter()
+                .map(|report| ("ven-1".parse().unwrap(), report))
+                .collect(),
+        }
+    }
+
+    /// Usage from 12:00 to 16:00 on the given day
+    fn day(date: &str, usage: [f64; 4]) -> Report {
+        report(&format!("{date}T12:00:00Z"), &usage)
+    }
+
+    fn high_x_of_y(x: u8, y: u8) -> BaselineMethod {
+        BaselineMethod {
+            x,
+            y,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn high_x_of_y_averages_the_days_with_the_highest_usage() {
+        let history = history(
+            vec![
+                day("2024-03-12", [1.0, 1.0, 4.0, 6.0]),
+                day("2024-03-11", [1.0, 1.0, 2.0, 2.0]),
+                // weekend days do not count for an event on a weekday
+                day("2024-03-10", [1.0, 1.0, 9.0, 9.0]),
+                day("2024-03-08", [1.0, 1.0, 6.0, 8.0]),
+                day("2024-03-07", [1.0, 1.0, 1.0, 1.0]),
+                day("2024-03-13", [1.0, 1.0, 3.0, 5.0]),
+            ],
+            vec![],
+        );
+
+        let reduction = delivered_reduction(&history, &high_x_of_y(2, 3));
+        assert_eq!(reduction.resources.len(), 1);
+        let resource = &reduction.resources[0];
+        assert_eq!(
+            resource.baseline_days,
+            vec![
+                "2024-03-08".parse::<NaiveDate>().unwrap(),
+                "2024-03-12".parse().unwrap()
+            ]
+        );
+        assert_eq!(resource.intervals[0].baseline, Some(5.0));
+        assert_eq!(resource.intervals[0].usage, Some(3.0));
+        assert_eq!(resource.intervals[0].reduction, Some(2.0));
+        assert_eq!(resource.intervals[1].baseline, Some(7.0));
+        assert_eq!(resource.intervals[1].reduction, Some(2.0));
+        assert_eq!(resource.total_reduction, Some(4.0));
+        assert_eq!(resource.adjustment_factor, None);
+    }
+
+    #[test]
+    fn days_with_events_and_without_usage_are_skipped() {
+        let history = history(
+            vec![
+                day("2024-03-12", [1.0, 1.0, 4.0, 6.0]),
+                // usage for one interval only
+                report("2024-03-11T14:00:00Z", &[2.0]),
+                day("2024-03-08", [1.0, 1.0, 6.0, 8.0]),
+                day("2024-03-07", [1.0, 1.0, 1.0, 1.0]),
+            ],
+            vec![at("2024-03-12T09:00:00Z")],
+        );
+
+        let reduction = delivered_reduction(&history, &high_x_of_y(2, 2));
+        let resource = &reduction.resources[0];
+        assert_eq!(
+            resource.baseline_days,
+            vec![
+                "2024-03-07".parse::<NaiveDate>().unwrap(),
+                "2024-03-08".parse().unwrap()
+            ]
+        );
+        assert_eq!(resource.intervals[0].baseline, Some(3.5));
+        // no usage reported during the event
+        assert_eq!(resource.intervals[0].usage, None);
+        assert_eq!(resource.total_reduction, None);
+
+        // fewer than x eligible days
+        let reduction = delivered_reduction(&history, &high_x_of_y(3, 3));
+        let resource = &reduction.resources[0];
+        assert!(resource.baseline_days.is_empty());
+        assert_eq!(resource.intervals[0].baseline, None);
+    }
+
+    #[test]
+    fn day_of_adjustment() {
+        let history = history(
+            vec![
+                day("2024-03-12", [1.0, 1.0, 4.0, 6.0]),
+                day("2024-03-11", [1.0, 1.0, 2.0, 4.0]),
+                // twice the usage before the event
+                day("2024-03-13", [2.0, 2.0, 3.0, 5.0]),
+            ],
+            vec![],
+        );
+
+        let method = BaselineMethod {
+            adjustment_hours: Some(2),
+            ..high_x_of_y(2, 2)
+        };
+        let resource = &delivered_reduction(&history, &method).resources[0];
+        assert_eq!(resource.adjustment_factor, Some(2.0));
+        assert_eq!(resource.intervals[0].baseline, Some(6.0));
+        assert_eq!(resource.intervals[1].baseline, Some(10.0));
+
+        let method = BaselineMethod {
+            adjustment_cap: Some(0.25),
+            ..method
+        };
+        let resource = &delivered_reduction(&history, &method).resources[0];
+        assert_eq!(resource.adjustment_factor, Some(1.25));
+        assert_eq!(resource.intervals[0].baseline, Some(3.75));
+
+        // fewer than x eligible days
+        let method = BaselineMethod {
+            adjustment_hours: Some(2),
+            ..high_x_of_y(3, 3)
+        };
+        let resource = &delivered_reduction(&history, &method).resources[0];
+        assert!(resource.baseline_days.is_empty());
+        assert_eq!(resource.adjustment_factor, None);
+        assert_eq!(resource.intervals[0].baseline, None);
+    }
+}
@@ -0,0 +1,257 @@
+//! Derives the [`AuthRole`]s from the claims of tokens of an external OAuth provider,
+//! see [`ClaimsMapping`].
+
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{
+    config::{ClaimRule, ClaimsMapping, MappedRole},
+    jwt::AuthRole,
+};
+
+impl ClaimsMapping {
+    /// The subject and roles of the token, or `None` if the subject claim is missing
+    pub(crate) fn map(&self, claims: &Value) -> Option<(String, Vec<AuthRole>)> {
+        let subject = match lookup(claims, &self.subject)? {
+            Value::String(subject) => subject.clone(),
+            Value::Number(subject) => subject.to_string(),
+            _ => return None,
+        };
+
+        let mut roles = vec![];
+        for rule in &self.rules {
+            for value in lookup(claims, &rule.claim).map(values).unwrap_or_default() {
+                if let Some(role) = rule.role_for(&value) {
+                    if !roles.contains(&role) {
+                        roles.push(role);
+                    }
+                }
+            }
+        }
+
+        Some((subject, roles))
+    }
+}
+
+impl ClaimRule {
+    fn role_for(&self, value: &str) -> Option<AuthRole> {
+        let remainder = match (&self.value, &self.prefix) {
+            (Some(expected), _) => (value == expected).then_some(value)?,
+            (_, Some(prefix)) => value.strip_prefix(prefix.as_str())?,
+            (None, None) => value,
+        };
+        let id = self.id.as_deref().unwrap_or(remainder);
+
+        let role = match self.role {
+            MappedRole::UserManager => AuthRole::UserManager,
+            MappedRole::VenManager => AuthRole::VenManager,
+            MappedRole::AnyBusiness => AuthRole::AnyBusiness,
+            MappedRole::Business if !id.is_empty() => AuthRole::Business(id.to_string()),
+            MappedRole::Ven => match id.parse() {
+                Ok(ven_id) => AuthRole::VEN(ven_id),
+                Err(_) => {
+                    debug!(claim = self.claim, id, "ignoring invalid VEN id in claim");
+                    return None;
+                }
+            },
+            MappedRole::Business => return None,
+        };
+        Some(role)
+    }
+}
+
+/// Looks up a top-level claim by its full name first, and by the dot separated path otherwise
+fn lookup<'a>(claims: &'a Value, path: &str) -> Option<&'a Value> {
+    claims.get(path).or_else(|| {
+        path.split('.')
+            .try_fold(claims, |claims, segment| claims.get(segment))
+    })
+}
+
+fn values(claim: &Value) -> Vec<String> {
+    match claim {
+        Value::String(value) => value.split_whitespace().map(str::to_string).collect(),
+        Value::Number(value) => vec![value.to_string()],
+        Value::Bool(true) => vec!["true".to_string()],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(|value| match value {
+                Value::String(value) => Some(value.clone()),
+                Value::Number(value) => Some(value.to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::{OAuthConfig, OAuthType},
+        jwt::JwtManager,
+    };
+    use jsonwebtoken::{encode, Algorithm, DecodingKey, EncodingKey, Header};
+    use serde_json::json;
+
+    fn mapping(rules: &str) -> ClaimsMapping {
+        toml::from_str(rules).unwrap()
+    }
+
+    #[test]
+    fn scope() {
+        let mapping = mapping(
+            r#"
+            [[rules]]
+            claim = "scope"
+            value = "openadr:business"
+            role = "ANY_BUSINESS"
+
+            [[rules]]
+            claim = "scope"
+            value = "openadr:vens"
+            role = "VEN_MANAGER"
+            "#,
+        );
+
+        let (sub, roles) = mapping
+            .map(&json!({"sub": "client", "scope": "openid openadr:business"}))
+            .unwrap();
+        assert_eq!(sub, "client");
+        assert_eq!(roles, vec![AuthRole::AnyBusiness]);
+
+        let (_, roles) = mapping
+            .map(&json!({"sub": "client", "scope": "openadr:businesses"}))
+            .unwrap();
+        assert!(roles.is_empty());
+    }
+
+    #[test]
+    fn ids_from_prefixed_groups() {
+        let mapping = mapping(
+            r#"
+            [[rules]]
+            claim = "realm_access.roles"
+            prefix = "ven:"
+            role = "VEN"
+
+            [[rules]]
+            claim = "https://example.com/groups"
+            prefix = "business-"
+            role = "BUSINESS"
+
+            [[rules]]
+            claim = "https://example.com/groups"
+            value = "admins"
+            role = "USER_MANAGER"
+            "#,
+        );
+
+        let (_, roles) = mapping
+            .map(&json!({
+                "sub": "client",
+                "realm_access": {"roles": ["ven:ven-1", "ven:ven-2", "ven:not a ven id", "offline_access"]},
+                "https://example.com/groups": ["business-acme", "admins", "business-"],
+            }))
+            .unwrap();
+        assert_eq!(
+            roles,
+            vec![
+                AuthRole::VEN("ven-1".parse().unwrap()),
+                AuthRole::VEN("ven-2".parse().unwrap()),
+                AuthRole::Business("acme".to_string()),
+                AuthRole::UserManager,
+            ]
+        );
+    }
+
+    #[test]
+    fn fixed_id_and_custom_subject() {
+        let mapping = mapping(
+            r#"
+            subject = "client_id"
+
+            [[rules]]
+            claim = "groups"
+            value = "acme-operators"
+            role = "BUSINESS"
+            id = "acme"
+
+            [[rules]]
+            claim = "ven_id"
+            role = "VEN"
+            "#,
+        );
+
+        let (sub, roles) = mapping
+            .map(&json!({
+                "client_id": "ven-client",
+                "groups": ["acme-operators", "acme-operators"],
+                "ven_id": "ven-1",
+            }))
+            .unwrap();
+        assert_eq!(sub, "ven-client");
+        assert_eq!(
+            roles,
+            vec![
+                AuthRole::Business("acme".to_string()),
+                AuthRole::VEN("ven-1".parse().unwrap()),
+            ]
+        );
+
+        assert!(mapping.map(&json!({"sub": "ven-client"})).is_none());
+    }
+
+    #[tokio::test]
+    async fn decode_mapped_token() {
+        let config = OAuthConfig {
+            oauth_type: OAuthType::External,
+            claims_mapping: Some(mapping(
+                r#"
+                [[rules]]
+                claim = "scope"
+                prefix = "ven:"
+                role = "VEN"
+                "#,
+            )),
+            ..Default::default()
+        };
+        let jwt_manager = JwtManager::external(
+            DecodingKey::from_secret(b"secret"),
+            vec![Algorithm::HS256],
+            &config,
+        );
+        let token = |claims| {
+            encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(b"secret"),
+            )
+            .unwrap()
+        };
+        let exp = chrono::Utc::now().timestamp() + 300;
+
+        let claims = jwt_manager
+            .decode_and_validate(&token(json!({
+                "sub": "ven-client",
+                "exp": exp,
+                "scope": "openid ven:ven-1",
+                // not issued by the internal OAuth provider, so ignored
+                "roles": [{"role": "UserManager"}],
+            })))
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, "ven-client");
+        assert_eq!(claims.roles, vec![AuthRole::VEN("ven-1".parse().unwrap())]);
+
+        assert!(jwt_manager
+            .decode_and_validate(&token(json!({"exp": exp, "scope": "ven:ven-1"})))
+            .await
+            .is_err());
+        assert!(jwt_manager
+            .decode_and_validate(&token(json!({"sub": "ven-client", "exp": 0})))
+            .await
+            .is_err());
+    }
+}
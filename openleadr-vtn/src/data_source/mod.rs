@@ -4,9 +4,14 @@ mod postgres;
 use axum::async_trait;
 use chrono::{DateTime, Utc};
 use openleadr_wire::{
+    batch::BatchUpdate,
+    enrollment::Enrollment,
     event::{EventContent, EventId},
-    program::{ProgramContent, ProgramId},
+    event_template::{EventTemplate, EventTemplateContent, EventTemplateId},
+    opt::{Opt, OptContent},
+    program::{ProgramContent, ProgramId, ProgramTimeZone},
     report::{ReportContent, ReportId},
+    report_summary::ReportSummary,
     resource::{Resource, ResourceContent, ResourceId},
     ven::{Ven, VenContent, VenId},
     Event, Program, Report,
@@ -17,6 +22,7 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::{
+    config::RetentionMode,
     error::AppError,
     jwt::{AuthRole, Claims, User},
 };
@@ -100,6 +106,7 @@ pub trait VenScopedCrud: Send + Sync + 'static {
     ) -> Result<Self::Type, Self::Error>;
 }
 
+#[async_trait]
 pub trait ProgramCrud:
     Crud<
     Type = Program,
@@ -110,7 +117,25 @@ pub trait ProgramCrud:
     PermissionFilter = User,
 >
 {
+    /// Like [`Crud::create`], but assigns the program to the given business
+    /// instead of the one inferred from the user.
+    /// Business users may only choose one of their own businesses.
+    async fn create_for_business(
+        &self,
+        new: ProgramContent,
+        business_id: &str,
+        user: &User,
+    ) -> Result<Program, AppError>;
+    /// Retrieve the program as it was stored at the given point in time,
+    /// including the time zone its events were evaluated in back then.
+    async fn retrieve_as_of(
+        &self,
+        id: &ProgramId,
+        as_of: DateTime<Utc>,
+        user: &User,
+    ) -> Result<Program, AppError>;
 }
+#[async_trait]
 pub trait ReportCrud:
     Crud<
     Type = Report,
@@ -121,7 +146,29 @@ pub trait ReportCrud:
     PermissionFilter = User,
 >
 {
+    /// Aggregates the reports of the event, see [`ReportSummary`]
+    async fn summarize(&self, event_id: &EventId, user: &User) -> Result<ReportSummary, AppError>;
+    /// The reports in the program of the event by the VENs enrolled in it or reporting on the event,
+    /// last modified at most `lookback_days` before the event started
+    async fn usage_history(
+        &self,
+        event_id: &EventId,
+        lookback_days: u32,
+        user: &User,
+    ) -> Result<UsageHistory, AppError>;
 }
+
+/// The input of the baseline calculation, see [`ReportCrud::usage_history`]
+#[derive(Debug, Clone)]
+pub struct UsageHistory {
+    pub event: Event,
+    pub time_zone: ProgramTimeZone,
+    /// Start of the other events of the program that did not get cancelled
+    pub program_event_starts: Vec<DateTime<Utc>>,
+    /// The reports along with the VEN that created them, ordered by their modification
+    pub reports: Vec<(VenId, Report)>,
+}
+#[async_trait]
 pub trait EventCrud:
     Crud<
     Type = Event,
@@ -132,6 +179,46 @@ pub trait EventCrud:
     PermissionFilter = User,
 >
 {
+    /// Create all events in a single transaction.
+    /// If any of them fails, none is stored and [`AppError::Batch`] is returned.
+    async fn create_batch(
+        &self,
+        new: Vec<EventContent>,
+        permission_filter: &User,
+    ) -> Result<Vec<Event>, AppError>;
+    /// Update all events in a single transaction.
+    /// If any of them fails, none is stored and [`AppError::Batch`] is returned.
+    async fn update_batch(
+        &self,
+        updates: Vec<BatchUpdate<EventId, EventContent>>,
+        permission_filter: &User,
+    ) -> Result<Vec<Event>, AppError>;
+    /// Retrieve the event as it was stored at the given point in time,
+    /// even if it got updated or deleted since then.
+    /// The lifecycle state of the returned event is not set.
+    async fn retrieve_as_of(
+        &self,
+        id: &EventId,
+        as_of: DateTime<Utc>,
+        permission_filter: &User,
+    ) -> Result<Event, AppError>;
+    /// Retrieve all versions of the event, ordered from the oldest to the most recent one.
+    /// Each version is valid from its `modification_date_time` on until the next version.
+    /// The lifecycle state of the returned versions is not set.
+    async fn retrieve_versions(
+        &self,
+        id: &EventId,
+        permission_filter: &User,
+    ) -> Result<Vec<Event>, AppError>;
+    /// Cancel the event, such that VENs disregard it, while it stays retrievable.
+    /// If given, the event gets superseded by the event `superseded_by`.
+    /// Returns [`AppError::Conflict`] if the event is already cancelled.
+    async fn cancel(
+        &self,
+        id: &EventId,
+        superseded_by: Option<&EventId>,
+        permission_filter: &User,
+    ) -> Result<Event, AppError>;
 }
 
 pub enum VenPermissions {
@@ -150,10 +237,10 @@ impl VenPermissions {
     }
 }
 
-impl TryFrom<Claims> for VenPermissions {
+impl TryFrom<&Claims> for VenPermissions {
     type Error = AppError;
 
-    fn try_from(claims: Claims) -> Result<Self, Self::Error> {
+    fn try_from(claims: &Claims) -> Result<Self, Self::Error> {
         if claims.is_ven_manager() {
             Ok(VenPermissions::AllAllowed)
         } else if claims.is_ven() {
@@ -173,11 +260,12 @@ pub trait VenCrud:
     NewType = VenContent,
     Error = AppError,
     Filter = crate::api::ven::QueryParams,
-    PermissionFilter = VenPermissions,
+    PermissionFilter = User,
 >
 {
 }
 
+#[async_trait]
 pub trait ResourceCrud:
     VenScopedCrud<
     Type = Resource,
@@ -188,6 +276,91 @@ pub trait ResourceCrud:
     PermissionFilter = User,
 >
 {
+    /// Create all resources of a VEN in a single transaction.
+    /// If any of them fails, none is stored and [`AppError::Batch`] is returned.
+    async fn create_batch(
+        &self,
+        new: Vec<ResourceContent>,
+        ven_id: VenId,
+        permission_filter: &User,
+    ) -> Result<Vec<Resource>, AppError>;
+    /// Update all resources of a VEN in a single transaction.
+    /// If any of them fails, none is stored and [`AppError::Batch`] is returned.
+    async fn update_batch(
+        &self,
+        updates: Vec<BatchUpdate<ResourceId, ResourceContent>>,
+        ven_id: VenId,
+        permission_filter: &User,
+    ) -> Result<Vec<Resource>, AppError>;
+}
+
+/// The kind of mutation recorded in the audit log
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuditOperation {
+    Create,
+    Update,
+    Delete,
+}
+
+/// The type of object a mutation recorded in the audit log applies to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuditObjectType {
+    Program,
+    Event,
+    Report,
+    Ven,
+    Resource,
+    User,
+    Business,
+    Enrollment,
+    VenRegistration,
+    EventTemplate,
+    Opt,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub(crate) id: i64,
+    #[serde(with = "openleadr_wire::serde_rfc3339")]
+    pub(crate) created_date_time: DateTime<Utc>,
+    /// The `sub` claim of the user that performed the mutation
+    pub(crate) actor: String,
+    /// The roles the user had at the time of the mutation
+    pub(crate) roles: Vec<AuthRole>,
+    pub(crate) operation: AuditOperation,
+    pub(crate) object_type: AuditObjectType,
+    #[serde(rename = "objectID")]
+    pub(crate) object_id: String,
+    /// The object before the mutation, `None` for [`AuditOperation::Create`]
+    pub(crate) before: Option<serde_json::Value>,
+    /// The object after the mutation, `None` for [`AuditOperation::Delete`]
+    pub(crate) after: Option<serde_json::Value>,
+}
+
+#[derive(Debug)]
+pub struct NewAuditEntry {
+    pub(crate) actor: String,
+    pub(crate) roles: Vec<AuthRole>,
+    pub(crate) operation: AuditOperation,
+    pub(crate) object_type: AuditObjectType,
+    pub(crate) object_id: String,
+    pub(crate) before: Option<serde_json::Value>,
+    pub(crate) after: Option<serde_json::Value>,
+}
+
+/// Append-only log of all mutations performed via the API.
+///
+/// The entries are recorded by the storage methods performing the mutations,
+/// within the same transaction, such that no mutation is stored without its entry.
+#[async_trait]
+pub trait AuditLog: Send + Sync + 'static {
+    async fn retrieve_all(
+        &self,
+        filter: &crate::api::audit::QueryParams,
+    ) -> Result<Vec<AuditEntry>, AppError>;
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -197,12 +370,25 @@ pub struct UserDetails {
     pub(crate) description: Option<String>,
     pub(crate) roles: Vec<AuthRole>,
     pub(crate) client_ids: Vec<String>,
+    /// Metadata of the credentials, ordered by client ID. The secrets are never returned.
+    pub(crate) credentials: Vec<CredentialDetails>,
     #[serde(with = "openleadr_wire::serde_rfc3339")]
     pub(crate) created: DateTime<Utc>,
     #[serde(with = "openleadr_wire::serde_rfc3339")]
     pub(crate) modified: DateTime<Utc>,
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CredentialDetails {
+    pub(crate) client_id: String,
+    #[serde(with = "openleadr_wire::serde_rfc3339")]
+    pub(crate) created: DateTime<Utc>,
+    /// The credential cannot be used to obtain tokens anymore after this point in time
+    pub(crate) expires: Option<DateTime<Utc>>,
+    /// The last time the credential was used to obtain a token
+    pub(crate) last_used: Option<DateTime<Utc>>,
+}
+
 impl UserDetails {
     pub fn id(&self) -> &str {
         &self.id
@@ -215,41 +401,345 @@ pub trait AuthSource: Send + Sync + 'static {
     async fn check_credentials(&self, client_id: &str, client_secret: &str) -> Option<AuthInfo>;
     async fn get_user(&self, user_id: &str) -> Result<UserDetails, AppError>;
     async fn get_all_users(&self) -> Result<Vec<UserDetails>, AppError>;
+    /// The mutations are recorded in the audit log as performed by the `manager`
     async fn add_user(
         &self,
         reference: &str,
         description: Option<&str>,
         roles: &[AuthRole],
+        manager: &User,
     ) -> Result<UserDetails, AppError>;
     async fn add_credential(
         &self,
         user_id: &str,
         client_id: &str,
         client_secret: &str,
+        expires: Option<DateTime<Utc>>,
+        manager: &User,
     ) -> Result<UserDetails, AppError>;
     async fn remove_credentials(
         &self,
         user_id: &str,
         client_id: &str,
+        manager: &User,
     ) -> Result<UserDetails, AppError>;
-    async fn remove_user(&self, user_id: &str) -> Result<UserDetails, AppError>;
+    async fn remove_user(&self, user_id: &str, manager: &User) -> Result<UserDetails, AppError>;
     async fn edit_user(
         &self,
         user_id: &str,
         reference: &str,
         description: Option<&str>,
         roles: &[AuthRole],
+        manager: &User,
     ) -> Result<UserDetails, AppError>;
 }
 
+/// The enrollments of VENs in programs.
+/// Business users manage the enrollments in the programs of their businesses.
+#[async_trait]
+pub trait EnrollmentSource: Send + Sync + 'static {
+    async fn enroll(
+        &self,
+        program_id: &ProgramId,
+        ven_id: &VenId,
+        user: &User,
+    ) -> Result<Enrollment, AppError>;
+    async fn unenroll(
+        &self,
+        program_id: &ProgramId,
+        ven_id: &VenId,
+        user: &User,
+    ) -> Result<Enrollment, AppError>;
+    /// Enrolls all VENs whose targets match the targets of the program.
+    /// Returns the new enrollments only.
+    async fn enroll_matching(
+        &self,
+        program_id: &ProgramId,
+        user: &User,
+    ) -> Result<Vec<Enrollment>, AppError>;
+    async fn retrieve_by_program(
+        &self,
+        program_id: &ProgramId,
+        user: &User,
+    ) -> Result<Vec<Enrollment>, AppError>;
+    async fn retrieve_by_ven(
+        &self,
+        ven_id: &VenId,
+        user: &User,
+    ) -> Result<Vec<Enrollment>, AppError>;
+}
+
+/// Recurring events, created ahead of time for the occurrences up to `until`.
+/// Business users manage the templates of the programs of their businesses.
+#[async_trait]
+pub trait EventTemplateSource: Send + Sync + 'static {
+    /// Creates the template along with the events of its occurrences from now until `until`
+    async fn create(
+        &self,
+        new: EventTemplateContent,
+        until: DateTime<Utc>,
+        user: &User,
+    ) -> Result<EventTemplate, AppError>;
+    async fn retrieve(&self, id: &EventTemplateId, user: &User) -> Result<EventTemplate, AppError>;
+    async fn retrieve_all(
+        &self,
+        program_id: Option<&ProgramId>,
+        user: &User,
+    ) -> Result<Vec<EventTemplate>, AppError>;
+    /// Replaces the events of the occurrences from now on, the events of past occurrences are kept
+    async fn update(
+        &self,
+        id: &EventTemplateId,
+        new: EventTemplateContent,
+        until: DateTime<Utc>,
+        user: &User,
+    ) -> Result<EventTemplate, AppError>;
+    /// Deletes the events of the occurrences from now on, the events of past occurrences are kept
+    async fn delete(&self, id: &EventTemplateId, user: &User) -> Result<EventTemplate, AppError>;
+    /// Creates the events of all templates for the occurrences from now until `until`
+    /// that have no event yet. Returns the number of events created.
+    async fn materialize(&self, until: DateTime<Utc>) -> Result<usize, AppError>;
+}
+
+/// The opt-in and opt-out statuses of VENs for events.
+/// VENs set the statuses of themselves and their resources,
+/// business users see the statuses for the events of the programs of their businesses.
+#[async_trait]
+pub trait OptSource: Send + Sync + 'static {
+    /// Replaces the status of the same VEN and resource for the event, if any.
+    /// Returns the new status along with the one it replaced.
+    async fn set(
+        &self,
+        event_id: &EventId,
+        new: OptContent,
+        user: &User,
+    ) -> Result<(Opt, Option<Opt>), AppError>;
+    async fn retrieve_all(&self, event_id: &EventId, user: &User) -> Result<Vec<Opt>, AppError>;
+}
+
+/// Number of objects removed by [`RetentionSource::expire`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Expired {
+    pub events: u64,
+    pub reports: u64,
+}
+
+/// Removal of past events and reports, see [`RetentionConfig`](crate::config::RetentionConfig)
+#[async_trait]
+pub trait RetentionSource: Send + Sync + 'static {
+    /// Removes the reports last modified before `reports_before`,
+    /// and afterward the events whose last interval ended before `events_before`
+    /// and that have no reports left.
+    /// Nothing is removed of the object type without a point in time.
+    async fn expire(
+        &self,
+        events_before: Option<DateTime<Utc>>,
+        reports_before: Option<DateTime<Utc>>,
+        mode: RetentionMode,
+    ) -> Result<Expired, AppError>;
+}
+
+/// A tenant of the VTN.
+/// Business users only see the programs of their businesses, and the events and reports thereof.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Business {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    #[serde(with = "openleadr_wire::serde_rfc3339")]
+    pub(crate) created: DateTime<Utc>,
+    #[serde(with = "openleadr_wire::serde_rfc3339")]
+    pub(crate) modified: DateTime<Utc>,
+}
+
+impl Business {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+#[async_trait]
+pub trait BusinessSource: Send + Sync + 'static {
+    async fn get_business(&self, id: &str) -> Result<Business, AppError>;
+    /// All businesses ordered by id, restricted to `ids` if given
+    async fn get_all_businesses(&self, ids: Option<&[String]>) -> Result<Vec<Business>, AppError>;
+    /// The mutations are recorded in the audit log as performed by the `manager`
+    async fn add_business(
+        &self,
+        id: &str,
+        name: &str,
+        description: Option<&str>,
+        manager: &User,
+    ) -> Result<Business, AppError>;
+    async fn edit_business(
+        &self,
+        id: &str,
+        name: &str,
+        description: Option<&str>,
+        manager: &User,
+    ) -> Result<Business, AppError>;
+    /// Fails with a conflict as long as the business owns programs
+    async fn remove_business(&self, id: &str, manager: &User) -> Result<Business, AppError>;
+}
+
+/// A refresh token issued by the internal OAuth provider.
+/// The token itself is not stored, but only its SHA-256 hash.
+#[cfg(feature = "internal-oauth")]
+#[derive(Debug, Clone)]
+pub struct RefreshToken {
+    pub(crate) token_hash: String,
+    pub(crate) client_id: String,
+    pub(crate) scope: String,
+    pub(crate) created: DateTime<Utc>,
+    pub(crate) expires: DateTime<Utc>,
+}
+
+/// Approval of a VEN that registered itself.
+/// Until approved, the VEN role is not part of the tokens issued to the user of the VEN.
+#[cfg(feature = "internal-oauth")]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ApprovalState {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A VEN that registered itself with a registration token
+#[cfg(feature = "internal-oauth")]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VenRegistration {
+    #[serde(rename = "venID")]
+    pub(crate) ven_id: VenId,
+    pub(crate) ven_name: String,
+    /// The user created for the VEN, `None` once the user got removed
+    #[serde(rename = "userID")]
+    pub(crate) user_id: Option<String>,
+    pub(crate) approval_state: ApprovalState,
+    #[serde(with = "openleadr_wire::serde_rfc3339")]
+    pub(crate) created: DateTime<Utc>,
+    #[serde(with = "openleadr_wire::serde_rfc3339")]
+    pub(crate) modified: DateTime<Utc>,
+}
+
+/// A one-time token a VEN registers itself with.
+/// The token itself is not stored, but only its SHA-256 hash.
+#[cfg(feature = "internal-oauth")]
+#[derive(Debug, Clone)]
+pub struct RegistrationToken {
+    pub(crate) token_hash: String,
+    pub(crate) description: Option<String>,
+    /// The `sub` claim of the VEN manager that issued the token
+    pub(crate) created_by: String,
+    pub(crate) created: DateTime<Utc>,
+    pub(crate) expires: DateTime<Utc>,
+}
+
+/// Self-registration of VENs, as an alternative to VEN managers creating VENs and their users
+#[cfg(feature = "internal-oauth")]
+#[async_trait]
+pub trait RegistrationSource: Send + Sync + 'static {
+    async fn add_token(&self, token: &RegistrationToken) -> Result<(), AppError>;
+    /// Consumes the registration token and creates the VEN pending approval,
+    /// along with a user holding the VEN role and the given credential.
+    /// Fails with [`AppError::Auth`] if the token is unknown, expired, or already used.
+    async fn register(
+        &self,
+        token_hash: &str,
+        new: VenContent,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<(Ven, VenRegistration), AppError>;
+    /// Visible to VEN managers and to the user of the VEN
+    async fn retrieve(&self, ven_id: &VenId, user: &User) -> Result<VenRegistration, AppError>;
+    async fn retrieve_all(
+        &self,
+        approval_state: Option<ApprovalState>,
+    ) -> Result<Vec<VenRegistration>, AppError>;
+    /// Revokes the tokens of the user of the VEN unless approved
+    async fn set_approval_state(
+        &self,
+        ven_id: &VenId,
+        approval_state: ApprovalState,
+        manager: &User,
+    ) -> Result<VenRegistration, AppError>;
+}
+
+/// Refresh tokens and the revocation list of the internal OAuth provider
+#[cfg(feature = "internal-oauth")]
+#[async_trait]
+pub trait TokenSource: Send + Sync + 'static {
+    async fn add_refresh_token(&self, token: &RefreshToken) -> Result<(), AppError>;
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshToken>, AppError>;
+    /// Removes the refresh token of the client, such that it can be used only once.
+    /// Returns the token if it existed, belonged to the client, and was not expired.
+    /// Tokens of other clients are left untouched.
+    async fn remove_refresh_token(
+        &self,
+        token_hash: &str,
+        client_id: &str,
+    ) -> Result<Option<RefreshToken>, AppError>;
+    /// Adds the access token to the revocation list until it expires
+    async fn revoke_access_token(&self, jti: &str, expires: DateTime<Utc>) -> Result<(), AppError>;
+    /// Whether the access token is on the revocation list,
+    /// or the tokens of its client issued at `issued_at` are revoked.
+    /// The `iat` claim has a precision of seconds only,
+    /// so tokens issued within the second of the revocation count as revoked.
+    async fn is_revoked(
+        &self,
+        jti: Option<&str>,
+        client_id: &str,
+        issued_at: DateTime<Utc>,
+    ) -> Result<bool, AppError>;
+}
+
+/// Utilization of the connection pool of the storage backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionPoolStatus {
+    /// Connections currently open, both idle and in use
+    pub size: u32,
+    pub idle: u32,
+    pub max: u32,
+}
+
+/// Number of objects per type currently stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectCounts {
+    pub programs: i64,
+    pub events: i64,
+    pub reports: i64,
+    pub vens: i64,
+    pub resources: i64,
+    pub users: i64,
+}
+
+/// Statistics about the storage backend, exposed via the `/metrics` endpoint
+#[async_trait]
+pub trait StorageMetrics: Send + Sync + 'static {
+    fn connection_pool(&self) -> ConnectionPoolStatus;
+    async fn object_counts(&self) -> Result<ObjectCounts, AppError>;
+}
+
 pub trait DataSource: Send + Sync + 'static {
     fn programs(&self) -> Arc<dyn ProgramCrud>;
     fn reports(&self) -> Arc<dyn ReportCrud>;
     fn events(&self) -> Arc<dyn EventCrud>;
     fn vens(&self) -> Arc<dyn VenCrud>;
     fn resources(&self) -> Arc<dyn ResourceCrud>;
+    fn audit_log(&self) -> Arc<dyn AuditLog>;
+    fn businesses(&self) -> Arc<dyn BusinessSource>;
+    fn enrollments(&self) -> Arc<dyn EnrollmentSource>;
+    fn event_templates(&self) -> Arc<dyn EventTemplateSource>;
+    fn retention(&self) -> Arc<dyn RetentionSource>;
+    fn opts(&self) -> Arc<dyn OptSource>;
     #[cfg(feature = "internal-oauth")]
     fn auth(&self) -> Arc<dyn AuthSource>;
+    #[cfg(feature = "internal-oauth")]
+    fn tokens(&self) -> Arc<dyn TokenSource>;
+    #[cfg(feature = "internal-oauth")]
+    fn registrations(&self) -> Arc<dyn RegistrationSource>;
+    fn metrics(&self) -> Arc<dyn StorageMetrics>;
     fn connection_active(&self) -> bool;
 }
 
@@ -258,4 +748,6 @@ pub trait DataSource: Send + Sync + 'static {
 pub struct AuthInfo {
     pub(crate) client_id: String,
     pub(crate) roles: Vec<AuthRole>,
+    /// Expiry of the credential, tokens must not outlive it
+    pub(crate) expires: Option<DateTime<Utc>>,
 }
@@ -0,0 +1,304 @@
+use crate::{
+    api::audit::QueryParams,
+    data_source::{AuditEntry, AuditLog, AuditObjectType, AuditOperation, NewAuditEntry},
+    error::AppError,
+    jwt::Claims,
+};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::{PgConnection, PgPool};
+use tracing::error;
+
+pub(crate) struct PgAuditLog {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgAuditLog {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[derive(Debug)]
+struct PostgresAuditEntry {
+    id: i64,
+    created_date_time: DateTime<Utc>,
+    actor: String,
+    roles: serde_json::Value,
+    operation: String,
+    object_type: String,
+    object_id: String,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+}
+
+impl TryFrom<PostgresAuditEntry> for AuditEntry {
+    type Error = AppError;
+
+    #[tracing::instrument(name = "TryFrom<PostgresAuditEntry> for AuditEntry")]
+    fn try_from(value: PostgresAuditEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            created_date_time: value.created_date_time,
+            actor: value.actor,
+            roles: serde_json::from_value(value.roles)
+                .inspect_err(|err| {
+                    error!(
+                        ?err,
+                        "Failed to deserialize JSON from DB to `Vec<AuthRole>`"
+                    )
+                })
+                .map_err(AppError::SerdeJsonInternalServerError)?,
+            operation: from_text(value.operation)?,
+            object_type: from_text(value.object_type)?,
+            object_id: value.object_id,
+            before: value.before,
+            after: value.after,
+        })
+    }
+}
+
+/// Parses the text representation of [`AuditOperation`] and [`AuditObjectType`] stored in the DB
+fn from_text<T: DeserializeOwned>(value: String) -> Result<T, AppError> {
+    serde_json::from_value(serde_json::Value::String(value))
+        .inspect_err(|err| error!(?err, "Failed to parse audit log entry from DB"))
+        .map_err(AppError::SerdeJsonInternalServerError)
+}
+
+fn operation_as_str(operation: AuditOperation) -> &'static str {
+    match operation {
+        AuditOperation::Create => "CREATE",
+        AuditOperation::Update => "UPDATE",
+        AuditOperation::Delete => "DELETE",
+    }
+}
+
+fn object_type_as_str(object_type: AuditObjectType) -> &'static str {
+    match object_type {
+        AuditObjectType::Program => "PROGRAM",
+        AuditObjectType::Event => "EVENT",
+        AuditObjectType::Report => "REPORT",
+        AuditObjectType::Ven => "VEN",
+        AuditObjectType::Resource => "RESOURCE",
+        AuditObjectType::User => "USER",
+        AuditObjectType::Business => "BUSINESS",
+        AuditObjectType::Enrollment => "ENROLLMENT",
+        AuditObjectType::VenRegistration => "VEN_REGISTRATION",
+        AuditObjectType::EventTemplate => "EVENT_TEMPLATE",
+        AuditObjectType::Opt => "OPT",
+    }
+}
+
+/// Records a mutation in the audit log.
+/// Storage methods call this within the transaction performing the mutation,
+/// such that a mutation is never stored without its entry and the entry describes exactly that mutation.
+pub(super) async fn record(
+    conn: &mut PgConnection,
+    entry: NewAuditEntry,
+) -> Result<AuditEntry, AppError> {
+    sqlx::query_as!(
+        PostgresAuditEntry,
+        r#"
+        INSERT INTO audit_log (actor, roles, operation, object_type, object_id, before, after)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#,
+        entry.actor,
+        serde_json::to_value(&entry.roles).map_err(AppError::SerdeJsonInternalServerError)?,
+        operation_as_str(entry.operation),
+        object_type_as_str(entry.object_type),
+        entry.object_id,
+        entry.before,
+        entry.after,
+    )
+    .fetch_one(conn)
+    .await?
+    .try_into()
+}
+
+pub(super) async fn created<T: Serialize>(
+    conn: &mut PgConnection,
+    actor: &Claims,
+    object_type: AuditObjectType,
+    object_id: impl ToString,
+    after: &T,
+) -> Result<(), AppError> {
+    record_mutation(
+        conn,
+        actor,
+        AuditOperation::Create,
+        object_type,
+        object_id,
+        None,
+        Some(after),
+    )
+    .await
+}
+
+pub(super) async fn updated<T: Serialize>(
+    conn: &mut PgConnection,
+    actor: &Claims,
+    object_type: AuditObjectType,
+    object_id: impl ToString,
+    before: &T,
+    after: &T,
+) -> Result<(), AppError> {
+    record_mutation(
+        conn,
+        actor,
+        AuditOperation::Update,
+        object_type,
+        object_id,
+        Some(before),
+        Some(after),
+    )
+    .await
+}
+
+pub(super) async fn deleted<T: Serialize>(
+    conn: &mut PgConnection,
+    actor: &Claims,
+    object_type: AuditObjectType,
+    object_id: impl ToString,
+    before: &T,
+) -> Result<(), AppError> {
+    record_mutation(
+        conn,
+        actor,
+        AuditOperation::Delete,
+        object_type,
+        object_id,
+        Some(before),
+        None,
+    )
+    .await
+}
+
+async fn record_mutation<T: Serialize>(
+    conn: &mut PgConnection,
+    actor: &Claims,
+    operation: AuditOperation,
+    object_type: AuditObjectType,
+    object_id: impl ToString,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<(), AppError> {
+    let to_value = |value: Option<&T>| {
+        value
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(AppError::SerdeJsonInternalServerError)
+    };
+
+    record(
+        conn,
+        NewAuditEntry {
+            actor: actor.sub.clone(),
+            roles: actor.roles.clone(),
+            operation,
+            object_type,
+            object_id: object_id.to_string(),
+            before: to_value(before)?,
+            after: to_value(after)?,
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl AuditLog for PgAuditLog {
+    async fn retrieve_all(&self, filter: &QueryParams) -> Result<Vec<AuditEntry>, AppError> {
+        sqlx::query_as!(
+            PostgresAuditEntry,
+            r#"
+            SELECT *
+            FROM audit_log
+            WHERE ($1::text IS NULL OR actor = $1)
+              AND ($2::text IS NULL OR operation = $2)
+              AND ($3::text IS NULL OR object_type = $3)
+              AND ($4::text IS NULL OR object_id = $4)
+              AND ($5::timestamptz IS NULL OR created_date_time >= $5)
+              AND ($6::timestamptz IS NULL OR created_date_time < $6)
+            ORDER BY id
+            OFFSET $7 LIMIT $8
+            "#,
+            filter.actor,
+            filter.operation.map(operation_as_str),
+            filter.object_type.map(object_type_as_str),
+            filter.object_id,
+            filter.since,
+            filter.until,
+            filter.skip,
+            filter.limit,
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use super::*;
+    use crate::jwt::AuthRole;
+
+    fn new_entry() -> NewAuditEntry {
+        NewAuditEntry {
+            actor: "user-1".to_string(),
+            roles: vec![AuthRole::AnyBusiness],
+            operation: AuditOperation::Update,
+            object_type: AuditObjectType::Program,
+            object_id: "program-1".to_string(),
+            before: Some(serde_json::json!({"programName": "before"})),
+            after: Some(serde_json::json!({"programName": "after"})),
+        }
+    }
+
+    #[sqlx::test]
+    async fn record(db: PgPool) {
+        let mut conn = db.acquire().await.unwrap();
+
+        let entry = super::record(&mut conn, new_entry()).await.unwrap();
+        assert_eq!(entry.actor, "user-1");
+        assert_eq!(entry.roles, vec![AuthRole::AnyBusiness]);
+        assert_eq!(entry.operation, AuditOperation::Update);
+        assert_eq!(entry.object_type, AuditObjectType::Program);
+        assert_eq!(entry.object_id, "program-1");
+        assert_eq!(entry.before, new_entry().before);
+        assert_eq!(entry.after, new_entry().after);
+    }
+
+    #[sqlx::test]
+    async fn append_only(db: PgPool) {
+        let log: PgAuditLog = db.clone().into();
+        let entry = super::record(&mut db.acquire().await.unwrap(), new_entry())
+            .await
+            .unwrap();
+
+        let update = sqlx::query!("UPDATE audit_log SET actor = 'someone-else'")
+            .execute(&db)
+            .await;
+        assert!(update.is_err());
+
+        let delete = sqlx::query!("DELETE FROM audit_log").execute(&db).await;
+        assert!(delete.is_err());
+
+        let filter = QueryParams {
+            actor: None,
+            operation: None,
+            object_type: None,
+            object_id: None,
+            since: None,
+            until: None,
+            skip: 0,
+            limit: 50,
+        };
+        assert_eq!(log.retrieve_all(&filter).await.unwrap(), vec![entry]);
+    }
+}
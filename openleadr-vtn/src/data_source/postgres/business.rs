@@ -0,0 +1,250 @@
+use crate::{
+    data_source::{postgres::audit, AuditObjectType, Business, BusinessSource},
+    error::AppError,
+    jwt::User,
+};
+use axum::async_trait;
+use sqlx::{PgConnection, PgPool};
+
+pub(crate) struct PgBusinessSource {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgBusinessSource {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+/// Locks the business for the remainder of the transaction
+async fn retrieve_for_update(db: &mut PgConnection, id: &str) -> Result<Business, AppError> {
+    Ok(sqlx::query_as!(
+        Business,
+        r#"
+        SELECT id, name, description, created, modified
+        FROM business
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_one(db)
+    .await?)
+}
+
+#[async_trait]
+impl BusinessSource for PgBusinessSource {
+    async fn get_business(&self, id: &str) -> Result<Business, AppError> {
+        Ok(sqlx::query_as!(
+            Business,
+            r#"
+            SELECT id, name, description, created, modified
+            FROM business
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_one(&self.db)
+        .await?)
+    }
+
+    async fn get_all_businesses(&self, ids: Option<&[String]>) -> Result<Vec<Business>, AppError> {
+        Ok(sqlx::query_as!(
+            Business,
+            r#"
+            SELECT id, name, description, created, modified
+            FROM business
+            WHERE ($1::text[] IS NULL OR id = ANY ($1))
+            ORDER BY id
+            "#,
+            ids
+        )
+        .fetch_all(&self.db)
+        .await?)
+    }
+
+    async fn add_business(
+        &self,
+        id: &str,
+        name: &str,
+        description: Option<&str>,
+        User(manager): &User,
+    ) -> Result<Business, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        let business = sqlx::query_as!(
+            Business,
+            r#"
+            INSERT INTO business (id, name, description, created, modified)
+            VALUES ($1, $2, $3, now(), now())
+            RETURNING id, name, description, created, modified
+            "#,
+            id,
+            name,
+            description
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        audit::created(
+            &mut tx,
+            manager,
+            AuditObjectType::Business,
+            business.id(),
+            &business,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(business)
+    }
+
+    async fn edit_business(
+        &self,
+        id: &str,
+        name: &str,
+        description: Option<&str>,
+        User(manager): &User,
+    ) -> Result<Business, AppError> {
+        let mut tx = self.db.begin().await?;
+        let before = retrieve_for_update(&mut tx, id).await?;
+
+        let business = sqlx::query_as!(
+            Business,
+            r#"
+            UPDATE business
+            SET name = $2,
+                description = $3,
+                modified = now()
+            WHERE id = $1
+            RETURNING id, name, description, created, modified
+            "#,
+            id,
+            name,
+            description
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        audit::updated(
+            &mut tx,
+            manager,
+            AuditObjectType::Business,
+            id,
+            &before,
+            &business,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(business)
+    }
+
+    async fn remove_business(&self, id: &str, User(manager): &User) -> Result<Business, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        let business = sqlx::query_as!(
+            Business,
+            r#"
+            DELETE FROM business
+            WHERE id = $1
+            RETURNING id, name, description, created, modified
+            "#,
+            id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|err| match err {
+            // the assignments to users are removed along with the business, but programs are not
+            sqlx::Error::Database(err) if err.is_foreign_key_violation() => {
+                AppError::Conflict("The business still owns programs".to_string(), Some(err))
+            }
+            err => err.into(),
+        })?;
+        audit::deleted(&mut tx, manager, AuditObjectType::Business, id, &business).await?;
+
+        tx.commit().await?;
+        Ok(business)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use crate::{
+        data_source::{postgres::business::PgBusinessSource, BusinessSource},
+        error::AppError,
+        jwt::{AuthRole, Claims, User},
+    };
+    use sqlx::PgPool;
+
+    fn manager() -> User {
+        User(Claims::new(vec![AuthRole::UserManager]))
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business"))]
+    async fn crud(db: PgPool) {
+        let source: PgBusinessSource = db.into();
+
+        let business = source
+            .add_business(
+                "business-2",
+                "Business 2",
+                Some("second retailer"),
+                &manager(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(business.name, "Business 2");
+
+        let all = source.get_all_businesses(None).await.unwrap();
+        assert_eq!(all.len(), 2);
+        let restricted = source
+            .get_all_businesses(Some(&["business-2".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(restricted, vec![business.clone()]);
+
+        let edited = source
+            .edit_business("business-2", "Retailer 2", None, &manager())
+            .await
+            .unwrap();
+        assert_eq!(edited.name, "Retailer 2");
+        assert_eq!(edited.description, None);
+        assert_eq!(edited.created, business.created);
+
+        assert!(matches!(
+            source
+                .add_business("business-2", "Duplicate", None, &manager())
+                .await,
+            Err(AppError::Conflict(_, _))
+        ));
+
+        let removed = source
+            .remove_business("business-2", &manager())
+            .await
+            .unwrap();
+        assert_eq!(removed, edited);
+        assert!(matches!(
+            source.get_business("business-2").await,
+            Err(AppError::NotFound)
+        ));
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business"))]
+    async fn remove_business_owning_programs(db: PgPool) {
+        let source: PgBusinessSource = db.clone().into();
+
+        assert!(matches!(
+            source.remove_business("business-1", &manager()).await,
+            Err(AppError::Conflict(_, _))
+        ));
+        assert!(source.get_business("business-1").await.is_ok());
+
+        // the audit entry is rolled back along with the removal
+        let entries: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM audit_log WHERE object_id = 'business-1'")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(entries, 0);
+    }
+}
@@ -0,0 +1,387 @@
+use crate::{
+    data_source::{
+        postgres::{audit, extract_business_ids},
+        AuditObjectType, EnrollmentSource,
+    },
+    error::AppError,
+    jwt::{Claims, User},
+};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use openleadr_wire::{enrollment::Enrollment, program::ProgramId, target::TargetMap, ven::VenId};
+use sqlx::PgPool;
+use tracing::{error, trace};
+
+pub(crate) struct PgEnrollmentSource {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgEnrollmentSource {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[derive(Debug)]
+struct PostgresEnrollment {
+    program_id: String,
+    program_name: String,
+    ven_id: String,
+    ven_name: String,
+    created_date_time: DateTime<Utc>,
+}
+
+impl TryFrom<PostgresEnrollment> for Enrollment {
+    type Error = AppError;
+
+    fn try_from(value: PostgresEnrollment) -> Result<Self, Self::Error> {
+        Ok(Self {
+            program_id: value.program_id.parse()?,
+            program_name: value.program_name,
+            ven_id: value.ven_id.parse()?,
+            ven_name: value.ven_name,
+            created_date_time: value.created_date_time,
+        })
+    }
+}
+
+/// Whether the VEN matches all target types of the program,
+/// i.e., has at least one of the values the program lists for each type.
+fn targets_match(program: &TargetMap, ven: &TargetMap) -> bool {
+    program.0.iter().all(|required| {
+        program
+            .0
+            .iter()
+            .filter(|alternative| alternative.label == required.label)
+            .any(|alternative| ven.0.contains(alternative))
+    })
+}
+
+/// The ID of an enrollment in the audit log
+fn audit_id(enrollment: &Enrollment) -> String {
+    format!("{}/{}", enrollment.program_id, enrollment.ven_id)
+}
+
+impl PgEnrollmentSource {
+    /// Returns the targets of the program if the user may manage its enrollments
+    async fn program_targets(
+        &self,
+        program_id: &ProgramId,
+        user: &Claims,
+    ) -> Result<Option<TargetMap>, AppError> {
+        if !user.is_business() {
+            return Err(AppError::Forbidden(
+                "User not authorized to manage the enrollments of this program",
+            ));
+        }
+        let business_ids = extract_business_ids(user);
+
+        let targets = sqlx::query_scalar!(
+            r#"
+            SELECT targets
+            FROM program
+            WHERE id = $1
+              AND ($2::text[] IS NULL OR business_id = ANY ($2))
+            "#,
+            program_id.as_str(),
+            business_ids.as_deref()
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        targets
+            .map(serde_json::from_value)
+            .transpose()
+            .inspect_err(|err| error!(?err, "Failed to deserialize JSON from DB to `TargetMap`"))
+            .map_err(AppError::SerdeJsonInternalServerError)
+    }
+}
+
+#[async_trait]
+impl EnrollmentSource for PgEnrollmentSource {
+    async fn enroll(
+        &self,
+        program_id: &ProgramId,
+        ven_id: &VenId,
+        User(user): &User,
+    ) -> Result<Enrollment, AppError> {
+        self.program_targets(program_id, user).await?;
+
+        let mut tx = self.db.begin().await?;
+
+        let enrollment: Enrollment = sqlx::query_as!(
+            PostgresEnrollment,
+            r#"
+            WITH inserted AS (
+                INSERT INTO ven_program (program_id, ven_id, created_date_time)
+                VALUES ($1, $2, now())
+                RETURNING *
+            )
+            SELECT i.program_id, p.program_name, i.ven_id, v.ven_name, i.created_date_time
+            FROM inserted i
+              JOIN program p ON p.id = i.program_id
+              JOIN ven v ON v.id = i.ven_id
+            "#,
+            program_id.as_str(),
+            ven_id.as_str()
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .try_into()?;
+        audit::created(
+            &mut tx,
+            user,
+            AuditObjectType::Enrollment,
+            audit_id(&enrollment),
+            &enrollment,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(enrollment)
+    }
+
+    async fn unenroll(
+        &self,
+        program_id: &ProgramId,
+        ven_id: &VenId,
+        User(user): &User,
+    ) -> Result<Enrollment, AppError> {
+        self.program_targets(program_id, user).await?;
+
+        let mut tx = self.db.begin().await?;
+
+        let enrollment: Enrollment = sqlx::query_as!(
+            PostgresEnrollment,
+            r#"
+            WITH deleted AS (
+                DELETE FROM ven_program
+                WHERE program_id = $1 AND ven_id = $2
+                RETURNING *
+            )
+            SELECT d.program_id, p.program_name, d.ven_id, v.ven_name, d.created_date_time
+            FROM deleted d
+              JOIN program p ON p.id = d.program_id
+              JOIN ven v ON v.id = d.ven_id
+            "#,
+            program_id.as_str(),
+            ven_id.as_str()
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .try_into()?;
+        audit::deleted(
+            &mut tx,
+            user,
+            AuditObjectType::Enrollment,
+            audit_id(&enrollment),
+            &enrollment,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(enrollment)
+    }
+
+    async fn enroll_matching(
+        &self,
+        program_id: &ProgramId,
+        User(user): &User,
+    ) -> Result<Vec<Enrollment>, AppError> {
+        let program_targets = self
+            .program_targets(program_id, user)
+            .await?
+            .filter(|targets| !targets.0.is_empty())
+            .ok_or(AppError::BadRequest(
+                "The program has no targets to match VENs against",
+            ))?;
+
+        let vens = sqlx::query!(
+            r#"
+            SELECT id, targets AS "targets!"
+            FROM ven
+            WHERE targets IS NOT NULL
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut ven_ids = Vec::new();
+        for ven in vens {
+            let targets: TargetMap = serde_json::from_value(ven.targets)
+                .inspect_err(|err| {
+                    error!(?err, "Failed to deserialize JSON from DB to `TargetMap`")
+                })
+                .map_err(AppError::SerdeJsonInternalServerError)?;
+            if targets_match(&program_targets, &targets) {
+                ven_ids.push(ven.id);
+            }
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let enrollments = sqlx::query_as!(
+            PostgresEnrollment,
+            r#"
+            WITH inserted AS (
+                INSERT INTO ven_program (program_id, ven_id, created_date_time)
+                SELECT $1, ven_id, now() FROM unnest($2::text[]) AS ven_id
+                ON CONFLICT DO NOTHING
+                RETURNING *
+            )
+            SELECT i.program_id, p.program_name, i.ven_id, v.ven_name, i.created_date_time
+            FROM inserted i
+              JOIN program p ON p.id = i.program_id
+              JOIN ven v ON v.id = i.ven_id
+            ORDER BY i.ven_id
+            "#,
+            program_id.as_str(),
+            &ven_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<Vec<Enrollment>, _>>()?;
+        for enrollment in &enrollments {
+            audit::created(
+                &mut tx,
+                user,
+                AuditObjectType::Enrollment,
+                audit_id(enrollment),
+                enrollment,
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        trace!(
+            %program_id,
+            matching = ven_ids.len(),
+            enrolled = enrollments.len(),
+            "enrolled matching VENs in program"
+        );
+        Ok(enrollments)
+    }
+
+    async fn retrieve_by_program(
+        &self,
+        program_id: &ProgramId,
+        User(user): &User,
+    ) -> Result<Vec<Enrollment>, AppError> {
+        // VEN users must not learn about other VENs
+        if !user.is_ven_manager() {
+            self.program_targets(program_id, user).await?;
+        }
+
+        sqlx::query_as!(
+            PostgresEnrollment,
+            r#"
+            SELECT vp.program_id, p.program_name, vp.ven_id, v.ven_name, vp.created_date_time
+            FROM ven_program vp
+              JOIN program p ON p.id = vp.program_id
+              JOIN ven v ON v.id = vp.ven_id
+            WHERE vp.program_id = $1
+            ORDER BY vp.created_date_time, vp.ven_id
+            "#,
+            program_id.as_str()
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+
+    async fn retrieve_by_ven(
+        &self,
+        ven_id: &VenId,
+        User(user): &User,
+    ) -> Result<Vec<Enrollment>, AppError> {
+        if !(user.is_ven_manager() || user.is_ven() || user.is_business()) {
+            return Err(AppError::Forbidden(
+                "User not authorized to access the enrollments of this VEN",
+            ));
+        }
+        let business_ids = extract_business_ids(user);
+
+        sqlx::query_as!(
+            PostgresEnrollment,
+            r#"
+            SELECT vp.program_id, p.program_name, vp.ven_id, v.ven_name, vp.created_date_time
+            FROM ven_program vp
+              JOIN program p ON p.id = vp.program_id
+              JOIN ven v ON v.id = vp.ven_id
+            WHERE vp.ven_id = $1
+              AND (
+                  $2
+                  OR ($3 AND vp.ven_id = ANY ($4))
+                  OR ($5 AND ($6::text[] IS NULL OR p.business_id = ANY ($6)))
+                  )
+            ORDER BY vp.created_date_time, vp.program_id
+            "#,
+            ven_id.as_str(),
+            user.is_ven_manager(),
+            user.is_ven(),
+            &user.ven_ids_string(),
+            user.is_business(),
+            business_ids.as_deref()
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::targets_match;
+    use openleadr_wire::target::{TargetEntry, TargetMap, TargetType};
+
+    fn targets(entries: &[(TargetType, &str)]) -> TargetMap {
+        TargetMap(
+            entries
+                .iter()
+                .map(|(label, value)| TargetEntry {
+                    label: label.clone(),
+                    values: [value.to_string()],
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn match_targets() {
+        let program = targets(&[
+            (TargetType::Group, "group-1"),
+            (TargetType::Group, "group-2"),
+            (TargetType::ServiceArea, "area-1"),
+        ]);
+
+        assert!(targets_match(
+            &program,
+            &targets(&[
+                (TargetType::Group, "group-2"),
+                (TargetType::ServiceArea, "area-1"),
+                (TargetType::ResourceName, "resource-1"),
+            ])
+        ));
+        // missing service area
+        assert!(!targets_match(
+            &program,
+            &targets(&[(TargetType::Group, "group-1")])
+        ));
+        // other group
+        assert!(!targets_match(
+            &program,
+            &targets(&[
+                (TargetType::Group, "group-3"),
+                (TargetType::ServiceArea, "area-1"),
+            ])
+        ));
+        assert!(targets_match(&TargetMap::default(), &TargetMap::default()));
+    }
+}
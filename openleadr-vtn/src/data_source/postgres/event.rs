@@ -1,8 +1,11 @@
 use crate::{
     api::event::QueryParams,
     data_source::{
-        postgres::{extract_business_ids, to_json_value, PgId, PgTargetsFilter},
-        Crud, EventCrud,
+        postgres::{
+            audit, end_savepoint, extract_business_ids, finish_batch, program::time_zone,
+            to_json_value, PgId, PgTargetsFilter,
+        },
+        AuditObjectType, Crud, EventCrud,
     },
     error::AppError,
     jwt::{BusinessIds, Claims, User},
@@ -10,15 +13,194 @@ use crate::{
 use axum::async_trait;
 use chrono::{DateTime, Utc};
 use openleadr_wire::{
-    event::{EventContent, EventId, Priority},
+    batch::BatchUpdate,
+    event::{EventContent, EventId, EventState, Priority},
+    program::{ProgramId, ProgramTimeZone},
     Event,
 };
-use sqlx::PgPool;
+use sqlx::{Acquire, PgConnection, PgPool};
 use std::str::FromStr;
 use tracing::{error, trace};
 
 #[async_trait]
-impl EventCrud for PgEventStorage {}
+impl EventCrud for PgEventStorage {
+    async fn create_batch(
+        &self,
+        new: Vec<EventContent>,
+        User(user): &User,
+    ) -> Result<Vec<Event>, AppError> {
+        let mut tx = self.db.begin().await?;
+        let mut results = Vec::with_capacity(new.len());
+
+        for content in new {
+            let mut savepoint = tx.begin().await?;
+            let result = Self::create_in(&mut savepoint, content, user).await;
+            results.push(end_savepoint(savepoint, result).await?);
+        }
+
+        finish_batch(tx, results).await
+    }
+
+    async fn update_batch(
+        &self,
+        updates: Vec<BatchUpdate<EventId, EventContent>>,
+        User(user): &User,
+    ) -> Result<Vec<Event>, AppError> {
+        let mut tx = self.db.begin().await?;
+        let mut results = Vec::with_capacity(updates.len());
+
+        for BatchUpdate { id, content } in updates {
+            let mut savepoint = tx.begin().await?;
+            let result = Self::update_in(&mut savepoint, &id, content, user).await;
+            results.push(end_savepoint(savepoint, result).await?);
+        }
+
+        finish_batch(tx, results).await
+    }
+
+    async fn retrieve_as_of(
+        &self,
+        id: &EventId,
+        as_of: DateTime<Utc>,
+        User(user): &User,
+    ) -> Result<Event, AppError> {
+        let business_ids = match user.business_ids() {
+            BusinessIds::Specific(ids) => Some(ids),
+            BusinessIds::Any => None,
+        };
+
+        sqlx::query_as!(
+            PostgresEvent,
+            r#"
+            SELECT v.id,
+                   v.created_date_time,
+                   v.modification_date_time,
+                   v.program_id,
+                   v.event_name,
+                   v.priority,
+                   v.targets,
+                   v.report_descriptors,
+                   v.payload_descriptors,
+                   v.interval_period,
+                   v.intervals
+            FROM event_version v
+              JOIN program p ON v.program_id = p.id
+            WHERE v.id = $1
+              AND v.modification_date_time <= $2
+              AND (v.valid_until IS NULL OR v.valid_until > $2)
+              AND (
+                  ($3 AND (NOT EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id)
+                           OR EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id AND vp.ven_id = ANY($4))))
+                  OR
+                  ($5 AND ($6::text[] IS NULL OR p.business_id = ANY ($6)))
+                  )
+            "#,
+            id.as_str(),
+            as_of,
+            user.is_ven(),
+            &user.ven_ids_string(),
+            user.is_business(),
+            business_ids.as_deref(),
+        )
+        .fetch_one(&self.db)
+        .await?
+        .try_into()
+    }
+
+    async fn retrieve_versions(
+        &self,
+        id: &EventId,
+        User(user): &User,
+    ) -> Result<Vec<Event>, AppError> {
+        let business_ids = match user.business_ids() {
+            BusinessIds::Specific(ids) => Some(ids),
+            BusinessIds::Any => None,
+        };
+
+        let versions = sqlx::query_as!(
+            PostgresEvent,
+            r#"
+            SELECT v.id,
+                   v.created_date_time,
+                   v.modification_date_time,
+                   v.program_id,
+                   v.event_name,
+                   v.priority,
+                   v.targets,
+                   v.report_descriptors,
+                   v.payload_descriptors,
+                   v.interval_period,
+                   v.intervals
+            FROM event_version v
+              JOIN program p ON v.program_id = p.id
+            WHERE v.id = $1
+              AND (
+                  ($2 AND (NOT EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id)
+                           OR EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id AND vp.ven_id = ANY($3))))
+                  OR
+                  ($4 AND ($5::text[] IS NULL OR p.business_id = ANY ($5)))
+                  )
+            ORDER BY v.version
+            "#,
+            id.as_str(),
+            user.is_ven(),
+            &user.ven_ids_string(),
+            user.is_business(),
+            business_ids.as_deref(),
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        if versions.is_empty() {
+            return Err(AppError::NotFound);
+        }
+
+        versions.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn cancel(
+        &self,
+        id: &EventId,
+        superseded_by: Option<&EventId>,
+        User(user): &User,
+    ) -> Result<Event, AppError> {
+        if superseded_by == Some(id) {
+            return Err(AppError::BadRequest("An event cannot supersede itself"));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let program_id = sqlx::query_as!(
+            PgId,
+            r#"SELECT program_id AS id FROM event WHERE id = $1"#,
+            id.as_str()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        check_write_permission(&program_id.id, user, &mut tx).await?;
+
+        if let Some(superseded_by) = superseded_by {
+            let Some(replacement) = sqlx::query_as!(
+                PgId,
+                r#"SELECT program_id AS id FROM event WHERE id = $1"#,
+                superseded_by.as_str()
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            else {
+                return Err(AppError::BadRequest("The superseding event does not exist"));
+            };
+            check_write_permission(&replacement.id, user, &mut tx).await?;
+        }
+
+        let event = Self::cancel_in(&mut tx, id, superseded_by, user).await?;
+
+        tx.commit().await?;
+        trace!(%id, ?superseded_by, "cancelled event");
+
+        Ok(event)
+    }
+}
 
 pub(crate) struct PgEventStorage {
     db: PgPool,
@@ -99,6 +281,8 @@ impl TryFrom<PostgresEvent> for Event {
             id: EventId::from_str(&value.id)?,
             created_date_time: value.created_date_time,
             modification_date_time: value.modification_date_time,
+            state: None,
+            superseded_by: None,
             content: EventContent {
                 program_id: value.program_id.parse()?,
                 event_name: value.event_name,
@@ -118,6 +302,7 @@ impl TryFrom<PostgresEvent> for Event {
 struct PostgresFilter<'a> {
     program_id: Option<&'a str>,
     targets: Vec<PgTargetsFilter<'a>>,
+    state: Option<String>,
 
     skip: i64,
     limit: i64,
@@ -127,6 +312,7 @@ impl<'a> From<&'a QueryParams> for PostgresFilter<'a> {
     fn from(query: &'a QueryParams) -> Self {
         let mut filter = Self {
             program_id: query.program_id.as_ref().map(|id| id.as_str()),
+            state: query.state.map(|state| state_as_str(state).to_string()),
             skip: query.skip,
             limit: query.limit,
             ..Default::default()
@@ -147,14 +333,153 @@ impl<'a> From<&'a QueryParams> for PostgresFilter<'a> {
     }
 }
 
+fn state_as_str(state: EventState) -> &'static str {
+    match state {
+        EventState::Scheduled => "SCHEDULED",
+        EventState::Active => "ACTIVE",
+        EventState::Completed => "COMPLETED",
+        EventState::Cancelled => "CANCELLED",
+        EventState::Superseded => "SUPERSEDED",
+    }
+}
+
+struct PostgresEventState {
+    event_id: String,
+    state: String,
+    superseded_by: Option<String>,
+}
+
+/// Sets the current lifecycle state of the events
+async fn with_states(conn: &mut PgConnection, events: Vec<Event>) -> Result<Vec<Event>, AppError> {
+    let ids: Vec<String> = events.iter().map(|e| e.id.as_str().to_string()).collect();
+    let states = retrieve_states(conn, &ids).await?;
+    set_states(events, states)
+}
+
+async fn with_state(conn: &mut PgConnection, event: Event) -> Result<Event, AppError> {
+    Ok(with_states(conn, vec![event]).await?.remove(0))
+}
+
+/// Locks the event for the remainder of the transaction
+async fn retrieve_for_update(conn: &mut PgConnection, id: &EventId) -> Result<Event, AppError> {
+    let event = sqlx::query_as!(
+        PostgresEvent,
+        r#"
+        SELECT * FROM event WHERE id = $1 FOR UPDATE
+        "#,
+        id.as_str()
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .try_into()?;
+
+    with_state(conn, event).await
+}
+
+async fn retrieve_states(
+    conn: &mut PgConnection,
+    ids: &[String],
+) -> Result<Vec<PostgresEventState>, AppError> {
+    Ok(sqlx::query_as!(
+        PostgresEventState,
+        r#"
+        SELECT event_id AS "event_id!", state AS "state!", superseded_by
+        FROM event_state
+        WHERE event_id = ANY($1)
+        "#,
+        ids
+    )
+    .fetch_all(&mut *conn)
+    .await?)
+}
+
+fn set_states(
+    mut events: Vec<Event>,
+    states: Vec<PostgresEventState>,
+) -> Result<Vec<Event>, AppError> {
+    for state in states {
+        let Some(event) = events.iter_mut().find(|e| e.id.as_str() == state.event_id) else {
+            continue;
+        };
+        event.state = Some(
+            serde_json::from_value(serde_json::Value::String(state.state))
+                .inspect_err(|err| {
+                    error!(?err, "Failed to deserialize state from DB to `EventState`")
+                })
+                .map_err(AppError::SerdeJsonInternalServerError)?,
+        );
+        event.superseded_by = state.superseded_by.map(|id| id.parse()).transpose()?;
+    }
+
+    Ok(events)
+}
+
+/// Stores the start and end of the intervals, which the state of the event is derived from
+async fn store_time_span(conn: &mut PgConnection, event: &Event) -> Result<(), AppError> {
+    let time_zone = time_zone(conn, &event.content.program_id).await?;
+    store_time_span_in(conn, event, &time_zone).await
+}
+
+/// Stores the start and end of the intervals of all events of the program again,
+/// as they depend on its time zone
+pub(super) async fn store_time_spans(
+    conn: &mut PgConnection,
+    program_id: &ProgramId,
+) -> Result<(), AppError> {
+    let time_zone = time_zone(conn, program_id).await?;
+    let events = sqlx::query_as!(
+        PostgresEvent,
+        r#"
+        SELECT * FROM event WHERE program_id = $1
+        "#,
+        program_id.as_str()
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for event in events {
+        store_time_span_in(conn, &event.try_into()?, &time_zone).await?;
+    }
+
+    Ok(())
+}
+
+async fn store_time_span_in(
+    conn: &mut PgConnection,
+    event: &Event,
+    time_zone: &ProgramTimeZone,
+) -> Result<(), AppError> {
+    let (starts, ends) = match event.content.time_span(time_zone) {
+        Some((starts, ends)) => (Some(starts), ends),
+        None => (None, None),
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO event_lifecycle (event_id, starts, ends)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (event_id) DO UPDATE SET starts = excluded.starts, ends = excluded.ends
+        "#,
+        event.id.as_str(),
+        starts,
+        ends
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
 struct MaybePgId {
     id: Option<String>,
 }
 
-async fn check_write_permission(
+/// Users with specific businesses may only write events of programs of their businesses,
+/// programs not connected to a business are reserved to users of any business
+pub(super) async fn check_write_permission(
     program_id: &str,
     user: &Claims,
-    db: &PgPool,
+    db: &mut PgConnection,
 ) -> Result<(), AppError> {
     if let Some(business_ids) = extract_business_ids(user) {
         let MaybePgId { id } = sqlx::query_as!(
@@ -164,36 +489,92 @@ async fn check_write_permission(
             "#,
             program_id
         )
-        .fetch_one(db)
+        .fetch_one(&mut *db)
         .await?;
 
-        // If no business is connected, anyone may write
-        if let Some(id) = id {
-            if !business_ids.contains(&id) {
-                Err(AppError::Auth("You do not have write permissions for events belonging to a program that belongs to another business logic".to_string()))?;
-            }
+        if !id.is_some_and(|id| business_ids.contains(&id)) {
+            return Err(AppError::Forbidden(
+                "User not authorized to write events of this program",
+            ));
         }
     };
     Ok(())
 }
 
-#[async_trait]
-impl Crud for PgEventStorage {
-    type Type = Event;
-    type Id = EventId;
-    type NewType = EventContent;
-    type Error = AppError;
-    type Filter = QueryParams;
-    type PermissionFilter = User;
-
-    async fn create(
+impl PgEventStorage {
+    async fn retrieve_all_as_of(
         &self,
-        new: Self::NewType,
-        User(user): &Self::PermissionFilter,
-    ) -> Result<Self::Type, Self::Error> {
-        check_write_permission(new.program_id.as_str(), user, &self.db).await?;
+        pg_filter: &PostgresFilter<'_>,
+        as_of: DateTime<Utc>,
+        user: &Claims,
+        business_ids: Option<&[String]>,
+    ) -> Result<Vec<Event>, AppError> {
+        sqlx::query_as!(
+            PostgresEvent,
+            r#"
+            SELECT v.id,
+                   v.created_date_time,
+                   v.modification_date_time,
+                   v.program_id,
+                   v.event_name,
+                   v.priority,
+                   v.targets,
+                   v.report_descriptors,
+                   v.payload_descriptors,
+                   v.interval_period,
+                   v.intervals
+            FROM event_version v
+              JOIN program p ON p.id = v.program_id
+            WHERE v.modification_date_time <= $1
+              AND (v.valid_until IS NULL OR v.valid_until > $1)
+              AND ($2::text IS NULL OR v.program_id like $2)
+              AND ($3::jsonb = '[]'::jsonb OR EXISTS (
+                  SELECT 1 FROM jsonb_array_elements(v.targets) t WHERE jsonb_build_array(t) <@ $3::jsonb
+                  ))
+              AND (
+                  ($4 AND (NOT EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id)
+                           OR EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id AND vp.ven_id = ANY($5))))
+                  OR
+                  ($6 AND ($7::text[] IS NULL OR p.business_id = ANY ($7)))
+                  )
+            ORDER BY v.priority ASC, v.created_date_time DESC
+            OFFSET $8 LIMIT $9
+            "#,
+            as_of,
+            pg_filter.program_id,
+            serde_json::to_value(&pg_filter.targets)
+                .map_err(AppError::SerdeJsonInternalServerError)?,
+            user.is_ven(),
+            &user.ven_ids_string(),
+            user.is_business(),
+            business_ids,
+            pg_filter.skip,
+            pg_filter.limit
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
 
-        Ok(sqlx::query_as!(
+    async fn create_in(
+        conn: &mut PgConnection,
+        new: EventContent,
+        user: &Claims,
+    ) -> Result<Event, AppError> {
+        check_write_permission(new.program_id.as_str(), user, conn).await?;
+        let event = Self::insert(conn, new).await?;
+        audit::created(conn, user, AuditObjectType::Event, &event.id, &event).await?;
+        Ok(event)
+    }
+
+    /// Inserts the event without checking the permissions of a user
+    pub(super) async fn insert(
+        conn: &mut PgConnection,
+        new: EventContent,
+    ) -> Result<Event, AppError> {
+        let event: Event = sqlx::query_as!(
             PostgresEvent,
             r#"
             INSERT INTO event (id, created_date_time, modification_date_time, program_id, event_name, priority, targets, report_descriptors, payload_descriptors, interval_period, intervals)
@@ -209,10 +590,154 @@ impl Crud for PgEventStorage {
             to_json_value(new.interval_period)?,
             serde_json::to_value(&new.intervals).map_err(AppError::SerdeJsonBadRequest)?,
         )
-            .fetch_one(&self.db)
+            .fetch_one(&mut *conn)
             .await?
-            .try_into()?
+            .try_into()?;
+
+        store_time_span(conn, &event).await?;
+        with_state(conn, event).await
+    }
+
+    /// Cancels the event without checking the permissions of a user
+    pub(super) async fn cancel_in(
+        conn: &mut PgConnection,
+        id: &EventId,
+        superseded_by: Option<&EventId>,
+        actor: &Claims,
+    ) -> Result<Event, AppError> {
+        let before = retrieve_for_update(conn, id).await?;
+
+        let cancelled = sqlx::query!(
+            r#"
+            INSERT INTO event_lifecycle (event_id, cancelled_date_time, superseded_by)
+            VALUES ($1, now(), $2)
+            ON CONFLICT (event_id) DO UPDATE
+                SET cancelled_date_time = excluded.cancelled_date_time,
+                    superseded_by = excluded.superseded_by
+                WHERE event_lifecycle.cancelled_date_time IS NULL
+            "#,
+            id.as_str(),
+            superseded_by.map(EventId::as_str),
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        if cancelled.rows_affected() == 0 {
+            return Err(AppError::Conflict(
+                "The event is already cancelled".to_string(),
+                None,
+            ));
+        }
+
+        // VENs polling for changes notice the cancellation by the modification date time
+        let event: Event = sqlx::query_as!(
+            PostgresEvent,
+            r#"
+            UPDATE event SET modification_date_time = now() WHERE id = $1 RETURNING *
+            "#,
+            id.as_str()
+        )
+        .fetch_one(&mut *conn)
+        .await?
+        .try_into()?;
+        let event = with_state(conn, event).await?;
+        audit::updated(conn, actor, AuditObjectType::Event, id, &before, &event).await?;
+
+        Ok(event)
+    }
+
+    /// Deletes the events without checking the permissions of a user
+    pub(super) async fn remove(
+        conn: &mut PgConnection,
+        ids: &[String],
+    ) -> Result<Vec<Event>, AppError> {
+        // the state is gone together with the event
+        let states = retrieve_states(conn, ids).await?;
+        let events = sqlx::query_as!(
+            PostgresEvent,
+            r#"
+            DELETE FROM event WHERE id = ANY($1) RETURNING *
+            "#,
+            ids
         )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<Vec<_>, _>>()?;
+        set_states(events, states)
+    }
+
+    async fn update_in(
+        conn: &mut PgConnection,
+        id: &EventId,
+        new: EventContent,
+        user: &Claims,
+    ) -> Result<Event, AppError> {
+        check_write_permission(new.program_id.as_str(), user, conn).await?;
+
+        let before = retrieve_for_update(conn, id).await?;
+
+        // make sure, you cannot 'steal' an event from another business
+        if before.content.program_id != new.program_id {
+            check_write_permission(before.content.program_id.as_str(), user, conn).await?;
+        }
+
+        let event: Event = sqlx::query_as!(
+            PostgresEvent,
+            r#"
+            UPDATE event
+            SET modification_date_time = now(),
+                program_id = $2,
+                event_name = $3,
+                priority = $4,
+                targets = $5,
+                report_descriptors = $6,
+                payload_descriptors = $7,
+                interval_period = $8,
+                intervals = $9
+            WHERE id = $1
+            RETURNING *
+            "#,
+            id.as_str(),
+            new.program_id.as_str(),
+            new.event_name,
+            Into::<Option<i64>>::into(new.priority),
+            to_json_value(new.targets)?,
+            to_json_value(new.report_descriptors)?,
+            to_json_value(new.payload_descriptors)?,
+            to_json_value(new.interval_period)?,
+            serde_json::to_value(&new.intervals).map_err(AppError::SerdeJsonBadRequest)?,
+        )
+        .fetch_one(&mut *conn)
+        .await?
+        .try_into()?;
+
+        store_time_span(conn, &event).await?;
+        let event = with_state(conn, event).await?;
+        audit::updated(conn, user, AuditObjectType::Event, id, &before, &event).await?;
+        Ok(event)
+    }
+}
+
+#[async_trait]
+impl Crud for PgEventStorage {
+    type Type = Event;
+    type Id = EventId;
+    type NewType = EventContent;
+    type Error = AppError;
+    type Filter = QueryParams;
+    type PermissionFilter = User;
+
+    async fn create(
+        &self,
+        new: Self::NewType,
+        User(user): &Self::PermissionFilter,
+    ) -> Result<Self::Type, Self::Error> {
+        let mut tx = self.db.begin().await?;
+        let event = Self::create_in(&mut tx, new, user).await?;
+        tx.commit().await?;
+        Ok(event)
     }
 
     async fn retrieve(
@@ -225,7 +750,8 @@ impl Crud for PgEventStorage {
             BusinessIds::Any => None,
         };
 
-        Ok(sqlx::query_as!(
+        let mut conn = self.db.acquire().await?;
+        let event = sqlx::query_as!(
             PostgresEvent,
             r#"
             SELECT e.*
@@ -245,9 +771,11 @@ impl Crud for PgEventStorage {
             user.is_business(),
             business_ids.as_deref(),
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *conn)
         .await?
-        .try_into()?)
+        .try_into()?;
+
+        with_state(&mut conn, event).await
     }
 
     async fn retrieve_all(
@@ -263,7 +791,14 @@ impl Crud for PgEventStorage {
             BusinessIds::Any => None,
         };
 
-        Ok(sqlx::query_as!(
+        if let Some(as_of) = filter.as_of {
+            return self
+                .retrieve_all_as_of(&pg_filter, as_of, user, business_ids.as_deref())
+                .await;
+        }
+
+        let mut conn = self.db.acquire().await?;
+        let events = sqlx::query_as!(
             PostgresEvent,
             r#"
             SELECT e.*
@@ -281,6 +816,7 @@ impl Crud for PgEventStorage {
                   OR 
                   ($5 AND ($6::text[] IS NULL OR p.business_id = ANY ($6)))
                   )
+              AND ($9::text IS NULL OR EXISTS (SELECT 1 FROM event_state s WHERE s.event_id = e.id AND s.state = $9))
             GROUP BY e.id, e.priority, e.created_date_time
             ORDER BY priority ASC , created_date_time DESC
             OFFSET $7 LIMIT $8
@@ -293,13 +829,16 @@ impl Crud for PgEventStorage {
             user.is_business(),
             business_ids.as_deref(),
             pg_filter.skip,
-            pg_filter.limit
+            pg_filter.limit,
+            pg_filter.state
         )
-        .fetch_all(&self.db)
+        .fetch_all(&mut *conn)
         .await?
         .into_iter()
         .map(TryInto::try_into)
-        .collect::<Result<_, _>>()?)
+        .collect::<Result<_, _>>()?;
+
+        with_states(&mut conn, events).await
     }
 
     async fn update(
@@ -308,50 +847,10 @@ impl Crud for PgEventStorage {
         new: Self::NewType,
         User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
-        check_write_permission(new.program_id.as_str(), user, &self.db).await?;
-
-        let previous_program_id = sqlx::query_as!(
-            PgId,
-            r#"SELECT program_id AS id FROM event WHERE id = $1"#,
-            id.as_str()
-        )
-        .fetch_one(&self.db)
-        .await?;
-
-        // make sure, you cannot 'steal' an event from another business
-        if previous_program_id.id != new.program_id.as_str() {
-            check_write_permission(&previous_program_id.id, user, &self.db).await?;
-        }
-
-        Ok(sqlx::query_as!(
-            PostgresEvent,
-            r#"
-            UPDATE event
-            SET modification_date_time = now(),
-                program_id = $2,
-                event_name = $3,
-                priority = $4,
-                targets = $5,
-                report_descriptors = $6,
-                payload_descriptors = $7,
-                interval_period = $8,
-                intervals = $9
-            WHERE id = $1
-            RETURNING *
-            "#,
-            id.as_str(),
-            new.program_id.as_str(),
-            new.event_name,
-            Into::<Option<i64>>::into(new.priority),
-            to_json_value(new.targets)?,
-            to_json_value(new.report_descriptors)?,
-            to_json_value(new.payload_descriptors)?,
-            to_json_value(new.interval_period)?,
-            serde_json::to_value(&new.intervals).map_err(AppError::SerdeJsonBadRequest)?,
-        )
-        .fetch_one(&self.db)
-        .await?
-        .try_into()?)
+        let mut tx = self.db.begin().await?;
+        let event = Self::update_in(&mut tx, id, new, user).await?;
+        tx.commit().await?;
+        Ok(event)
     }
 
     async fn delete(
@@ -359,26 +858,28 @@ impl Crud for PgEventStorage {
         id: &Self::Id,
         User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
+        let mut tx = self.db.begin().await?;
+
         let program_id = sqlx::query_as!(
             PgId,
             r#"SELECT program_id AS id FROM event WHERE id = $1"#,
             id.as_str()
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *tx)
         .await?;
 
-        check_write_permission(&program_id.id, user, &self.db).await?;
+        check_write_permission(&program_id.id, user, &mut tx).await?;
 
-        Ok(sqlx::query_as!(
-            PostgresEvent,
-            r#"
-            DELETE FROM event WHERE id = $1 RETURNING *
-            "#,
-            id.as_str()
-        )
-        .fetch_one(&self.db)
-        .await?
-        .try_into()?)
+        let Some(event) = Self::remove(&mut tx, &[id.as_str().to_string()])
+            .await?
+            .pop()
+        else {
+            return Err(AppError::NotFound);
+        };
+        audit::deleted(&mut tx, user, AuditObjectType::Event, id, &event).await?;
+        tx.commit().await?;
+
+        Ok(event)
     }
 }
 
@@ -389,13 +890,13 @@ mod tests {
 
     use crate::{
         api::event::QueryParams,
-        data_source::{postgres::event::PgEventStorage, Crud},
+        data_source::{postgres::event::PgEventStorage, Crud, EventCrud},
         error::AppError,
-        jwt::{Claims, User},
+        jwt::{AuthRole, Claims, User},
     };
     use chrono::{DateTime, Duration, Utc};
     use openleadr_wire::{
-        event::{EventContent, EventInterval, EventType, EventValuesMap},
+        event::{EventContent, EventInterval, EventState, EventType, EventValuesMap},
         interval::IntervalPeriod,
         target::{TargetEntry, TargetMap, TargetType},
         values_map::Value,
@@ -410,6 +911,8 @@ mod tests {
                 target_values: None,
                 skip: 0,
                 limit: 50,
+                as_of: None,
+                state: None,
             }
         }
     }
@@ -419,6 +922,8 @@ mod tests {
             id: "event-1".parse().unwrap(),
             created_date_time: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
             modification_date_time: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
+            state: Some(EventState::Completed),
+            superseded_by: None,
             content: EventContent {
                 program_id: "program-1".parse().unwrap(),
                 event_name: Some("event-1-name".to_string()),
@@ -461,6 +966,8 @@ mod tests {
             id: "event-2".parse().unwrap(),
             created_date_time: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
             modification_date_time: "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
+            state: Some(EventState::Active),
+            superseded_by: None,
             content: EventContent {
                 program_id: "program-2".parse().unwrap(),
                 event_name: Some("event-2-name".to_string()),
@@ -690,6 +1197,42 @@ mod tests {
                 .unwrap();
             assert_eq!(events.len(), 0);
         }
+
+        #[sqlx::test(fixtures("programs", "events"))]
+        async fn filter_state_get_all(db: PgPool) {
+            let repo: PgEventStorage = db.into();
+
+            let events = repo
+                .retrieve_all(
+                    &QueryParams {
+                        state: Some(EventState::Completed),
+                        ..Default::default()
+                    },
+                    &User(Claims::any_business_user()),
+                )
+                .await
+                .unwrap();
+            assert_eq!(events, vec![event_1()]);
+
+            repo.cancel(
+                &"event-2".parse().unwrap(),
+                None,
+                &User(Claims::any_business_user()),
+            )
+            .await
+            .unwrap();
+            let events = repo
+                .retrieve_all(
+                    &QueryParams {
+                        state: Some(EventState::Active),
+                        ..Default::default()
+                    },
+                    &User(Claims::any_business_user()),
+                )
+                .await
+                .unwrap();
+            assert_eq!(events, vec![event_3()]);
+        }
     }
 
     mod get {
@@ -736,6 +1279,24 @@ mod tests {
             assert!(event.created_date_time > Utc::now() - Duration::minutes(10));
             assert!(event.modification_date_time < Utc::now() + Duration::minutes(10));
             assert!(event.modification_date_time > Utc::now() - Duration::minutes(10));
+            assert_eq!(event.state, Some(EventState::Completed));
+        }
+
+        #[sqlx::test(fixtures("programs"))]
+        async fn add_scheduled(db: PgPool) {
+            let repo: PgEventStorage = db.into();
+            let mut content = event_2().content;
+            content.interval_period = Some(IntervalPeriod {
+                start: Utc::now() + Duration::days(1),
+                duration: Some("PT1H".parse().unwrap()),
+                randomize_start: None,
+            });
+
+            let event = repo
+                .create(content, &User(Claims::any_business_user()))
+                .await
+                .unwrap();
+            assert_eq!(event.state, Some(EventState::Scheduled));
         }
 
         #[sqlx::test(fixtures("programs", "events"))]
@@ -856,4 +1417,95 @@ mod tests {
             assert!(matches!(event, Err(AppError::NotFound)));
         }
     }
+
+    mod cancel {
+        use super::*;
+
+        #[sqlx::test(fixtures("programs", "events"))]
+        async fn cancel(db: PgPool) {
+            let repo: PgEventStorage = db.into();
+            let event = repo
+                .cancel(
+                    &"event-1".parse().unwrap(),
+                    None,
+                    &User(Claims::any_business_user()),
+                )
+                .await
+                .unwrap();
+            assert_eq!(event.state, Some(EventState::Cancelled));
+            assert_eq!(event.content, event_1().content);
+            assert!(event.modification_date_time > Utc::now() - Duration::minutes(10));
+
+            // the event stays available
+            let retrieved = repo
+                .retrieve(
+                    &"event-1".parse().unwrap(),
+                    &User(Claims::any_business_user()),
+                )
+                .await
+                .unwrap();
+            assert_eq!(retrieved, event);
+
+            let again = repo
+                .cancel(
+                    &"event-1".parse().unwrap(),
+                    None,
+                    &User(Claims::any_business_user()),
+                )
+                .await;
+            assert!(matches!(again, Err(AppError::Conflict(_, _))));
+        }
+
+        #[sqlx::test(fixtures("programs", "events"))]
+        async fn supersede(db: PgPool) {
+            let repo: PgEventStorage = db.into();
+            let event = repo
+                .cancel(
+                    &"event-1".parse().unwrap(),
+                    Some(&"event-2".parse().unwrap()),
+                    &User(Claims::any_business_user()),
+                )
+                .await
+                .unwrap();
+            assert_eq!(event.state, Some(EventState::Superseded));
+            assert_eq!(event.superseded_by, Some("event-2".parse().unwrap()));
+        }
+
+        #[sqlx::test(fixtures("programs", "events"))]
+        async fn invalid_replacement(db: PgPool) {
+            let repo: PgEventStorage = db.into();
+            let itself = repo
+                .cancel(
+                    &"event-1".parse().unwrap(),
+                    Some(&"event-1".parse().unwrap()),
+                    &User(Claims::any_business_user()),
+                )
+                .await;
+            assert!(matches!(itself, Err(AppError::BadRequest(_))));
+
+            let not_existent = repo
+                .cancel(
+                    &"event-1".parse().unwrap(),
+                    Some(&"not-existent".parse().unwrap()),
+                    &User(Claims::any_business_user()),
+                )
+                .await;
+            assert!(matches!(not_existent, Err(AppError::BadRequest(_))));
+        }
+
+        #[sqlx::test(fixtures("users", "programs", "events", "business"))]
+        async fn cancel_other_business(db: PgPool) {
+            let repo: PgEventStorage = db.into();
+            let event = repo
+                .cancel(
+                    &"event-3".parse().unwrap(),
+                    None,
+                    &User(Claims::new(vec![AuthRole::Business(
+                        "business-2".to_string(),
+                    )])),
+                )
+                .await;
+            assert!(matches!(event, Err(AppError::Forbidden(_))));
+        }
+    }
 }
@@ -0,0 +1,683 @@
+use crate::{
+    data_source::{
+        postgres::{
+            audit, event::check_write_permission, event::PgEventStorage, extract_business_ids,
+            program::time_zone,
+        },
+        AuditObjectType, EventTemplateSource,
+    },
+    error::AppError,
+    jwt::{Claims, User},
+};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use openleadr_wire::{
+    event::EventId,
+    event_template::{EventTemplate, EventTemplateContent, EventTemplateId},
+    program::ProgramId,
+};
+use sqlx::{PgConnection, PgPool};
+use tracing::{error, info};
+
+pub(crate) struct PgEventTemplateSource {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgEventTemplateSource {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[derive(Debug)]
+struct PostgresEventTemplate {
+    id: String,
+    created_date_time: DateTime<Utc>,
+    modification_date_time: DateTime<Utc>,
+    content: serde_json::Value,
+}
+
+impl TryFrom<PostgresEventTemplate> for EventTemplate {
+    type Error = AppError;
+
+    fn try_from(value: PostgresEventTemplate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id.parse()?,
+            created_date_time: value.created_date_time,
+            modification_date_time: value.modification_date_time,
+            content: serde_json::from_value(value.content)
+                .inspect_err(|err| {
+                    error!(
+                        ?err,
+                        "Failed to deserialize JSON from DB to `EventTemplateContent`"
+                    )
+                })
+                .map_err(AppError::SerdeJsonInternalServerError)?,
+        })
+    }
+}
+
+impl PgEventTemplateSource {
+    /// Creates the events of the occurrences within `from..until` that have no event yet,
+    /// recording `actor` as their creator in the audit log
+    async fn materialize_in(
+        conn: &mut PgConnection,
+        template: &EventTemplate,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        actor: &Claims,
+    ) -> Result<usize, AppError> {
+        let time_zone = time_zone(conn, &template.content.program_id).await?;
+
+        let existing = sqlx::query_scalar!(
+            r#"
+            SELECT occurrence
+            FROM event_template_occurrence
+            WHERE template_id = $1
+              AND occurrence >= $2
+              AND occurrence < $3
+            "#,
+            template.id.as_str(),
+            from,
+            until
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut created = 0;
+        for occurrence in template.content.occurrences(&time_zone, from, until) {
+            if existing.contains(&occurrence) {
+                continue;
+            }
+
+            let event = PgEventStorage::insert(conn, template.content.event_at(occurrence)).await?;
+            audit::created(conn, actor, AuditObjectType::Event, &event.id, &event).await?;
+            sqlx::query!(
+                r#"
+                INSERT INTO event_template_occurrence (template_id, occurrence, event_id)
+                VALUES ($1, $2, $3)
+                "#,
+                template.id.as_str(),
+                occurrence,
+                event.id.as_str()
+            )
+            .execute(&mut *conn)
+            .await?;
+            created += 1;
+        }
+
+        Ok(created)
+    }
+
+    /// Detaches the events of the occurrences from `from` on from the template,
+    /// such that they can be created again from the current template.
+    /// Occurrences whose event got deleted or cancelled before are kept, such that they stay so.
+    /// Returns the occurrences along with their former events, see [`Self::cancel_detached`].
+    async fn detach_future_events(
+        conn: &mut PgConnection,
+        id: &EventTemplateId,
+        from: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, EventId)>, AppError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM event_template_occurrence o
+            WHERE o.template_id = $1
+              AND o.occurrence >= $2
+              AND o.event_id IS NOT NULL
+              AND NOT EXISTS (SELECT 1
+                              FROM event_lifecycle l
+                              WHERE l.event_id = o.event_id
+                                AND l.cancelled_date_time IS NOT NULL)
+            RETURNING o.occurrence, o.event_id AS "event_id!"
+            "#,
+            id.as_str(),
+            from
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| Ok((row.occurrence, row.event_id.parse()?)))
+        .collect()
+    }
+
+    /// Cancels the detached events, such that VENs notice they are void,
+    /// superseded by the event the template created for the same occurrence, if any
+    async fn cancel_detached(
+        conn: &mut PgConnection,
+        id: &EventTemplateId,
+        detached: Vec<(DateTime<Utc>, EventId)>,
+        actor: &Claims,
+    ) -> Result<(), AppError> {
+        for (occurrence, event_id) in detached {
+            let replacement = sqlx::query_scalar!(
+                r#"
+                SELECT event_id
+                FROM event_template_occurrence
+                WHERE template_id = $1
+                  AND occurrence = $2
+                "#,
+                id.as_str(),
+                occurrence
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+            .flatten()
+            .map(|id| id.parse())
+            .transpose()?;
+
+            PgEventStorage::cancel_in(conn, &event_id, replacement.as_ref(), actor).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Locks the template for the remainder of the transaction
+    async fn retrieve_for_update(
+        conn: &mut PgConnection,
+        id: &EventTemplateId,
+        business_ids: Option<&[String]>,
+    ) -> Result<EventTemplate, AppError> {
+        sqlx::query_as!(
+            PostgresEventTemplate,
+            r#"
+            SELECT t.id, t.created_date_time, t.modification_date_time, t.content
+            FROM event_template t
+              JOIN program p ON p.id = t.program_id
+            WHERE t.id = $1
+              AND ($2::text[] IS NULL OR p.business_id = ANY ($2))
+            FOR UPDATE OF t
+            "#,
+            id.as_str(),
+            business_ids
+        )
+        .fetch_one(&mut *conn)
+        .await?
+        .try_into()
+    }
+}
+
+#[async_trait]
+impl EventTemplateSource for PgEventTemplateSource {
+    async fn create(
+        &self,
+        new: EventTemplateContent,
+        until: DateTime<Utc>,
+        User(user): &User,
+    ) -> Result<EventTemplate, AppError> {
+        let mut tx = self.db.begin().await?;
+        check_write_permission(new.program_id.as_str(), user, &mut tx).await?;
+
+        let template: EventTemplate = sqlx::query_as!(
+            PostgresEventTemplate,
+            r#"
+            INSERT INTO event_template (id, created_date_time, modification_date_time, program_id, content)
+            VALUES (gen_random_uuid(), now(), now(), $1, $2)
+            RETURNING id, created_date_time, modification_date_time, content
+            "#,
+            new.program_id.as_str(),
+            serde_json::to_value(&new).map_err(AppError::SerdeJsonBadRequest)?
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .try_into()?;
+
+        Self::materialize_in(&mut tx, &template, Utc::now(), until, user).await?;
+        audit::created(
+            &mut tx,
+            user,
+            AuditObjectType::EventTemplate,
+            &template.id,
+            &template,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(template)
+    }
+
+    async fn retrieve(
+        &self,
+        id: &EventTemplateId,
+        User(user): &User,
+    ) -> Result<EventTemplate, AppError> {
+        let business_ids = extract_business_ids(user);
+
+        sqlx::query_as!(
+            PostgresEventTemplate,
+            r#"
+            SELECT t.id, t.created_date_time, t.modification_date_time, t.content
+            FROM event_template t
+              JOIN program p ON p.id = t.program_id
+            WHERE t.id = $1
+              AND ($2::text[] IS NULL OR p.business_id = ANY ($2))
+            "#,
+            id.as_str(),
+            business_ids.as_deref()
+        )
+        .fetch_one(&self.db)
+        .await?
+        .try_into()
+    }
+
+    async fn retrieve_all(
+        &self,
+        program_id: Option<&ProgramId>,
+        User(user): &User,
+    ) -> Result<Vec<EventTemplate>, AppError> {
+        let business_ids = extract_business_ids(user);
+
+        sqlx::query_as!(
+            PostgresEventTemplate,
+            r#"
+            SELECT t.id, t.created_date_time, t.modification_date_time, t.content
+            FROM event_template t
+              JOIN program p ON p.id = t.program_id
+            WHERE ($1::text IS NULL OR t.program_id = $1)
+              AND ($2::text[] IS NULL OR p.business_id = ANY ($2))
+            ORDER BY t.created_date_time, t.id
+            "#,
+            program_id.map(ProgramId::as_str),
+            business_ids.as_deref()
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+
+    async fn update(
+        &self,
+        id: &EventTemplateId,
+        new: EventTemplateContent,
+        until: DateTime<Utc>,
+        User(user): &User,
+    ) -> Result<EventTemplate, AppError> {
+        let business_ids = extract_business_ids(user);
+        let mut tx = self.db.begin().await?;
+
+        let previous = Self::retrieve_for_update(&mut tx, id, business_ids.as_deref()).await?;
+        check_write_permission(previous.content.program_id.as_str(), user, &mut tx).await?;
+        // make sure, you cannot move a template to a program of another business
+        if previous.content.program_id != new.program_id {
+            check_write_permission(new.program_id.as_str(), user, &mut tx).await?;
+        }
+
+        let template: EventTemplate = sqlx::query_as!(
+            PostgresEventTemplate,
+            r#"
+            UPDATE event_template
+            SET modification_date_time = now(),
+                program_id = $2,
+                content = $3
+            WHERE id = $1
+            RETURNING id, created_date_time, modification_date_time, content
+            "#,
+            id.as_str(),
+            new.program_id.as_str(),
+            serde_json::to_value(&new).map_err(AppError::SerdeJsonBadRequest)?
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .try_into()?;
+
+        let now = Utc::now();
+        let detached = Self::detach_future_events(&mut tx, id, now).await?;
+        Self::materialize_in(&mut tx, &template, now, until, user).await?;
+        Self::cancel_detached(&mut tx, id, detached, user).await?;
+        audit::updated(
+            &mut tx,
+            user,
+            AuditObjectType::EventTemplate,
+            id,
+            &previous,
+            &template,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(template)
+    }
+
+    async fn delete(
+        &self,
+        id: &EventTemplateId,
+        User(user): &User,
+    ) -> Result<EventTemplate, AppError> {
+        let business_ids = extract_business_ids(user);
+        let mut tx = self.db.begin().await?;
+
+        let template = Self::retrieve_for_update(&mut tx, id, business_ids.as_deref()).await?;
+        check_write_permission(template.content.program_id.as_str(), user, &mut tx).await?;
+
+        let detached = Self::detach_future_events(&mut tx, id, Utc::now()).await?;
+        Self::cancel_detached(&mut tx, id, detached, user).await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM event_template WHERE id = $1
+            "#,
+            id.as_str()
+        )
+        .execute(&mut *tx)
+        .await?;
+        audit::deleted(&mut tx, user, AuditObjectType::EventTemplate, id, &template).await?;
+
+        tx.commit().await?;
+        Ok(template)
+    }
+
+    async fn materialize(&self, until: DateTime<Utc>) -> Result<usize, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        // templates locked by a concurrent update are materialized by that update
+        let templates = sqlx::query_as!(
+            PostgresEventTemplate,
+            r#"
+            SELECT id, created_date_time, modification_date_time, content
+            FROM event_template
+            ORDER BY id
+            FOR UPDATE SKIP LOCKED
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(TryInto::<EventTemplate>::try_into)
+        .collect::<Result<Vec<_>, _>>()?;
+
+        let now = Utc::now();
+        let system = Claims::system();
+        let mut created = 0;
+        for template in &templates {
+            let count = Self::materialize_in(&mut tx, template, now, until, &system).await?;
+            if count > 0 {
+                info!(%template.id, count, "created events from template");
+            }
+            created += count;
+        }
+
+        tx.commit().await?;
+        Ok(created)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use crate::{
+        data_source::{
+            postgres::{event::PgEventStorage, event_template::PgEventTemplateSource},
+            Crud, EventCrud, EventTemplateSource,
+        },
+        error::AppError,
+        jwt::{AuthRole, Claims, User},
+    };
+    use chrono::{Duration, Utc};
+    use openleadr_wire::{
+        event::{EventId, EventState},
+        event_template::EventTemplateContent,
+    };
+    use sqlx::PgPool;
+
+    fn daily_at_noon(program_id: &str, event_name: &str) -> EventTemplateContent {
+        let start_date = (Utc::now() - Duration::days(1)).date_naive();
+        serde_json::from_value(serde_json::json!({
+            "programID": program_id,
+            "recurrence": {
+                "frequency": "DAILY",
+                "startTime": "12:00:00",
+                "startDate": start_date,
+            },
+            "intervalDuration": "PT1H",
+            "eventName": event_name,
+            "intervals": [{"id": 0, "payloads": [{"type": "PRICE", "values": [0.25]}]}]
+        }))
+        .unwrap()
+    }
+
+    /// The names of the events that are not cancelled
+    async fn event_names(db: &PgPool) -> Vec<Option<String>> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT e.event_name
+            FROM event e
+              LEFT JOIN event_lifecycle l ON l.event_id = e.id
+            WHERE l.cancelled_date_time IS NULL
+            ORDER BY e.event_name
+            "#
+        )
+        .fetch_all(db)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(fixtures("programs"))]
+    async fn materialize_ahead(db: PgPool) {
+        let source: PgEventTemplateSource = db.clone().into();
+        let user = User(Claims::any_business_user());
+
+        source
+            .create(
+                daily_at_noon("program-1", "noon"),
+                Utc::now() + Duration::days(3),
+                &user,
+            )
+            .await
+            .unwrap();
+        assert_eq!(event_names(&db).await.len(), 3);
+
+        let until = Utc::now() + Duration::days(5);
+        assert_eq!(source.materialize(until).await.unwrap(), 2);
+        assert_eq!(source.materialize(until).await.unwrap(), 0);
+        assert_eq!(event_names(&db).await.len(), 5);
+
+        // the events created by the background job are recorded on behalf of the system
+        let actors: Vec<String> = sqlx::query_scalar(
+            "SELECT actor FROM audit_log WHERE object_type = 'EVENT' AND operation = 'CREATE' ORDER BY id",
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(actors.len(), 5);
+        assert_eq!(actors[3..], ["system", "system"]);
+
+        // deleted events are not created again
+        sqlx::query!("DELETE FROM event WHERE id = (SELECT id FROM event LIMIT 1)")
+            .execute(&db)
+            .await
+            .unwrap();
+        assert_eq!(source.materialize(until).await.unwrap(), 0);
+        assert_eq!(event_names(&db).await.len(), 4);
+    }
+
+    #[sqlx::test(fixtures("programs"))]
+    async fn changes_apply_to_future_occurrences(db: PgPool) {
+        let source: PgEventTemplateSource = db.clone().into();
+        let user = User(Claims::any_business_user());
+        let until = Utc::now() + Duration::days(2);
+
+        let template = source
+            .create(daily_at_noon("program-1", "before"), until, &user)
+            .await
+            .unwrap();
+        // make the first occurrence one that already started
+        sqlx::query!(
+            r#"
+            UPDATE event_template_occurrence
+            SET occurrence = occurrence - interval '10 days'
+            WHERE occurrence = (SELECT min(occurrence) FROM event_template_occurrence)
+            "#
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        source
+            .update(
+                &template.id,
+                daily_at_noon("program-1", "after"),
+                until,
+                &user,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            event_names(&db).await,
+            vec![
+                Some("after".to_string()),
+                Some("after".to_string()),
+                Some("before".to_string())
+            ]
+        );
+
+        source.delete(&template.id, &user).await.unwrap();
+        assert_eq!(event_names(&db).await, vec![Some("before".to_string())]);
+        // all created events but the past one got cancelled by the update or the deletion
+        let count = |operation: &'static str| {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT count(*) FROM audit_log WHERE object_type = 'EVENT' AND operation = $1",
+            )
+            .bind(operation)
+            .fetch_one(&db)
+        };
+        assert_eq!(
+            count("UPDATE").await.unwrap(),
+            count("CREATE").await.unwrap() - 1
+        );
+        assert_eq!(count("DELETE").await.unwrap(), 0);
+        assert!(matches!(
+            source.retrieve(&template.id, &user).await,
+            Err(AppError::NotFound)
+        ));
+    }
+
+    #[sqlx::test(fixtures("programs"))]
+    async fn deleted_occurrences_stay_deleted_after_changes(db: PgPool) {
+        let source: PgEventTemplateSource = db.clone().into();
+        let user = User(Claims::any_business_user());
+        let until = Utc::now() + Duration::days(3);
+
+        let template = source
+            .create(daily_at_noon("program-1", "before"), until, &user)
+            .await
+            .unwrap();
+        let created = event_names(&db).await.len();
+
+        // e.g., no event on a holiday
+        sqlx::query!(
+            r#"
+            DELETE FROM event
+            WHERE id = (SELECT event_id FROM event_template_occurrence ORDER BY occurrence DESC LIMIT 1)
+            "#
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        source
+            .update(
+                &template.id,
+                daily_at_noon("program-1", "after"),
+                until,
+                &user,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            event_names(&db).await,
+            vec![Some("after".to_string()); created - 1]
+        );
+        assert_eq!(source.materialize(until).await.unwrap(), 0);
+        assert_eq!(event_names(&db).await.len(), created - 1);
+    }
+
+    #[sqlx::test(fixtures("programs"))]
+    async fn cancelled_occurrences_stay_cancelled_after_changes(db: PgPool) {
+        let source: PgEventTemplateSource = db.clone().into();
+        let events: PgEventStorage = db.clone().into();
+        let user = User(Claims::any_business_user());
+        let until = Utc::now() + Duration::days(3);
+
+        let template = source
+            .create(daily_at_noon("program-1", "before"), until, &user)
+            .await
+            .unwrap();
+        let created = event_names(&db).await.len();
+
+        let last: EventId = sqlx::query_scalar!(
+            "SELECT event_id FROM event_template_occurrence ORDER BY occurrence DESC LIMIT 1"
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap()
+        .unwrap()
+        .parse()
+        .unwrap();
+        events.cancel(&last, None, &user).await.unwrap();
+
+        source
+            .update(
+                &template.id,
+                daily_at_noon("program-1", "after"),
+                until,
+                &user,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            event_names(&db).await,
+            vec![Some("after".to_string()); created - 1]
+        );
+        assert_eq!(source.materialize(until).await.unwrap(), 0);
+
+        let cancelled = events.retrieve(&last, &user).await.unwrap();
+        assert_eq!(cancelled.state, Some(EventState::Cancelled));
+        assert_eq!(cancelled.content.event_name.as_deref(), Some("before"));
+
+        // the other events are superseded by those of the changed template
+        let superseded: i64 = sqlx::query_scalar(
+            "SELECT count(*) FROM event_lifecycle WHERE superseded_by IS NOT NULL",
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(superseded, created as i64 - 1);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business"))]
+    async fn business_permissions(db: PgPool) {
+        let source: PgEventTemplateSource = db.into();
+        let until = Utc::now() + Duration::days(1);
+        let business_1 = User(Claims::new(vec![AuthRole::Business(
+            "business-1".to_string(),
+        )]));
+        let business_2 = User(Claims::new(vec![AuthRole::Business(
+            "business-2".to_string(),
+        )]));
+
+        assert!(matches!(
+            source
+                .create(daily_at_noon("program-3", "noon"), until, &business_2)
+                .await,
+            Err(AppError::Forbidden(_))
+        ));
+        let template = source
+            .create(daily_at_noon("program-3", "noon"), until, &business_1)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            source.retrieve_all(None, &business_1).await.unwrap(),
+            vec![template.clone()]
+        );
+        assert!(source
+            .retrieve_all(None, &business_2)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            source.delete(&template.id, &business_2).await,
+            Err(AppError::NotFound)
+        ));
+    }
+}
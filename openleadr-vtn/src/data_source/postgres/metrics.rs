@@ -0,0 +1,65 @@
+use crate::{
+    data_source::{ConnectionPoolStatus, ObjectCounts, StorageMetrics},
+    error::AppError,
+};
+use axum::async_trait;
+use sqlx::PgPool;
+
+pub(crate) struct PgStorageMetrics {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgStorageMetrics {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl StorageMetrics for PgStorageMetrics {
+    fn connection_pool(&self) -> ConnectionPoolStatus {
+        ConnectionPoolStatus {
+            size: self.db.size(),
+            idle: self.db.num_idle() as u32,
+            max: self.db.options().get_max_connections(),
+        }
+    }
+
+    async fn object_counts(&self) -> Result<ObjectCounts, AppError> {
+        Ok(sqlx::query_as!(
+            ObjectCounts,
+            r#"
+            SELECT (SELECT count(*) FROM program)  AS "programs!",
+                   (SELECT count(*) FROM event)    AS "events!",
+                   (SELECT count(*) FROM report)   AS "reports!",
+                   (SELECT count(*) FROM ven)      AS "vens!",
+                   (SELECT count(*) FROM resource) AS "resources!",
+                   (SELECT count(*) FROM "user")   AS "users!"
+            "#
+        )
+        .fetch_one(&self.db)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(fixtures("users", "programs", "events", "vens"))]
+    async fn object_counts(db: PgPool) {
+        let metrics: PgStorageMetrics = db.into();
+
+        let counts = metrics.object_counts().await.unwrap();
+        assert_eq!(counts.programs, 3);
+        assert_eq!(counts.events, 3);
+        assert_eq!(counts.reports, 0);
+        assert_eq!(counts.vens, 2);
+
+        let pool = metrics.connection_pool();
+        assert!(pool.size >= 1);
+        assert!(pool.idle <= pool.size);
+        assert!(pool.size <= pool.max);
+    }
+}
@@ -1,13 +1,20 @@
 #[cfg(feature = "internal-oauth")]
-use crate::data_source::{postgres::user::PgAuthSource, AuthSource};
+use crate::data_source::{
+    postgres::{registration::PgRegistrationSource, token::PgTokenSource, user::PgAuthSource},
+    AuthSource, RegistrationSource, TokenSource,
+};
 
 use crate::{
+    config::DatabaseConfig,
     data_source::{
         postgres::{
-            event::PgEventStorage, program::PgProgramStorage, report::PgReportStorage,
-            ven::PgVenStorage,
+            audit::PgAuditLog, business::PgBusinessSource, enrollment::PgEnrollmentSource,
+            event::PgEventStorage, event_template::PgEventTemplateSource,
+            metrics::PgStorageMetrics, opt::PgOptSource, program::PgProgramStorage,
+            report::PgReportStorage, retention::PgRetentionSource, ven::PgVenStorage,
         },
-        DataSource, EventCrud, ProgramCrud, ReportCrud, ResourceCrud, VenCrud,
+        AuditLog, BusinessSource, DataSource, EnrollmentSource, EventCrud, EventTemplateSource,
+        OptSource, ProgramCrud, ReportCrud, ResourceCrud, RetentionSource, StorageMetrics, VenCrud,
     },
     error::AppError,
     jwt::{BusinessIds, Claims},
@@ -16,14 +23,25 @@ use dotenvy::dotenv;
 use openleadr_wire::target::{TargetMap, TargetType};
 use resource::PgResourceStorage;
 use serde::Serialize;
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use sqlx::{postgres::PgPoolOptions, PgPool, Postgres, Transaction};
 use std::sync::Arc;
 use tracing::{error, info, trace};
 
+mod audit;
+mod business;
+mod enrollment;
 mod event;
+mod event_template;
+mod metrics;
+mod opt;
 mod program;
+#[cfg(feature = "internal-oauth")]
+mod registration;
 mod report;
 mod resource;
+mod retention;
+#[cfg(feature = "internal-oauth")]
+mod token;
 #[cfg(feature = "internal-oauth")]
 mod user;
 mod ven;
@@ -54,11 +72,49 @@ impl DataSource for PostgresStorage {
         Arc::<PgResourceStorage>::new(self.db.clone().into())
     }
 
+    fn audit_log(&self) -> Arc<dyn AuditLog> {
+        Arc::<PgAuditLog>::new(self.db.clone().into())
+    }
+
+    fn businesses(&self) -> Arc<dyn BusinessSource> {
+        Arc::<PgBusinessSource>::new(self.db.clone().into())
+    }
+
+    fn enrollments(&self) -> Arc<dyn EnrollmentSource> {
+        Arc::<PgEnrollmentSource>::new(self.db.clone().into())
+    }
+
+    fn event_templates(&self) -> Arc<dyn EventTemplateSource> {
+        Arc::<PgEventTemplateSource>::new(self.db.clone().into())
+    }
+
+    fn retention(&self) -> Arc<dyn RetentionSource> {
+        Arc::<PgRetentionSource>::new(self.db.clone().into())
+    }
+
+    fn opts(&self) -> Arc<dyn OptSource> {
+        Arc::<PgOptSource>::new(self.db.clone().into())
+    }
+
     #[cfg(feature = "internal-oauth")]
     fn auth(&self) -> Arc<dyn AuthSource> {
         Arc::<PgAuthSource>::new(self.db.clone().into())
     }
 
+    #[cfg(feature = "internal-oauth")]
+    fn tokens(&self) -> Arc<dyn TokenSource> {
+        Arc::<PgTokenSource>::new(self.db.clone().into())
+    }
+
+    #[cfg(feature = "internal-oauth")]
+    fn registrations(&self) -> Arc<dyn RegistrationSource> {
+        Arc::<PgRegistrationSource>::new(self.db.clone().into())
+    }
+
+    fn metrics(&self) -> Arc<dyn StorageMetrics> {
+        Arc::<PgStorageMetrics>::new(self.db.clone().into())
+    }
+
     /// Verify the connection pool is open and has at least one connection
     fn connection_active(&self) -> bool {
         !self.db.is_closed() && self.db.size() > 0
@@ -70,15 +126,35 @@ impl PostgresStorage {
         Ok(Self { db })
     }
 
+    /// Connects to the database given by the `DATABASE_URL` environment variable, which may also be set in a `.env` file
     pub async fn from_env() -> Result<Self, sqlx::Error> {
         dotenv().ok();
-        let db_url = std::env::var("DATABASE_URL")
-            .expect("Missing DATABASE_URL env var even though the 'postgres' feature is active");
+        let url = std::env::var("DATABASE_URL").map_err(|_| {
+            sqlx::Error::Configuration(
+                "Missing DATABASE_URL env var even though the 'postgres' feature is active".into(),
+            )
+        })?;
+
+        Self::from_config(&DatabaseConfig {
+            url: Some(url),
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn from_config(config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
+        let db_url = config.url.as_deref().ok_or_else(|| {
+            sqlx::Error::Configuration(
+                "Missing database URL even though the 'postgres' feature is active".into(),
+            )
+        })?;
 
         let db = PgPoolOptions::new()
-            .min_connections(1)
-            .connect(&db_url)
-            .await?;
+            .min_connections(config.min_connections)
+            .max_connections(config.max_connections)
+            .connect(db_url)
+            .await
+            .inspect_err(|err| error!(?err, "could not connect to Postgres database"))?;
 
         let connect_options = db.connect_options();
         let safe_db_url = format!(
@@ -88,15 +164,43 @@ impl PostgresStorage {
             connect_options.get_database().unwrap_or_default()
         );
 
-        Self::new(db)
-            .inspect_err(|err| error!(?err, "could not connect to Postgres database"))
-            .inspect(|_| {
-                info!(
-                    "Successfully connected to Postgres backend at {}",
-                    safe_db_url
-                )
-            })
+        Self::new(db).inspect(|_| {
+            info!(
+                "Successfully connected to Postgres backend at {}",
+                safe_db_url
+            )
+        })
+    }
+}
+
+/// Releases the savepoint of a single batch item if the item succeeded, or rolls it back otherwise.
+/// Rolling back the savepoint keeps the surrounding transaction usable for the remaining items.
+async fn end_savepoint<T>(
+    savepoint: Transaction<'_, Postgres>,
+    result: Result<T, AppError>,
+) -> Result<Result<T, AppError>, AppError> {
+    match result {
+        Ok(_) => savepoint.commit().await?,
+        Err(_) => savepoint.rollback().await?,
     }
+    Ok(result)
+}
+
+/// Commits the batch transaction if all items succeeded.
+/// Otherwise, the whole transaction is rolled back and the errors are reported per item.
+async fn finish_batch<T>(
+    tx: Transaction<'_, Postgres>,
+    results: Vec<Result<T, AppError>>,
+) -> Result<Vec<T>, AppError> {
+    if results.iter().any(Result::is_err) {
+        tx.rollback().await?;
+        return Err(AppError::Batch(
+            results.into_iter().map(Result::err).collect(),
+        ));
+    }
+
+    tx.commit().await?;
+    results.into_iter().collect()
 }
 
 fn to_json_value<T: Serialize>(v: Option<T>) -> Result<Option<serde_json::Value>, AppError> {
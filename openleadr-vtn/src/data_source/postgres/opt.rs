@@ -0,0 +1,407 @@
+use crate::{
+    data_source::{
+        postgres::{audit, extract_business_ids},
+        AuditObjectType, OptSource,
+    },
+    error::AppError,
+    jwt::User,
+};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use openleadr_wire::{
+    event::EventId,
+    opt::{IntervalRange, Opt, OptContent, OptType},
+};
+use sqlx::PgPool;
+use tracing::{error, info};
+
+pub(crate) struct PgOptSource {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgOptSource {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[derive(Debug)]
+struct PostgresOpt {
+    id: String,
+    created_date_time: DateTime<Utc>,
+    modification_date_time: DateTime<Utc>,
+    event_id: String,
+    ven_id: String,
+    resource_id: Option<String>,
+    opt_type: String,
+    interval_first: Option<i32>,
+    interval_last: Option<i32>,
+    reason: Option<String>,
+}
+
+impl TryFrom<PostgresOpt> for Opt {
+    type Error = AppError;
+
+    fn try_from(value: PostgresOpt) -> Result<Self, Self::Error> {
+        let opt_type = serde_json::from_value(serde_json::Value::String(value.opt_type))
+            .inspect_err(|err| error!(?err, "Failed to deserialize opt type from DB to `OptType`"))
+            .map_err(AppError::SerdeJsonInternalServerError)?;
+
+        Ok(Self {
+            id: value.id.parse()?,
+            created_date_time: value.created_date_time,
+            modification_date_time: value.modification_date_time,
+            event_id: value.event_id.parse()?,
+            content: OptContent {
+                ven_id: value.ven_id.parse()?,
+                resource_id: value.resource_id.map(|id| id.parse()).transpose()?,
+                opt_type,
+                interval_range: value
+                    .interval_first
+                    .zip(value.interval_last)
+                    .map(|(first, last)| IntervalRange { first, last }),
+                reason: value.reason,
+            },
+        })
+    }
+}
+
+fn opt_type_as_str(opt_type: OptType) -> &'static str {
+    match opt_type {
+        OptType::OptIn => "OPT_IN",
+        OptType::OptOut => "OPT_OUT",
+    }
+}
+
+#[async_trait]
+impl OptSource for PgOptSource {
+    async fn set(
+        &self,
+        event_id: &EventId,
+        new: OptContent,
+        User(user): &User,
+    ) -> Result<(Opt, Option<Opt>), AppError> {
+        if !user.ven_ids().contains(&new.ven_id) {
+            return Err(AppError::Forbidden(
+                "User not authorized to set the opt status of this VEN",
+            ));
+        }
+
+        // the event has to be visible to the VEN
+        let binding_events = sqlx::query_scalar!(
+            r#"
+            SELECT p.binding_events
+            FROM event e
+              JOIN program p ON p.id = e.program_id
+            WHERE e.id = $1
+              AND (NOT EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id)
+                   OR EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id AND vp.ven_id = $2))
+            "#,
+            event_id.as_str(),
+            new.ven_id.as_str()
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        if binding_events == Some(true) && new.opt_type == OptType::OptOut {
+            return Err(AppError::Conflict(
+                "The events of this program are binding and cannot be opted out of".to_string(),
+                None,
+            ));
+        }
+
+        if let Some(resource_id) = &new.resource_id {
+            let owned = sqlx::query_scalar!(
+                r#"
+                SELECT EXISTS (SELECT 1 FROM resource WHERE id = $1 AND ven_id = $2) AS "owned!"
+                "#,
+                resource_id.as_str(),
+                new.ven_id.as_str()
+            )
+            .fetch_one(&self.db)
+            .await?;
+
+            if !owned {
+                return Err(AppError::BadRequest(
+                    "The resource does not belong to the VEN",
+                ));
+            }
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let previous: Option<Opt> = sqlx::query_as!(
+            PostgresOpt,
+            r#"
+            SELECT *
+            FROM event_opt
+            WHERE event_id = $1
+              AND ven_id = $2
+              AND resource_id IS NOT DISTINCT FROM $3
+            FOR UPDATE
+            "#,
+            event_id.as_str(),
+            new.ven_id.as_str(),
+            new.resource_id.as_ref().map(|id| id.as_str()),
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(TryInto::try_into)
+        .transpose()?;
+
+        let opt: Opt = sqlx::query_as!(
+            PostgresOpt,
+            r#"
+            INSERT INTO event_opt (id, created_date_time, modification_date_time, event_id, ven_id, resource_id, opt_type, interval_first, interval_last, reason)
+            VALUES (gen_random_uuid(), now(), now(), $1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (event_id, ven_id, resource_id) DO UPDATE
+                SET modification_date_time = now(),
+                    opt_type = excluded.opt_type,
+                    interval_first = excluded.interval_first,
+                    interval_last = excluded.interval_last,
+                    reason = excluded.reason
+            RETURNING *
+            "#,
+            event_id.as_str(),
+            new.ven_id.as_str(),
+            new.resource_id.as_ref().map(|id| id.as_str()),
+            opt_type_as_str(new.opt_type),
+            new.interval_range.map(|range| range.first),
+            new.interval_range.map(|range| range.last),
+            new.reason,
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .try_into()?;
+
+        match &previous {
+            Some(previous) => {
+                audit::updated(&mut tx, user, AuditObjectType::Opt, &opt.id, previous, &opt).await?
+            }
+            None => audit::created(&mut tx, user, AuditObjectType::Opt, &opt.id, &opt).await?,
+        }
+
+        tx.commit().await?;
+
+        info!(%opt.id, %opt.event_id, ven_id = %opt.content.ven_id, opt_type = ?opt.content.opt_type, "set opt status");
+
+        Ok((opt, previous))
+    }
+
+    async fn retrieve_all(
+        &self,
+        event_id: &EventId,
+        User(user): &User,
+    ) -> Result<Vec<Opt>, AppError> {
+        let business_ids = extract_business_ids(user);
+
+        sqlx::query_as!(
+            PostgresOpt,
+            r#"
+            SELECT o.*
+            FROM event_opt o
+              JOIN event e ON e.id = o.event_id
+              JOIN program p ON p.id = e.program_id
+            WHERE o.event_id = $1
+              AND (
+                  ($2 AND o.ven_id = ANY($3))
+                  OR
+                  ($4 AND ($5::text[] IS NULL OR p.business_id = ANY ($5)))
+                  )
+            ORDER BY o.created_date_time, o.id
+            "#,
+            event_id.as_str(),
+            user.is_ven(),
+            &user.ven_ids_string(),
+            user.is_business(),
+            business_ids.as_deref(),
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use super::*;
+    use crate::jwt::{AuthRole, Claims};
+
+    fn ven_user(ven_id: &str) -> User {
+        User(Claims::new(vec![AuthRole::VEN(ven_id.parse().unwrap())]))
+    }
+
+    fn business_user(business_id: &str) -> User {
+        User(Claims::new(vec![AuthRole::Business(
+            business_id.to_string(),
+        )]))
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "events", "vens", "vens-programs", "resources"))]
+    async fn set_replaces_previous(db: PgPool) {
+        let source: PgOptSource = db.into();
+        let event_id = "event-1".parse().unwrap();
+        let user = ven_user("ven-1");
+
+        let (opt, previous) = source
+            .set(
+                &event_id,
+                OptContent::opt_out("ven-1".parse().unwrap()).with_reason("maintenance"),
+                &user,
+            )
+            .await
+            .unwrap();
+        assert_eq!(previous, None);
+        assert_eq!(opt.content.reason.as_deref(), Some("maintenance"));
+
+        let (replaced, previous) = source
+            .set(
+                &event_id,
+                OptContent {
+                    opt_type: OptType::OptIn,
+                    ..OptContent::opt_out("ven-1".parse().unwrap())
+                },
+                &user,
+            )
+            .await
+            .unwrap();
+        assert_eq!(previous, Some(opt.clone()));
+        assert_eq!(replaced.id, opt.id);
+        assert_eq!(replaced.content.opt_type, OptType::OptIn);
+
+        // the status of a resource is separate from the one of the VEN
+        let (resource_opt, previous) = source
+            .set(
+                &event_id,
+                OptContent::opt_out("ven-1".parse().unwrap())
+                    .with_resource_id("resource-1".parse().unwrap())
+                    .with_interval_range(IntervalRange { first: 0, last: 0 }),
+                &user,
+            )
+            .await
+            .unwrap();
+        assert_eq!(previous, None);
+
+        let opts = source.retrieve_all(&event_id, &user).await.unwrap();
+        assert_eq!(opts, vec![replaced, resource_opt]);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "events", "vens", "vens-programs", "resources"))]
+    async fn permissions(db: PgPool) {
+        let source: PgOptSource = db.into();
+        let event_id = "event-1".parse().unwrap();
+
+        // for another VEN
+        let result = source
+            .set(
+                &event_id,
+                OptContent::opt_out("ven-2".parse().unwrap()),
+                &ven_user("ven-1"),
+            )
+            .await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+
+        // with a resource of another VEN
+        let result = source
+            .set(
+                &event_id,
+                OptContent::opt_out("ven-1".parse().unwrap())
+                    .with_resource_id("resource-2".parse().unwrap()),
+                &ven_user("ven-1"),
+            )
+            .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        // for an event of a program the VEN is not enrolled in
+        let result = source
+            .set(
+                &"event-2".parse().unwrap(),
+                OptContent::opt_out("ven-1".parse().unwrap()),
+                &ven_user("ven-1"),
+            )
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        source
+            .set(
+                &event_id,
+                OptContent::opt_out("ven-2".parse().unwrap()),
+                &ven_user("ven-2"),
+            )
+            .await
+            .unwrap();
+
+        // VENs only see their own status
+        let opts = source
+            .retrieve_all(&event_id, &ven_user("ven-1"))
+            .await
+            .unwrap();
+        assert!(opts.is_empty());
+        let opts = source
+            .retrieve_all(&event_id, &ven_user("ven-2"))
+            .await
+            .unwrap();
+        assert_eq!(opts.len(), 1);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "business", "events", "vens", "vens-programs"))]
+    async fn business_sees_opts_of_own_programs(db: PgPool) {
+        let source: PgOptSource = db.into();
+        let event_id = "event-3".parse().unwrap();
+
+        source
+            .set(
+                &event_id,
+                OptContent::opt_out("ven-1".parse().unwrap()),
+                &ven_user("ven-1"),
+            )
+            .await
+            .unwrap();
+
+        let opts = source
+            .retrieve_all(&event_id, &business_user("business-1"))
+            .await
+            .unwrap();
+        assert_eq!(opts.len(), 1);
+        let opts = source
+            .retrieve_all(&event_id, &business_user("business-2"))
+            .await
+            .unwrap();
+        assert!(opts.is_empty());
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "events", "vens", "vens-programs"))]
+    async fn binding_events(db: PgPool) {
+        sqlx::query!("UPDATE program SET binding_events = true WHERE id = 'program-1'")
+            .execute(&db)
+            .await
+            .unwrap();
+        let source: PgOptSource = db.into();
+        let event_id = "event-1".parse().unwrap();
+
+        let result = source
+            .set(
+                &event_id,
+                OptContent::opt_out("ven-1".parse().unwrap()),
+                &ven_user("ven-1"),
+            )
+            .await;
+        assert!(matches!(result, Err(AppError::Conflict(_, _))));
+
+        // opting in is fine
+        source
+            .set(
+                &event_id,
+                OptContent {
+                    opt_type: OptType::OptIn,
+                    ..OptContent::opt_out("ven-1".parse().unwrap())
+                },
+                &ven_user("ven-1"),
+            )
+            .await
+            .unwrap();
+    }
+}
@@ -1,23 +1,96 @@
 use crate::{
     api::program::QueryParams,
     data_source::{
-        postgres::{extract_business_id, extract_vens, to_json_value, PgTargetsFilter},
-        Crud, ProgramCrud,
+        postgres::{
+            audit, event, extract_business_id, extract_business_ids, extract_vens, to_json_value,
+            PgTargetsFilter,
+        },
+        AuditObjectType, Crud, ProgramCrud,
     },
     error::AppError,
-    jwt::User,
+    jwt::{BusinessIds, Claims, User},
 };
 use axum::async_trait;
 use chrono::{DateTime, Utc};
 use openleadr_wire::{
-    program::{ProgramContent, ProgramId},
+    program::{ProgramContent, ProgramId, ProgramTimeZone},
     Program,
 };
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use tracing::{error, trace};
 
 #[async_trait]
-impl ProgramCrud for PgProgramStorage {}
+impl ProgramCrud for PgProgramStorage {
+    async fn create_for_business(
+        &self,
+        new: ProgramContent,
+        business_id: &str,
+        User(user): &User,
+    ) -> Result<Program, AppError> {
+        match user.business_ids() {
+            BusinessIds::Any => {}
+            BusinessIds::Specific(ids) if ids.iter().any(|id| id == business_id) => {}
+            BusinessIds::Specific(_) => Err(AppError::Forbidden(
+                "User cannot create programs for this business",
+            ))?,
+        }
+
+        self.insert(new, Some(business_id), user).await
+    }
+
+    async fn retrieve_as_of(
+        &self,
+        id: &ProgramId,
+        as_of: DateTime<Utc>,
+        User(user): &User,
+    ) -> Result<Program, AppError> {
+        let business_ids = extract_business_ids(user);
+
+        sqlx::query_as!(
+            PostgresProgram,
+            r#"
+            SELECT v.id,
+                   v.created_date_time,
+                   v.modification_date_time,
+                   v.program_name,
+                   v.program_long_name,
+                   v.retailer_name,
+                   v.retailer_long_name,
+                   v.program_type,
+                   v.country,
+                   v.principal_subdivision,
+                   v.time_zone_offset,
+                   v.time_zone,
+                   v.interval_period,
+                   v.program_descriptions,
+                   v.binding_events,
+                   v.local_price,
+                   v.payload_descriptors,
+                   v.targets
+            FROM program_version v
+              JOIN program p ON v.id = p.id
+            WHERE v.id = $1
+              AND v.modification_date_time <= $2
+              AND (v.valid_until IS NULL OR v.valid_until > $2)
+              AND (
+                  ($3 AND (NOT EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id)
+                           OR EXISTS (SELECT 1 FROM ven_program vp WHERE vp.program_id = p.id AND vp.ven_id = ANY($4))))
+                  OR
+                  ($5 AND ($6::text[] IS NULL OR p.business_id = ANY ($6)))
+                  )
+            "#,
+            id.as_str(),
+            as_of,
+            user.is_ven(),
+            &user.ven_ids_string(),
+            user.is_business(),
+            business_ids.as_deref(),
+        )
+        .fetch_one(&self.db)
+        .await?
+        .try_into()
+    }
+}
 
 pub(crate) struct PgProgramStorage {
     db: PgPool,
@@ -29,6 +102,197 @@ impl From<PgPool> for PgProgramStorage {
     }
 }
 
+impl PgProgramStorage {
+    async fn insert(
+        &self,
+        new: ProgramContent,
+        business_id: Option<&str>,
+        user: &Claims,
+    ) -> Result<Program, AppError> {
+        let (targets, vens) = extract_vens(new.targets);
+
+        let mut tx = self.db.begin().await?;
+
+        let program: Program = sqlx::query_as!(
+            PostgresProgram,
+            r#"
+            INSERT INTO program (id,
+                                 created_date_time,
+                                 modification_date_time,
+                                 program_name,
+                                 program_long_name,
+                                 retailer_name,
+                                 retailer_long_name,
+                                 program_type,
+                                 country,
+                                 principal_subdivision,
+                                 interval_period,
+                                 program_descriptions,
+                                 binding_events,
+                                 local_price,
+                                 payload_descriptors,
+                                 targets,
+                                 business_id,
+                                 time_zone_offset,
+                                 time_zone)
+            VALUES (gen_random_uuid(), now(), now(), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            RETURNING id,
+                      created_date_time,
+                      modification_date_time,
+                      program_name,
+                      program_long_name,
+                      retailer_name,
+                      retailer_long_name,
+                      program_type,
+                      country,
+                      principal_subdivision,
+                      time_zone_offset,
+                      time_zone,
+                      interval_period,
+                      program_descriptions,
+                      binding_events,
+                      local_price,
+                      payload_descriptors,
+                      targets
+            "#,
+            new.program_name,
+            new.program_long_name,
+            new.retailer_name,
+            new.retailer_long_name,
+            new.program_type,
+            new.country,
+            new.principal_subdivision,
+            to_json_value(new.interval_period)?,
+            to_json_value(new.program_descriptions)?,
+            new.binding_events,
+            new.local_price,
+            to_json_value(new.payload_descriptors)?,
+            to_json_value(targets)?,
+            business_id,
+            new.time_zone_offset.map(|offset| offset.to_string()),
+            new.time_zone.map(|tz| tz.name()),
+        )
+            .fetch_one(&mut *tx)
+            .await?
+            .try_into()?;
+
+        if let Some(vens) = vens {
+            let rows_affected = sqlx::query!(
+                r#"
+                INSERT INTO ven_program (program_id, ven_id)
+                    (SELECT $1, id FROM ven WHERE ven_name = ANY ($2))
+                "#,
+                program.id.as_str(),
+                &vens
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if rows_affected as usize != vens.len() {
+                Err(AppError::Conflict(
+                    "One or multiple VEN names linked in the program do not exist".to_string(),
+                    None,
+                ))?
+            }
+        };
+        audit::created(
+            &mut tx,
+            user,
+            AuditObjectType::Program,
+            &program.id,
+            &program,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(program)
+    }
+
+    /// Locks the program for the remainder of the transaction
+    async fn retrieve_for_update(
+        conn: &mut PgConnection,
+        id: &ProgramId,
+    ) -> Result<Program, AppError> {
+        sqlx::query_as!(
+            PostgresProgram,
+            r#"
+            SELECT id,
+                   created_date_time,
+                   modification_date_time,
+                   program_name,
+                   program_long_name,
+                   retailer_name,
+                   retailer_long_name,
+                   program_type,
+                   country,
+                   principal_subdivision,
+                   time_zone_offset,
+                   time_zone,
+                   interval_period,
+                   program_descriptions,
+                   binding_events,
+                   local_price,
+                   payload_descriptors,
+                   targets
+            FROM program
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id.as_str(),
+        )
+        .fetch_one(conn)
+        .await?
+        .try_into()
+    }
+}
+
+struct PostgresTimeZone {
+    time_zone_offset: Option<String>,
+    time_zone: Option<String>,
+}
+
+impl TryFrom<PostgresTimeZone> for ProgramTimeZone {
+    type Error = AppError;
+
+    fn try_from(value: PostgresTimeZone) -> Result<Self, Self::Error> {
+        let time_zone_offset = value
+            .time_zone_offset
+            .map(|t| serde_json::from_value(serde_json::Value::String(t)))
+            .transpose()
+            .inspect_err(|err| {
+                error!(
+                    ?err,
+                    "Failed to deserialize time zone offset from DB to `TimeZoneOffset`"
+                )
+            })
+            .map_err(AppError::SerdeJsonInternalServerError)?;
+        let time_zone = value
+            .time_zone
+            .map(|t| serde_json::from_value(serde_json::Value::String(t)))
+            .transpose()
+            .inspect_err(|err| error!(?err, "Failed to deserialize time zone from DB to `Tz`"))
+            .map_err(AppError::SerdeJsonInternalServerError)?;
+
+        Ok(ProgramTimeZone::new(time_zone, time_zone_offset.as_ref()))
+    }
+}
+
+/// The time zone calendar durations of the events in the program are evaluated in
+pub(super) async fn time_zone(
+    conn: &mut PgConnection,
+    program_id: &ProgramId,
+) -> Result<ProgramTimeZone, AppError> {
+    sqlx::query_as!(
+        PostgresTimeZone,
+        r#"
+        SELECT time_zone_offset, time_zone FROM program WHERE id = $1
+        "#,
+        program_id.as_str()
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .try_into()
+}
+
 #[derive(Debug)]
 struct PostgresProgram {
     id: String,
@@ -41,6 +305,8 @@ struct PostgresProgram {
     program_type: Option<String>,
     country: Option<String>,
     principal_subdivision: Option<String>,
+    time_zone_offset: Option<String>,
+    time_zone: Option<String>,
     interval_period: Option<serde_json::Value>,
     program_descriptions: Option<serde_json::Value>,
     binding_events: Option<bool>,
@@ -54,6 +320,23 @@ impl TryFrom<PostgresProgram> for Program {
 
     #[tracing::instrument(name = "TryFrom<PostgresProgram> for Program")]
     fn try_from(value: PostgresProgram) -> Result<Self, Self::Error> {
+        let time_zone_offset = match value.time_zone_offset {
+            None => None,
+            Some(t) => serde_json::from_value(serde_json::Value::String(t))
+                .inspect_err(|err| {
+                    error!(
+                        ?err,
+                        "Failed to deserialize time zone offset from DB to `TimeZoneOffset`"
+                    )
+                })
+                .map_err(AppError::SerdeJsonInternalServerError)?,
+        };
+        let time_zone = match value.time_zone {
+            None => None,
+            Some(t) => serde_json::from_value(serde_json::Value::String(t))
+                .inspect_err(|err| error!(?err, "Failed to deserialize time zone from DB to `Tz`"))
+                .map_err(AppError::SerdeJsonInternalServerError)?,
+        };
         let interval_period = match value.interval_period {
             None => None,
             Some(t) => serde_json::from_value(t)
@@ -108,7 +391,8 @@ impl TryFrom<PostgresProgram> for Program {
                 program_type: value.program_type,
                 country: value.country,
                 principal_subdivision: value.principal_subdivision,
-                time_zone_offset: None,
+                time_zone_offset,
+                time_zone,
                 interval_period,
                 program_descriptions,
                 binding_events: value.binding_events,
@@ -165,89 +449,8 @@ impl Crud for PgProgramStorage {
         new: Self::NewType,
         User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
-        let (targets, vens) = extract_vens(new.targets);
         let business_id = extract_business_id(user)?;
-
-        let mut tx = self.db.begin().await?;
-
-        let program: Program = sqlx::query_as!(
-            PostgresProgram,
-            r#"
-            INSERT INTO program (id,
-                                 created_date_time,
-                                 modification_date_time,
-                                 program_name,
-                                 program_long_name,
-                                 retailer_name,
-                                 retailer_long_name,
-                                 program_type,
-                                 country,
-                                 principal_subdivision,
-                                 interval_period,
-                                 program_descriptions,
-                                 binding_events,
-                                 local_price,
-                                 payload_descriptors,
-                                 targets,
-                                 business_id)
-            VALUES (gen_random_uuid(), now(), now(), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
-            RETURNING id,
-                      created_date_time,
-                      modification_date_time,
-                      program_name,
-                      program_long_name,
-                      retailer_name,
-                      retailer_long_name,
-                      program_type,
-                      country,
-                      principal_subdivision,
-                      interval_period,
-                      program_descriptions,
-                      binding_events,
-                      local_price,
-                      payload_descriptors,
-                      targets
-            "#,
-            new.program_name,
-            new.program_long_name,
-            new.retailer_name,
-            new.retailer_long_name,
-            new.program_type,
-            new.country,
-            new.principal_subdivision,
-            to_json_value(new.interval_period)?,
-            to_json_value(new.program_descriptions)?,
-            new.binding_events,
-            new.local_price,
-            to_json_value(new.payload_descriptors)?,
-            to_json_value(targets)?,
-            business_id,
-        )
-            .fetch_one(&mut *tx)
-            .await?
-            .try_into()?;
-
-        if let Some(vens) = vens {
-            let rows_affected = sqlx::query!(
-                r#"
-                INSERT INTO ven_program (program_id, ven_id)
-                    (SELECT $1, id FROM ven WHERE ven_name = ANY ($2))
-                "#,
-                program.id.as_str(),
-                &vens
-            )
-            .execute(&mut *tx)
-            .await?
-            .rows_affected();
-            if rows_affected as usize != vens.len() {
-                Err(AppError::Conflict(
-                    "One or multiple VEN names linked in the program do not exist".to_string(),
-                    None,
-                ))?
-            }
-        };
-        tx.commit().await?;
-        Ok(program)
+        self.insert(new, business_id.as_deref(), user).await
     }
 
     async fn retrieve(
@@ -255,6 +458,8 @@ impl Crud for PgProgramStorage {
         id: &Self::Id,
         User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
+        let business_ids = extract_business_ids(user);
+
         Ok(sqlx::query_as!(
             PostgresProgram,
             r#"
@@ -268,6 +473,8 @@ impl Crud for PgProgramStorage {
                    p.program_type,
                    p.country,
                    p.principal_subdivision,
+                   p.time_zone_offset,
+                   p.time_zone,
                    p.interval_period,
                    p.program_descriptions,
                    p.binding_events,
@@ -277,11 +484,17 @@ impl Crud for PgProgramStorage {
             FROM program p
               LEFT JOIN ven_program vp ON p.id = vp.program_id
             WHERE id = $1
-              AND (NOT $2 OR vp.ven_id IS NULL OR vp.ven_id = ANY($3)) -- Filter for VEN ids
+              AND (
+                  ($2 AND (vp.ven_id IS NULL OR vp.ven_id = ANY($3)))
+                  OR
+                  ($4 AND ($5::text[] IS NULL OR p.business_id = ANY ($5)))
+                  )
             "#,
             id.as_str(),
             user.is_ven(),
-            &user.ven_ids_string()
+            &user.ven_ids_string(),
+            user.is_business(),
+            business_ids.as_deref()
         )
         .fetch_one(&self.db)
         .await?
@@ -295,6 +508,7 @@ impl Crud for PgProgramStorage {
     ) -> Result<Vec<Self::Type>, Self::Error> {
         let pg_filter: PostgresFilter = filter.into();
         trace!(?pg_filter);
+        let business_ids = extract_business_ids(user);
 
         Ok(sqlx::query_as!(
             PostgresProgram,
@@ -309,6 +523,8 @@ impl Crud for PgProgramStorage {
                    p.program_type,
                    p.country,
                    p.principal_subdivision,
+                   p.time_zone_offset,
+                   p.time_zone,
                    p.interval_period,
                    p.program_descriptions,
                    p.binding_events,
@@ -326,17 +542,18 @@ impl Crud for PgProgramStorage {
               AND (
                   ($2 AND (vp.ven_id IS NULL OR vp.ven_id = ANY($3)))
                   OR
-                  ($4)
+                  ($4 AND ($5::text[] IS NULL OR p.business_id = ANY ($5)))
                   )
             GROUP BY p.id, p.created_date_time
             ORDER BY p.created_date_time DESC
-            OFFSET $5 LIMIT $6
+            OFFSET $6 LIMIT $7
             "#,
             serde_json::to_value(pg_filter.targets)
                 .map_err(AppError::SerdeJsonInternalServerError)?,
             user.is_ven(),
             &user.ven_ids_string(),
             user.is_business(),
+            business_ids.as_deref(),
             pg_filter.skip,
             pg_filter.limit,
         )
@@ -354,9 +571,10 @@ impl Crud for PgProgramStorage {
         User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
         let (targets, vens) = extract_vens(new.targets);
-        let business_id = extract_business_id(user)?;
+        let business_ids = extract_business_ids(user);
 
         let mut tx = self.db.begin().await?;
+        let before = Self::retrieve_for_update(&mut tx, id).await?;
 
         let program: Program = sqlx::query_as!(
             PostgresProgram,
@@ -375,9 +593,11 @@ impl Crud for PgProgramStorage {
                 binding_events = $11,
                 local_price = $12,
                 payload_descriptors = $13,
-                targets = $14
+                targets = $14,
+                time_zone_offset = $16,
+                time_zone = $17
             WHERE id = $1
-                AND ($15::text IS NULL OR business_id = $15)
+                AND ($15::text[] IS NULL OR business_id = ANY ($15))
             RETURNING p.id,
                    p.created_date_time,
                    p.modification_date_time,
@@ -388,6 +608,8 @@ impl Crud for PgProgramStorage {
                    p.program_type,
                    p.country,
                    p.principal_subdivision,
+                   p.time_zone_offset,
+                   p.time_zone,
                    p.interval_period,
                    p.program_descriptions,
                    p.binding_events,
@@ -409,7 +631,9 @@ impl Crud for PgProgramStorage {
             new.local_price,
             to_json_value(new.payload_descriptors)?,
             to_json_value(targets)?,
-            business_id
+            business_ids.as_deref(),
+            new.time_zone_offset.map(|offset| offset.to_string()),
+            new.time_zone.map(|tz| tz.name()),
         )
         .fetch_one(&mut *tx)
         .await?
@@ -442,6 +666,18 @@ impl Crud for PgProgramStorage {
                 ))?
             }
         };
+        if program.content.effective_time_zone() != before.content.effective_time_zone() {
+            event::store_time_spans(&mut tx, &program.id).await?;
+        }
+        audit::updated(
+            &mut tx,
+            user,
+            AuditObjectType::Program,
+            id,
+            &before,
+            &program,
+        )
+        .await?;
         tx.commit().await?;
         Ok(program)
     }
@@ -451,14 +687,15 @@ impl Crud for PgProgramStorage {
         id: &Self::Id,
         User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
-        let business_id = extract_business_id(user)?;
+        let business_ids = extract_business_ids(user);
+        let mut tx = self.db.begin().await?;
 
-        Ok(sqlx::query_as!(
+        let program: Program = sqlx::query_as!(
             PostgresProgram,
             r#"
             DELETE FROM program p
                    WHERE id = $1
-                     AND ($2::text IS NULL OR business_id = $2)
+                     AND ($2::text[] IS NULL OR business_id = ANY ($2))
             RETURNING p.id,
                    p.created_date_time,
                    p.modification_date_time,
@@ -469,6 +706,8 @@ impl Crud for PgProgramStorage {
                    p.program_type,
                    p.country,
                    p.principal_subdivision,
+                   p.time_zone_offset,
+                   p.time_zone,
                    p.interval_period,
                    p.program_descriptions,
                    p.binding_events,
@@ -477,11 +716,15 @@ impl Crud for PgProgramStorage {
                    p.targets
             "#,
             id.as_str(),
-            business_id,
+            business_ids.as_deref(),
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *tx)
         .await?
-        .try_into()?)
+        .try_into()?;
+
+        audit::deleted(&mut tx, user, AuditObjectType::Program, id, &program).await?;
+        tx.commit().await?;
+        Ok(program)
     }
 }
 
@@ -528,6 +771,7 @@ mod tests {
                 country: Some("country".to_string()),
                 principal_subdivision: Some("principal-subdivision".to_string()),
                 time_zone_offset: None,
+                time_zone: None,
                 interval_period: Some(IntervalPeriod::new(
                     "2024-07-25 08:31:10.776000 +00:00".parse().unwrap(),
                 )),
@@ -567,6 +811,7 @@ mod tests {
                 country: None,
                 principal_subdivision: None,
                 time_zone_offset: None,
+                time_zone: None,
                 interval_period: None,
                 program_descriptions: None,
                 binding_events: None,
@@ -756,6 +1001,26 @@ mod tests {
                 .await;
             assert!(matches!(program, Err(AppError::Conflict(_, _))));
         }
+
+        #[sqlx::test]
+        async fn add_time_zone(db: PgPool) {
+            let repo: PgProgramStorage = db.into();
+            let mut content = program_1().content;
+            content.time_zone_offset = Some(openleadr_wire::Duration::PT1H.into());
+            content.time_zone = Some(chrono_tz::Europe::Amsterdam);
+
+            let program = repo
+                .create(content.clone(), &User(Claims::any_business_user()))
+                .await
+                .unwrap();
+            assert_eq!(program.content, content);
+
+            let program = repo
+                .retrieve(&program.id, &User(Claims::any_business_user()))
+                .await
+                .unwrap();
+            assert_eq!(program.content, content);
+        }
     }
 
     mod modify {
@@ -810,6 +1075,51 @@ mod tests {
                 .unwrap();
             assert_eq!(program.content, updated);
         }
+
+        #[sqlx::test(fixtures("programs", "events"))]
+        async fn time_zone_moves_event_ends(db: PgPool) {
+            // a day across the switch to summer time in Amsterdam
+            let period = serde_json::json!({"start": "2024-03-30T12:00:00Z", "duration": "P1D"});
+            sqlx::query(
+                "UPDATE event SET interval_period = $1, intervals = jsonb_build_array((intervals -> 0) - 'intervalPeriod') WHERE id = 'event-1'",
+            )
+            .bind(&period)
+            .execute(&db)
+            .await
+            .unwrap();
+            sqlx::query(
+                "UPDATE event_lifecycle SET starts = '2024-03-30T12:00:00Z', ends = '2024-03-31T12:00:00Z' WHERE event_id = 'event-1'",
+            )
+            .execute(&db)
+            .await
+            .unwrap();
+
+            let repo: PgProgramStorage = db.clone().into();
+            let mut updated = program_1().content;
+            updated.time_zone = Some(chrono_tz::Europe::Amsterdam);
+            repo.update(
+                &"program-1".parse().unwrap(),
+                updated,
+                &User(Claims::any_business_user()),
+            )
+            .await
+            .unwrap();
+
+            let (starts, ends): (DateTime<Utc>, DateTime<Utc>) = sqlx::query_as(
+                "SELECT starts, ends FROM event_lifecycle WHERE event_id = 'event-1'",
+            )
+            .fetch_one(&db)
+            .await
+            .unwrap();
+            assert_eq!(
+                starts,
+                "2024-03-30T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+            );
+            assert_eq!(
+                ends,
+                "2024-03-31T11:00:00Z".parse::<DateTime<Utc>>().unwrap()
+            );
+        }
     }
 
     mod delete {
@@ -0,0 +1,452 @@
+use crate::{
+    data_source::{
+        postgres::{
+            audit,
+            user::{hash_secret, PgAuthSource},
+            ven::PgVenStorage,
+            PgId,
+        },
+        ApprovalState, AuditObjectType, AuditOperation, NewAuditEntry, RegistrationSource,
+        RegistrationToken, VenRegistration,
+    },
+    error::AppError,
+    jwt::{AuthRole, User},
+};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use openleadr_wire::ven::{Ven, VenContent, VenId};
+use sqlx::{PgConnection, PgPool};
+use tracing::error;
+
+pub(crate) struct PgRegistrationSource {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgRegistrationSource {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[derive(Debug)]
+struct PostgresRegistration {
+    ven_id: String,
+    ven_name: String,
+    user_id: Option<String>,
+    approval_state: String,
+    created: DateTime<Utc>,
+    modified: DateTime<Utc>,
+}
+
+impl TryFrom<PostgresRegistration> for VenRegistration {
+    type Error = AppError;
+
+    fn try_from(value: PostgresRegistration) -> Result<Self, Self::Error> {
+        Ok(Self {
+            ven_id: value.ven_id.parse()?,
+            ven_name: value.ven_name,
+            user_id: value.user_id,
+            approval_state: serde_json::from_value(serde_json::Value::String(value.approval_state))
+                .inspect_err(|err| error!(?err, "Failed to parse approval state from DB"))
+                .map_err(AppError::SerdeJsonInternalServerError)?,
+            created: value.created,
+            modified: value.modified,
+        })
+    }
+}
+
+fn approval_state_as_str(approval_state: ApprovalState) -> &'static str {
+    match approval_state {
+        ApprovalState::Pending => "PENDING",
+        ApprovalState::Approved => "APPROVED",
+        ApprovalState::Rejected => "REJECTED",
+    }
+}
+
+/// Locks the registration for the remainder of the transaction
+async fn retrieve_for_update(
+    conn: &mut PgConnection,
+    ven_id: &VenId,
+) -> Result<VenRegistration, AppError> {
+    sqlx::query_as!(
+        PostgresRegistration,
+        r#"
+        SELECT r.ven_id, v.ven_name, r.user_id, r.approval_state, r.created, r.modified
+        FROM ven_registration r
+          JOIN ven v ON v.id = r.ven_id
+        WHERE r.ven_id = $1
+        FOR UPDATE OF r
+        "#,
+        ven_id.as_str()
+    )
+    .fetch_one(conn)
+    .await?
+    .try_into()
+}
+
+#[async_trait]
+impl RegistrationSource for PgRegistrationSource {
+    async fn add_token(&self, token: &RegistrationToken) -> Result<(), AppError> {
+        let mut tx = self.db.begin().await?;
+
+        // expired tokens cannot be used anymore, so there is no point in keeping them
+        sqlx::query!(
+            r#"
+            DELETE FROM registration_token WHERE expires <= now()
+            "#
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO registration_token (token_hash, description, created_by, created, expires)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            token.token_hash,
+            token.description,
+            token.created_by,
+            token.created,
+            token.expires
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn register(
+        &self,
+        token_hash: &str,
+        new: VenContent,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<(Ven, VenRegistration), AppError> {
+        let mut tx = self.db.begin().await?;
+
+        // deleting the token right away ensures it can be used only once
+        let consumed = sqlx::query!(
+            r#"
+            DELETE FROM registration_token
+            WHERE token_hash = $1
+              AND expires > now()
+            "#,
+            token_hash
+        )
+        .execute(&mut *tx)
+        .await?;
+        if consumed.rows_affected() == 0 {
+            return Err(AppError::Auth(
+                "Invalid or expired registration token".to_string(),
+            ));
+        }
+        // hashing is expensive on purpose, so only done for valid tokens
+        let hash = hash_secret(client_secret)?;
+
+        let ven = PgVenStorage::insert(&mut *tx, new).await?;
+
+        let user = sqlx::query_as!(
+            PgId,
+            r#"
+            INSERT INTO "user" (id, reference, description, created, modified)
+            VALUES (gen_random_uuid(), $1, 'Self-registered VEN', now(), now())
+            RETURNING id
+            "#,
+            ven.content.ven_name,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        PgAuthSource::add_role(&mut tx, &user.id, &AuthRole::VEN(ven.id.clone())).await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO user_credentials (user_id, client_id, client_secret, created)
+            VALUES ($1, $2, $3, now())
+            "#,
+            user.id,
+            client_id,
+            hash
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let registration: VenRegistration = sqlx::query_as!(
+            PostgresRegistration,
+            r#"
+            WITH inserted AS (
+                INSERT INTO ven_registration (ven_id, user_id, approval_state, created, modified)
+                VALUES ($1, $2, 'PENDING', now(), now())
+                RETURNING *
+            )
+            SELECT i.ven_id, v.ven_name, i.user_id, i.approval_state, i.created, i.modified
+            FROM inserted i
+              JOIN ven v ON v.id = i.ven_id
+            "#,
+            ven.id.as_str(),
+            user.id
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .try_into()?;
+
+        // there are no claims yet, so the VEN acts on its own behalf
+        audit::record(
+            &mut tx,
+            NewAuditEntry {
+                actor: client_id.to_string(),
+                roles: vec![AuthRole::VEN(ven.id.clone())],
+                operation: AuditOperation::Create,
+                object_type: AuditObjectType::Ven,
+                object_id: ven.id.to_string(),
+                before: None,
+                after: Some(
+                    serde_json::to_value(&ven).map_err(AppError::SerdeJsonInternalServerError)?,
+                ),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok((ven, registration))
+    }
+
+    async fn retrieve(
+        &self,
+        ven_id: &VenId,
+        User(user): &User,
+    ) -> Result<VenRegistration, AppError> {
+        sqlx::query_as!(
+            PostgresRegistration,
+            r#"
+            SELECT r.ven_id, v.ven_name, r.user_id, r.approval_state, r.created, r.modified
+            FROM ven_registration r
+              JOIN ven v ON v.id = r.ven_id
+            WHERE r.ven_id = $1
+              AND (
+                  $2
+                  OR EXISTS (SELECT 1
+                             FROM user_credentials c
+                             WHERE c.user_id = r.user_id
+                               AND c.client_id = $3)
+                  )
+            "#,
+            ven_id.as_str(),
+            user.is_ven_manager(),
+            user.sub
+        )
+        .fetch_one(&self.db)
+        .await?
+        .try_into()
+    }
+
+    async fn retrieve_all(
+        &self,
+        approval_state: Option<ApprovalState>,
+    ) -> Result<Vec<VenRegistration>, AppError> {
+        sqlx::query_as!(
+            PostgresRegistration,
+            r#"
+            SELECT r.ven_id, v.ven_name, r.user_id, r.approval_state, r.created, r.modified
+            FROM ven_registration r
+              JOIN ven v ON v.id = r.ven_id
+            WHERE ($1::text IS NULL OR r.approval_state = $1)
+            ORDER BY r.created, r.ven_id
+            "#,
+            approval_state.map(approval_state_as_str)
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+
+    async fn set_approval_state(
+        &self,
+        ven_id: &VenId,
+        approval_state: ApprovalState,
+        User(manager): &User,
+    ) -> Result<VenRegistration, AppError> {
+        let mut tx = self.db.begin().await?;
+        let before = retrieve_for_update(&mut tx, ven_id).await?;
+
+        let registration: VenRegistration = sqlx::query_as!(
+            PostgresRegistration,
+            r#"
+            WITH updated AS (
+                UPDATE ven_registration
+                SET approval_state = $2,
+                    modified = now()
+                WHERE ven_id = $1
+                RETURNING *
+            )
+            SELECT u.ven_id, v.ven_name, u.user_id, u.approval_state, u.created, u.modified
+            FROM updated u
+              JOIN ven v ON v.id = u.ven_id
+            "#,
+            ven_id.as_str(),
+            approval_state_as_str(approval_state)
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .try_into()?;
+
+        // tokens issued while approved contain the VEN role
+        if approval_state != ApprovalState::Approved {
+            if let Some(user_id) = &registration.user_id {
+                PgAuthSource::revoke_tokens(&mut tx, user_id, None).await?;
+            }
+        }
+
+        audit::updated(
+            &mut tx,
+            manager,
+            AuditObjectType::VenRegistration,
+            ven_id,
+            &before,
+            &registration,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(registration)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use crate::{
+        data_source::{
+            postgres::{registration::PgRegistrationSource, user::PgAuthSource},
+            ApprovalState, AuthSource, RegistrationSource, RegistrationToken,
+        },
+        error::AppError,
+        jwt::{AuthRole, Claims, User},
+    };
+    use chrono::{Duration, Utc};
+    use openleadr_wire::ven::VenContent;
+    use sqlx::PgPool;
+
+    fn token(token_hash: &str, expires_in: Duration) -> RegistrationToken {
+        RegistrationToken {
+            token_hash: token_hash.to_string(),
+            description: None,
+            created_by: "admin".to_string(),
+            created: Utc::now(),
+            expires: Utc::now() + expires_in,
+        }
+    }
+
+    fn new_ven() -> VenContent {
+        VenContent::new("self-registered".to_string(), None, None, None)
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn ven_role_requires_approval(db: PgPool) {
+        let source: PgRegistrationSource = db.clone().into();
+        let auth: PgAuthSource = db.into();
+
+        source
+            .add_token(&token("hash", Duration::hours(1)))
+            .await
+            .unwrap();
+        let (ven, registration) = source
+            .register("hash", new_ven(), "ven-client", "ven-secret")
+            .await
+            .unwrap();
+        assert_eq!(registration.approval_state, ApprovalState::Pending);
+
+        // the user holds the VEN role, but it is not granted until approved
+        let user = auth
+            .get_user(registration.user_id.as_deref().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(user.roles, vec![AuthRole::VEN(ven.id.clone())]);
+        let client = auth
+            .check_credentials("ven-client", "ven-secret")
+            .await
+            .unwrap();
+        assert!(client.roles.is_empty());
+
+        let manager = User(Claims::new(vec![AuthRole::VenManager]));
+        source
+            .set_approval_state(&ven.id, ApprovalState::Approved, &manager)
+            .await
+            .unwrap();
+        let client = auth
+            .check_credentials("ven-client", "ven-secret")
+            .await
+            .unwrap();
+        assert_eq!(client.roles, vec![AuthRole::VEN(ven.id.clone())]);
+
+        source
+            .set_approval_state(&ven.id, ApprovalState::Rejected, &manager)
+            .await
+            .unwrap();
+        let client = auth
+            .check_credentials("ven-client", "ven-secret")
+            .await
+            .unwrap();
+        assert!(client.roles.is_empty());
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn registration_token_is_single_use(db: PgPool) {
+        let source: PgRegistrationSource = db.into();
+
+        source
+            .add_token(&token("expired", -Duration::hours(1)))
+            .await
+            .unwrap();
+        assert!(matches!(
+            source
+                .register("expired", new_ven(), "client-1", "secret")
+                .await,
+            Err(AppError::Auth(_))
+        ));
+
+        source
+            .add_token(&token("hash", Duration::hours(1)))
+            .await
+            .unwrap();
+        source
+            .register("hash", new_ven(), "client-2", "secret")
+            .await
+            .unwrap();
+        assert!(matches!(
+            source
+                .register("hash", new_ven(), "client-3", "secret")
+                .await,
+            Err(AppError::Auth(_))
+        ));
+        assert_eq!(source.retrieve_all(None).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn visible_to_own_user(db: PgPool) {
+        let source: PgRegistrationSource = db.into();
+        source
+            .add_token(&token("hash", Duration::hours(1)))
+            .await
+            .unwrap();
+        let (ven, _) = source
+            .register("hash", new_ven(), "ven-client", "ven-secret")
+            .await
+            .unwrap();
+
+        // a pending VEN has no roles yet, so it is identified by its client ID
+        let mut own = Claims::new(vec![]);
+        own.sub = "ven-client".to_string();
+        assert!(source.retrieve(&ven.id, &User(own)).await.is_ok());
+
+        let mut other = Claims::new(vec![]);
+        other.sub = "admin".to_string();
+        assert!(matches!(
+            source.retrieve(&ven.id, &User(other)).await,
+            Err(AppError::NotFound)
+        ));
+
+        let manager = Claims::new(vec![AuthRole::VenManager]);
+        assert!(source.retrieve(&ven.id, &User(manager)).await.is_ok());
+    }
+}
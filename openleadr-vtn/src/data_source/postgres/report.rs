@@ -1,8 +1,11 @@
 use crate::{
     api::report::QueryParams,
     data_source::{
-        postgres::{extract_business_ids, to_json_value, PgId},
-        Crud, ReportCrud,
+        postgres::{
+            audit, event::PgEventStorage, extract_business_ids, program::time_zone, to_json_value,
+            PgId,
+        },
+        AuditObjectType, Crud, ReportCrud, UsageHistory,
     },
     error::AppError,
     jwt::User,
@@ -10,14 +13,181 @@ use crate::{
 use axum::async_trait;
 use chrono::{DateTime, Utc};
 use openleadr_wire::{
+    event::EventId,
     report::{ReportContent, ReportId},
+    report_summary::ReportSummary,
+    ven::VenId,
     Report,
 };
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
 use tracing::{error, info, trace};
 
 #[async_trait]
-impl ReportCrud for PgReportStorage {}
+impl ReportCrud for PgReportStorage {
+    async fn summarize(&self, event_id: &EventId, user: &User) -> Result<ReportSummary, AppError> {
+        // checks that the user may see the event
+        let event = PgEventStorage::from(self.db.clone())
+            .retrieve(event_id, user)
+            .await?;
+
+        let mut conn = self.db.acquire().await?;
+        let time_zone = time_zone(&mut conn, &event.content.program_id).await?;
+
+        let reports = sqlx::query_as!(
+            PostgresReport,
+            r#"
+            SELECT * FROM report WHERE event_id = $1 ORDER BY created_date_time, id
+            "#,
+            event_id.as_str()
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(Report::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+        let report_ids: Vec<String> = reports.iter().map(|r| r.id.to_string()).collect();
+        let attributions = sqlx::query!(
+            r#"
+            SELECT report_id, ven_id FROM report_ven WHERE report_id = ANY($1)
+            "#,
+            &report_ids
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| Ok((row.report_id, row.ven_id.parse()?)))
+        .collect::<Result<HashMap<String, VenId>, AppError>>()?;
+
+        let enrolled_vens = sqlx::query_scalar!(
+            r#"
+            SELECT ven_id FROM ven_program WHERE program_id = $1 ORDER BY ven_id
+            "#,
+            event.content.program_id.as_str()
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|id| id.parse())
+        .collect::<Result<Vec<VenId>, _>>()?;
+
+        let summary = ReportSummary::new(
+            &event,
+            &time_zone,
+            reports
+                .iter()
+                .map(|report| (attributions.get(report.id.as_str()), report)),
+            &enrolled_vens,
+        );
+        trace!(%event_id, report_count = summary.report_count, "summarized reports");
+
+        Ok(summary)
+    }
+
+    async fn usage_history(
+        &self,
+        event_id: &EventId,
+        lookback_days: u32,
+        user: &User,
+    ) -> Result<UsageHistory, AppError> {
+        // checks that the user may see the event
+        let event = PgEventStorage::from(self.db.clone())
+            .retrieve(event_id, user)
+            .await?;
+
+        let mut conn = self.db.acquire().await?;
+        let time_zone = time_zone(&mut conn, &event.content.program_id).await?;
+
+        let Some((starts, _)) = event.content.time_span(&time_zone) else {
+            return Ok(UsageHistory {
+                event,
+                time_zone,
+                program_event_starts: vec![],
+                reports: vec![],
+            });
+        };
+        let since = starts - chrono::Duration::days(lookback_days.into());
+
+        let program_event_starts = sqlx::query_scalar!(
+            r#"
+            SELECT l.starts AS "starts!"
+            FROM event e
+              JOIN event_lifecycle l ON l.event_id = e.id
+            WHERE e.program_id = $1
+              AND e.id <> $2
+              AND l.cancelled_date_time IS NULL
+              AND l.starts >= $3
+              AND l.starts < $4
+            "#,
+            event.content.program_id.as_str(),
+            event_id.as_str(),
+            since,
+            starts,
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let reports = sqlx::query!(
+            r#"
+            SELECT r.*, rv.ven_id
+            FROM report r
+              JOIN report_ven rv ON rv.report_id = r.id
+            WHERE r.program_id = $2
+              AND r.modification_date_time >= $1
+              AND (rv.ven_id IN (SELECT ven_id FROM ven_program WHERE program_id = $2)
+                   OR rv.ven_id IN (SELECT rv.ven_id
+                                    FROM report_ven rv
+                                      JOIN report r ON r.id = rv.report_id
+                                    WHERE r.event_id = $3))
+            ORDER BY r.modification_date_time, r.id
+            "#,
+            since,
+            event.content.program_id.as_str(),
+            event_id.as_str(),
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let report = PostgresReport {
+                id: row.id,
+                created_date_time: row.created_date_time,
+                modification_date_time: row.modification_date_time,
+                program_id: row.program_id,
+                event_id: row.event_id,
+                client_name: row.client_name,
+                report_name: row.report_name,
+                payload_descriptors: row.payload_descriptors,
+                resources: row.resources,
+            };
+            Ok((row.ven_id.parse()?, report.try_into()?))
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+        trace!(%event_id, report_count = reports.len(), "retrieved usage history");
+
+        Ok(UsageHistory {
+            event,
+            time_zone,
+            program_event_starts,
+            reports,
+        })
+    }
+}
+
+/// Locks the report for the remainder of the transaction
+async fn retrieve_for_update(conn: &mut PgConnection, id: &ReportId) -> Result<Report, AppError> {
+    sqlx::query_as!(
+        PostgresReport,
+        r#"
+        SELECT * FROM report WHERE id = $1 FOR UPDATE
+        "#,
+        id.as_str()
+    )
+    .fetch_one(conn)
+    .await?
+    .try_into()
+}
 
 pub(crate) struct PgReportStorage {
     db: PgPool,
@@ -129,6 +299,30 @@ impl Crud for PgReportStorage {
             ));
         }
 
+        // the report is attributed to the VEN of the user that is permitted in the program and
+        // identified by the client name, or else to the only permitted one of the user
+        let candidates: Vec<String> = user
+            .ven_ids_string()
+            .into_iter()
+            .filter(|id| permitted_vens.is_empty() || permitted_vens.contains(id))
+            .collect();
+        let ven_id = match sqlx::query_scalar!(
+            r#"
+            SELECT id FROM ven WHERE id = ANY($1) AND (id = $2 OR ven_name = $2) ORDER BY id = $2 DESC LIMIT 1
+            "#,
+            &candidates,
+            new.client_name,
+        )
+        .fetch_optional(&self.db)
+        .await?
+        {
+            Some(ven_id) => Some(ven_id),
+            None if candidates.len() == 1 => candidates.into_iter().next(),
+            None => None,
+        };
+
+        let mut tx = self.db.begin().await?;
+
         let report: Report = sqlx::query_as!(
             PostgresReport,
             r#"
@@ -143,10 +337,25 @@ impl Crud for PgReportStorage {
             to_json_value(new.payload_descriptors)?,
             serde_json::to_value(new.resources).map_err(AppError::SerdeJsonBadRequest)?,
         )
-            .fetch_one(&self.db)
+            .fetch_one(&mut *tx)
             .await?
             .try_into()?;
 
+        if let Some(ven_id) = ven_id {
+            sqlx::query!(
+                r#"
+                INSERT INTO report_ven (report_id, ven_id) VALUES ($1, $2)
+                "#,
+                report.id.as_str(),
+                ven_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        audit::created(&mut tx, user, AuditObjectType::Report, &report.id, &report).await?;
+        tx.commit().await?;
+
         info!(report_id = report.id.as_str(), "created report");
 
         Ok(report)
@@ -241,6 +450,9 @@ impl Crud for PgReportStorage {
         User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
         let business_ids = extract_business_ids(user);
+        let mut tx = self.db.begin().await?;
+        let before = retrieve_for_update(&mut tx, id).await?;
+
         let report: Report = sqlx::query_as!(
             PostgresReport,
             r#"
@@ -275,10 +487,13 @@ impl Crud for PgReportStorage {
             to_json_value(new.payload_descriptors)?,
             serde_json::to_value(new.resources).map_err(AppError::SerdeJsonBadRequest)?,
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *tx)
         .await?
         .try_into()?;
 
+        audit::updated(&mut tx, user, AuditObjectType::Report, id, &before, &report).await?;
+        tx.commit().await?;
+
         info!(report_id = report.id.as_str(), "updated report");
 
         Ok(report)
@@ -290,6 +505,7 @@ impl Crud for PgReportStorage {
         User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
         let business_ids = extract_business_ids(user);
+        let mut tx = self.db.begin().await?;
 
         let report: Report = sqlx::query_as!(
             PostgresReport,
@@ -304,12 +520,66 @@ impl Crud for PgReportStorage {
             id.as_str(),
             business_ids.as_deref(),
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *tx)
         .await?
         .try_into()?;
 
+        audit::deleted(&mut tx, user, AuditObjectType::Report, id, &report).await?;
+        tx.commit().await?;
+
         info!(report_id = report.id.as_str(), "deleted report");
 
         Ok(report)
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use crate::{
+        data_source::{postgres::report::PgReportStorage, Crud, ReportCrud},
+        jwt::{AuthRole, Claims, User},
+    };
+    use openleadr_wire::report::ReportContent;
+    use sqlx::PgPool;
+
+    #[sqlx::test(fixtures("users", "programs", "business", "events", "vens", "vens-programs"))]
+    async fn usage_history_of_the_program_only(db: PgPool) {
+        sqlx::query("INSERT INTO business (id, name) VALUES ('business-2', 'Business 2')")
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE program SET business_id = 'business-2' WHERE id = 'program-1'")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // ven-1 reports in program-1 of business-2 and program-3 of business-1
+        let storage = PgReportStorage::from(db);
+        let ven = User(Claims::new(vec![AuthRole::VEN("ven-1".parse().unwrap())]));
+        for (program_id, event_id) in [("program-1", "event-1"), ("program-3", "event-3")] {
+            let report = ReportContent {
+                program_id: program_id.parse().unwrap(),
+                event_id: event_id.parse().unwrap(),
+                client_name: "ven-1-name".to_string(),
+                report_name: None,
+                payload_descriptors: None,
+                resources: vec![],
+            };
+            storage.create(report, &ven).await.unwrap();
+        }
+
+        let business = User(Claims::new(vec![AuthRole::Business(
+            "business-2".to_string(),
+        )]));
+        let history = storage
+            .usage_history(&"event-1".parse().unwrap(), 30, &business)
+            .await
+            .unwrap();
+        assert_eq!(history.reports.len(), 1);
+        assert!(history
+            .reports
+            .iter()
+            .all(|(_, report)| report.content.program_id.as_str() == "program-1"));
+    }
+}
@@ -1,23 +1,60 @@
 use crate::{
     api::resource::QueryParams,
     data_source::{
-        postgres::{to_json_value, PgTargetsFilter},
-        ResourceCrud, VenScopedCrud,
+        postgres::{audit, end_savepoint, finish_batch, to_json_value, PgTargetsFilter},
+        AuditObjectType, ResourceCrud, VenScopedCrud,
     },
     error::AppError,
-    jwt::User,
+    jwt::{Claims, User},
 };
 use axum::async_trait;
 use chrono::{DateTime, Utc};
 use openleadr_wire::{
+    batch::BatchUpdate,
     resource::{Resource, ResourceContent, ResourceId},
     ven::VenId,
 };
-use sqlx::PgPool;
+use sqlx::{Acquire, PgConnection, PgPool};
 use tracing::{error, trace};
 
 #[async_trait]
-impl ResourceCrud for PgResourceStorage {}
+impl ResourceCrud for PgResourceStorage {
+    async fn create_batch(
+        &self,
+        new: Vec<ResourceContent>,
+        ven_id: VenId,
+        User(user): &User,
+    ) -> Result<Vec<Resource>, AppError> {
+        let mut tx = self.db.begin().await?;
+        let mut results = Vec::with_capacity(new.len());
+
+        for content in new {
+            let mut savepoint = tx.begin().await?;
+            let result = Self::create_in(&mut savepoint, content, &ven_id, user).await;
+            results.push(end_savepoint(savepoint, result).await?);
+        }
+
+        finish_batch(tx, results).await
+    }
+
+    async fn update_batch(
+        &self,
+        updates: Vec<BatchUpdate<ResourceId, ResourceContent>>,
+        ven_id: VenId,
+        User(user): &User,
+    ) -> Result<Vec<Resource>, AppError> {
+        let mut tx = self.db.begin().await?;
+        let mut results = Vec::with_capacity(updates.len());
+
+        for BatchUpdate { id, content } in updates {
+            let mut savepoint = tx.begin().await?;
+            let result = Self::update_in(&mut savepoint, &id, &ven_id, content, user).await;
+            results.push(end_savepoint(savepoint, result).await?);
+        }
+
+        finish_batch(tx, results).await
+    }
+}
 
 pub(crate) struct PgResourceStorage {
     db: PgPool,
@@ -124,32 +161,11 @@ impl VenScopedCrud for PgResourceStorage {
         &self,
         new: Self::NewType,
         ven_id: VenId,
-        _user: &Self::PermissionFilter,
+        User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
-        let resource: Resource = sqlx::query_as!(
-            PostgresResource,
-            r#"
-            INSERT INTO resource (
-                id,
-                created_date_time,
-                modification_date_time,
-                resource_name,
-                ven_id,
-                attributes,
-                targets
-            )
-            VALUES (gen_random_uuid(), now(), now(), $1, $2, $3, $4)
-            RETURNING *
-            "#,
-            new.resource_name,
-            ven_id.as_str(),
-            to_json_value(new.attributes)?,
-            to_json_value(new.targets)?,
-        )
-        .fetch_one(&self.db)
-        .await?
-        .try_into()?;
-
+        let mut tx = self.db.begin().await?;
+        let resource = Self::create_in(&mut tx, new, &ven_id, user).await?;
+        tx.commit().await?;
         Ok(resource)
     }
 
@@ -241,57 +257,137 @@ impl VenScopedCrud for PgResourceStorage {
         id: &Self::Id,
         ven_id: VenId,
         new: Self::NewType,
-        _user: &Self::PermissionFilter,
+        User(user): &Self::PermissionFilter,
+    ) -> Result<Self::Type, Self::Error> {
+        let mut tx = self.db.begin().await?;
+        let resource = Self::update_in(&mut tx, id, &ven_id, new, user).await?;
+        tx.commit().await?;
+        Ok(resource)
+    }
+
+    async fn delete(
+        &self,
+        id: &Self::Id,
+        ven_id: VenId,
+        User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
+        let mut tx = self.db.begin().await?;
         let resource: Resource = sqlx::query_as!(
             PostgresResource,
             r#"
-            UPDATE resource
-            SET modification_date_time = now(),
-                resource_name = $3,
-                ven_id = $4,
-                attributes = $5,
-                targets = $6
-            WHERE id = $1 AND ven_id = $2
-            RETURNING *
+            DELETE FROM resource r
+            WHERE r.id = $1 AND r.ven_id = $2
+            RETURNING r.*
             "#,
             id.as_str(),
             ven_id.as_str(),
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .try_into()?;
+
+        audit::deleted(&mut tx, user, AuditObjectType::Resource, id, &resource).await?;
+        tx.commit().await?;
+        Ok(resource)
+    }
+}
+
+impl PgResourceStorage {
+    async fn create_in(
+        conn: &mut PgConnection,
+        new: ResourceContent,
+        ven_id: &VenId,
+        user: &Claims,
+    ) -> Result<Resource, AppError> {
+        let resource: Resource = sqlx::query_as!(
+            PostgresResource,
+            r#"
+            INSERT INTO resource (
+                id,
+                created_date_time,
+                modification_date_time,
+                resource_name,
+                ven_id,
+                attributes,
+                targets
+            )
+            VALUES (gen_random_uuid(), now(), now(), $1, $2, $3, $4)
+            RETURNING *
+            "#,
             new.resource_name,
             ven_id.as_str(),
             to_json_value(new.attributes)?,
-            to_json_value(new.targets)?
+            to_json_value(new.targets)?,
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *conn)
         .await?
         .try_into()?;
 
+        audit::created(
+            conn,
+            user,
+            AuditObjectType::Resource,
+            &resource.id,
+            &resource,
+        )
+        .await?;
         Ok(resource)
     }
 
-    async fn delete(
-        &self,
-        id: &Self::Id,
-        ven_id: VenId,
-        _user: &Self::PermissionFilter,
-    ) -> Result<Self::Type, Self::Error> {
-        Ok(sqlx::query_as!(
+    async fn update_in(
+        conn: &mut PgConnection,
+        id: &ResourceId,
+        ven_id: &VenId,
+        new: ResourceContent,
+        user: &Claims,
+    ) -> Result<Resource, AppError> {
+        let before: Resource = sqlx::query_as!(
             PostgresResource,
             r#"
-            DELETE FROM resource r
-            WHERE r.id = $1 AND r.ven_id = $2
-            RETURNING r.*
+            SELECT * FROM resource WHERE id = $1 AND ven_id = $2 FOR UPDATE
             "#,
             id.as_str(),
             ven_id.as_str(),
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *conn)
+        .await?
+        .try_into()?;
+
+        let resource: Resource = sqlx::query_as!(
+            PostgresResource,
+            r#"
+            UPDATE resource
+            SET modification_date_time = now(),
+                resource_name = $3,
+                ven_id = $4,
+                attributes = $5,
+                targets = $6
+            WHERE id = $1 AND ven_id = $2
+            RETURNING *
+            "#,
+            id.as_str(),
+            ven_id.as_str(),
+            new.resource_name,
+            ven_id.as_str(),
+            to_json_value(new.attributes)?,
+            to_json_value(new.targets)?
+        )
+        .fetch_one(&mut *conn)
         .await?
-        .try_into()?)
+        .try_into()?;
+
+        audit::updated(
+            conn,
+            user,
+            AuditObjectType::Resource,
+            id,
+            &before,
+            &resource,
+        )
+        .await?;
+        Ok(resource)
     }
-}
 
-impl PgResourceStorage {
     pub(crate) async fn retrieve_by_ven(
         db: &PgPool,
         ven_id: &VenId,
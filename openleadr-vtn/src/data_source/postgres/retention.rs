@@ -0,0 +1,227 @@
+use crate::{
+    config::RetentionMode,
+    data_source::{Expired, RetentionSource},
+    error::AppError,
+};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use tracing::trace;
+
+pub(crate) struct PgRetentionSource {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgRetentionSource {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+impl PgRetentionSource {
+    async fn expire_reports(
+        conn: &mut PgConnection,
+        before: DateTime<Utc>,
+        mode: RetentionMode,
+    ) -> Result<u64, AppError> {
+        let count = sqlx::query_scalar!(
+            r#"
+            WITH expired AS (
+                DELETE FROM report WHERE modification_date_time < $1 RETURNING *
+            ), archived AS (
+                INSERT INTO report_archive (id, created_date_time, modification_date_time, archived_date_time, program_id, event_id, client_name, report_name, payload_descriptors, resources, ven_id)
+                SELECT e.id, e.created_date_time, e.modification_date_time, now(), e.program_id, e.event_id, e.client_name, e.report_name, e.payload_descriptors, e.resources, rv.ven_id
+                FROM expired e
+                  -- the statement still sees the report_ven rows the delete cascades to
+                  LEFT JOIN report_ven rv ON rv.report_id = e.id
+                WHERE $2
+            )
+            SELECT count(*) AS "count!" FROM expired
+            "#,
+            before,
+            mode == RetentionMode::Archive,
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(count as u64)
+    }
+
+    async fn expire_events(
+        conn: &mut PgConnection,
+        before: DateTime<Utc>,
+        mode: RetentionMode,
+    ) -> Result<u64, AppError> {
+        // events without an end are never removed, as they are not over yet
+        let ids = sqlx::query_scalar!(
+            r#"
+            DELETE FROM event e
+            USING event_lifecycle l
+            WHERE l.event_id = e.id
+              AND l.ends < $1
+              AND NOT EXISTS (SELECT 1 FROM report r WHERE r.event_id = e.id)
+            RETURNING e.id
+            "#,
+            before
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        if mode == RetentionMode::Purge {
+            sqlx::query!(
+                r#"
+                DELETE FROM event_version WHERE id = ANY($1)
+                "#,
+                &ids
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(ids.len() as u64)
+    }
+}
+
+#[async_trait]
+impl RetentionSource for PgRetentionSource {
+    async fn expire(
+        &self,
+        events_before: Option<DateTime<Utc>>,
+        reports_before: Option<DateTime<Utc>>,
+        mode: RetentionMode,
+    ) -> Result<Expired, AppError> {
+        let mut tx = self.db.begin().await?;
+        let mut expired = Expired::default();
+
+        // first the reports, such that their events can be removed in the same run
+        if let Some(before) = reports_before {
+            expired.reports = Self::expire_reports(&mut tx, before, mode).await?;
+        }
+        if let Some(before) = events_before {
+            expired.events = Self::expire_events(&mut tx, before, mode).await?;
+        }
+
+        tx.commit().await?;
+        trace!(?expired, ?mode, "expired events and reports");
+
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    struct Counts {
+        events: i64,
+        event_versions: i64,
+        reports: i64,
+        archived_reports: i64,
+    }
+
+    async fn counts(db: &PgPool) -> Counts {
+        sqlx::query_as!(
+            Counts,
+            r#"
+            SELECT (SELECT count(*) FROM event)          AS "events!",
+                   (SELECT count(*) FROM event_version)  AS "event_versions!",
+                   (SELECT count(*) FROM report)         AS "reports!",
+                   (SELECT count(*) FROM report_archive) AS "archived_reports!"
+            "#
+        )
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(fixtures("programs", "events", "reports"))]
+    async fn events_with_reports_are_kept(db: PgPool) {
+        let source: PgRetentionSource = db.clone().into();
+
+        let expired = source
+            .expire(Some(Utc::now()), None, RetentionMode::Archive)
+            .await
+            .unwrap();
+        assert_eq!(expired, Expired::default());
+        assert_eq!(counts(&db).await.events, 3);
+    }
+
+    #[sqlx::test(fixtures("users", "programs", "events", "reports", "vens"))]
+    async fn archive(db: PgPool) {
+        let source: PgRetentionSource = db.clone().into();
+        sqlx::query("INSERT INTO report_ven (report_id, ven_id) VALUES ('report-1', 'ven-1')")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // the reports were last modified in 2024
+        let expired = source
+            .expire(
+                Some(Utc::now()),
+                Some("2024-01-01T00:00:00Z".parse().unwrap()),
+                RetentionMode::Archive,
+            )
+            .await
+            .unwrap();
+        assert_eq!(expired, Expired::default());
+
+        // only event-1 ended, the intervals of the other events have no start
+        let expired = source
+            .expire(
+                Some(Utc::now() - Duration::days(1)),
+                Some(Utc::now() - Duration::days(1)),
+                RetentionMode::Archive,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            expired,
+            Expired {
+                events: 1,
+                reports: 2
+            }
+        );
+        let counts = counts(&db).await;
+        assert_eq!(counts.reports, 0);
+        assert_eq!(counts.archived_reports, 2);
+        assert_eq!(counts.events, 2);
+        let vens: Vec<(String, Option<String>)> =
+            sqlx::query_as("SELECT id, ven_id FROM report_archive ORDER BY id")
+                .fetch_all(&db)
+                .await
+                .unwrap();
+        assert_eq!(
+            vens,
+            [
+                ("report-1".to_string(), Some("ven-1".to_string())),
+                ("report-2".to_string(), None)
+            ]
+        );
+        // the history of the removed event is kept
+        assert_eq!(counts.event_versions, 3);
+    }
+
+    #[sqlx::test(fixtures("programs", "events", "reports"))]
+    async fn purge(db: PgPool) {
+        let source: PgRetentionSource = db.clone().into();
+
+        let expired = source
+            .expire(Some(Utc::now()), Some(Utc::now()), RetentionMode::Purge)
+            .await
+            .unwrap();
+        assert_eq!(
+            expired,
+            Expired {
+                events: 1,
+                reports: 2
+            }
+        );
+        let counts = counts(&db).await;
+        assert_eq!(counts.reports, 0);
+        assert_eq!(counts.archived_reports, 0);
+        assert_eq!(counts.events, 2);
+        assert_eq!(counts.event_versions, 2);
+    }
+}
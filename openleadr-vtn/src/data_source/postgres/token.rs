@@ -0,0 +1,250 @@
+use crate::{
+    data_source::{RefreshToken, TokenSource},
+    error::AppError,
+};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+pub struct PgTokenSource {
+    db: PgPool,
+}
+
+impl From<PgPool> for PgTokenSource {
+    fn from(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl TokenSource for PgTokenSource {
+    async fn add_refresh_token(&self, token: &RefreshToken) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO refresh_token (token_hash, client_id, scope, created, expires)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            token.token_hash,
+            token.client_id,
+            token.scope,
+            token.created,
+            token.expires,
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshToken>, AppError> {
+        Ok(sqlx::query_as!(
+            RefreshToken,
+            r#"
+            SELECT token_hash, client_id, scope, created, expires
+            FROM refresh_token
+            WHERE token_hash = $1
+            "#,
+            token_hash,
+        )
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn remove_refresh_token(
+        &self,
+        token_hash: &str,
+        client_id: &str,
+    ) -> Result<Option<RefreshToken>, AppError> {
+        Ok(sqlx::query_as!(
+            RefreshToken,
+            r#"
+            DELETE FROM refresh_token
+            WHERE token_hash = $1
+              AND client_id = $2
+              AND expires > now()
+            RETURNING token_hash, client_id, scope, created, expires
+            "#,
+            token_hash,
+            client_id,
+        )
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn revoke_access_token(&self, jti: &str, expires: DateTime<Utc>) -> Result<(), AppError> {
+        let mut tx = self.db.begin().await?;
+
+        // expired tokens are rejected anyway
+        sqlx::query!("DELETE FROM revoked_token WHERE expires < now()")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO revoked_token (jti, expires)
+            VALUES ($1, $2)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+            jti,
+            expires,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn is_revoked(
+        &self,
+        jti: Option<&str>,
+        client_id: &str,
+        issued_at: DateTime<Utc>,
+    ) -> Result<bool, AppError> {
+        Ok(sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (SELECT FROM revoked_token WHERE jti = $1)
+                OR EXISTS (SELECT FROM revoked_client
+                           WHERE client_id = $2
+                             AND date_trunc('second', revoked_at) >= date_trunc('second', $3::timestamptz))
+                AS "revoked!"
+            "#,
+            jti,
+            client_id,
+            issued_at,
+        )
+        .fetch_one(&self.db)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "live-db-test")]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn refresh_token(token_hash: &str) -> RefreshToken {
+        let now = Utc::now();
+        RefreshToken {
+            token_hash: token_hash.to_string(),
+            client_id: "admin".to_string(),
+            scope: "read_all".to_string(),
+            created: now,
+            expires: now + Duration::days(1),
+        }
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn refresh_token_used_once(db: PgPool) {
+        let tokens: PgTokenSource = db.into();
+        tokens
+            .add_refresh_token(&refresh_token("hash"))
+            .await
+            .unwrap();
+
+        let token = tokens.get_refresh_token("hash").await.unwrap().unwrap();
+        assert_eq!(token.client_id, "admin");
+        assert_eq!(token.scope, "read_all");
+
+        // of another client
+        assert!(tokens
+            .remove_refresh_token("hash", "other")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(tokens
+            .remove_refresh_token("hash", "admin")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(tokens
+            .remove_refresh_token("hash", "admin")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(tokens.get_refresh_token("hash").await.unwrap().is_none());
+    }
+
+    #[sqlx::test(fixtures("users"))]
+    async fn removed_with_credentials(db: PgPool) {
+        let tokens: PgTokenSource = db.clone().into();
+        tokens
+            .add_refresh_token(&refresh_token("hash"))
+            .await
+            .unwrap();
+
+        sqlx::query!("DELETE FROM user_credentials WHERE client_id = 'admin'")
+            .execute(&db)
+            .await
+            .unwrap();
+        assert!(tokens.get_refresh_token("hash").await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn revoke(db: PgPool) {
+        let tokens: PgTokenSource = db.clone().into();
+        let issued_at = Utc::now() - Duration::minutes(1);
+
+        assert!(!tokens
+            .is_revoked(Some("jti-1"), "client", issued_at)
+            .await
+            .unwrap());
+        tokens
+            .revoke_access_token("jti-1", Utc::now() + Duration::hours(1))
+            .await
+            .unwrap();
+        // idempotent
+        tokens
+            .revoke_access_token("jti-1", Utc::now() + Duration::hours(1))
+            .await
+            .unwrap();
+        assert!(tokens
+            .is_revoked(Some("jti-1"), "client", issued_at)
+            .await
+            .unwrap());
+        assert!(!tokens
+            .is_revoked(Some("jti-2"), "client", issued_at)
+            .await
+            .unwrap());
+
+        sqlx::query!("INSERT INTO revoked_client (client_id, revoked_at) VALUES ('client', now())")
+            .execute(&db)
+            .await
+            .unwrap();
+        assert!(tokens.is_revoked(None, "client", issued_at).await.unwrap());
+        assert!(!tokens
+            .is_revoked(None, "client", Utc::now() + Duration::minutes(1))
+            .await
+            .unwrap());
+        assert!(!tokens.is_revoked(None, "other", issued_at).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn revoke_within_second(db: PgPool) {
+        let tokens: PgTokenSource = db.clone().into();
+        let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+
+        sqlx::query!(
+            "INSERT INTO revoked_client (client_id, revoked_at) VALUES ('client', '2026-01-01T10:00:00.500Z')"
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        // the issue time is truncated to the second, like the `iat` claim
+        for issued_at in [
+            "2026-01-01T09:59:59Z",
+            "2026-01-01T10:00:00Z",
+            "2026-01-01T10:00:00.900Z",
+        ] {
+            assert!(tokens
+                .is_revoked(None, "client", at(issued_at))
+                .await
+                .unwrap());
+        }
+        assert!(!tokens
+            .is_revoked(None, "client", at("2026-01-01T10:00:01Z"))
+            .await
+            .unwrap());
+    }
+}
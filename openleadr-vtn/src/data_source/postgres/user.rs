@@ -1,16 +1,19 @@
 use crate::{
-    data_source::{postgres::PgId, AuthInfo, AuthSource, UserDetails},
+    data_source::{
+        postgres::{audit, PgId},
+        AuditObjectType, AuthInfo, AuthSource, CredentialDetails, UserDetails,
+    },
     error::AppError,
-    jwt::AuthRole,
+    jwt::{AuthRole, User},
 };
 use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
-    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
 };
 use axum::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::{Executor, PgConnection, PgPool, Postgres};
-use tracing::warn;
+use sqlx::{types::Json, Executor, PgConnection, PgPool, Postgres};
+use tracing::{debug, info, warn};
 
 pub struct PgAuthSource {
     db: PgPool,
@@ -28,6 +31,7 @@ struct IntermediateUser {
     reference: String,
     description: Option<String>,
     client_ids: Option<Vec<String>>,
+    credentials: Option<Json<Vec<CredentialDetails>>>,
     created: DateTime<Utc>,
     modified: DateTime<Utc>,
     business_ids: Option<Vec<String>>,
@@ -72,31 +76,63 @@ impl TryFrom<IntermediateUser> for UserDetails {
             roles.push(AuthRole::AnyBusiness)
         }
 
+        let mut credentials = u.credentials.map(|Json(c)| c).unwrap_or_default();
+        credentials.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+
         Ok(Self {
             id: u.id,
             reference: u.reference,
             description: u.description,
             roles,
             client_ids: u.client_ids.unwrap_or_default(),
+            credentials,
             created: u.created,
             modified: u.modified,
         })
     }
 }
 
-struct IdAndSecret {
+struct StoredCredential {
     id: String,
     client_secret: String,
+    expires: Option<DateTime<Utc>>,
+}
+
+/// Client secrets are hashed with Argon2id, using the parameters recommended by OWASP:
+/// 19 MiB of memory, 2 iterations, and a parallelism of 1.
+/// Hashes with other parameters are replaced on the next successful login.
+fn argon2() -> Argon2<'static> {
+    let params = Params::new(19 * 1024, 2, 1, None).expect("valid Argon2 parameters");
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+pub(super) fn hash_secret(client_secret: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(argon2()
+        .hash_password(client_secret.as_bytes(), &salt)?
+        .to_string())
+}
+
+fn outdated_hash(hash: &PasswordHash) -> bool {
+    let current = argon2();
+    hash.algorithm != Algorithm::Argon2id.ident()
+        || hash.version != Some(Version::V0x13.into())
+        || Params::try_from(hash).map_or(true, |params| {
+            params.m_cost() != current.params().m_cost()
+                || params.t_cost() != current.params().t_cost()
+                || params.p_cost() != current.params().p_cost()
+        })
 }
 
 #[async_trait]
 impl AuthSource for PgAuthSource {
     async fn check_credentials(&self, client_id: &str, client_secret: &str) -> Option<AuthInfo> {
         let db_entry = sqlx::query_as!(
-            IdAndSecret,
+            StoredCredential,
             r#"
             SELECT id,
-                   client_secret
+                   client_secret,
+                   expires
             FROM "user"
                 JOIN user_credentials ON user_id = id
             WHERE client_id = $1
@@ -111,18 +147,74 @@ impl AuthSource for PgAuthSource {
             .inspect_err(|err| warn!("Failed to parse client_secret_hash in DB: {}", err))
             .ok()?;
 
-        Argon2::default()
+        argon2()
             .verify_password(client_secret.as_bytes(), &parsed_hash)
             .ok()?;
 
+        if db_entry
+            .expires
+            .is_some_and(|expires| expires <= Utc::now())
+        {
+            debug!(client_id, "rejected expired credential");
+            return None;
+        }
+
+        let new_hash = if outdated_hash(&parsed_hash) {
+            info!(
+                client_id,
+                "rehashing client secret with the current parameters"
+            );
+            hash_secret(client_secret)
+                .inspect_err(|err| warn!(client_id, "cannot rehash client secret: {err}"))
+                .ok()
+        } else {
+            None
+        };
+        sqlx::query!(
+            r#"
+            UPDATE user_credentials
+            SET last_used = now(),
+                client_secret = coalesce($2, client_secret)
+            WHERE client_id = $1
+            "#,
+            client_id,
+            new_hash,
+        )
+        .execute(&self.db)
+        .await
+        .inspect_err(|err| warn!(client_id, "cannot update credential: {err}"))
+        .ok()?;
+
         let user = Self::get_user(&self.db, &db_entry.id)
             .await
             .inspect_err(|err| warn!(client_id, "error fetching user: {err}"))
             .ok()?;
 
+        // self-registered VENs have to be approved before their users can act as the VEN
+        let unapproved = sqlx::query_scalar!(
+            r#"
+            SELECT r.ven_id
+            FROM ven_registration r
+                JOIN user_ven uv ON uv.ven_id = r.ven_id
+            WHERE uv.user_id = $1
+              AND r.approval_state <> 'APPROVED'
+            "#,
+            db_entry.id,
+        )
+        .fetch_all(&self.db)
+        .await
+        .inspect_err(|err| warn!(client_id, "error fetching VEN registrations: {err}"))
+        .ok()?;
+        let roles = user
+            .roles
+            .into_iter()
+            .filter(|role| !matches!(role, AuthRole::VEN(id) if unapproved.iter().any(|ven_id| ven_id == id.as_str())))
+            .collect();
+
         Some(AuthInfo {
             client_id: client_id.to_string(),
-            roles: user.roles,
+            roles,
+            expires: db_entry.expires,
         })
     }
 
@@ -136,6 +228,12 @@ impl AuthSource for PgAuthSource {
             r#"
             SELECT u.*,
                    array_agg(DISTINCT c.client_id) FILTER ( WHERE c.client_id IS NOT NULL )     AS client_ids,
+                   jsonb_agg(DISTINCT jsonb_build_object(
+                       'client_id', c.client_id,
+                       'created', c.created,
+                       'expires', c.expires,
+                       'last_used', c.last_used
+                   )) FILTER ( WHERE c.client_id IS NOT NULL )                                  AS "credentials: Json<Vec<CredentialDetails>>",
                    array_agg(DISTINCT b.business_id) FILTER ( WHERE b.business_id IS NOT NULL ) AS business_ids,
                    array_agg(DISTINCT ven.ven_id) FILTER ( WHERE ven.ven_id IS NOT NULL )       AS ven_ids,
                    ab.user_id IS NOT NULL                                                       AS "is_any_business_user!",
@@ -170,6 +268,7 @@ impl AuthSource for PgAuthSource {
         reference: &str,
         description: Option<&str>,
         roles: &[AuthRole],
+        User(manager): &User,
     ) -> Result<UserDetails, AppError> {
         let mut tx = self.db.begin().await?;
 
@@ -200,6 +299,7 @@ impl AuthSource for PgAuthSource {
         let user = Self::get_user(&mut *tx, &user.id)
             .await
             .inspect_err(|err| warn!("cannot find user just created: {}", err))?;
+        audit::created(&mut tx, manager, AuditObjectType::User, user.id(), &user).await?;
 
         tx.commit().await?;
         Ok(user)
@@ -210,30 +310,39 @@ impl AuthSource for PgAuthSource {
         user_id: &str,
         client_id: &str,
         client_secret: &str,
+        expires: Option<DateTime<Utc>>,
+        User(manager): &User,
     ) -> Result<UserDetails, AppError> {
-        let salt = SaltString::generate(&mut OsRng);
-
-        let argon2 = Argon2::default();
-        let hash = argon2
-            .hash_password(client_secret.as_bytes(), &salt)?
-            .to_string();
+        let hash = hash_secret(client_secret)?;
 
         let mut tx = self.db.begin().await?;
+        let before = Self::retrieve_for_update(&mut tx, user_id).await?;
 
         sqlx::query!(
             r#"
             INSERT INTO user_credentials 
-                (user_id, client_id, client_secret) 
+                (user_id, client_id, client_secret, created, expires) 
             VALUES 
-                ($1, $2, $3)
+                ($1, $2, $3, now(), $4)
             "#,
             user_id,
             client_id,
-            &hash
+            &hash,
+            expires
         )
         .execute(&mut *tx)
         .await?;
         let user = Self::get_user(&mut *tx, user_id).await?;
+        // recorded as an update of the user, such that the secret never ends up in the audit log
+        audit::updated(
+            &mut tx,
+            manager,
+            AuditObjectType::User,
+            user_id,
+            &before,
+            &user,
+        )
+        .await?;
         tx.commit().await?;
 
         Ok(user)
@@ -243,8 +352,11 @@ impl AuthSource for PgAuthSource {
         &self,
         user_id: &str,
         client_id: &str,
+        User(manager): &User,
     ) -> Result<UserDetails, AppError> {
         let mut tx = self.db.begin().await?;
+        let before = Self::retrieve_for_update(&mut tx, user_id).await?;
+        Self::revoke_tokens(&mut tx, user_id, Some(client_id)).await?;
         sqlx::query!(
             r#"
             DELETE FROM user_credentials WHERE user_id = $1 AND client_id = $2
@@ -255,20 +367,37 @@ impl AuthSource for PgAuthSource {
         .execute(&mut *tx)
         .await?;
         let user = Self::get_user(&mut *tx, user_id).await?;
+        audit::updated(
+            &mut tx,
+            manager,
+            AuditObjectType::User,
+            user_id,
+            &before,
+            &user,
+        )
+        .await?;
         tx.commit().await?;
         Ok(user)
     }
 
-    async fn remove_user(&self, user_id: &str) -> Result<UserDetails, AppError> {
-        let user = Self::get_user(&self.db, user_id).await?;
+    async fn remove_user(
+        &self,
+        user_id: &str,
+        User(manager): &User,
+    ) -> Result<UserDetails, AppError> {
+        let mut tx = self.db.begin().await?;
+        let user = Self::retrieve_for_update(&mut tx, user_id).await?;
+        Self::revoke_tokens(&mut tx, user_id, None).await?;
         sqlx::query!(
             r#"
             DELETE FROM "user" WHERE id = $1
             "#,
             user_id
         )
-        .execute(&self.db)
+        .execute(&mut *tx)
         .await?;
+        audit::deleted(&mut tx, manager, AuditObjectType::User, user_id, &user).await?;
+        tx.commit().await?;
 
         Ok(user)
     }
@@ -279,8 +408,10 @@ impl AuthSource for PgAuthSource {
         reference: &str,
         description: Option<&str>,
         roles: &[AuthRole],
+        User(manager): &User,
     ) -> Result<UserDetails, AppError> {
         let mut tx = self.db.begin().await?;
+        let before = Self::retrieve_for_update(&mut tx, user_id).await?;
 
         sqlx::query!(
             r#"
@@ -297,6 +428,8 @@ impl AuthSource for PgAuthSource {
         .execute(&mut *tx)
         .await?;
 
+        // the roles are part of the access tokens, so changed roles require new tokens
+        Self::revoke_tokens(&mut tx, user_id, None).await?;
         Self::delete_all_roles(&mut tx, user_id).await?;
 
         for role in roles {
@@ -312,6 +445,15 @@ impl AuthSource for PgAuthSource {
         let user = Self::get_user(&mut *tx, user_id)
             .await
             .inspect_err(|err| warn!("cannot find user just updated: {}", err))?;
+        audit::updated(
+            &mut tx,
+            manager,
+            AuditObjectType::User,
+            user_id,
+            &before,
+            &user,
+        )
+        .await?;
 
         tx.commit().await?;
         Ok(user)
@@ -319,6 +461,47 @@ impl AuthSource for PgAuthSource {
 }
 
 impl PgAuthSource {
+    /// Locks the user for the remainder of the transaction
+    async fn retrieve_for_update(
+        db: &mut PgConnection,
+        user_id: &str,
+    ) -> Result<UserDetails, AppError> {
+        sqlx::query!(
+            r#"
+            SELECT id FROM "user" WHERE id = $1 FOR UPDATE
+            "#,
+            user_id
+        )
+        .fetch_one(&mut *db)
+        .await?;
+
+        Self::get_user(&mut *db, user_id).await
+    }
+
+    /// Revokes the access tokens issued so far to the given or all credentials of the user
+    pub(super) async fn revoke_tokens(
+        db: &mut PgConnection,
+        user_id: &str,
+        client_id: Option<&str>,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO revoked_client (client_id, revoked_at)
+            SELECT client_id, now()
+            FROM user_credentials
+            WHERE user_id = $1
+              AND ($2::text IS NULL OR client_id = $2)
+            ON CONFLICT (client_id) DO UPDATE SET revoked_at = excluded.revoked_at
+            "#,
+            user_id,
+            client_id
+        )
+        .execute(&mut *db)
+        .await?;
+
+        Ok(())
+    }
+
     async fn delete_all_roles(db: &mut PgConnection, user_id: &str) -> Result<(), AppError> {
         sqlx::query!(
             r#"
@@ -368,7 +551,7 @@ impl PgAuthSource {
         Ok(())
     }
 
-    async fn add_role(
+    pub(super) async fn add_role(
         tx: &mut PgConnection,
         user_id: &str,
         role: &AuthRole,
@@ -422,6 +605,12 @@ impl PgAuthSource {
             r#"
             SELECT u.*,
                    array_agg(DISTINCT c.client_id) FILTER ( WHERE c.client_id IS NOT NULL )     AS client_ids,
+                   jsonb_agg(DISTINCT jsonb_build_object(
+                       'client_id', c.client_id,
+                       'created', c.created,
+                       'expires', c.expires,
+                       'last_used', c.last_used
+                   )) FILTER ( WHERE c.client_id IS NOT NULL )                                  AS "credentials: Json<Vec<CredentialDetails>>",
                    array_agg(DISTINCT b.business_id) FILTER ( WHERE b.business_id IS NOT NULL ) AS business_ids,
                    array_agg(DISTINCT ven.ven_id) FILTER ( WHERE ven.ven_id IS NOT NULL )       AS ven_ids,
                    ab.user_id IS NOT NULL                                                       AS "is_any_business_user!",
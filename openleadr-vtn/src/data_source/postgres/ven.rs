@@ -1,10 +1,11 @@
 use crate::{
     api::ven::QueryParams,
     data_source::{
-        postgres::{resource::PgResourceStorage, to_json_value, PgTargetsFilter},
-        Crud, VenCrud, VenPermissions,
+        postgres::{audit, resource::PgResourceStorage, to_json_value, PgTargetsFilter},
+        AuditObjectType, Crud, VenCrud, VenPermissions,
     },
     error::AppError,
+    jwt::User,
 };
 use axum::async_trait;
 use chrono::{DateTime, Utc};
@@ -12,7 +13,7 @@ use openleadr_wire::{
     resource::Resource,
     ven::{Ven, VenContent, VenId},
 };
-use sqlx::PgPool;
+use sqlx::{Executor, PgConnection, PgPool, Postgres};
 use std::collections::{hash_map::Entry, HashMap};
 use tracing::{error, trace};
 
@@ -106,20 +107,12 @@ impl<'a> From<&'a QueryParams> for PostgresFilter<'a> {
     }
 }
 
-#[async_trait]
-impl Crud for PgVenStorage {
-    type Type = Ven;
-    type Id = VenId;
-    type NewType = VenContent;
-    type Error = AppError;
-    type Filter = QueryParams;
-    type PermissionFilter = VenPermissions;
-
-    async fn create(
-        &self,
-        new: Self::NewType,
-        _user: &Self::PermissionFilter,
-    ) -> Result<Self::Type, Self::Error> {
+impl PgVenStorage {
+    /// Also used by the self-registration of VENs, as part of its transaction
+    pub(super) async fn insert<'c, E>(db: E, new: VenContent) -> Result<Ven, AppError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {
         let ven: Ven = sqlx::query_as!(
             PostgresVen,
             r#"
@@ -138,7 +131,7 @@ impl Crud for PgVenStorage {
             to_json_value(new.attributes)?,
             to_json_value(new.targets)?,
         )
-        .fetch_one(&self.db)
+        .fetch_one(db)
         .await?
         .try_into_ven_with_resources(None)?;
 
@@ -147,12 +140,55 @@ impl Crud for PgVenStorage {
         Ok(ven)
     }
 
+    /// Locks the VEN for the remainder of the transaction
+    async fn retrieve_for_update(
+        conn: &mut PgConnection,
+        id: &VenId,
+        resources: Option<Vec<Resource>>,
+    ) -> Result<Ven, AppError> {
+        sqlx::query_as!(
+            PostgresVen,
+            r#"
+            SELECT * FROM ven WHERE id = $1 FOR UPDATE
+            "#,
+            id.as_str(),
+        )
+        .fetch_one(conn)
+        .await?
+        .try_into_ven_with_resources(resources)
+    }
+}
+
+#[async_trait]
+impl Crud for PgVenStorage {
+    type Type = Ven;
+    type Id = VenId;
+    type NewType = VenContent;
+    type Error = AppError;
+    type Filter = QueryParams;
+    type PermissionFilter = User;
+
+    async fn create(
+        &self,
+        new: Self::NewType,
+        User(user): &Self::PermissionFilter,
+    ) -> Result<Self::Type, Self::Error> {
+        VenPermissions::try_from(user)?;
+
+        let mut tx = self.db.begin().await?;
+        let ven = Self::insert(&mut *tx, new).await?;
+        audit::created(&mut tx, user, AuditObjectType::Ven, &ven.id, &ven).await?;
+        tx.commit().await?;
+
+        Ok(ven)
+    }
+
     async fn retrieve(
         &self,
         id: &Self::Id,
-        permissions: &Self::PermissionFilter,
+        User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
-        let ids = permissions.as_value();
+        let ids = VenPermissions::try_from(user)?.as_value();
 
         let resources = PgResourceStorage::retrieve_by_ven(&self.db, id).await?;
         let resources = if resources.is_empty() {
@@ -184,12 +220,12 @@ impl Crud for PgVenStorage {
     async fn retrieve_all(
         &self,
         filter: &Self::Filter,
-        permissions: &Self::PermissionFilter,
+        User(user): &Self::PermissionFilter,
     ) -> Result<Vec<Self::Type>, Self::Error> {
         let pg_filter: PostgresFilter = filter.into();
         trace!(?pg_filter);
 
-        let ids = permissions.as_value();
+        let ids = VenPermissions::try_from(user)?.as_value();
 
         let pg_vens: Vec<PostgresVen> = sqlx::query_as!(
             PostgresVen,
@@ -256,8 +292,10 @@ impl Crud for PgVenStorage {
         &self,
         id: &Self::Id,
         new: Self::NewType,
-        _user: &Self::PermissionFilter,
+        User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
+        VenPermissions::try_from(user)?;
+
         let resources = PgResourceStorage::retrieve_by_ven(&self.db, id).await?;
         let resources = if resources.is_empty() {
             None
@@ -265,6 +303,9 @@ impl Crud for PgVenStorage {
             Some(resources)
         };
 
+        let mut tx = self.db.begin().await?;
+        let before = Self::retrieve_for_update(&mut tx, id, resources.clone()).await?;
+
         let ven: Ven = sqlx::query_as!(
             PostgresVen,
             r#"
@@ -281,10 +322,13 @@ impl Crud for PgVenStorage {
             to_json_value(new.attributes)?,
             to_json_value(new.targets)?
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *tx)
         .await?
         .try_into_ven_with_resources(resources)?;
 
+        audit::updated(&mut tx, user, AuditObjectType::Ven, id, &before, &ven).await?;
+        tx.commit().await?;
+
         trace!(ven_id = id.as_str(), "updated ven");
 
         Ok(ven)
@@ -293,8 +337,10 @@ impl Crud for PgVenStorage {
     async fn delete(
         &self,
         id: &Self::Id,
-        _user: &Self::PermissionFilter,
+        User(user): &Self::PermissionFilter,
     ) -> Result<Self::Type, Self::Error> {
+        VenPermissions::try_from(user)?;
+
         if !PgResourceStorage::retrieve_by_ven(&self.db, id)
             .await?
             .is_empty()
@@ -304,6 +350,7 @@ impl Crud for PgVenStorage {
             ))?
         }
 
+        let mut tx = self.db.begin().await?;
         let ven: Ven = sqlx::query_as!(
             PostgresVen,
             r#"
@@ -313,10 +360,13 @@ impl Crud for PgVenStorage {
             "#,
             id.as_str(),
         )
-        .fetch_one(&self.db)
+        .fetch_one(&mut *tx)
         .await?
         .try_into_ven_with_resources(None)?;
 
+        audit::deleted(&mut tx, user, AuditObjectType::Ven, id, &ven).await?;
+        tx.commit().await?;
+
         trace!(ven_id = id.as_str(), "deleted ven");
 
         Ok(ven)
@@ -330,6 +380,7 @@ mod tests {
         api::ven::QueryParams,
         data_source::{postgres::ven::PgVenStorage, Crud},
         error::AppError,
+        jwt::{AuthRole, Claims, User},
     };
     use openleadr_wire::{
         target::{TargetEntry, TargetMap, TargetType},
@@ -349,6 +400,10 @@ mod tests {
         }
     }
 
+    fn ven_manager() -> User {
+        User(Claims::new(vec![AuthRole::VenManager]))
+    }
+
     fn ven_1() -> Ven {
         Ven {
             id: "ven-1".parse().unwrap(),
@@ -382,7 +437,7 @@ mod tests {
     }
 
     mod get_all {
-        use crate::data_source::postgres::ven::{PgVenStorage, VenPermissions};
+        use crate::data_source::postgres::ven::PgVenStorage;
 
         use super::*;
         use openleadr_wire::target::TargetType;
@@ -391,7 +446,7 @@ mod tests {
         async fn default_get_all(db: PgPool) {
             let repo: PgVenStorage = db.into();
             let mut vens = repo
-                .retrieve_all(&Default::default(), &VenPermissions::AllAllowed)
+                .retrieve_all(&Default::default(), &ven_manager())
                 .await
                 .unwrap();
             assert_eq!(vens.len(), 2);
@@ -408,7 +463,7 @@ mod tests {
                         limit: 1,
                         ..Default::default()
                     },
-                    &VenPermissions::AllAllowed,
+                    &ven_manager(),
                 )
                 .await
                 .unwrap();
@@ -424,7 +479,7 @@ mod tests {
                         skip: 1,
                         ..Default::default()
                     },
-                    &VenPermissions::AllAllowed,
+                    &ven_manager(),
                 )
                 .await
                 .unwrap();
@@ -436,7 +491,7 @@ mod tests {
                         skip: 2,
                         ..Default::default()
                     },
-                    &VenPermissions::AllAllowed,
+                    &ven_manager(),
                 )
                 .await
                 .unwrap();
@@ -454,7 +509,7 @@ mod tests {
                         target_values: Some(vec!["group-1".to_string()]),
                         ..Default::default()
                     },
-                    &VenPermissions::AllAllowed,
+                    &ven_manager(),
                 )
                 .await
                 .unwrap();
@@ -467,7 +522,7 @@ mod tests {
                         target_values: Some(vec!["not-existent".to_string()]),
                         ..Default::default()
                     },
-                    &VenPermissions::AllAllowed,
+                    &ven_manager(),
                 )
                 .await
                 .unwrap();
@@ -479,7 +534,7 @@ mod tests {
                         ven_name: Some("ven-2-name".to_string()),
                         ..Default::default()
                     },
-                    &VenPermissions::AllAllowed,
+                    &ven_manager(),
                 )
                 .await
                 .unwrap();
@@ -492,7 +547,7 @@ mod tests {
                         ven_name: Some("ven-not-existent".to_string()),
                         ..Default::default()
                     },
-                    &VenPermissions::AllAllowed,
+                    &ven_manager(),
                 )
                 .await
                 .unwrap();
@@ -501,8 +556,6 @@ mod tests {
     }
 
     mod get {
-        use crate::data_source::postgres::ven::VenPermissions;
-
         use super::*;
 
         #[sqlx::test(fixtures("users", "vens"))]
@@ -510,7 +563,7 @@ mod tests {
             let repo: PgVenStorage = db.into();
 
             let ven = repo
-                .retrieve(&"ven-1".parse().unwrap(), &VenPermissions::AllAllowed)
+                .retrieve(&"ven-1".parse().unwrap(), &ven_manager())
                 .await
                 .unwrap();
             assert_eq!(ven, ven_1());
@@ -520,10 +573,7 @@ mod tests {
         async fn get_not_existent(db: PgPool) {
             let repo: PgVenStorage = db.into();
             let ven = repo
-                .retrieve(
-                    &"ven-not-existent".parse().unwrap(),
-                    &VenPermissions::AllAllowed,
-                )
+                .retrieve(&"ven-not-existent".parse().unwrap(), &ven_manager())
                 .await;
 
             assert!(matches!(ven, Err(AppError::NotFound)));
@@ -531,8 +581,6 @@ mod tests {
     }
 
     mod add {
-        use crate::data_source::postgres::ven::VenPermissions;
-
         use super::*;
         use chrono::{Duration, Utc};
 
@@ -540,10 +588,7 @@ mod tests {
         async fn add(db: PgPool) {
             let repo: PgVenStorage = db.into();
 
-            let ven = repo
-                .create(ven_1().content, &VenPermissions::AllAllowed)
-                .await
-                .unwrap();
+            let ven = repo.create(ven_1().content, &ven_manager()).await.unwrap();
             assert!(ven.created_date_time < Utc::now() + Duration::minutes(10));
             assert!(ven.created_date_time > Utc::now() - Duration::minutes(10));
             assert!(ven.modification_date_time < Utc::now() + Duration::minutes(10));
@@ -554,16 +599,12 @@ mod tests {
         async fn add_existing_name(db: PgPool) {
             let repo: PgVenStorage = db.into();
 
-            let ven = repo
-                .create(ven_1().content, &VenPermissions::AllAllowed)
-                .await;
+            let ven = repo.create(ven_1().content, &ven_manager()).await;
             assert!(matches!(ven, Err(AppError::Conflict(_, _))));
         }
     }
 
     mod modify {
-        use crate::data_source::postgres::ven::VenPermissions;
-
         use super::*;
         use chrono::{DateTime, Duration, Utc};
 
@@ -571,11 +612,7 @@ mod tests {
         async fn updates_modify_time(db: PgPool) {
             let repo: PgVenStorage = db.into();
             let ven = repo
-                .update(
-                    &"ven-1".parse().unwrap(),
-                    ven_1().content,
-                    &VenPermissions::AllAllowed,
-                )
+                .update(&"ven-1".parse().unwrap(), ven_1().content, &ven_manager())
                 .await
                 .unwrap();
 
@@ -597,17 +634,13 @@ mod tests {
             updated.ven_name = "updated_name".parse().unwrap();
 
             let ven = repo
-                .update(
-                    &"ven-1".parse().unwrap(),
-                    updated.clone(),
-                    &VenPermissions::AllAllowed,
-                )
+                .update(&"ven-1".parse().unwrap(), updated.clone(), &ven_manager())
                 .await
                 .unwrap();
 
             assert_eq!(ven.content, updated);
             let ven = repo
-                .retrieve(&"ven-1".parse().unwrap(), &VenPermissions::AllAllowed)
+                .retrieve(&"ven-1".parse().unwrap(), &ven_manager())
                 .await
                 .unwrap();
             assert_eq!(ven.content, updated);
@@ -615,26 +648,24 @@ mod tests {
     }
 
     mod delete {
-        use crate::data_source::postgres::ven::VenPermissions;
-
         use super::*;
 
         #[sqlx::test(fixtures("users", "vens"))]
         async fn delete_existing(db: PgPool) {
             let repo: PgVenStorage = db.into();
             let ven = repo
-                .delete(&"ven-1".parse().unwrap(), &VenPermissions::AllAllowed)
+                .delete(&"ven-1".parse().unwrap(), &ven_manager())
                 .await
                 .unwrap();
             assert_eq!(ven, ven_1());
 
             let ven = repo
-                .retrieve(&"ven-1".parse().unwrap(), &VenPermissions::AllAllowed)
+                .retrieve(&"ven-1".parse().unwrap(), &ven_manager())
                 .await;
             assert!(matches!(ven, Err(AppError::NotFound)));
 
             let ven = repo
-                .retrieve(&"ven-2".parse().unwrap(), &VenPermissions::AllAllowed)
+                .retrieve(&"ven-2".parse().unwrap(), &ven_manager())
                 .await
                 .unwrap();
             assert_eq!(ven, ven_2());
@@ -644,10 +675,7 @@ mod tests {
         async fn delete_not_existing(db: PgPool) {
             let repo: PgVenStorage = db.into();
             let ven = repo
-                .delete(
-                    &"ven-not-existing".parse().unwrap(),
-                    &VenPermissions::AllAllowed,
-                )
+                .delete(&"ven-not-existing".parse().unwrap(), &ven_manager())
                 .await;
             assert!(matches!(ven, Err(AppError::NotFound)));
         }
@@ -2,19 +2,22 @@
 use argon2::password_hash;
 use axum::{
     extract::rejection::{FormRejection, JsonRejection},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use axum_extra::extract::QueryRejection;
-use openleadr_wire::{problem::Problem, IdentifierError};
+use metrics::counter;
+use openleadr_wire::{oauth::Scope, problem::Problem, IdentifierError};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "sqlx")]
 use sqlx::error::DatabaseError;
+use std::time::Duration;
 #[cfg(feature = "sqlx")]
 use tracing::warn;
 use tracing::{error, info, trace};
-use uuid::Uuid;
+
+use crate::{metrics::AUTH_FAILURES_TOTAL, telemetry::current_request_id};
 
 #[derive(thiserror::Error, Debug)]
 pub enum AppError {
@@ -32,6 +35,9 @@ pub enum AppError {
     BadRequest(&'static str),
     #[error("Forbidden: {0}")]
     Forbidden(&'static str),
+    /// The token is valid but does not grant the scope the route requires, see RFC 6750, section 3.1
+    #[error("Insufficient scope: {0} required")]
+    InsufficientScope(Scope),
     #[error("Not implemented {0}")]
     NotImplemented(&'static str),
     #[cfg(feature = "sqlx")]
@@ -47,7 +53,6 @@ pub enum AppError {
     Sql(sqlx::Error),
     #[error("Storage connection pool closed")]
     StorageConnectionError,
-    #[cfg(feature = "sqlx")]
     #[error("Json (de)serialization error : {0}")]
     SerdeJsonInternalServerError(serde_json::Error),
     #[cfg(feature = "sqlx")]
@@ -62,6 +67,13 @@ pub enum AppError {
     PasswordHashError(password_hash::Error),
     #[error("Unsupported Media Type: {0}")]
     UnsupportedMediaType(String),
+    /// At least one item of a batch request failed, therefore, none of the items got stored.
+    /// Contains the error for each failed item and `None` for each item that was rolled back.
+    #[error("Batch rejected: {} of {} items failed", .0.iter().flatten().count(), .0.len())]
+    Batch(Vec<Option<AppError>>),
+    /// A rate limit or lockout got exceeded, the request may be retried after the given duration
+    #[error("Too many requests, retry after {0:?}")]
+    TooManyRequests(Duration),
 }
 
 #[cfg(feature = "sqlx")]
@@ -113,7 +125,7 @@ impl From<password_hash::Error> for AppError {
 
 impl AppError {
     fn into_problem(self) -> Problem {
-        let reference = Uuid::new_v4();
+        let reference = current_request_id();
 
         match self {
             AppError::Validation(err) => {
@@ -196,6 +208,7 @@ impl AppError {
                     "Forbidden: {}",
                     err
                 );
+                counter!(AUTH_FAILURES_TOTAL, "kind" => "forbidden", "reason" => err).increment(1);
                 Problem {
                     r#type: Default::default(),
                     title: Some(StatusCode::FORBIDDEN.to_string()),
@@ -204,6 +217,20 @@ impl AppError {
                     instance: Some(reference.to_string()),
                 }
             }
+            AppError::InsufficientScope(scope) => {
+                trace!(%reference, %scope, "Insufficient scope");
+                counter!(AUTH_FAILURES_TOTAL, "kind" => "forbidden", "reason" => "insufficient_scope")
+                    .increment(1);
+                Problem {
+                    r#type: Default::default(),
+                    title: Some(StatusCode::FORBIDDEN.to_string()),
+                    status: StatusCode::FORBIDDEN,
+                    detail: Some(format!(
+                        "The access token does not grant the {scope} scope required for this request"
+                    )),
+                    instance: Some(reference.to_string()),
+                }
+            }
             AppError::NotImplemented(err) => {
                 error!(%reference, "Not implemented: {}", err);
                 Problem {
@@ -230,6 +257,8 @@ impl AppError {
                     "Authentication error: {}",
                     err
                 );
+                counter!(AUTH_FAILURES_TOTAL, "kind" => "unauthorized", "reason" => err.clone())
+                    .increment(1);
                 Problem {
                     r#type: Default::default(),
                     title: Some(StatusCode::UNAUTHORIZED.to_string()),
@@ -259,7 +288,6 @@ impl AppError {
                     instance: Some(reference.to_string()),
                 }
             }
-            #[cfg(feature = "sqlx")]
             AppError::SerdeJsonInternalServerError(err) => {
                 trace!(%reference, "serde json error: {}", err);
                 Problem {
@@ -344,12 +372,104 @@ impl AppError {
                     instance: Some(reference.to_string()),
                 }
             }
+            AppError::TooManyRequests(retry_after) => {
+                trace!(%reference, ?retry_after, "Too many requests");
+                Problem {
+                    r#type: Default::default(),
+                    title: Some(StatusCode::TOO_MANY_REQUESTS.to_string()),
+                    status: StatusCode::TOO_MANY_REQUESTS,
+                    detail: Some(format!(
+                        "Too many requests, retry after {} seconds",
+                        retry_after_seconds(retry_after)
+                    )),
+                    instance: Some(reference.to_string()),
+                }
+            }
+            AppError::Batch(errors) => {
+                let total = errors.len();
+                let problems = errors
+                    .into_iter()
+                    .flatten()
+                    .map(AppError::into_problem)
+                    .collect::<Vec<_>>();
+                trace!(%reference, "Batch rejected: {} of {} items failed", problems.len(), total);
+                let status = batch_status(&problems);
+                Problem {
+                    r#type: Default::default(),
+                    title: Some(status.to_string()),
+                    status,
+                    detail: Some(format!(
+                        "{} of {} items in the batch failed, none of the items were stored",
+                        problems.len(),
+                        total
+                    )),
+                    instance: Some(reference.to_string()),
+                }
+            }
         }
     }
 }
 
+/// The status of a rejected batch is the status of its first failed item.
+fn batch_status(problems: &[Problem]) -> StatusCode {
+    problems
+        .iter()
+        .map(|problem| problem.status)
+        .find(|status| *status != StatusCode::FAILED_DEPENDENCY)
+        .unwrap_or(StatusCode::BAD_REQUEST)
+}
+
+/// Problem reported for an item of a rejected batch that was valid on its own,
+/// but got rolled back because other items failed.
+fn rolled_back_problem() -> Problem {
+    Problem {
+        r#type: Default::default(),
+        title: Some(StatusCode::FAILED_DEPENDENCY.to_string()),
+        status: StatusCode::FAILED_DEPENDENCY,
+        detail: Some("Not stored, as other items in the same batch failed".to_string()),
+        instance: None,
+    }
+}
+
+/// Whole seconds for the `Retry-After` header, rounded up such that a retry is not rejected again
+fn retry_after_seconds(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        if let AppError::Batch(errors) = self {
+            let problems = errors
+                .into_iter()
+                .map(|err| err.map_or_else(rolled_back_problem, AppError::into_problem))
+                .collect::<Vec<_>>();
+            return (batch_status(&problems), Json(problems)).into_response();
+        }
+
+        if let AppError::InsufficientScope(scope) = self {
+            let challenge = format!(r#"Bearer error="insufficient_scope", scope="{scope}""#);
+            let problem = self.into_problem();
+            return (
+                problem.status,
+                [(header::WWW_AUTHENTICATE, challenge)],
+                Json(problem),
+            )
+                .into_response();
+        }
+
+        if let AppError::TooManyRequests(retry_after) = self {
+            let problem = self.into_problem();
+            return (
+                problem.status,
+                [(
+                    header::RETRY_AFTER,
+                    retry_after_seconds(retry_after).to_string(),
+                )],
+                Json(problem),
+            )
+                .into_response();
+        }
+
         let problem = self.into_problem();
         (problem.status, Json(problem)).into_response()
     }
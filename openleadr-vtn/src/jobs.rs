@@ -0,0 +1,90 @@
+//! Background jobs running alongside the HTTP server
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use metrics::counter;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::{
+    config::{EventTemplatesConfig, RetentionConfig},
+    data_source::{EventTemplateSource, RetentionSource},
+    metrics::EXPIRED_OBJECTS_TOTAL,
+    state::AppState,
+};
+
+/// Spawns the background jobs, which run until the runtime shuts down
+pub fn spawn(state: &AppState) -> Vec<JoinHandle<()>> {
+    let mut jobs = vec![tokio::spawn(materialize_event_templates(
+        state.storage.event_templates(),
+        state.event_templates.clone(),
+    ))];
+
+    if state.retention.is_enabled() {
+        jobs.push(tokio::spawn(expire(
+            state.storage.retention(),
+            state.retention.clone(),
+        )));
+    }
+
+    jobs
+}
+
+/// Creates the events of the upcoming occurrences of all event templates
+async fn materialize_event_templates(
+    templates: Arc<dyn EventTemplateSource>,
+    config: EventTemplatesConfig,
+) {
+    let mut interval = tokio::time::interval(config.interval());
+
+    loop {
+        interval.tick().await;
+
+        match templates.materialize(Utc::now() + config.lookahead()).await {
+            Ok(0) => {}
+            Ok(count) => info!(count, "created events from event templates"),
+            Err(err) => error!(%err, "cannot create events from event templates"),
+        }
+    }
+}
+
+/// Removes the events and reports past their retention period
+async fn expire(retention: Arc<dyn RetentionSource>, config: RetentionConfig) {
+    let mut interval = tokio::time::interval(config.interval());
+
+    loop {
+        interval.tick().await;
+
+        let now = Utc::now();
+        let before = |retention: Option<Duration>| {
+            retention
+                .and_then(|retention| chrono::Duration::from_std(retention).ok())
+                .and_then(|retention| now.checked_sub_signed(retention))
+        };
+
+        match retention
+            .expire(
+                before(config.event_retention()),
+                before(config.report_retention()),
+                config.mode,
+            )
+            .await
+        {
+            Ok(expired) => {
+                counter!(EXPIRED_OBJECTS_TOTAL, "object_type" => "EVENT").increment(expired.events);
+                counter!(EXPIRED_OBJECTS_TOTAL, "object_type" => "REPORT")
+                    .increment(expired.reports);
+                if expired.events > 0 || expired.reports > 0 {
+                    info!(
+                        events = expired.events,
+                        reports = expired.reports,
+                        mode = ?config.mode,
+                        "removed expired events and reports"
+                    );
+                }
+            }
+            Err(err) => error!(%err, "cannot remove expired events and reports"),
+        }
+    }
+}
@@ -0,0 +1,427 @@
+//! Keys of an external OAuth provider, fetched from its JSON Web Key Set (JWKS) endpoint.
+
+use std::time::Duration;
+
+use jsonwebtoken::{
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, PublicKeyUse},
+    Algorithm, DecodingKey,
+};
+use tokio::{
+    sync::{Mutex, RwLock},
+    time::Instant,
+};
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// Minimum time between two fetches, such that tokens with random `kid`s
+/// cannot make the VTN flood the OAuth provider with requests
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub(crate) struct JwksKey {
+    kid: Option<String>,
+    pub(crate) key: DecodingKey,
+    /// The algorithms a token signed by this key may use
+    pub(crate) algorithms: Vec<Algorithm>,
+}
+
+#[derive(Default)]
+struct State {
+    keys: Vec<JwksKey>,
+    fetched_at: Option<Instant>,
+    attempted_at: Option<Instant>,
+}
+
+impl State {
+    /// A token without `kid` is only accepted if the key set contains a single key
+    fn find(&self, kid: Option<&str>) -> Option<&JwksKey> {
+        match kid {
+            Some(kid) => self.keys.iter().find(|key| key.kid.as_deref() == Some(kid)),
+            None if self.keys.len() == 1 => self.keys.first(),
+            None => None,
+        }
+    }
+}
+
+pub struct Jwks {
+    url: Url,
+    client: reqwest::Client,
+    refresh_interval: Duration,
+    min_refresh_interval: Duration,
+    state: RwLock<State>,
+    refresh_lock: Mutex<()>,
+}
+
+impl Jwks {
+    /// The keys are fetched on first use, such that the VTN can start while the OAuth provider is unavailable
+    pub fn new(url: Url, refresh_interval: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            url,
+            client,
+            refresh_interval,
+            min_refresh_interval: MIN_REFRESH_INTERVAL.min(refresh_interval),
+            state: RwLock::default(),
+            refresh_lock: Mutex::default(),
+        }
+    }
+
+    /// Returns the key with the given `kid`.
+    ///
+    /// Fetches the key set again if it is older than the refresh interval
+    /// or does not contain the key, as the OAuth provider might have rotated its keys.
+    /// If the OAuth provider is unavailable, the previously fetched keys are used.
+    pub(crate) async fn find(&self, kid: Option<&str>) -> Option<JwksKey> {
+        let attempted_at = {
+            let state = self.state.read().await;
+            let key = state.find(kid);
+            let fresh = state
+                .fetched_at
+                .is_some_and(|fetched_at| fetched_at.elapsed() < self.refresh_interval);
+            let recently_attempted = state
+                .attempted_at
+                .is_some_and(|attempted_at| attempted_at.elapsed() < self.min_refresh_interval);
+
+            if (key.is_some() && fresh) || recently_attempted {
+                return key.cloned();
+            }
+            state.attempted_at
+        };
+
+        self.refresh(attempted_at).await;
+        self.state.read().await.find(kid).cloned()
+    }
+
+    async fn refresh(&self, previous_attempt: Option<Instant>) {
+        let _guard = self.refresh_lock.lock().await;
+        // another request refreshed the keys while we were waiting for the lock
+        if self.state.read().await.attempted_at != previous_attempt {
+            return;
+        }
+        self.state.write().await.attempted_at = Some(Instant::now());
+
+        match self.fetch().await {
+            Ok(keys) => {
+                info!(url = %self.url, count = keys.len(), "fetched JWKS");
+                let mut state = self.state.write().await;
+                state.keys = keys;
+                state.fetched_at = Some(Instant::now());
+            }
+            Err(err) => {
+                warn!(url = %self.url, %err, "cannot fetch JWKS, using previously fetched keys")
+            }
+        }
+    }
+
+    async fn fetch(&self) -> Result<Vec<JwksKey>, reqwest::Error> {
+        let jwks: JwkSet = self
+            .client
+            .get(self.url.clone())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(jwks.keys.iter().filter_map(jwks_key).collect())
+    }
+}
+
+fn jwks_key(jwk: &Jwk) -> Option<JwksKey> {
+    let kid = jwk.common.key_id.clone();
+
+    if jwk.common.public_key_use == Some(PublicKeyUse::Encryption) {
+        debug!(?kid, "ignoring JWK used for encryption");
+        return None;
+    }
+
+    let algorithms = match (&jwk.algorithm, jwk.common.key_algorithm) {
+        // a symmetric key must never be published, as anybody could sign tokens with it
+        (AlgorithmParameters::OctetKey(_), _) => None,
+        (_, Some(key_algorithm)) => key_algorithm
+            .to_string()
+            .parse()
+            .ok()
+            .map(|algorithm| vec![algorithm]),
+        (AlgorithmParameters::RSA(_), None) => Some(vec![
+            Algorithm::RS256,
+            Algorithm::RS384,
+            Algorithm::RS512,
+            Algorithm::PS256,
+            Algorithm::PS384,
+            Algorithm::PS512,
+        ]),
+        (AlgorithmParameters::EllipticCurve(params), None) => match params.curve {
+            EllipticCurve::P256 => Some(vec![Algorithm::ES256]),
+            EllipticCurve::P384 => Some(vec![Algorithm::ES384]),
+            _ => None,
+        },
+        (AlgorithmParameters::OctetKeyPair(_), None) => Some(vec![Algorithm::EdDSA]),
+    };
+
+    let key = algorithms.and_then(|algorithms| {
+        DecodingKey::from_jwk(jwk).ok().map(|key| JwksKey {
+            kid: kid.clone(),
+            key,
+            algorithms,
+        })
+    });
+    if key.is_none() {
+        debug!(?kid, "ignoring unsupported JWK");
+    }
+    key
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::{OAuthConfig, OAuthType},
+        jwt::JwtManager,
+    };
+    use axum::{extract::State as AxumState, http::StatusCode, routing::get, Json, Router};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::{json, Value};
+    use std::sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    };
+    use tokio::net::TcpListener;
+
+    const ASSETS: &str = "./tests/assets/jwks";
+
+    impl Jwks {
+        fn with_min_refresh_interval(mut self, min_refresh_interval: Duration) -> Self {
+            self.min_refresh_interval = min_refresh_interval;
+            self
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Provider {
+        keys: Arc<std::sync::Mutex<Vec<Value>>>,
+        fetches: Arc<AtomicUsize>,
+        unavailable: Arc<AtomicBool>,
+    }
+
+    impl Provider {
+        fn publish(&self, key: &str) {
+            let jwk = std::fs::read_to_string(format!("{ASSETS}/{key}.jwk.json")).unwrap();
+            self.keys
+                .lock()
+                .unwrap()
+                .push(serde_json::from_str(&jwk).unwrap());
+        }
+
+        fn fetches(&self) -> usize {
+            self.fetches.load(Ordering::SeqCst)
+        }
+    }
+
+    async fn jwks(AxumState(provider): AxumState<Provider>) -> Result<Json<Value>, StatusCode> {
+        provider.fetches.fetch_add(1, Ordering::SeqCst);
+        if provider.unavailable.load(Ordering::SeqCst) {
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+        Ok(Json(json!({ "keys": *provider.keys.lock().unwrap() })))
+    }
+
+    /// Serves the JWKS like an OAuth provider would
+    async fn start_provider() -> (Provider, Url) {
+        let provider = Provider::default();
+        let router = Router::new()
+            .route("/.well-known/jwks.json", get(jwks))
+            .with_state(provider.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/.well-known/jwks.json",
+            listener.local_addr().unwrap()
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        (provider, url.parse().unwrap())
+    }
+
+    fn token(key: &str, kid: Option<&str>, claims: Value) -> String {
+        let pem = std::fs::read(format!("{ASSETS}/{key}.key")).unwrap();
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = kid.map(str::to_string);
+        encode(&header, &claims, &EncodingKey::from_ec_pem(&pem).unwrap()).unwrap()
+    }
+
+    fn claims() -> Value {
+        let now = chrono::Utc::now().timestamp();
+        json!({
+            "sub": "ven-client",
+            "exp": now + 300,
+            "nbf": now,
+            "iss": "https://idp.example.com",
+            "aud": "openleadr-vtn",
+            "roles": [{"role": "VEN", "id": "ven-1"}],
+        })
+    }
+
+    fn jwt_manager(jwks: Jwks) -> JwtManager {
+        let config = OAuthConfig {
+            oauth_type: OAuthType::External,
+            issuer: Some("https://idp.example.com".to_string()),
+            audience: vec!["openleadr-vtn".to_string()],
+            ..Default::default()
+        };
+        JwtManager::from_jwks(jwks, &config)
+    }
+
+    #[tokio::test]
+    async fn select_key_by_kid() {
+        let (provider, url) = start_provider().await;
+        provider.publish("ec-1");
+        provider.publish("ec-2");
+        let jwt_manager = jwt_manager(Jwks::new(url, Duration::from_secs(300)));
+
+        let decoded = jwt_manager
+            .decode_and_validate(&token("ec-2", Some("key-2"), claims()))
+            .await
+            .unwrap();
+        assert_eq!(decoded.sub, "ven-client");
+        assert_eq!(decoded.ven_ids_string(), vec!["ven-1"]);
+        assert!(jwt_manager
+            .decode_and_validate(&token("ec-1", Some("key-1"), claims()))
+            .await
+            .is_ok());
+        assert_eq!(provider.fetches(), 1);
+
+        // signed by another key than the kid refers to
+        assert!(jwt_manager
+            .decode_and_validate(&token("ec-1", Some("key-2"), claims()))
+            .await
+            .is_err());
+        // ambiguous without kid
+        assert!(jwt_manager
+            .decode_and_validate(&token("ec-1", None, claims()))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn refresh_on_unknown_kid() {
+        let (provider, url) = start_provider().await;
+        provider.publish("ec-1");
+        let jwks = Jwks::new(url, Duration::from_secs(300))
+            .with_min_refresh_interval(Duration::from_millis(50));
+        let jwt_manager = jwt_manager(jwks);
+
+        assert!(jwt_manager
+            .decode_and_validate(&token("ec-1", None, claims()))
+            .await
+            .is_ok());
+        assert_eq!(provider.fetches(), 1);
+
+        // the provider rotated its keys
+        provider.publish("ec-2");
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(jwt_manager
+            .decode_and_validate(&token("ec-2", Some("key-2"), claims()))
+            .await
+            .is_ok());
+        assert_eq!(provider.fetches(), 2);
+
+        // unknown keys do not cause another fetch within the minimum interval
+        assert!(jwt_manager
+            .decode_and_validate(&token("ec-2", Some("key-3"), claims()))
+            .await
+            .is_err());
+        assert_eq!(provider.fetches(), 2);
+    }
+
+    #[tokio::test]
+    async fn periodic_refresh() {
+        let (provider, url) = start_provider().await;
+        provider.publish("ec-1");
+        let jwks = Jwks::new(url, Duration::from_millis(100));
+
+        assert!(jwks.find(Some("key-1")).await.is_some());
+        assert!(jwks.find(Some("key-1")).await.is_some());
+        assert_eq!(provider.fetches(), 1);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(jwks.find(Some("key-1")).await.is_some());
+        assert_eq!(provider.fetches(), 2);
+    }
+
+    #[tokio::test]
+    async fn provider_unavailable() {
+        let (provider, url) = start_provider().await;
+        provider.publish("ec-1");
+        let jwks = Jwks::new(url, Duration::from_millis(100));
+        assert!(jwks.find(Some("key-1")).await.is_some());
+
+        // keep using the previously fetched keys
+        provider.unavailable.store(true, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(jwks.find(Some("key-1")).await.is_some());
+        assert_eq!(provider.fetches(), 2);
+    }
+
+    #[tokio::test]
+    async fn ignore_symmetric_keys() {
+        let (provider, url) = start_provider().await;
+        provider.keys.lock().unwrap().push(json!({
+            "kty": "oct",
+            "kid": "secret",
+            "k": "c2VjcmV0LXNlY3JldC1zZWNyZXQtc2VjcmV0LXNlY3JldA",
+        }));
+        let jwks = Jwks::new(url, Duration::from_secs(300));
+        assert!(jwks.find(Some("secret")).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn validate_claims() {
+        let (provider, url) = start_provider().await;
+        provider.publish("ec-1");
+        let jwt_manager = jwt_manager(Jwks::new(url, Duration::from_secs(300)));
+        let now = chrono::Utc::now().timestamp();
+
+        let invalid = [
+            ("iss", json!("https://other.example.com")),
+            ("aud", json!("other")),
+            ("exp", json!(now - 120)),
+            ("nbf", json!(now + 120)),
+        ];
+        for (claim, value) in invalid {
+            let mut claims = claims();
+            claims[claim] = value;
+            assert!(
+                jwt_manager
+                    .decode_and_validate(&token("ec-1", Some("key-1"), claims))
+                    .await
+                    .is_err(),
+                "accepted invalid {claim}"
+            );
+        }
+
+        for claim in ["iss", "aud", "exp"] {
+            let mut claims = claims();
+            claims.as_object_mut().unwrap().remove(claim);
+            assert!(
+                jwt_manager
+                    .decode_and_validate(&token("ec-1", Some("key-1"), claims))
+                    .await
+                    .is_err(),
+                "accepted missing {claim}"
+            );
+        }
+
+        // within the leeway
+        let mut claims = claims();
+        claims["exp"] = json!(now - 30);
+        assert!(jwt_manager
+            .decode_and_validate(&token("ec-1", Some("key-1"), claims))
+            .await
+            .is_ok());
+    }
+}
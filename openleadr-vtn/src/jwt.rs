@@ -3,28 +3,50 @@ use std::sync::Arc;
 #[cfg(feature = "internal-oauth")]
 use crate::api::auth::ResponseOAuthError;
 #[cfg(feature = "internal-oauth")]
-use jsonwebtoken::{encode, Header};
+use crate::data_source::TokenSource;
+#[cfg(feature = "internal-oauth")]
+use crate::signing_keys::SigningKeys;
+#[cfg(feature = "internal-oauth")]
+use jsonwebtoken::{encode, jwk::JwkSet, Header};
 #[cfg(feature = "internal-oauth")]
 use openleadr_wire::oauth::{OAuthError, OAuthErrorType};
 
-use crate::error::AppError;
+use crate::{
+    config::{ClaimsMapping, OAuthConfig},
+    error::AppError,
+    jwks::Jwks,
+    rate_limit::RateLimits,
+    tls::ClientCertificate,
+};
 use axum::{
     async_trait,
-    extract::{FromRef, FromRequestParts},
-    http::request::Parts,
+    extract::{FromRef, FromRequestParts, MatchedPath},
+    http::{request::Parts, Method},
 };
 use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
-use jsonwebtoken::{DecodingKey, EncodingKey};
-use openleadr_wire::ven::VenId;
+use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Validation};
+use openleadr_wire::{oauth::Scope, ven::VenId};
 use tracing::trace;
 
 pub struct JwtManager {
     #[cfg(feature = "internal-oauth")]
     encoding_key: Option<EncodingKey>,
-    decoding_key: DecodingKey,
+    decoding_keys: DecodingKeys,
+    validation: Validation,
+    claims_mapping: Option<ClaimsMapping>,
+    #[cfg(feature = "internal-oauth")]
+    revocation_list: Option<Arc<dyn TokenSource>>,
+}
+
+enum DecodingKeys {
+    Static(DecodingKey),
+    Jwks(Box<Jwks>),
+    /// The asymmetric keys of the internal OAuth provider
+    #[cfg(feature = "internal-oauth")]
+    Internal(SigningKeys),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -54,14 +76,94 @@ impl AuthRole {
     pub fn is_ven_manager(&self) -> bool {
         matches!(self, AuthRole::VenManager)
     }
+
+    /// The scopes a token of a client with this role may grant
+    #[cfg(feature = "internal-oauth")]
+    fn scopes(&self) -> &'static [Scope] {
+        match self {
+            AuthRole::Business(_) | AuthRole::AnyBusiness => &[
+                Scope::ReadAll,
+                Scope::WritePrograms,
+                Scope::WriteEvents,
+                // businesses delete reports
+                Scope::WriteReports,
+                Scope::WriteSubscriptions,
+            ],
+            AuthRole::VEN(_) => &[
+                Scope::ReadAll,
+                Scope::WriteReports,
+                Scope::WriteSubscriptions,
+                Scope::WriteVens,
+            ],
+            AuthRole::VenManager => &[Scope::ReadAll, Scope::WriteVens],
+            AuthRole::UserManager => &[],
+        }
+    }
+}
+
+/// All scopes a token of a client with the given roles may grant
+#[cfg(feature = "internal-oauth")]
+pub(crate) fn allowed_scopes(roles: &[AuthRole]) -> Vec<Scope> {
+    Scope::ALL
+        .into_iter()
+        .filter(|scope| roles.iter().any(|role| role.scopes().contains(scope)))
+        .collect()
+}
+
+/// The scope the OpenADR specification requires for the route, if any,
+/// extended to the routes of this implementation that write events
+fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let write_scope = match segments[0] {
+        // opting in or out is a response of the VEN to the event, like a report
+        "events" if segments.get(2) == Some(&"opts") => Scope::WriteReports,
+        "programs" => Scope::WritePrograms,
+        "events" => Scope::WriteEvents,
+        // templates create and delete the events of their occurrences
+        "event-templates" => Scope::WriteEvents,
+        "reports" => Scope::WriteReports,
+        "subscriptions" => Scope::WriteSubscriptions,
+        "vens" => Scope::WriteVens,
+        _ => return None,
+    };
+
+    if method == Method::GET || method == Method::HEAD {
+        Some(Scope::ReadAll)
+    } else {
+        Some(write_scope)
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub(crate) struct Claims {
-    exp: usize,
-    nbf: usize,
+    pub(crate) exp: usize,
+    #[serde(default)]
+    pub(crate) nbf: usize,
+    #[serde(default)]
+    pub(crate) iat: usize,
+    /// Identifies the token on the revocation list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) jti: Option<String>,
     pub(crate) sub: String,
     pub(crate) roles: Vec<AuthRole>,
+    /// Space separated scopes. Tokens without this claim are restricted by their roles only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) scope: Option<String>,
+}
+
+impl Claims {
+    /// The actor of the changes background jobs make, as recorded in the audit log
+    pub(crate) fn system() -> Self {
+        Self {
+            exp: 0,
+            nbf: 0,
+            iat: 0,
+            jti: None,
+            sub: "system".to_string(),
+            roles: vec![],
+            scope: None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -71,8 +173,11 @@ impl Claims {
         Self {
             exp: 0,
             nbf: 0,
+            iat: 0,
+            jti: None,
             sub: "".to_string(),
             roles,
+            scope: None,
         }
     }
 
@@ -88,6 +193,20 @@ pub enum BusinessIds {
 }
 
 impl Claims {
+    /// Claims of a VEN authenticated by a client certificate that is mapped to the VEN
+    fn from_client_certificate(certificate: &ClientCertificate) -> Option<Self> {
+        let ven_id = certificate.ven_id.clone()?;
+        Some(Self {
+            exp: 0,
+            nbf: 0,
+            iat: 0,
+            jti: None,
+            sub: certificate.fingerprint.clone(),
+            roles: vec![AuthRole::VEN(ven_id)],
+            scope: None,
+        })
+    }
+
     pub fn ven_ids(&self) -> Vec<VenId> {
         self.roles
             .iter()
@@ -143,6 +262,14 @@ impl Claims {
     pub fn is_ven_manager(&self) -> bool {
         self.roles.iter().any(AuthRole::is_ven_manager)
     }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scope.as_ref().map_or(true, |granted| {
+            granted
+                .split_whitespace()
+                .any(|granted| granted == scope.as_str())
+        })
+    }
 }
 
 impl JwtManager {
@@ -152,19 +279,79 @@ impl JwtManager {
             panic!("You should not provide a JWT encoding key as the 'internal-oauth' feature is disabled. \
             Please recompile with the 'internal-oauth' feature enabled if you want to use it.");
         }
-        #[cfg(feature = "internal-oauth")]
-        {
-            Self {
-                encoding_key,
-                decoding_key,
-            }
-        }
-        #[cfg(not(feature = "internal-oauth"))]
-        {
-            Self { decoding_key }
+        Self::with_keys(
+            encoding_key,
+            DecodingKeys::Static(decoding_key),
+            Validation::default(),
+            None,
+        )
+    }
+
+    /// Create a new JWT manager for the internal OAuth provider signing its tokens
+    /// with the active asymmetric key and accepting tokens of all keys
+    #[cfg(feature = "internal-oauth")]
+    pub(crate) fn internal(signing_keys: SigningKeys) -> Self {
+        Self::with_keys(
+            None,
+            DecodingKeys::Internal(signing_keys),
+            Validation::default(),
+            None,
+        )
+    }
+
+    /// Create a new JWT manager validating the tokens of an external OAuth provider
+    /// with a static key that supports the given algorithms
+    pub fn external(
+        decoding_key: DecodingKey,
+        algorithms: Vec<Algorithm>,
+        config: &OAuthConfig,
+    ) -> Self {
+        let mut validation = external_validation(config);
+        validation.algorithms = algorithms;
+        Self::with_keys(
+            None,
+            DecodingKeys::Static(decoding_key),
+            validation,
+            config.claims_mapping.clone(),
+        )
+    }
+
+    /// Create a new JWT manager validating the tokens of an external OAuth provider
+    /// with the keys published in its JSON Web Key Set
+    pub fn from_jwks(jwks: Jwks, config: &OAuthConfig) -> Self {
+        Self::with_keys(
+            None,
+            DecodingKeys::Jwks(Box::new(jwks)),
+            external_validation(config),
+            config.claims_mapping.clone(),
+        )
+    }
+
+    #[allow(unused_variables)]
+    fn with_keys(
+        encoding_key: Option<EncodingKey>,
+        decoding_keys: DecodingKeys,
+        validation: Validation,
+        claims_mapping: Option<ClaimsMapping>,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "internal-oauth")]
+            encoding_key,
+            decoding_keys,
+            validation,
+            claims_mapping,
+            #[cfg(feature = "internal-oauth")]
+            revocation_list: None,
         }
     }
 
+    /// Reject tokens on the revocation list of the internal OAuth provider
+    #[cfg(feature = "internal-oauth")]
+    pub fn with_revocation_list(mut self, revocation_list: Arc<dyn TokenSource>) -> Self {
+        self.revocation_list = Some(revocation_list);
+        self
+    }
+
     /// Create a new JWT token with the given claims and expiration time
     #[cfg(feature = "internal-oauth")]
     pub(crate) fn create(
@@ -172,6 +359,7 @@ impl JwtManager {
         expires_in: std::time::Duration,
         client_id: String,
         roles: Vec<AuthRole>,
+        scopes: &[Scope],
     ) -> Result<String, ResponseOAuthError> {
         let now = chrono::Utc::now();
         let exp = now + expires_in;
@@ -179,11 +367,19 @@ impl JwtManager {
         let claims = Claims {
             exp: exp.timestamp() as usize,
             nbf: now.timestamp() as usize,
+            iat: now.timestamp() as usize,
+            jti: Some(uuid::Uuid::new_v4().to_string()),
             sub: client_id,
             roles,
+            scope: Some(Scope::join(scopes)),
         };
 
-        if let Some(encoding_key) = &self.encoding_key {
+        if let DecodingKeys::Internal(signing_keys) = &self.decoding_keys {
+            let key = signing_keys.active();
+            let mut header = Header::new(key.algorithm);
+            header.kid = Some(key.kid.clone());
+            Ok(encode(&header, &claims, &key.encoding_key)?)
+        } else if let Some(encoding_key) = &self.encoding_key {
             let token = encode(&Header::default(), &claims, encoding_key)?;
             Ok(token)
         } else {
@@ -195,14 +391,120 @@ impl JwtManager {
         }
     }
 
+    /// The public keys of the internal OAuth provider, empty if it uses a symmetric key
+    #[cfg(feature = "internal-oauth")]
+    pub(crate) fn jwk_set(&self) -> JwkSet {
+        match &self.decoding_keys {
+            DecodingKeys::Internal(signing_keys) => signing_keys.jwk_set(),
+            _ => JwkSet { keys: vec![] },
+        }
+    }
+
     /// Decode and validate a given JWT token, returning the validated claims
-    fn decode_and_validate(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-        let validation = jsonwebtoken::Validation::default();
-        let token_data = jsonwebtoken::decode::<Claims>(token, &self.decoding_key, &validation)?;
+    pub(crate) async fn decode_and_validate(&self, token: &str) -> Result<Claims, AppError> {
+        let claims = self.decode_claims(token).await.map_err(|err| {
+            trace!(%err, "Invalid authentication token");
+            AppError::Forbidden("Invalid authentication token provided")
+        })?;
+
+        #[cfg(feature = "internal-oauth")]
+        if let Some(revocation_list) = &self.revocation_list {
+            let issued_at =
+                chrono::DateTime::from_timestamp(claims.iat as i64, 0).unwrap_or_default();
+            if revocation_list
+                .is_revoked(claims.jti.as_deref(), &claims.sub, issued_at)
+                .await?
+            {
+                trace!(jti = ?claims.jti, client_id = claims.sub, "Revoked authentication token");
+                return Err(AppError::Forbidden("Invalid authentication token provided"));
+            }
+        }
+
+        Ok(claims)
+    }
+
+    async fn decode_claims(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        let Some(claims_mapping) = &self.claims_mapping else {
+            return self.decode(token).await;
+        };
+
+        let claims: serde_json::Value = self.decode(token).await?;
+        let (sub, roles) = claims_mapping
+            .map(&claims)
+            .ok_or_else(|| ErrorKind::MissingRequiredClaim(claims_mapping.subject.clone()))?;
+        let timestamp = |claim| claims.get(claim).and_then(serde_json::Value::as_u64);
+
+        Ok(Claims {
+            exp: timestamp("exp").unwrap_or_default() as usize,
+            nbf: timestamp("nbf").unwrap_or_default() as usize,
+            iat: timestamp("iat").unwrap_or_default() as usize,
+            jti: claims
+                .get("jti")
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string),
+            sub,
+            roles,
+            scope: claims
+                .get("scope")
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string),
+        })
+    }
+
+    async fn decode<T: serde::de::DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<T, jsonwebtoken::errors::Error> {
+        let token_data = match &self.decoding_keys {
+            DecodingKeys::Static(decoding_key) => {
+                jsonwebtoken::decode::<T>(token, decoding_key, &self.validation)?
+            }
+            DecodingKeys::Jwks(jwks) => {
+                let header = jsonwebtoken::decode_header(token)?;
+                let key = jwks
+                    .find(header.kid.as_deref())
+                    .await
+                    .ok_or(ErrorKind::InvalidKeyFormat)?;
+                let mut validation = self.validation.clone();
+                validation.algorithms = key.algorithms;
+                jsonwebtoken::decode::<T>(token, &key.key, &validation)?
+            }
+            #[cfg(feature = "internal-oauth")]
+            DecodingKeys::Internal(signing_keys) => {
+                let header = jsonwebtoken::decode_header(token)?;
+                let key = signing_keys
+                    .find(header.kid.as_deref())
+                    .ok_or(ErrorKind::InvalidKeyFormat)?;
+                let mut validation = self.validation.clone();
+                validation.algorithms = vec![key.algorithm];
+                jsonwebtoken::decode::<T>(token, &key.decoding_key, &validation)?
+            }
+        };
         Ok(token_data.claims)
     }
 }
 
+/// Validation of the `exp`, `nbf`, `iss`, and `aud` claims of tokens of an external OAuth provider.
+/// The `iss` and `aud` claims are required if an issuer or audience is configured.
+fn external_validation(config: &OAuthConfig) -> Validation {
+    let mut validation = Validation::default();
+    validation.leeway = config.leeway_seconds;
+    validation.validate_nbf = config.validate_nbf;
+
+    let mut required_claims = vec!["exp"];
+    if let Some(issuer) = &config.issuer {
+        validation.set_issuer(&[issuer]);
+        required_claims.push("iss");
+    }
+    if !config.audience.is_empty() {
+        validation.set_audience(&config.audience);
+        required_claims.push("aud");
+    }
+    validation.set_required_spec_claims(&required_claims);
+
+    validation
+}
+
 /// User claims extracted from the request
 pub struct User(pub(crate) Claims);
 
@@ -230,6 +532,17 @@ where
         let Ok(bearer) =
             TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await
         else {
+            // a bearer token takes precedence over the client certificate
+            if let Some(claims) = parts
+                .extensions
+                .get::<ClientCertificate>()
+                .and_then(Claims::from_client_certificate)
+            {
+                trace!(user = ?claims, "Extracted User from client certificate");
+                limit_client(parts, &claims)?;
+                return Ok(User(claims));
+            }
+
             return Err(AppError::Auth(
                 "Authorization via Bearer token in Authorization header required".to_string(),
             ));
@@ -237,16 +550,33 @@ where
 
         let jwt_manager = Arc::<JwtManager>::from_ref(state);
 
-        let Ok(claims) = jwt_manager.decode_and_validate(bearer.0.token()) else {
-            return Err(AppError::Forbidden("Invalid authentication token provided"));
-        };
+        let claims = jwt_manager.decode_and_validate(bearer.0.token()).await?;
 
         trace!(user = ?claims, "Extracted User from request");
+        limit_client(parts, &claims)?;
+
+        if let Some(scope) = parts
+            .extensions
+            .get::<MatchedPath>()
+            .and_then(|path| required_scope(&parts.method, path.as_str()))
+        {
+            if !claims.has_scope(scope) {
+                return Err(AppError::InsufficientScope(scope));
+            }
+        }
 
         Ok(User(claims))
     }
 }
 
+/// Applies the rate limit per client, if the rate limiting middleware is active
+fn limit_client(parts: &Parts, claims: &Claims) -> Result<(), AppError> {
+    match parts.extensions.get::<Arc<RateLimits>>() {
+        Some(rate_limits) => rate_limits.check_client(&claims.sub),
+        None => Ok(()),
+    }
+}
+
 #[async_trait]
 impl<S: Send + Sync> FromRequestParts<S> for BusinessUser
 where
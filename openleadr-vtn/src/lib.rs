@@ -1,5 +1,16 @@
 mod api;
+mod baseline;
+mod claims_mapping;
+pub mod config;
 pub mod data_source;
 mod error;
+pub mod jobs;
+pub mod jwks;
 pub mod jwt;
+mod metrics;
+mod rate_limit;
+#[cfg(feature = "internal-oauth")]
+mod signing_keys;
 pub mod state;
+mod telemetry;
+pub mod tls;
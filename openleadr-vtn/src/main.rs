@@ -1,36 +1,222 @@
+use std::{net::SocketAddr, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
 use tokio::{net::TcpListener, signal};
 use tracing::{error, info};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[cfg(feature = "postgres")]
 use openleadr_vtn::data_source::PostgresStorage;
-use openleadr_vtn::state::AppState;
+use openleadr_vtn::{
+    config::{Config, ConfigError},
+    jobs,
+    state::AppState,
+    tls,
+};
+
+/// OpenADR 3.0 VTN server
+///
+/// Settings are read from the config file, the environment variables, and the flags,
+/// where later sources take precedence.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    /// Path to a TOML config file
+    #[arg(short, long, env = "VTN_CONFIG")]
+    config: Option<PathBuf>,
+    /// Address to listen on, e.g., `0.0.0.0:3000`
+    #[arg(long)]
+    bind_address: Option<SocketAddr>,
+    /// Postgres connection URL
+    #[arg(long)]
+    database_url: Option<String>,
+    /// Log filter in the format of the `RUST_LOG` environment variable
+    #[arg(long)]
+    log_filter: Option<String>,
+}
+
+impl Cli {
+    fn config(self) -> Result<Config, ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => Config::from_toml_file(path)?,
+            None => Config::default(),
+        };
+        config.apply_env()?;
+
+        if let Some(bind_address) = self.bind_address {
+            config.server.bind_address = bind_address;
+        }
+        if let Some(database_url) = self.database_url {
+            config.database.url = Some(database_url);
+        }
+        if let Some(log_filter) = self.log_filter {
+            config.log.filter = log_filter;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    #[cfg(feature = "postgres")]
+    dotenvy::dotenv().ok();
+
+    let config = match Cli::parse().config() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    #[cfg(feature = "otlp")]
+    let tracer_provider = otlp::tracer_provider();
+    #[cfg(feature = "otlp")]
+    let otlp_layer = tracer_provider
+        .as_ref()
+        .ok()
+        .and_then(Option::as_ref)
+        .map(otlp::layer);
+    #[cfg(not(feature = "otlp"))]
+    let otlp_layer = None::<tracing_subscriber::layer::Identity>;
+
+    // validated before
+    let env_filter = config.log.env_filter().unwrap_or_default();
     tracing_subscriber::registry()
         .with(fmt::layer().with_file(true).with_line_number(true))
-        .with(EnvFilter::from_default_env())
+        .with(env_filter)
+        .with(otlp_layer)
         .init();
 
-    let addr = "0.0.0.0:3000";
-    let listener = TcpListener::bind(addr).await.unwrap();
-    info!("listening on http://{}", listener.local_addr().unwrap());
+    // errors can only be logged after the subscriber got initialized
+    #[cfg(feature = "otlp")]
+    let tracer_provider = tracer_provider
+        .inspect(|provider| {
+            if provider.is_some() {
+                info!("exporting traces via OTLP");
+            }
+        })
+        .unwrap_or_else(|err| {
+            error!(%err, "cannot set up OTLP trace export, continuing without");
+            None
+        });
 
     #[cfg(feature = "postgres")]
-    let storage = PostgresStorage::from_env().await.unwrap();
+    let storage = match PostgresStorage::from_config(&config.database).await {
+        Ok(storage) => storage,
+        Err(err) => {
+            error!(%err, "cannot connect to the database");
+            return ExitCode::FAILURE;
+        }
+    };
 
     #[cfg(not(feature = "postgres"))]
     compile_error!(
         "No storage backend selected. Please enable the `postgres` feature flag during compilation"
     );
 
-    let state = AppState::new(storage);
-    if let Err(e) = axum::serve(listener, state.into_router())
-        .with_graceful_shutdown(shutdown_signal())
-        .await
+    let state = match AppState::from_config(storage, &config) {
+        Ok(state) => state,
+        Err(err) => {
+            error!(%err, "invalid configuration");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    jobs::spawn(&state);
+
+    let listener = match TcpListener::bind(config.server.bind_address).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(%err, addr = %config.server.bind_address, "cannot listen on address");
+            return ExitCode::FAILURE;
+        }
+    };
+    let scheme = if config.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    if let Ok(addr) = listener.local_addr() {
+        info!("listening on {}://{}", scheme, addr);
+    }
+
+    let mut exit_code = ExitCode::SUCCESS;
+    match &config.tls {
+        Some(tls_config) => {
+            if let Err(err) =
+                tls::serve(listener, state.into_router(), tls_config, shutdown_signal()).await
+            {
+                error!(%err, "cannot serve HTTPS");
+                exit_code = ExitCode::FAILURE;
+            }
+        }
+        None => {
+            // the client address is used by the rate limit per IP
+            let service = state
+                .into_router()
+                .into_make_service_with_connect_info::<SocketAddr>();
+            if let Err(e) = axum::serve(listener, service)
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+            {
+                error!("webserver crashed: {}", e);
+                exit_code = ExitCode::FAILURE;
+            }
+        }
+    }
+
+    #[cfg(feature = "otlp")]
+    if let Some(tracer_provider) = tracer_provider {
+        // flush the spans that were not exported yet
+        if let Err(err) = tracer_provider.shutdown() {
+            error!(%err, "failed to shut down OTLP trace export");
+        }
+    }
+
+    exit_code
+}
+
+/// Exports traces via OTLP/HTTP if the `OTEL_EXPORTER_OTLP_ENDPOINT` environment variable is set.
+///
+/// The exporter can be configured further via the standard `OTEL_EXPORTER_OTLP_*` environment variables.
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry::{
+        trace::{TraceError, TracerProvider as _},
+        KeyValue,
+    };
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+    use tracing::Subscriber;
+    use tracing_subscriber::{registry::LookupSpan, Layer};
+
+    pub(super) fn tracer_provider() -> Result<Option<TracerProvider>, TraceError> {
+        if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_none() {
+            return Ok(None);
+        }
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()?;
+
+        Ok(Some(
+            TracerProvider::builder()
+                .with_batch_exporter(exporter, runtime::Tokio)
+                .with_resource(Resource::new([KeyValue::new(
+                    "service.name",
+                    "openleadr-vtn",
+                )]))
+                .build(),
+        ))
+    }
+
+    pub(super) fn layer<S>(tracer_provider: &TracerProvider) -> impl Layer<S>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
     {
-        error!("webserver crashed: {}", e);
+        tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("openleadr-vtn"))
     }
 }
 
@@ -0,0 +1,73 @@
+use std::{sync::OnceLock, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{counter, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tracing::warn;
+
+pub(crate) const HTTP_REQUESTS_TOTAL: &str = "openleadr_http_requests_total";
+pub(crate) const HTTP_REQUEST_DURATION_SECONDS: &str = "openleadr_http_request_duration_seconds";
+pub(crate) const AUTH_FAILURES_TOTAL: &str = "openleadr_auth_failures_total";
+pub(crate) const RATE_LIMITED_TOTAL: &str = "openleadr_rate_limited_total";
+pub(crate) const REPORTS_TOTAL: &str = "openleadr_reports_total";
+pub(crate) const EXPIRED_OBJECTS_TOTAL: &str = "openleadr_expired_objects_total";
+pub(crate) const OBJECTS: &str = "openleadr_objects";
+pub(crate) const DB_POOL_CONNECTIONS: &str = "openleadr_db_pool_connections";
+pub(crate) const DB_POOL_MAX_CONNECTIONS: &str = "openleadr_db_pool_max_connections";
+
+const DURATION_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Returns the handle to the Prometheus recorder, installing it as the global recorder on first use.
+///
+/// If another global recorder got installed before, e.g., by an application embedding the VTN,
+/// the metrics of the VTN are not recorded and the handle renders an empty page.
+pub(crate) fn prometheus_handle() -> &'static PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+    HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+                DURATION_BUCKETS,
+            )
+            .expect("bucket list is not empty")
+            .build_recorder();
+        let handle = recorder.handle();
+
+        if let Err(err) = metrics::set_global_recorder(recorder) {
+            warn!(%err, "Cannot install Prometheus recorder, metrics will not be recorded");
+        }
+
+        handle
+    })
+}
+
+/// Records the number and duration of requests per route, method, and response status
+pub(crate) async fn track_requests(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    // use the route instead of the actual path to prevent a label per object ID
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+
+    let response = next.run(req).await;
+
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    histogram!(HTTP_REQUEST_DURATION_SECONDS, &labels).record(start.elapsed().as_secs_f64());
+
+    response
+}
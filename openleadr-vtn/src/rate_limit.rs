@@ -0,0 +1,431 @@
+//! Rate limiting and lockout of clients.
+//!
+//! Requests are limited by token buckets per client IP address, per authenticated client,
+//! per client ID on the OAuth endpoints, and per client IP address on the self-registration of VENs.
+//! Additionally, a client ID is locked out after repeated failed credential checks,
+//! such that secrets cannot be guessed by brute force.
+
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::counter;
+use tokio::time::Instant;
+#[cfg(feature = "internal-oauth")]
+use tracing::info;
+use tracing::trace;
+
+use crate::{
+    config::{RateLimit, RateLimitConfig},
+    error::AppError,
+    metrics::RATE_LIMITED_TOTAL,
+};
+
+/// Idle entries are removed in this interval, such that the memory usage stays bounded
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct RateLimits {
+    per_ip: Option<Limiter<IpAddr>>,
+    per_client: Option<Limiter<String>>,
+    #[cfg(feature = "internal-oauth")]
+    auth: Option<Limiter<String>>,
+    #[cfg(feature = "internal-oauth")]
+    registration: Option<Limiter<IpAddr>>,
+    #[cfg(feature = "internal-oauth")]
+    lockout: Option<Lockout>,
+}
+
+impl RateLimits {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            per_ip: config.per_ip.map(|limit| Limiter::new(limit, "ip")),
+            per_client: config.per_client.map(|limit| Limiter::new(limit, "client")),
+            #[cfg(feature = "internal-oauth")]
+            auth: config.auth.map(|limit| Limiter::new(limit, "auth")),
+            #[cfg(feature = "internal-oauth")]
+            registration: config.auth.map(|limit| Limiter::new(limit, "registration")),
+            #[cfg(feature = "internal-oauth")]
+            lockout: (config.lockout_threshold > 0)
+                .then(|| Lockout::new(config.lockout_threshold, config.lockout_duration())),
+        }
+    }
+
+    /// Limits the requests of an authenticated client to the API routes
+    pub(crate) fn check_client(&self, client_id: &str) -> Result<(), AppError> {
+        match &self.per_client {
+            Some(limiter) => limiter.check(client_id),
+            None => Ok(()),
+        }
+    }
+
+    /// Limits the requests to the OAuth endpoints per client ID,
+    /// and rejects locked out client IDs before their credentials are checked
+    #[cfg(feature = "internal-oauth")]
+    pub(crate) fn check_auth(&self, client_id: &str) -> Result<(), AppError> {
+        if let Some(lockout) = &self.lockout {
+            lockout.check(client_id)?;
+        }
+        match &self.auth {
+            Some(limiter) => limiter.check(client_id),
+            None => Ok(()),
+        }
+    }
+
+    /// Limits the self-registrations of VENs per client IP address, if the address is known,
+    /// as the registration tokens are checked before the caller is known
+    #[cfg(feature = "internal-oauth")]
+    pub(crate) fn check_registration(&self, ip: Option<IpAddr>) -> Result<(), AppError> {
+        match (&self.registration, ip) {
+            (Some(limiter), Some(ip)) => limiter.check(&ip),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records the result of a credential check, locking out the client ID after too many failures
+    #[cfg(feature = "internal-oauth")]
+    pub(crate) fn credentials_checked(&self, client_id: &str, valid: bool) {
+        if let Some(lockout) = &self.lockout {
+            if valid {
+                lockout.reset(client_id);
+            } else {
+                lockout.failed(client_id);
+            }
+        }
+    }
+}
+
+/// Limits the requests per client IP address, if the address is known.
+///
+/// Makes the rate limits available to the [`User`](crate::jwt::User) extractor
+/// as request extension, which limits the requests per authenticated client.
+pub(crate) async fn limit_requests(
+    State(rate_limits): State<Arc<RateLimits>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    if let (Some(limiter), Some(ip)) = (&rate_limits.per_ip, ip) {
+        if let Err(err) = limiter.check(&ip) {
+            return err.into_response();
+        }
+    }
+
+    req.extensions_mut().insert(rate_limits);
+    next.run(req).await
+}
+
+/// Token buckets per key
+struct Limiter<K> {
+    /// Number of requests per second the buckets are refilled with
+    rate: f64,
+    capacity: f64,
+    label: &'static str,
+    state: Mutex<LimiterState<K>>,
+}
+
+struct LimiterState<K> {
+    buckets: HashMap<K, Bucket>,
+    pruned: Instant,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl<K: Eq + Hash> Limiter<K> {
+    fn new(limit: RateLimit, label: &'static str) -> Self {
+        Self {
+            rate: f64::from(limit.requests_per_minute) / 60.0,
+            capacity: f64::from(limit.burst),
+            label,
+            state: Mutex::new(LimiterState {
+                buckets: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token from the bucket of the key, or returns how long to wait for the next one
+    fn check<Q>(&self, key: &Q) -> Result<(), AppError>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ToOwned<Owned = K> + ?Sized,
+    {
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("rate limiter lock poisoned");
+
+        if now.duration_since(state.pruned) >= PRUNE_INTERVAL {
+            // a bucket that would be full again behaves like a new one
+            let (rate, capacity) = (self.rate, self.capacity);
+            state.buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate < capacity
+            });
+            state.pruned = now;
+        }
+
+        if !state.buckets.contains_key(key) {
+            state.buckets.insert(
+                key.to_owned(),
+                Bucket {
+                    tokens: self.capacity,
+                    updated: now,
+                },
+            );
+        }
+        let bucket = state.buckets.get_mut(key).expect("inserted before");
+        bucket.tokens = (bucket.tokens
+            + now.duration_since(bucket.updated).as_secs_f64() * self.rate)
+            .min(self.capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let retry_after = Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate);
+        trace!(limit = self.label, ?retry_after, "rate limit exceeded");
+        counter!(RATE_LIMITED_TOTAL, "limit" => self.label).increment(1);
+        Err(AppError::TooManyRequests(retry_after))
+    }
+}
+
+/// Consecutive failed credential checks per client ID
+#[cfg(feature = "internal-oauth")]
+struct Lockout {
+    threshold: u32,
+    duration: Duration,
+    state: Mutex<LockoutState>,
+}
+
+#[cfg(feature = "internal-oauth")]
+struct LockoutState {
+    failures: HashMap<String, Failures>,
+    pruned: Instant,
+}
+
+#[cfg(feature = "internal-oauth")]
+struct Failures {
+    count: u32,
+    last: Instant,
+}
+
+#[cfg(feature = "internal-oauth")]
+impl Lockout {
+    fn new(threshold: u32, duration: Duration) -> Self {
+        Self {
+            threshold,
+            duration,
+            state: Mutex::new(LockoutState {
+                failures: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+        }
+    }
+
+    fn check(&self, client_id: &str) -> Result<(), AppError> {
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("lockout lock poisoned");
+
+        if now.duration_since(state.pruned) >= PRUNE_INTERVAL {
+            let duration = self.duration;
+            state
+                .failures
+                .retain(|_, failures| now.duration_since(failures.last) < duration);
+            state.pruned = now;
+        }
+
+        let Some(failures) = state.failures.get(client_id) else {
+            return Ok(());
+        };
+        let locked_until = failures.last + self.duration;
+        if failures.count < self.threshold {
+            return Ok(());
+        }
+        if locked_until <= now {
+            // the lockout expired, the client ID gets a new set of attempts
+            state.failures.remove(client_id);
+            return Ok(());
+        }
+
+        let retry_after = locked_until - now;
+        trace!(client_id, ?retry_after, "client ID is locked out");
+        counter!(RATE_LIMITED_TOTAL, "limit" => "lockout").increment(1);
+        Err(AppError::TooManyRequests(retry_after))
+    }
+
+    fn failed(&self, client_id: &str) {
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("lockout lock poisoned");
+        let failures = state
+            .failures
+            .entry(client_id.to_string())
+            .or_insert(Failures {
+                count: 0,
+                last: now,
+            });
+        // failures long ago do not count
+        if now.duration_since(failures.last) >= self.duration {
+            failures.count = 0;
+        }
+        failures.count += 1;
+        failures.last = now;
+
+        if failures.count == self.threshold {
+            info!(
+                client_id,
+                failures = failures.count,
+                "locking out client ID after failed credential checks"
+            );
+        }
+    }
+
+    fn reset(&self, client_id: &str) {
+        self.state
+            .lock()
+            .expect("lockout lock poisoned")
+            .failures
+            .remove(client_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn limit(requests_per_minute: u32, burst: u32) -> Option<RateLimit> {
+        Some(RateLimit {
+            requests_per_minute,
+            burst,
+        })
+    }
+
+    fn retry_after(result: Result<(), AppError>) -> Duration {
+        match result {
+            Err(AppError::TooManyRequests(retry_after)) => retry_after,
+            other => panic!("expected rate limit error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_bucket() {
+        let rate_limits = RateLimits::new(&RateLimitConfig {
+            per_client: limit(60, 3),
+            ..Default::default()
+        });
+
+        for _ in 0..3 {
+            rate_limits.check_client("client-1").unwrap();
+        }
+        assert_eq!(
+            retry_after(rate_limits.check_client("client-1")),
+            Duration::from_secs(1)
+        );
+        // buckets are per client
+        rate_limits.check_client("client-2").unwrap();
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert_eq!(
+            retry_after(rate_limits.check_client("client-1")),
+            Duration::from_millis(500)
+        );
+        tokio::time::advance(Duration::from_millis(500)).await;
+        rate_limits.check_client("client-1").unwrap();
+        assert!(rate_limits.check_client("client-1").is_err());
+
+        // refilled up to the burst only
+        tokio::time::advance(Duration::from_secs(3600)).await;
+        for _ in 0..3 {
+            rate_limits.check_client("client-1").unwrap();
+        }
+        assert!(rate_limits.check_client("client-1").is_err());
+    }
+
+    #[cfg(feature = "internal-oauth")]
+    #[tokio::test(start_paused = true)]
+    async fn disabled() {
+        let rate_limits = RateLimits::new(&RateLimitConfig {
+            per_client: None,
+            auth: None,
+            lockout_threshold: 0,
+            ..Default::default()
+        });
+
+        for _ in 0..100 {
+            rate_limits.check_client("client-1").unwrap();
+            rate_limits.credentials_checked("client-1", false);
+            rate_limits.check_auth("client-1").unwrap();
+        }
+    }
+
+    #[cfg(feature = "internal-oauth")]
+    #[tokio::test(start_paused = true)]
+    async fn lockout() {
+        let rate_limits = RateLimits::new(&RateLimitConfig {
+            auth: None,
+            lockout_threshold: 3,
+            lockout_seconds: 60,
+            ..Default::default()
+        });
+
+        for _ in 0..2 {
+            rate_limits.check_auth("client-1").unwrap();
+            rate_limits.credentials_checked("client-1", false);
+        }
+        // a successful check resets the failures
+        rate_limits.credentials_checked("client-1", true);
+        for _ in 0..3 {
+            rate_limits.check_auth("client-1").unwrap();
+            rate_limits.credentials_checked("client-1", false);
+        }
+        assert_eq!(
+            retry_after(rate_limits.check_auth("client-1")),
+            Duration::from_secs(60)
+        );
+        rate_limits.check_auth("client-2").unwrap();
+
+        tokio::time::advance(Duration::from_secs(45)).await;
+        assert_eq!(
+            retry_after(rate_limits.check_auth("client-1")),
+            Duration::from_secs(15)
+        );
+
+        tokio::time::advance(Duration::from_secs(15)).await;
+        rate_limits.check_auth("client-1").unwrap();
+        rate_limits.credentials_checked("client-1", false);
+        rate_limits.check_auth("client-1").unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn prune() {
+        let rate_limits = RateLimits::new(&RateLimitConfig {
+            per_client: limit(60, 2),
+            ..Default::default()
+        });
+        let limiter = rate_limits.per_client.as_ref().unwrap();
+
+        limiter.check("client-1").unwrap();
+        limiter.check("client-2").unwrap();
+        limiter.check("client-2").unwrap();
+        tokio::time::advance(PRUNE_INTERVAL).await;
+        limiter.check("client-3").unwrap();
+
+        let state = limiter.state.lock().unwrap();
+        assert_eq!(state.buckets.len(), 1);
+        assert!(state.buckets.contains_key("client-3"));
+    }
+}
@@ -0,0 +1,289 @@
+//! Asymmetric keys the internal OAuth provider signs its tokens with.
+//! The public keys are published as JSON Web Key Set at `/.well-known/jwks.json`,
+//! such that other services can validate the tokens without a shared secret.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+        EllipticCurveKeyType, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType,
+        PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey,
+};
+use ring::{
+    rand::SystemRandom,
+    rsa::PublicKeyComponents,
+    signature::{
+        EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair, ECDSA_P256_SHA256_FIXED_SIGNING,
+        ECDSA_P384_SHA384_FIXED_SIGNING,
+    },
+};
+use rustls::pki_types::PrivateKeyDer;
+use sha2::{Digest, Sha256};
+
+use crate::config::{ConfigError, OAuthKeyType};
+
+#[derive(Clone)]
+pub(crate) struct SigningKey {
+    /// The RFC 7638 thumbprint of the public key, such that it is stable across restarts
+    pub(crate) kid: String,
+    pub(crate) algorithm: Algorithm,
+    pub(crate) encoding_key: EncodingKey,
+    pub(crate) decoding_key: DecodingKey,
+    jwk: Jwk,
+}
+
+impl SigningKey {
+    /// Reads a PEM encoded private key.
+    /// RSA keys may be in PKCS#1 or PKCS#8 format, EC (P-256 or P-384) and Ed25519 keys in PKCS#8.
+    pub(crate) fn from_pem(key_type: OAuthKeyType, pem: &[u8]) -> Result<Self, ConfigError> {
+        let invalid = |reason: &dyn std::fmt::Display| {
+            ConfigError::Invalid(format!("Cannot read {key_type} private key: {reason}"))
+        };
+        let der = rustls_pemfile::private_key(&mut &pem[..])
+            .map_err(|err| invalid(&err))?
+            .ok_or_else(|| invalid(&"no private key found in PEM file"))?;
+
+        let (algorithm, params, thumbprint, encoding_key) = match (key_type, &der) {
+            (OAuthKeyType::Rsa, PrivateKeyDer::Pkcs1(_) | PrivateKeyDer::Pkcs8(_)) => {
+                let key_pair = match &der {
+                    PrivateKeyDer::Pkcs1(der) => RsaKeyPair::from_der(der.secret_pkcs1_der()),
+                    _ => RsaKeyPair::from_pkcs8(der.secret_der()),
+                }
+                .map_err(|err| invalid(&err))?;
+                let components: PublicKeyComponents<Vec<u8>> = key_pair.public().into();
+                let n = URL_SAFE_NO_PAD.encode(components.n);
+                let e = URL_SAFE_NO_PAD.encode(components.e);
+                (
+                    Algorithm::RS256,
+                    AlgorithmParameters::RSA(RSAKeyParameters {
+                        key_type: RSAKeyType::RSA,
+                        n: n.clone(),
+                        e: e.clone(),
+                    }),
+                    format!(r#"{{"e":"{e}","kty":"RSA","n":"{n}"}}"#),
+                    EncodingKey::from_rsa_pem(pem).map_err(|err| invalid(&err))?,
+                )
+            }
+            (OAuthKeyType::Ec, PrivateKeyDer::Pkcs8(der)) => {
+                let rng = SystemRandom::new();
+                let (algorithm, curve, crv, key_pair) = EcdsaKeyPair::from_pkcs8(
+                    &ECDSA_P256_SHA256_FIXED_SIGNING,
+                    der.secret_pkcs8_der(),
+                    &rng,
+                )
+                .map(|key_pair| (Algorithm::ES256, EllipticCurve::P256, "P-256", key_pair))
+                .or_else(|_| {
+                    EcdsaKeyPair::from_pkcs8(
+                        &ECDSA_P384_SHA384_FIXED_SIGNING,
+                        der.secret_pkcs8_der(),
+                        &rng,
+                    )
+                    .map(|key_pair| (Algorithm::ES384, EllipticCurve::P384, "P-384", key_pair))
+                })
+                .map_err(|err| invalid(&err))?;
+                // uncompressed point: 0x04, followed by the x and y coordinates
+                let point = &key_pair.public_key().as_ref()[1..];
+                let (x, y) = point.split_at(point.len() / 2);
+                let x = URL_SAFE_NO_PAD.encode(x);
+                let y = URL_SAFE_NO_PAD.encode(y);
+                (
+                    algorithm,
+                    AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                        key_type: EllipticCurveKeyType::EC,
+                        curve,
+                        x: x.clone(),
+                        y: y.clone(),
+                    }),
+                    format!(r#"{{"crv":"{crv}","kty":"EC","x":"{x}","y":"{y}"}}"#),
+                    EncodingKey::from_ec_der(der.secret_pkcs8_der()),
+                )
+            }
+            (OAuthKeyType::Ed, PrivateKeyDer::Pkcs8(der)) => {
+                let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der.secret_pkcs8_der())
+                    .map_err(|err| invalid(&err))?;
+                let x = URL_SAFE_NO_PAD.encode(key_pair.public_key());
+                (
+                    Algorithm::EdDSA,
+                    AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: x.clone(),
+                    }),
+                    format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{x}"}}"#),
+                    EncodingKey::from_ed_der(der.secret_pkcs8_der()),
+                )
+            }
+            (OAuthKeyType::Hmac, _) => return Err(invalid(&"HMAC keys are not asymmetric")),
+            _ => return Err(invalid(&"unsupported key format, use PKCS#8")),
+        };
+
+        let kid = URL_SAFE_NO_PAD.encode(Sha256::digest(thumbprint.as_bytes()));
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(key_algorithm(algorithm)),
+                key_id: Some(kid.clone()),
+                ..Default::default()
+            },
+            algorithm: params,
+        };
+        let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|err| invalid(&err))?;
+
+        Ok(Self {
+            kid,
+            algorithm,
+            encoding_key,
+            decoding_key,
+            jwk,
+        })
+    }
+}
+
+/// The keys of the internal OAuth provider.
+/// New tokens are signed with the first key, while tokens signed by any of the keys are accepted.
+/// This allows rotating keys without invalidating the tokens issued before.
+pub(crate) struct SigningKeys {
+    keys: Vec<SigningKey>,
+}
+
+impl SigningKeys {
+    pub(crate) fn new(active: SigningKey, additional: Vec<SigningKey>) -> Self {
+        let mut keys = vec![active];
+        for key in additional {
+            if keys.iter().all(|existing| existing.kid != key.kid) {
+                keys.push(key);
+            }
+        }
+        Self { keys }
+    }
+
+    pub(crate) fn active(&self) -> &SigningKey {
+        &self.keys[0]
+    }
+
+    /// Tokens without `kid` are validated with the active key
+    pub(crate) fn find(&self, kid: Option<&str>) -> Option<&SigningKey> {
+        match kid {
+            Some(kid) => self.keys.iter().find(|key| key.kid == kid),
+            None => Some(self.active()),
+        }
+    }
+
+    pub(crate) fn jwk_set(&self) -> JwkSet {
+        JwkSet {
+            keys: self.keys.iter().map(|key| key.jwk.clone()).collect(),
+        }
+    }
+}
+
+fn key_algorithm(algorithm: Algorithm) -> KeyAlgorithm {
+    match algorithm {
+        Algorithm::ES256 => KeyAlgorithm::ES256,
+        Algorithm::ES384 => KeyAlgorithm::ES384,
+        Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+        _ => KeyAlgorithm::RS256,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use jsonwebtoken::{decode, encode, Header, Validation};
+
+    const ASSETS: &str = "./tests/assets/signing";
+
+    fn key(key_type: OAuthKeyType, file: &str) -> SigningKey {
+        let pem = std::fs::read(format!("{ASSETS}/{file}")).unwrap();
+        SigningKey::from_pem(key_type, &pem).unwrap()
+    }
+
+    fn sign_and_verify(key: &SigningKey) {
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        let claims =
+            serde_json::json!({"sub": "client", "exp": chrono::Utc::now().timestamp() + 60});
+        let token = encode(&header, &claims, &key.encoding_key).unwrap();
+
+        // validate with the published key only, as another service would
+        let jwk_set = SigningKeys::new(key.clone(), vec![]).jwk_set();
+        let jwk = jwk_set.find(&key.kid).unwrap();
+        let decoded = decode::<serde_json::Value>(
+            &token,
+            &DecodingKey::from_jwk(jwk).unwrap(),
+            &Validation::new(key.algorithm),
+        )
+        .unwrap();
+        assert_eq!(decoded.claims["sub"], "client");
+    }
+
+    #[test]
+    fn rsa() {
+        let pkcs1 = key(OAuthKeyType::Rsa, "rsa.pem");
+        assert_eq!(pkcs1.algorithm, Algorithm::RS256);
+        sign_and_verify(&pkcs1);
+
+        let pkcs8 = key(OAuthKeyType::Rsa, "rsa-2.pem");
+        sign_and_verify(&pkcs8);
+        assert_ne!(pkcs1.kid, pkcs8.kid);
+    }
+
+    #[test]
+    fn ec() {
+        let p384 = key(OAuthKeyType::Ec, "ec.pem");
+        assert_eq!(p384.algorithm, Algorithm::ES384);
+        sign_and_verify(&p384);
+
+        let p256 = key(OAuthKeyType::Ec, "../jwks/ec-1.key");
+        assert_eq!(p256.algorithm, Algorithm::ES256);
+        sign_and_verify(&p256);
+
+        let AlgorithmParameters::EllipticCurve(params) = &p256.jwk.algorithm else {
+            panic!("expected an EC key");
+        };
+        assert_eq!(params.x, "GEyEU7UV05k7ylsraopU9hqV9jac-_WBn09JwF8g0nc");
+        assert_eq!(params.y, "H3mb09QoXQnTL0ZMHzoXBnZ4N1GE6wN3oKSbuYxE43c");
+    }
+
+    #[test]
+    fn ed25519() {
+        let key = key(OAuthKeyType::Ed, "ed.pem");
+        assert_eq!(key.algorithm, Algorithm::EdDSA);
+        sign_and_verify(&key);
+    }
+
+    #[test]
+    fn thumbprint() {
+        // example of RFC 7638, section 3.1
+        let n = "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw";
+        let thumbprint = format!(r#"{{"e":"AQAB","kty":"RSA","n":"{n}"}}"#);
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(Sha256::digest(thumbprint.as_bytes())),
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+    }
+
+    #[test]
+    fn wrong_key_type() {
+        let pem = std::fs::read(format!("{ASSETS}/ed.pem")).unwrap();
+        assert!(SigningKey::from_pem(OAuthKeyType::Rsa, &pem).is_err());
+        assert!(SigningKey::from_pem(OAuthKeyType::Ec, &pem).is_err());
+        assert!(SigningKey::from_pem(OAuthKeyType::Ed, b"not a pem").is_err());
+    }
+
+    #[test]
+    fn rotation() {
+        let old = key(OAuthKeyType::Rsa, "rsa.pem");
+        let new = key(OAuthKeyType::Rsa, "rsa-2.pem");
+        let (old_kid, new_kid) = (old.kid.clone(), new.kid.clone());
+
+        let keys = SigningKeys::new(new, vec![old, key(OAuthKeyType::Rsa, "rsa-2.pem")]);
+        assert_eq!(keys.active().kid, new_kid);
+        assert_eq!(keys.find(Some(&old_kid)).unwrap().kid, old_kid);
+        assert_eq!(keys.find(None).unwrap().kid, new_kid);
+        assert!(keys.find(Some("unknown")).is_none());
+        // duplicates are ignored
+        assert_eq!(keys.jwk_set().keys.len(), 2);
+    }
+}
@@ -1,218 +1,351 @@
 #[cfg(feature = "internal-oauth")]
 use crate::api::auth;
 #[cfg(feature = "internal-oauth")]
-use crate::{api::user, data_source::AuthSource};
+use crate::signing_keys::{SigningKey, SigningKeys};
 #[cfg(feature = "internal-oauth")]
-use axum::routing::{delete, post};
+use crate::{
+    api::{registration, user},
+    data_source::{AuthSource, RegistrationSource, TokenSource},
+};
 
 use crate::{
-    api::{event, healthcheck, program, report, resource, ven},
-    data_source::{DataSource, EventCrud, ProgramCrud, ReportCrud, ResourceCrud, VenCrud},
+    api::{
+        audit, business, enrollment, event, event_template, healthcheck, metrics, opt, program,
+        report, resource, ven,
+    },
+    config::{
+        Config, ConfigError, CorsConfig, CredentialsConfig, EventTemplatesConfig, LimitsConfig,
+        OAuthConfig, OAuthKeyType, OAuthType, RetentionConfig,
+    },
+    data_source::{
+        AuditLog, BusinessSource, DataSource, EnrollmentSource, EventCrud, EventTemplateSource,
+        OptSource, ProgramCrud, ReportCrud, ResourceCrud, VenCrud,
+    },
     error::AppError,
+    jwks::Jwks,
     jwt::JwtManager,
+    metrics::{prometheus_handle, track_requests},
+    rate_limit::{limit_requests, RateLimits},
+    telemetry::{make_span, request_id},
 };
 use axum::{
-    extract::{FromRef, Request},
+    extract::{DefaultBodyLimit, FromRef, Request},
+    http::Method,
     middleware,
     middleware::Next,
     response::IntoResponse,
-    routing::get,
+    routing::{delete, get, post},
 };
 use base64::{
     alphabet,
     engine::{general_purpose::PAD, GeneralPurpose},
     Engine,
 };
-use jsonwebtoken::{DecodingKey, EncodingKey};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
 use reqwest::StatusCode;
-use std::{cmp::PartialEq, env, env::VarError, fs::File, io::Read, str::FromStr, sync::Arc};
-use tower_http::trace::TraceLayer;
-use tracing::{info, warn};
+use std::{path::Path, sync::Arc};
+use tower_http::{
+    cors::{AllowOrigin, Any, CorsLayer},
+    timeout::TimeoutLayer,
+    trace::TraceLayer,
+};
+use tracing::warn;
 
 #[derive(Clone, FromRef)]
 pub struct AppState {
     pub storage: Arc<dyn DataSource>,
     pub jwt_manager: Arc<JwtManager>,
+    #[from_ref(skip)]
+    cors: CorsConfig,
+    #[from_ref(skip)]
+    limits: LimitsConfig,
+    pub credentials: CredentialsConfig,
+    pub event_templates: EventTemplatesConfig,
+    #[from_ref(skip)]
+    pub retention: RetentionConfig,
+    rate_limits: Arc<RateLimits>,
 }
 
-#[derive(Debug, Default, Copy, Clone)]
-enum OAuthType {
-    #[default]
-    Internal,
-    External,
+fn decode_hmac_secret(base64_secret: &str) -> Result<Vec<u8>, ConfigError> {
+    let secret = GeneralPurpose::new(&alphabet::STANDARD, PAD)
+        .decode(base64_secret)
+        .map_err(|_| {
+            ConfigError::Invalid("OAuth base64 secret contains invalid base64 string".to_string())
+        })?;
+    if secret.len() < 32 {
+        // https://datatracker.ietf.org/doc/html/rfc7518#section-3.2
+        return Err(ConfigError::Invalid(
+            "OAuth base64 secret must have at least 32 bytes".to_string(),
+        ));
+    }
+    Ok(secret)
 }
 
-impl FromStr for OAuthType {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_ascii_lowercase().as_str() {
-            "internal" => Ok(Self::Internal),
-            "external" => Ok(Self::External),
-            _ => Err(()),
-        }
+#[cfg_attr(not(feature = "internal-oauth"), allow(unused_variables))]
+fn internal_oauth(
+    config: &OAuthConfig,
+    storage: &impl DataSource,
+) -> Result<JwtManager, ConfigError> {
+    if !cfg!(feature = "internal-oauth") {
+        return Err(ConfigError::Invalid(
+            "Internal OAuth provider is not available, as the VTN was compiled without the 'internal-oauth' feature".to_string(),
+        ));
+    }
+    #[cfg(feature = "internal-oauth")]
+    if let Some(key_type) = config
+        .key_type
+        .filter(|key_type| *key_type != OAuthKeyType::Hmac)
+    {
+        let jwt_manager = JwtManager::internal(signing_keys(config, key_type)?);
+        return Ok(jwt_manager.with_revocation_list(storage.tokens()));
+    }
+    if !config.additional_pems.is_empty() {
+        return Err(ConfigError::Invalid(
+            "Additional OAuth PEM files require an asymmetric key type for the internal OAuth provider".to_string(),
+        ));
     }
+    let secret = match &config.base64_secret {
+        Some(base64_secret) => decode_hmac_secret(base64_secret)?,
+        None => {
+            warn!("Generating random secret as no OAuth base64 secret is configured");
+            let secret: [u8; 32] = rand::random();
+            secret.to_vec()
+        }
+    };
+    let jwt_manager = JwtManager::new(
+        Some(EncodingKey::from_secret(&secret)),
+        DecodingKey::from_secret(&secret),
+    );
+    #[cfg(feature = "internal-oauth")]
+    let jwt_manager = jwt_manager.with_revocation_list(storage.tokens());
+    Ok(jwt_manager)
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum OAuthKeyType {
-    Hmac,
-    Rsa,
-    Ec,
-    Ed,
+#[cfg(feature = "internal-oauth")]
+fn signing_keys(config: &OAuthConfig, key_type: OAuthKeyType) -> Result<SigningKeys, ConfigError> {
+    let path = config.pem.as_deref().ok_or_else(|| {
+        ConfigError::Invalid(format!(
+            "OAuth PEM file must be set for internal OAuth provider with key type {key_type}"
+        ))
+    })?;
+    let active = SigningKey::from_pem(key_type, &read_file(path)?)?;
+    let additional = config
+        .additional_pems
+        .iter()
+        .map(|path| SigningKey::from_pem(key_type, &read_file(path)?))
+        .collect::<Result<_, _>>()?;
+    Ok(SigningKeys::new(active, additional))
 }
 
-impl FromStr for OAuthKeyType {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_ascii_lowercase().as_str() {
-            "hmac" => Ok(Self::Hmac),
-            "rsa" => Ok(Self::Rsa),
-            "ec" => Ok(Self::Ec),
-            "ed" => Ok(Self::Ed),
-            _ => Err(()),
-        }
-    }
+fn read_pem(config: &OAuthConfig, key_type: &str) -> Result<Vec<u8>, ConfigError> {
+    let path = config.pem.as_deref().ok_or_else(|| {
+        ConfigError::Invalid(format!(
+            "OAuth PEM file must be set for external OAuth provider with key type {key_type}"
+        ))
+    })?;
+    read_file(path)
 }
 
-fn hmac_from_env() -> Result<Vec<u8>, VarError> {
-    env::var("OAUTH_BASE64_SECRET").map(|base64_secret| {
-        let secret = GeneralPurpose::new(&alphabet::STANDARD, PAD)
-            .decode(base64_secret)
-            .expect("OAUTH_BASE64_SECRET contains invalid base64 string");
-        if secret.len() < 32 {
-            // https://datatracker.ietf.org/doc/html/rfc7518#section-3.2
-            panic!("OAUTH_BASE64_SECRET must have at least 32 bytes");
-        }
-        secret
+fn read_file(path: &Path) -> Result<Vec<u8>, ConfigError> {
+    std::fs::read(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
     })
 }
 
-fn internal_oauth_from_env(key_type: Option<OAuthKeyType>) -> JwtManager {
-    if let Some(k_type) = key_type {
-        if k_type != OAuthKeyType::Hmac {
-            panic!("Internal OAuth provider only supports HMAC JWT keys");
-        }
-    }
-    let secret = hmac_from_env().unwrap_or_else(|_| {
-        warn!("Generating random secret as OAUTH_BASE64_SECRET env var was not found");
-        let secret: [u8; 32] = rand::random();
-        secret.to_vec()
-    });
-    JwtManager::new(
-        Some(EncodingKey::from_secret(&secret)),
-        DecodingKey::from_secret(&secret),
-    )
+fn invalid_key(key_type: &'static str) -> impl FnOnce(jsonwebtoken::errors::Error) -> ConfigError {
+    move |err| ConfigError::Invalid(format!("Cannot read {key_type} key: {err}"))
 }
 
-fn external_oauth_from_env(key_type: Option<OAuthKeyType>) -> JwtManager {
-    let key_type = key_type.expect("Must specify key type for external OAuth provider. Use OAUTH_KEY_TYPE environment variable");
-    match key_type {
+fn external_oauth(config: &OAuthConfig) -> Result<JwtManager, ConfigError> {
+    if let Some(jwks_url) = &config.jwks_url {
+        let jwks = Jwks::new(jwks_url.clone(), config.jwks_refresh_interval());
+        return Ok(JwtManager::from_jwks(jwks, config));
+    }
+
+    let key_type = config.key_type.ok_or_else(|| {
+        ConfigError::Invalid(
+            "Must specify key type or JWKS URL for external OAuth provider, e.g., via the OAUTH_KEY_TYPE environment variable".to_string(),
+        )
+    })?;
+    let (decoding_key, algorithms) = match key_type {
         OAuthKeyType::Hmac => {
-            let secret = hmac_from_env().expect("OAUTH_BASE64_SECRET environment variable must be set for external OAuth provider with key type HMAC");
-            JwtManager::new(None, DecodingKey::from_secret(&secret))
-        }
-        OAuthKeyType::Rsa => {
-            let rsa_file = env::var("OAUTH_PEM").expect("OAUTH_PEM environment variable must be set for external OAuth provider with key type RSA");
-            let pem_bytes = File::open(rsa_file)
-                .expect("File specified in OAUTH_PEM environment variable does not exist")
-                .bytes()
-                .collect::<Result<Vec<u8>, _>>()
-                .expect("Cannot read RSA key");
-            JwtManager::new(
-                None,
-                DecodingKey::from_rsa_pem(&pem_bytes).expect("Cannot read RSA key"),
-            )
-        }
-        OAuthKeyType::Ec => {
-            let ec_file = env::var("OAUTH_PEM").expect("OAUTH_PEM environment variable must be set for external OAuth provider with key type EC");
-            let pem_bytes = File::open(ec_file)
-                .expect("File specified in OAUTH_PEM environment variable does not exist")
-                .bytes()
-                .collect::<Result<Vec<u8>, _>>()
-                .expect("Cannot read EC key");
-            JwtManager::new(
-                None,
-                DecodingKey::from_ec_pem(&pem_bytes).expect("Cannot read EC key"),
-            )
-        }
-        OAuthKeyType::Ed => {
-            let ed_file = env::var("OAUTH_PEM").expect("OAUTH_PEM environment variable must be set for external OAuth provider with key type ED");
-            let pem_bytes = File::open(ed_file)
-                .expect("File specified in OAUTH_PEM environment variable does not exist")
-                .bytes()
-                .collect::<Result<Vec<u8>, _>>()
-                .expect("Cannot read Ed key");
-            JwtManager::new(
-                None,
-                DecodingKey::from_ed_pem(&pem_bytes).expect("Cannot read Ed key"),
+            let base64_secret = config.base64_secret.as_deref().ok_or_else(|| {
+                ConfigError::Invalid(
+                    "OAuth base64 secret must be set for external OAuth provider with key type HMAC"
+                        .to_string(),
+                )
+            })?;
+            (
+                DecodingKey::from_secret(&decode_hmac_secret(base64_secret)?),
+                vec![Algorithm::HS256, Algorithm::HS384, Algorithm::HS512],
             )
         }
-    }
+        OAuthKeyType::Rsa => (
+            DecodingKey::from_rsa_pem(&read_pem(config, "RSA")?).map_err(invalid_key("RSA"))?,
+            vec![
+                Algorithm::RS256,
+                Algorithm::RS384,
+                Algorithm::RS512,
+                Algorithm::PS256,
+                Algorithm::PS384,
+                Algorithm::PS512,
+            ],
+        ),
+        OAuthKeyType::Ec => (
+            DecodingKey::from_ec_pem(&read_pem(config, "EC")?).map_err(invalid_key("EC"))?,
+            vec![Algorithm::ES256, Algorithm::ES384],
+        ),
+        OAuthKeyType::Ed => (
+            DecodingKey::from_ed_pem(&read_pem(config, "ED")?).map_err(invalid_key("Ed"))?,
+            vec![Algorithm::EdDSA],
+        ),
+    };
+    Ok(JwtManager::external(decoding_key, algorithms, config))
 }
 
 impl AppState {
+    /// Creates the state from the OAuth settings in the environment variables,
+    /// see [`Config::apply_env`].
+    ///
+    /// # Panics
+    ///
+    /// If the environment variables contain an invalid configuration.
+    /// Use [`AppState::from_config`] to handle these errors instead.
     pub fn new<S: DataSource>(storage: S) -> Self {
-        let oauth_type: OAuthType = env::var("OAUTH_TYPE")
-            .inspect_err(|_|{
-            info!("Did not find OAUTH_TYPE environment variable, using internal OAuth provider.")}
-            )
-            .map(|env| env.parse()
-                .expect("Invalid value for OAUTH_TYPE environment variable. Allowed are INTERNAL and EXTERNAL."))
-            .unwrap_or_default();
-
-        let key_type: Option<OAuthKeyType> = env::var("OAUTH_KEY_TYPE").ok().map(|k| k.parse().expect("Invalid value for OAUTH_KEY_TYPE environment variable. Allowed are HMAC, RSA, EC, and ED."));
+        let mut config = Config::default();
+        config
+            .apply_env()
+            .and_then(|_| Self::from_config(storage, &config))
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        let jwt_manager = match oauth_type {
-            OAuthType::Internal => internal_oauth_from_env(key_type),
-            OAuthType::External => external_oauth_from_env(key_type),
+    pub fn from_config<S: DataSource>(storage: S, config: &Config) -> Result<Self, ConfigError> {
+        let jwt_manager = match config.oauth.oauth_type {
+            OAuthType::Internal => internal_oauth(&config.oauth, &storage)?,
+            OAuthType::External => external_oauth(&config.oauth)?,
         };
+        // fail early instead of when building the router
+        config.cors.allowed_origins()?;
+
+        // metrics recorded before the recorder got installed are lost
+        prometheus_handle();
 
-        Self {
+        Ok(Self {
             storage: Arc::new(storage),
             jwt_manager: Arc::new(jwt_manager),
+            cors: config.cors.clone(),
+            limits: config.limits.clone(),
+            credentials: config.credentials.clone(),
+            event_templates: config.event_templates.clone(),
+            retention: config.retention.clone(),
+            rate_limits: Arc::new(RateLimits::new(&config.rate_limit)),
+        })
+    }
+
+    fn cors_layer(&self) -> Option<CorsLayer> {
+        if self.cors.allowed_origins.is_empty() {
+            return None;
         }
+
+        let allow_origin = match self.cors.allowed_origins() {
+            Ok(Some(origins)) => AllowOrigin::list(origins),
+            Ok(None) => AllowOrigin::any(),
+            // validated in `from_config`
+            Err(_) => return None,
+        };
+
+        Some(
+            CorsLayer::new()
+                .allow_origin(allow_origin)
+                .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+                .allow_headers(Any),
+        )
     }
 
     fn router_without_state() -> axum::Router<Self> {
         #[allow(unused_mut)]
         let mut router = axum::Router::new()
             .route("/health", get(healthcheck))
+            .route("/metrics", get(metrics::get))
             .route("/programs", get(program::get_all).post(program::add))
             .route(
                 "/programs/:id",
                 get(program::get).put(program::edit).delete(program::delete),
             )
+            .route(
+                "/programs/:id/vens",
+                get(enrollment::get_by_program).post(enrollment::add),
+            )
+            .route("/programs/:id/vens/match", post(enrollment::add_matching))
+            .route("/programs/:id/vens/:ven_id", delete(enrollment::delete))
             .route("/reports", get(report::get_all).post(report::add))
             .route(
                 "/reports/:id",
                 get(report::get).put(report::edit).delete(report::delete),
             )
             .route("/events", get(event::get_all).post(event::add))
+            .route(
+                "/events/batch",
+                post(event::add_batch).put(event::edit_batch),
+            )
             .route(
                 "/events/:id",
                 get(event::get).put(event::edit).delete(event::delete),
             )
+            .route("/events/:id/versions", get(event::get_versions))
+            .route("/events/:id/cancel", post(event::cancel))
+            .route("/events/:id/opts", get(opt::get_all).post(opt::set))
+            .route("/events/:id/report-summary", get(report::get_summary))
+            .route(
+                "/events/:id/delivered-reduction",
+                get(report::get_delivered_reduction),
+            )
+            .route(
+                "/event-templates",
+                get(event_template::get_all).post(event_template::add),
+            )
+            .route(
+                "/event-templates/:id",
+                get(event_template::get)
+                    .put(event_template::edit)
+                    .delete(event_template::delete),
+            )
             .route("/vens", get(ven::get_all).post(ven::add))
             .route(
                 "/vens/:id",
                 get(ven::get).put(ven::edit).delete(ven::delete),
             )
+            .route("/vens/:ven_id/programs", get(enrollment::get_by_ven))
             .route(
                 "/vens/:ven_id/resources",
                 get(resource::get_all).post(resource::add),
             )
+            .route(
+                "/vens/:ven_id/resources/batch",
+                post(resource::add_batch).put(resource::edit_batch),
+            )
             .route(
                 "/vens/:ven_id/resources/:id",
                 get(resource::get)
                     .put(resource::edit)
                     .delete(resource::delete),
+            )
+            .route("/audit", get(audit::get_all))
+            .route("/businesses", get(business::get_all).post(business::add))
+            .route(
+                "/businesses/:id",
+                get(business::get)
+                    .put(business::edit)
+                    .delete(business::delete),
             );
         #[cfg(feature = "internal-oauth")]
         {
             router = router
                 .route("/auth/token", post(auth::token))
+                .route("/auth/revoke", post(auth::revoke))
+                .route("/auth/introspect", post(auth::introspect))
+                .route("/.well-known/jwks.json", get(auth::jwks))
                 .route("/users", get(user::get_all).post(user::add_user))
                 .route(
                     "/users/:id",
@@ -224,16 +357,36 @@ impl AppState {
                 .route(
                     "/users/:user_id/:client_id",
                     delete(user::delete_credential),
+                )
+                .route("/registration-tokens", post(registration::add_token))
+                .route("/registrations", get(registration::get_all))
+                .route(
+                    "/registrations/:ven_id",
+                    get(registration::get).put(registration::edit),
                 );
         }
         router
             .fallback(handler_404)
             .layer(middleware::from_fn(method_not_allowed))
-            .layer(TraceLayer::new_for_http())
     }
 
     pub fn into_router(self) -> axum::Router {
-        Self::router_without_state().with_state(self)
+        let router = Self::router_without_state()
+            .layer(DefaultBodyLimit::max(self.limits.max_body_size))
+            .layer(TimeoutLayer::new(self.limits.request_timeout()))
+            .layer(middleware::from_fn_with_state(
+                self.rate_limits.clone(),
+                limit_requests,
+            ))
+            .layer(middleware::from_fn(track_requests))
+            .layer(TraceLayer::new_for_http().make_span_with(make_span))
+            .layer(middleware::from_fn(request_id));
+        // outermost, such that preflight requests are answered before any other processing
+        let router = match self.cors_layer() {
+            Some(cors) => router.layer(cors),
+            None => router,
+        };
+        router.with_state(self)
     }
 }
 
@@ -257,6 +410,20 @@ impl FromRef<AppState> for Arc<dyn AuthSource> {
     }
 }
 
+#[cfg(feature = "internal-oauth")]
+impl FromRef<AppState> for Arc<dyn TokenSource> {
+    fn from_ref(state: &AppState) -> Arc<dyn TokenSource> {
+        state.storage.tokens()
+    }
+}
+
+#[cfg(feature = "internal-oauth")]
+impl FromRef<AppState> for Arc<dyn RegistrationSource> {
+    fn from_ref(state: &AppState) -> Arc<dyn RegistrationSource> {
+        state.storage.registrations()
+    }
+}
+
 impl FromRef<AppState> for Arc<dyn ProgramCrud> {
     fn from_ref(state: &AppState) -> Arc<dyn ProgramCrud> {
         state.storage.programs()
@@ -287,10 +454,44 @@ impl FromRef<AppState> for Arc<dyn ResourceCrud> {
     }
 }
 
+impl FromRef<AppState> for Arc<dyn AuditLog> {
+    fn from_ref(state: &AppState) -> Arc<dyn AuditLog> {
+        state.storage.audit_log()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn EnrollmentSource> {
+    fn from_ref(state: &AppState) -> Arc<dyn EnrollmentSource> {
+        state.storage.enrollments()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn EventTemplateSource> {
+    fn from_ref(state: &AppState) -> Arc<dyn EventTemplateSource> {
+        state.storage.event_templates()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn OptSource> {
+    fn from_ref(state: &AppState) -> Arc<dyn OptSource> {
+        state.storage.opts()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn BusinessSource> {
+    fn from_ref(state: &AppState) -> Arc<dyn BusinessSource> {
+        state.storage.businesses()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[cfg(feature = "internal-oauth")]
+    use crate::data_source::{RefreshToken, TokenSource};
+    use crate::data_source::{RetentionSource, StorageMetrics};
+
     struct MockDataSource {}
     impl DataSource for MockDataSource {
         fn programs(&self) -> Arc<dyn ProgramCrud> {
@@ -313,149 +514,364 @@ mod test {
             unimplemented!()
         }
 
+        fn audit_log(&self) -> Arc<dyn AuditLog> {
+            unimplemented!()
+        }
+
+        fn businesses(&self) -> Arc<dyn BusinessSource> {
+            unimplemented!()
+        }
+
+        fn enrollments(&self) -> Arc<dyn EnrollmentSource> {
+            unimplemented!()
+        }
+
+        fn event_templates(&self) -> Arc<dyn EventTemplateSource> {
+            unimplemented!()
+        }
+
+        fn retention(&self) -> Arc<dyn RetentionSource> {
+            unimplemented!()
+        }
+
+        fn opts(&self) -> Arc<dyn OptSource> {
+            unimplemented!()
+        }
+
+        fn metrics(&self) -> Arc<dyn StorageMetrics> {
+            unimplemented!()
+        }
+
         #[cfg(feature = "internal-oauth")]
         fn auth(&self) -> Arc<dyn AuthSource> {
             unimplemented!()
         }
 
+        #[cfg(feature = "internal-oauth")]
+        fn tokens(&self) -> Arc<dyn TokenSource> {
+            Arc::new(MockTokenSource {})
+        }
+
+        #[cfg(feature = "internal-oauth")]
+        fn registrations(&self) -> Arc<dyn RegistrationSource> {
+            unimplemented!()
+        }
+
         fn connection_active(&self) -> bool {
             unimplemented!()
         }
     }
 
-    mod state_from_env_var {
+    #[cfg(feature = "internal-oauth")]
+    struct MockTokenSource {}
+    #[cfg(feature = "internal-oauth")]
+    #[axum::async_trait]
+    impl TokenSource for MockTokenSource {
+        async fn add_refresh_token(&self, _: &RefreshToken) -> Result<(), AppError> {
+            unimplemented!()
+        }
+
+        async fn get_refresh_token(&self, _: &str) -> Result<Option<RefreshToken>, AppError> {
+            unimplemented!()
+        }
+
+        async fn remove_refresh_token(
+            &self,
+            _: &str,
+            _: &str,
+        ) -> Result<Option<RefreshToken>, AppError> {
+            unimplemented!()
+        }
+
+        async fn revoke_access_token(
+            &self,
+            _: &str,
+            _: chrono::DateTime<chrono::Utc>,
+        ) -> Result<(), AppError> {
+            unimplemented!()
+        }
+
+        async fn is_revoked(
+            &self,
+            _: Option<&str>,
+            _: &str,
+            _: chrono::DateTime<chrono::Utc>,
+        ) -> Result<bool, AppError> {
+            Ok(false)
+        }
+    }
+
+    mod state_from_config {
         use super::*;
-        use serial_test::serial;
+        use std::path::PathBuf;
+
+        const SECRET: &str = "60QL3fluRYn/21n0zNoPe1np5aB6P9C75b0Nbkwu4FM=";
 
-        fn clean_env() {
-            env::remove_var("OAUTH_BASE64_SECRET");
-            env::remove_var("OAUTH_TYPE");
-            env::remove_var("OAUTH_KEY_TYPE");
-            env::remove_var("OAUTH_PEM");
+        fn from_oauth(oauth: OAuthConfig) -> Result<AppState, ConfigError> {
+            let config = Config {
+                oauth,
+                ..Default::default()
+            };
+            AppState::from_config(MockDataSource {}, &config)
+        }
+
+        fn assert_invalid(result: Result<AppState, ConfigError>, expected: &str) {
+            match result {
+                Err(ConfigError::Invalid(msg)) => {
+                    assert!(msg.contains(expected), "expected {expected:?} in {msg:?}")
+                }
+                Err(err) => panic!("unexpected error {err}"),
+                Ok(_) => panic!("expected error {expected:?}"),
+            }
         }
 
         #[test]
-        #[should_panic(expected = "OAUTH_BASE64_SECRET must have at least 32 bytes")]
-        #[serial]
         fn internal_oauth_short_secret() {
-            clean_env();
-            env::set_var("OAUTH_BASE64_SECRET", "1234");
-            AppState::new(MockDataSource {});
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    base64_secret: Some("1234".to_string()),
+                    ..Default::default()
+                }),
+                "OAuth base64 secret must have at least 32 bytes",
+            );
         }
 
         #[test]
-        #[should_panic(expected = "OAUTH_BASE64_SECRET contains invalid base64 string")]
-        #[serial]
         fn internal_oauth_invalid_base64_secret() {
-            clean_env();
-            env::set_var("OAUTH_BASE64_SECRET", "&");
-            AppState::new(MockDataSource {});
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    base64_secret: Some("&".to_string()),
+                    ..Default::default()
+                }),
+                "OAuth base64 secret contains invalid base64 string",
+            );
         }
 
         #[test]
-        #[serial]
-        fn implicit_internal_oauth() {
-            clean_env();
-            env::set_var(
-                "OAUTH_BASE64_SECRET",
-                "60QL3fluRYn/21n0zNoPe1np5aB6P9C75b0Nbkwu4FM=",
-            );
-            AppState::new(MockDataSource {});
+        fn internal_oauth_random_secret() {
+            assert!(from_oauth(OAuthConfig::default()).is_ok());
+        }
+
+        #[test]
+        fn internal_oauth() {
+            assert!(from_oauth(OAuthConfig {
+                oauth_type: OAuthType::Internal,
+                base64_secret: Some(SECRET.to_string()),
+                ..Default::default()
+            })
+            .is_ok());
         }
 
         #[test]
-        #[serial]
-        fn explicit_internal_oauth() {
-            clean_env();
-            env::set_var("OAUTH_TYPE", "INTERNAL");
-            env::set_var(
-                "OAUTH_BASE64_SECRET",
-                "60QL3fluRYn/21n0zNoPe1np5aB6P9C75b0Nbkwu4FM=",
+        fn internal_explicit_key_type_oauth() {
+            assert!(from_oauth(OAuthConfig {
+                oauth_type: OAuthType::Internal,
+                key_type: Some(OAuthKeyType::Hmac),
+                base64_secret: Some(SECRET.to_string()),
+                ..Default::default()
+            })
+            .is_ok());
+        }
+
+        #[test]
+        fn internal_missing_pem() {
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    oauth_type: OAuthType::Internal,
+                    key_type: Some(OAuthKeyType::Rsa),
+                    ..Default::default()
+                }),
+                "OAuth PEM file must be set for internal OAuth provider with key type RSA",
             );
-            AppState::new(MockDataSource {});
         }
 
         #[test]
-        #[serial]
-        fn explicit_internal_explicit_key_type_oauth() {
-            clean_env();
-            env::set_var("OAUTH_TYPE", "INTERNAL");
-            env::set_var("OAUTH_KEY_TYPE", "HMAC");
-            env::set_var(
-                "OAUTH_BASE64_SECRET",
-                "60QL3fluRYn/21n0zNoPe1np5aB6P9C75b0Nbkwu4FM=",
+        fn internal_public_key_instead_of_private() {
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    oauth_type: OAuthType::Internal,
+                    key_type: Some(OAuthKeyType::Rsa),
+                    pem: Some(PathBuf::from("./tests/assets/public-rsa.pem")),
+                    ..Default::default()
+                }),
+                "Cannot read RSA private key",
             );
-            AppState::new(MockDataSource {});
         }
 
         #[test]
-        #[should_panic(expected = "Internal OAuth provider only supports HMAC JWT keys")]
-        #[serial]
-        fn explicit_internal_explicit_wrong_key_type_oauth() {
-            clean_env();
-            env::set_var("OAUTH_TYPE", "INTERNAL");
-            env::set_var("OAUTH_KEY_TYPE", "RSA");
-            env::set_var(
-                "OAUTH_BASE64_SECRET",
-                "60QL3fluRYn/21n0zNoPe1np5aB6P9C75b0Nbkwu4FM=",
+        fn internal_additional_pems_with_hmac() {
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    oauth_type: OAuthType::Internal,
+                    base64_secret: Some(SECRET.to_string()),
+                    additional_pems: vec![PathBuf::from("./tests/assets/signing/rsa.pem")],
+                    ..Default::default()
+                }),
+                "Additional OAuth PEM files require an asymmetric key type",
+            );
+        }
+
+        #[cfg(feature = "internal-oauth")]
+        #[tokio::test]
+        async fn internal_key_rotation() {
+            use openleadr_wire::oauth::Scope;
+            use std::time::Duration;
+
+            let jwt_manager = |pem: &str, additional_pems: &[&str]| {
+                from_oauth(OAuthConfig {
+                    oauth_type: OAuthType::Internal,
+                    key_type: Some(OAuthKeyType::Ec),
+                    pem: Some(PathBuf::from(pem)),
+                    additional_pems: additional_pems.iter().map(PathBuf::from).collect(),
+                    ..Default::default()
+                })
+                .unwrap()
+                .jwt_manager
+            };
+            let create = |jwt_manager: &JwtManager| {
+                jwt_manager
+                    .create(
+                        Duration::from_secs(60),
+                        "client".to_string(),
+                        vec![],
+                        &[Scope::ReadAll],
+                    )
+                    .unwrap()
+            };
+
+            let old = jwt_manager("./tests/assets/signing/ec.pem", &[]);
+            let old_token = create(&old);
+            assert_eq!(old.jwk_set().keys.len(), 1);
+
+            let rotated = jwt_manager(
+                "./tests/assets/jwks/ec-1.key",
+                &["./tests/assets/signing/ec.pem"],
+            );
+            assert_eq!(rotated.jwk_set().keys.len(), 2);
+            let claims = rotated.decode_and_validate(&old_token).await.unwrap();
+            assert_eq!(claims.sub, "client");
+
+            let new_token = create(&rotated);
+            assert!(rotated.decode_and_validate(&new_token).await.is_ok());
+            assert!(old.decode_and_validate(&new_token).await.is_err());
+        }
+
+        #[cfg(feature = "internal-oauth")]
+        #[tokio::test]
+        async fn publish_jwks() {
+            use axum::body::Body;
+            use http_body_util::BodyExt;
+            use tower::ServiceExt;
+
+            let router = from_oauth(OAuthConfig {
+                oauth_type: OAuthType::Internal,
+                key_type: Some(OAuthKeyType::Ed),
+                pem: Some(PathBuf::from("./tests/assets/signing/ed.pem")),
+                ..Default::default()
+            })
+            .unwrap()
+            .into_router();
+
+            let response = router
+                .oneshot(
+                    Request::builder()
+                        .uri("/.well-known/jwks.json")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let jwks: jsonwebtoken::jwk::JwkSet = serde_json::from_slice(&body).unwrap();
+            assert_eq!(jwks.keys.len(), 1);
+            assert_eq!(
+                jwks.keys[0].common.key_algorithm,
+                Some(jsonwebtoken::jwk::KeyAlgorithm::EdDSA)
             );
-            AppState::new(MockDataSource {});
         }
 
         #[test]
-        #[should_panic(
-            expected = "Must specify key type for external OAuth provider. Use OAUTH_KEY_TYPE environment variable"
-        )]
-        #[serial]
         fn external_missing_key_type_oauth() {
-            clean_env();
-            env::set_var("OAUTH_TYPE", "EXTERNAL");
-            env::set_var("OAUTH_PEM", "./key.pem");
-            AppState::new(MockDataSource {});
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    oauth_type: OAuthType::External,
+                    pem: Some(PathBuf::from("./key.pem")),
+                    ..Default::default()
+                }),
+                "Must specify key type or JWKS URL for external OAuth provider",
+            );
         }
 
         #[test]
-        #[should_panic(
-            expected = "OAUTH_PEM environment variable must be set for external OAuth provider with key type RSA"
-        )]
-        #[serial]
         fn external_missing_key_oauth() {
-            clean_env();
-            env::set_var("OAUTH_TYPE", "EXTERNAL");
-            env::set_var("OAUTH_KEY_TYPE", "RSA");
-            AppState::new(MockDataSource {});
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    oauth_type: OAuthType::External,
+                    key_type: Some(OAuthKeyType::Rsa),
+                    ..Default::default()
+                }),
+                "OAuth PEM file must be set for external OAuth provider with key type RSA",
+            );
+        }
+
+        #[test]
+        fn external_missing_pem_file() {
+            let result = from_oauth(OAuthConfig {
+                oauth_type: OAuthType::External,
+                key_type: Some(OAuthKeyType::Rsa),
+                pem: Some(PathBuf::from("./tests/assets/does-not-exist.pem")),
+                ..Default::default()
+            });
+            assert!(matches!(result, Err(ConfigError::Io { .. })));
         }
 
         #[test]
-        #[serial]
         fn external_rsa() {
-            clean_env();
-            env::set_var("OAUTH_TYPE", "EXTERNAL");
-            env::set_var("OAUTH_KEY_TYPE", "RSA");
-            env::set_var("OAUTH_PEM", "./tests/assets/public-rsa.pem");
-            AppState::new(MockDataSource {});
+            assert!(from_oauth(OAuthConfig {
+                oauth_type: OAuthType::External,
+                key_type: Some(OAuthKeyType::Rsa),
+                pem: Some(PathBuf::from("./tests/assets/public-rsa.pem")),
+                ..Default::default()
+            })
+            .is_ok());
         }
 
         #[test]
-        #[should_panic(expected = "Cannot read EC key: Error(InvalidKeyFormat)")]
-        #[serial]
         fn external_provide_rsa_key_instead_of_ec() {
-            clean_env();
-            env::set_var("OAUTH_TYPE", "EXTERNAL");
-            env::set_var("OAUTH_KEY_TYPE", "EC");
-            env::set_var("OAUTH_PEM", "./tests/assets/public-rsa.pem");
-            AppState::new(MockDataSource {});
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    oauth_type: OAuthType::External,
+                    key_type: Some(OAuthKeyType::Ec),
+                    pem: Some(PathBuf::from("./tests/assets/public-rsa.pem")),
+                    ..Default::default()
+                }),
+                "Cannot read EC key",
+            );
         }
 
         #[test]
-        #[should_panic(expected = "Cannot read Ed key: Error(InvalidKeyFormat)")]
-        #[serial]
         fn external_provide_rsa_key_instead_of_ed() {
-            clean_env();
-            env::set_var("OAUTH_TYPE", "EXTERNAL");
-            env::set_var("OAUTH_KEY_TYPE", "ED");
-            env::set_var("OAUTH_PEM", "./tests/assets/public-rsa.pem");
-            AppState::new(MockDataSource {});
+            assert_invalid(
+                from_oauth(OAuthConfig {
+                    oauth_type: OAuthType::External,
+                    key_type: Some(OAuthKeyType::Ed),
+                    pem: Some(PathBuf::from("./tests/assets/public-rsa.pem")),
+                    ..Default::default()
+                }),
+                "Cannot read Ed key",
+            );
+        }
+
+        #[test]
+        fn invalid_cors_origin() {
+            let config = Config {
+                cors: CorsConfig {
+                    allowed_origins: vec!["https://example.com\n".to_string()],
+                },
+                ..Default::default()
+            };
+            assert!(AppState::from_config(MockDataSource {}, &config).is_err());
         }
     }
 }
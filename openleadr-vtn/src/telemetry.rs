@@ -0,0 +1,165 @@
+use std::fmt::{Display, Formatter};
+
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{
+    propagation::{Extractor, TextMapPropagator},
+    Context,
+};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use tracing::{info_span, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+pub(crate) const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Maximum length of a request id provided by the client
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: RequestId;
+}
+
+/// Identifies a single request in the logs, traces, and the `instance` of a
+/// [`Problem`](openleadr_wire::problem::Problem) response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RequestId(String);
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl RequestId {
+    /// Use the request id provided by the client in the `X-Request-Id` header if it is sane,
+    /// such that a VEN can correlate its requests with the VTN logs.
+    /// Otherwise, generate a new one.
+    fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| {
+                (1..=MAX_REQUEST_ID_LENGTH).contains(&id.len())
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            })
+            .map(|id| Self(id.to_string()))
+            .unwrap_or_else(Self::generate)
+    }
+
+    fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The id of the request currently handled.
+/// Outside a request, e.g., in background tasks, a new id is generated on each call.
+pub(crate) fn current_request_id() -> RequestId {
+    REQUEST_ID
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| RequestId::generate())
+}
+
+/// Assigns a [`RequestId`] to each request and returns it in the `X-Request-Id` response header
+pub(crate) async fn request_id(mut req: Request, next: Next) -> Response {
+    let request_id = RequestId::from_headers(req.headers());
+    req.extensions_mut().insert(request_id.clone());
+
+    let mut response = REQUEST_ID.scope(request_id.clone(), next.run(req)).await;
+
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+/// Extracts the trace context of the caller from the W3C `traceparent` and `tracestate` headers
+fn parent_context(headers: &HeaderMap) -> Context {
+    TraceContextPropagator::new().extract(&HeaderExtractor(headers))
+}
+
+/// Creates the span for a request, as a child of the trace of the caller, if any.
+pub(crate) fn make_span(req: &Request) -> Span {
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(RequestId::as_str)
+        .unwrap_or_default();
+
+    let span = info_span!(
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        version = ?req.version(),
+        request_id,
+    );
+    span.set_parent(parent_context(req.headers()));
+    span
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TraceContextExt;
+
+    #[test]
+    fn request_id_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("abc-123_4.5"));
+        assert_eq!(RequestId::from_headers(&headers).as_str(), "abc-123_4.5");
+
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("no spaces"));
+        let generated = RequestId::from_headers(&headers);
+        assert!(Uuid::parse_str(generated.as_str()).is_ok());
+
+        let too_long = "a".repeat(MAX_REQUEST_ID_LENGTH + 1);
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_str(&too_long).unwrap());
+        assert_ne!(RequestId::from_headers(&headers).as_str(), too_long);
+
+        let generated = RequestId::from_headers(&HeaderMap::new());
+        assert!(Uuid::parse_str(generated.as_str()).is_ok());
+    }
+
+    #[test]
+    fn parent_from_traceparent() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            HeaderValue::from_static("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+        );
+
+        let context = parent_context(&headers);
+        let span_context = context.span().span_context().clone();
+        assert!(span_context.is_remote());
+        assert_eq!(
+            span_context.trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(span_context.span_id().to_string(), "00f067aa0ba902b7");
+
+        let context = parent_context(&HeaderMap::new());
+        assert!(!context.span().span_context().is_valid());
+    }
+}
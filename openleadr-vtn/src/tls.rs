@@ -0,0 +1,524 @@
+//! HTTPS termination with optional client certificate authentication (mutual TLS).
+
+use std::{
+    future::Future,
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    Router,
+};
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
+use openleadr_wire::ven::VenId;
+use rustls::{
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use sha2::{Digest, Sha256};
+use tokio::{net::TcpListener, sync::watch};
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+use tracing::{debug, error, info, trace, warn};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::config::{ClientCertificateMapping, TlsConfig};
+
+/// Connections that did not complete the TLS handshake within this time are closed
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time open connections get to finish their requests after a shutdown signal
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("cannot read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("no certificate found in {0}")]
+    NoCertificate(PathBuf),
+    #[error("no private key found in {0}")]
+    NoPrivateKey(PathBuf),
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+    #[error("invalid client CA: {0}")]
+    ClientVerifier(#[from] rustls::server::VerifierBuilderError),
+}
+
+/// The client certificate presented during the TLS handshake.
+///
+/// Available as request extension on connections with a verified client certificate.
+#[derive(Debug, Clone)]
+pub struct ClientCertificate {
+    /// Hex encoded SHA-256 fingerprint of the DER encoded certificate
+    pub fingerprint: String,
+    /// Distinguished name of the certificate subject
+    pub subject: String,
+    /// The VEN the certificate is mapped to in the [`TlsConfig`], if any
+    pub ven_id: Option<VenId>,
+}
+
+impl ClientCertificate {
+    fn new(der: &CertificateDer<'_>, mappings: &[ClientCertificateMapping]) -> Self {
+        let fingerprint = Sha256::digest(der)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        let subject = X509Certificate::from_der(der)
+            .map(|(_, cert)| cert.subject().to_string())
+            .unwrap_or_default();
+
+        let ven_id = mappings
+            .iter()
+            .find(|mapping| {
+                mapping
+                    .fingerprint
+                    .as_deref()
+                    .is_some_and(|expected| normalize_fingerprint(expected) == fingerprint)
+                    || mapping.subject.as_deref() == Some(subject.as_str())
+            })
+            .map(|mapping| mapping.ven_id.clone());
+
+        if ven_id.is_none() {
+            debug!(%fingerprint, %subject, "client certificate is not mapped to a VEN");
+        }
+
+        Self {
+            fingerprint,
+            subject,
+            ven_id,
+        }
+    }
+}
+
+fn normalize_fingerprint(fingerprint: &str) -> String {
+    fingerprint
+        .chars()
+        .filter(|c| *c != ':')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn open(path: &Path) -> Result<io::BufReader<std::fs::File>, TlsError> {
+    std::fs::File::open(path)
+        .map(io::BufReader::new)
+        .map_err(|source| TlsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|source| TlsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificate(path.to_path_buf()));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> Result<PrivateKeyDer<'static>, TlsError> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .map_err(|source| TlsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?
+        .ok_or_else(|| TlsError::NoPrivateKey(path.to_path_buf()))
+}
+
+/// Loads the certificates and keys referenced in the [`TlsConfig`]
+pub fn server_config(config: &TlsConfig) -> Result<Arc<ServerConfig>, TlsError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()?;
+
+    let builder = match &config.client_ca {
+        Some(client_ca) => builder.with_client_cert_verifier(client_verifier(
+            client_ca,
+            config.require_client_cert,
+            provider,
+        )?),
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config =
+        builder.with_single_cert(read_certs(&config.cert)?, read_key(&config.key)?)?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(Arc::new(server_config))
+}
+
+fn client_verifier(
+    client_ca: &Path,
+    require_client_cert: bool,
+    provider: Arc<CryptoProvider>,
+) -> Result<Arc<dyn rustls::server::danger::ClientCertVerifier>, TlsError> {
+    let mut roots = RootCertStore::empty();
+    for cert in read_certs(client_ca)? {
+        roots.add(cert)?;
+    }
+
+    let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+    let builder = if require_client_cert {
+        builder
+    } else {
+        builder.allow_unauthenticated()
+    };
+    Ok(builder.build()?)
+}
+
+fn modification_times(config: &TlsConfig) -> Vec<Option<SystemTime>> {
+    [
+        Some(&config.cert),
+        Some(&config.key),
+        config.client_ca.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+    .collect()
+}
+
+/// Reloads the server config whenever one of the files changed
+async fn reload(config: TlsConfig, sender: watch::Sender<Arc<ServerConfig>>) {
+    let mut interval = tokio::time::interval(config.reload_interval());
+    let mut last_modified = modification_times(&config);
+
+    loop {
+        interval.tick().await;
+
+        let modified = modification_times(&config);
+        if modified == last_modified {
+            continue;
+        }
+        // retry only after the next change, e.g., if the certificate got updated but the key not yet
+        last_modified = modified;
+
+        match server_config(&config) {
+            Ok(server_config) => {
+                info!("reloaded TLS certificates");
+                sender.send_replace(server_config);
+            }
+            Err(err) => error!(%err, "cannot reload TLS certificates, keeping the previous ones"),
+        }
+    }
+}
+
+/// Serves the router via HTTPS until the `shutdown` future completes.
+///
+/// The certificate of a client is available to the handlers as [`ClientCertificate`] request extension,
+/// and its address as [`ConnectInfo<SocketAddr>`](ConnectInfo).
+pub async fn serve(
+    listener: TcpListener,
+    router: Router,
+    config: &TlsConfig,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), TlsError> {
+    let (config_sender, config_receiver) = watch::channel(server_config(config)?);
+    let reloader = tokio::spawn(reload(config.clone(), config_sender));
+    let mappings: Arc<[ClientCertificateMapping]> = config.client_certificates.clone().into();
+
+    // each connection holds a receiver, such that we can wait for all of them to close
+    let (shutdown_sender, shutdown_receiver) = watch::channel(());
+
+    tokio::pin!(shutdown);
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    // e.g., too many open files, so give other connections the chance to close
+                    warn!(%err, "cannot accept connection");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let acceptor = TlsAcceptor::from(Arc::clone(&config_receiver.borrow()));
+        tokio::spawn(serve_connection(
+            stream,
+            remote_addr,
+            acceptor,
+            router.clone(),
+            Arc::clone(&mappings),
+            shutdown_receiver.clone(),
+        ));
+    }
+
+    reloader.abort();
+    drop(listener);
+    drop(shutdown_receiver);
+    shutdown_sender.send_replace(());
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown_sender.closed())
+        .await
+        .is_err()
+    {
+        warn!("not all connections closed within {SHUTDOWN_TIMEOUT:?}");
+    }
+
+    Ok(())
+}
+
+async fn serve_connection(
+    stream: tokio::net::TcpStream,
+    remote_addr: SocketAddr,
+    acceptor: TlsAcceptor,
+    router: Router,
+    mappings: Arc<[ClientCertificateMapping]>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    let stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(err)) => {
+            debug!(%err, %remote_addr, "TLS handshake failed");
+            return;
+        }
+        Err(_) => {
+            debug!(%remote_addr, "TLS handshake timed out");
+            return;
+        }
+    };
+
+    let client_certificate = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .map(|cert| ClientCertificate::new(cert, &mappings));
+    trace!(%remote_addr, ?client_certificate, "accepted TLS connection");
+
+    let service = router.map_request(move |mut req: Request<Incoming>| {
+        req.extensions_mut().insert(ConnectInfo(remote_addr));
+        if let Some(client_certificate) = &client_certificate {
+            req.extensions_mut().insert(client_certificate.clone());
+        }
+        req
+    });
+
+    let builder = auto::Builder::new(TokioExecutor::new());
+    let connection = builder
+        .serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(service));
+    tokio::pin!(connection);
+
+    let result = tokio::select! {
+        result = connection.as_mut() => result,
+        _ = shutdown.changed() => {
+            connection.as_mut().graceful_shutdown();
+            connection.await
+        }
+    };
+    if let Err(err) = result {
+        debug!(%err, %remote_addr, "error serving connection");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::jwt::{JwtManager, User};
+    use axum::routing::get;
+    use jsonwebtoken::DecodingKey;
+    use reqwest::{Certificate, Identity, StatusCode};
+
+    const ASSETS: &str = "./tests/assets/tls";
+    const CLIENT_FINGERPRINT: &str =
+        "4E:73:F7:F6:85:D5:45:6E:17:8E:D7:BD:AB:81:65:7D:F6:8D:EF:D0:38:0B:70:F6:91:F9:0C:7A:91:DF:18:39";
+
+    fn asset(name: &str) -> PathBuf {
+        Path::new(ASSETS).join(name)
+    }
+
+    fn tls_config(require_client_cert: bool) -> TlsConfig {
+        TlsConfig {
+            client_ca: Some(asset("ca.pem")),
+            require_client_cert,
+            client_certificates: vec![ClientCertificateMapping {
+                fingerprint: Some(CLIENT_FINGERPRINT.to_string()),
+                subject: None,
+                ven_id: "ven-1".parse().unwrap(),
+            }],
+            ..TlsConfig::new(asset("server.pem"), asset("server.key"))
+        }
+    }
+
+    #[test]
+    fn map_client_certificate() {
+        let cert = read_certs(&asset("client.pem")).unwrap().remove(0);
+
+        let by_fingerprint = ClientCertificate::new(&cert, &tls_config(true).client_certificates);
+        assert_eq!(
+            by_fingerprint.fingerprint,
+            normalize_fingerprint(CLIENT_FINGERPRINT)
+        );
+        assert_eq!(by_fingerprint.subject, "O=OpenLEADR, CN=ven-1");
+        assert_eq!(by_fingerprint.ven_id, Some("ven-1".parse().unwrap()));
+
+        let by_subject = ClientCertificate::new(
+            &cert,
+            &[ClientCertificateMapping {
+                fingerprint: None,
+                subject: Some("O=OpenLEADR, CN=ven-1".to_string()),
+                ven_id: "ven-2".parse().unwrap(),
+            }],
+        );
+        assert_eq!(by_subject.ven_id, Some("ven-2".parse().unwrap()));
+
+        let unmapped = ClientCertificate::new(&cert, &[]);
+        assert_eq!(unmapped.ven_id, None);
+    }
+
+    #[test]
+    fn invalid_files() {
+        let mut config = tls_config(false);
+        config.key = asset("does-not-exist.key");
+        assert!(matches!(server_config(&config), Err(TlsError::Io { .. })));
+
+        let mut config = tls_config(false);
+        config.key = asset("server.pem");
+        assert!(matches!(
+            server_config(&config),
+            Err(TlsError::NoPrivateKey(_))
+        ));
+
+        let mut config = tls_config(false);
+        config.client_ca = Some(asset("server.key"));
+        assert!(matches!(
+            server_config(&config),
+            Err(TlsError::NoCertificate(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reload_on_change() {
+        let dir = std::env::temp_dir().join(format!("openleadr-tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let config = TlsConfig::new(dir.join("cert.pem"), dir.join("key.pem"));
+        std::fs::copy(asset("server.pem"), &config.cert).unwrap();
+        std::fs::copy(asset("server.key"), &config.key).unwrap();
+
+        let initial = server_config(&config).unwrap();
+        let (sender, mut receiver) = watch::channel(Arc::clone(&initial));
+        let reloader = tokio::spawn(reload(config.clone(), sender));
+
+        // invalid files are not loaded
+        std::fs::write(&config.cert, "").unwrap();
+        let modified = SystemTime::now() + Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(&config.cert)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        tokio::time::sleep(config.reload_interval() * 2).await;
+        assert!(!receiver.has_changed().unwrap());
+
+        std::fs::copy(asset("server.pem"), &config.cert).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&config.cert)
+            .unwrap()
+            .set_modified(modified + Duration::from_secs(60))
+            .unwrap();
+        receiver.changed().await.unwrap();
+        assert!(!Arc::ptr_eq(&initial, &receiver.borrow()));
+
+        reloader.abort();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    async fn whoami(User(claims): User) -> String {
+        claims.ven_ids_string().join(",")
+    }
+
+    async fn start_server(config: TlsConfig) -> (SocketAddr, watch::Sender<()>) {
+        let jwt_manager = Arc::new(JwtManager::new(None, DecodingKey::from_secret(b"secret")));
+        let router = Router::new()
+            .route("/whoami", get(whoami))
+            .with_state(jwt_manager);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown, mut shutdown_receiver) = watch::channel(());
+
+        tokio::spawn(async move {
+            serve(listener, router, &config, async move {
+                shutdown_receiver.changed().await.ok();
+            })
+            .await
+            .unwrap();
+        });
+
+        (addr, shutdown)
+    }
+
+    fn client(addr: SocketAddr, with_identity: bool) -> reqwest::Client {
+        let ca = std::fs::read(asset("ca.pem")).unwrap();
+        let mut builder = reqwest::Client::builder()
+            .add_root_certificate(Certificate::from_pem(&ca).unwrap())
+            .resolve("localhost", addr);
+        if with_identity {
+            let mut identity = std::fs::read(asset("client.pem")).unwrap();
+            identity.extend(std::fs::read(asset("client.key")).unwrap());
+            builder = builder.identity(Identity::from_pem(&identity).unwrap());
+        }
+        builder.build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn mutual_tls() {
+        let (addr, shutdown) = start_server(tls_config(true)).await;
+        let url = format!("https://localhost:{}/whoami", addr.port());
+
+        let response = client(addr, true).get(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ven-1");
+
+        // a bearer token takes precedence over the certificate
+        let response = client(addr, true)
+            .get(&url)
+            .bearer_auth("invalid")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        assert!(client(addr, false).get(&url).send().await.is_err());
+
+        shutdown.send_replace(());
+    }
+
+    #[tokio::test]
+    async fn optional_client_certificate() {
+        let mut config = tls_config(false);
+        config.client_certificates.clear();
+        let (addr, shutdown) = start_server(config).await;
+        let url = format!("https://localhost:{}/whoami", addr.port());
+
+        let response = client(addr, false).get(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // not mapped to a VEN
+        let response = client(addr, true).get(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        shutdown.send_replace(());
+    }
+}
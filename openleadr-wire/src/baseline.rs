@@ -0,0 +1,144 @@
+//! Types used for the `/events/{eventID}/delivered-reduction` endpoint
+//!
+//! A customer baseline estimates the usage of a resource during an event had there been no event,
+//! based on the `USAGE` the VEN reported for the resource on previous days.
+//! The delivered reduction is the baseline minus the reported usage during the event.
+//! This endpoint is an extension of this implementation and not part of the OpenADR 3.0 specification.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use validator::{Validate, ValidationError};
+
+use crate::{event::EventId, program::ProgramId, report::ResourceName, ven::VenId};
+
+/// How the baseline is computed, the "high X of Y" method with an optional day-of adjustment.
+///
+/// Of the `y` most recent eligible days before the event, the baseline averages the usage of the `x` days
+/// with the highest usage during the event's intervals.
+/// Eligible days are of the same kind, weekday or weekend, as the event day,
+/// have no other event of the program, and have usage reported for all intervals.
+/// For example, "10 of 10" averages the last ten eligible days.
+///
+/// The day-of adjustment scales the baseline by the usage during the `adjustment_hours` before the event
+/// relative to the baseline of these hours, limited to `1 ± adjustment_cap`.
+/// It accounts for the conditions on the event day, such as the weather, that the previous days do not reflect.
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_x_of_y"))]
+pub struct BaselineMethod {
+    /// Number of days with the highest usage the baseline averages
+    #[serde(default = "default_days")]
+    #[validate(range(min = 1))]
+    pub x: u8,
+    /// Number of most recent eligible days considered
+    #[serde(default = "default_days")]
+    #[validate(range(min = 1, max = 45))]
+    pub y: u8,
+    /// Hours before the event the day-of adjustment is based on, none if not set
+    #[validate(range(min = 1, max = 24))]
+    pub adjustment_hours: Option<u8>,
+    /// Maximum relative adjustment, e.g., 0.4 for a factor between 0.6 and 1.4, unlimited if not set
+    #[validate(range(min = 0.0))]
+    pub adjustment_cap: Option<f64>,
+}
+
+fn default_days() -> u8 {
+    10
+}
+
+impl Default for BaselineMethod {
+    /// "10 of 10" without adjustment
+    fn default() -> Self {
+        Self {
+            x: default_days(),
+            y: default_days(),
+            adjustment_hours: None,
+            adjustment_cap: None,
+        }
+    }
+}
+
+fn validate_x_of_y(method: &BaselineMethod) -> Result<(), ValidationError> {
+    if method.x <= method.y {
+        Ok(())
+    } else {
+        Err(ValidationError::new("x_of_y").with_message("x must not be greater than y".into()))
+    }
+}
+
+/// The reductions the resources delivered during an event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveredReduction {
+    /// URL safe VTN assigned object ID of the event.
+    #[serde(rename = "eventID")]
+    pub event_id: EventId,
+    /// URL safe VTN assigned object ID of the program of the event.
+    #[serde(rename = "programID")]
+    pub program_id: ProgramId,
+    pub method: BaselineMethod,
+    /// Ordered by VEN and resource name
+    pub resources: Vec<ResourceReduction>,
+}
+
+/// The reduction a resource of a VEN delivered during an event
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceReduction {
+    /// URL safe VTN assigned object ID of the VEN.
+    #[serde(rename = "venID")]
+    pub ven_id: VenId,
+    /// The name the VEN reports the resource by
+    pub resource_name: ResourceName,
+    /// The days the baseline averages, empty if there are fewer than `x` eligible days
+    pub baseline_days: Vec<NaiveDate>,
+    /// The factor the baseline got scaled by, if adjusted
+    pub adjustment_factor: Option<f64>,
+    /// The intervals of the event that have a start and an end
+    pub intervals: Vec<IntervalReduction>,
+    /// Sum of the reductions of the intervals
+    pub total_reduction: Option<f64>,
+}
+
+/// Baseline, usage, and reduction of a resource for an interval of the event
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntervalReduction {
+    /// The id of the interval of the event
+    pub id: i32,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub baseline: Option<f64>,
+    /// The reported usage
+    pub usage: Option<f64>,
+    /// Baseline minus usage, positive if the usage was reduced
+    pub reduction: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_defaults() {
+        let method: BaselineMethod = serde_json::from_str("{}").unwrap();
+        assert_eq!(method, BaselineMethod::default());
+        assert!(method.validate().is_ok());
+
+        let method: BaselineMethod =
+            serde_json::from_str(r#"{"x": 4, "y": 5, "adjustmentHours": 3, "adjustmentCap": 0.4}"#)
+                .unwrap();
+        assert!(method.validate().is_ok());
+
+        let method = BaselineMethod {
+            x: 6,
+            y: 5,
+            ..Default::default()
+        };
+        assert!(method.validate().is_err());
+    }
+}
@@ -0,0 +1,67 @@
+//! Types used for the bulk `batch` endpoints
+//!
+//! Creating or updating many objects one request at a time is slow.
+//! The VTN therefore offers `/events/batch` and `/vens/{venID}/resources/batch` endpoints,
+//! which apply a list of objects atomically.
+//! These endpoints are an extension of this implementation and not part of the OpenADR 3.0 specification.
+//!
+//! On success, the VTN returns the list of created or updated objects in the same order as in the request.
+//! If any of the items cannot be applied, none of them is stored and
+//! the VTN responds with a list of [`Problem`](crate::problem::Problem)s, one for each item in the request.
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// A single item in a batch update request:
+/// the ID of the object to update together with its new content.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct BatchUpdate<Id, Content: Validate> {
+    /// URL safe VTN assigned object ID of the object to update.
+    pub id: Id,
+    /// The new content of the object
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub content: Content,
+}
+
+impl<Id, Content: Validate> BatchUpdate<Id, Content> {
+    pub fn new(id: Id, content: Content) -> Self {
+        Self { id, content }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::{EventContent, EventId, EventInterval, EventType, EventValuesMap},
+        values_map::Value,
+    };
+
+    #[test]
+    fn serde_update() {
+        let example = r#"{"id":"event-1","objectType":"EVENT","programID":"program-1","priority":null,"intervals":[{"id":0,"payloads":[{"type":"PRICE","values":[0.17]}]}]}"#;
+
+        let expected = BatchUpdate::new(
+            "event-1".parse::<EventId>().unwrap(),
+            EventContent::new(
+                "program-1".parse().unwrap(),
+                vec![EventInterval {
+                    id: 0,
+                    interval_period: None,
+                    payloads: vec![EventValuesMap {
+                        value_type: EventType::Price,
+                        values: vec![Value::Number(0.17)],
+                    }],
+                }],
+            ),
+        );
+
+        let parsed: BatchUpdate<EventId, EventContent> = serde_json::from_str(example).unwrap();
+        assert_eq!(parsed, expected);
+
+        let roundtrip: BatchUpdate<EventId, EventContent> =
+            serde_json::from_str(&serde_json::to_string(&expected).unwrap()).unwrap();
+        assert_eq!(roundtrip, expected);
+    }
+}
@@ -0,0 +1,809 @@
+//! ISO 8601 durations with calendar-aware arithmetic
+//!
+//! Durations in OpenADR may contain years, months, weeks, and days,
+//! which do not have a fixed length in seconds.
+//! Their length depends on the point in time they are added to, and the time zone thereof:
+//! a month can have 28 to 31 days, and a day can have 23 to 25 hours across daylight saving time transitions.
+
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Write},
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Days, LocalResult, Months, NaiveDateTime, Offset, TimeDelta, TimeZone};
+use serde::{de::Unexpected, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An ISO 8601 formatted duration, e.g., `PT1H` or `P1Y2M3DT4H5M6S`.
+///
+/// Equality and ordering consider the calendar:
+/// `PT60M` equals `PT1H`, `P12M` equals `P1Y`, and `P1W` equals `P7D`,
+/// but `P1D` does not equal `PT24H`, as a day may have 23 or 25 hours.
+/// For the same reason, the ordering is partial, e.g., `P1M` and `P30D` are incomparable.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Duration {
+    pub years: u32,
+    pub months: u32,
+    pub weeks: u32,
+    pub days: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+}
+
+impl Duration {
+    /// One (1) hour
+    pub const PT1H: Self = Self::hours(1);
+
+    /// Indicates that an event's intervals continue indefinitely into the future until the event is
+    /// deleted or modified. This effectively represents an infinite duration.
+    pub const P9999Y: Self = Self::years(9999);
+
+    #[deprecated(
+        note = "the sentinel for an infinite duration is `P9999Y`, use `Duration::P9999Y`"
+    )]
+    pub const P999Y: Self = Self::P9999Y;
+
+    pub const PT0S: Self = Self::seconds(0);
+
+    pub const fn years(years: u32) -> Self {
+        Self {
+            years,
+            ..Self::ZERO
+        }
+    }
+
+    pub const fn months(months: u32) -> Self {
+        Self {
+            months,
+            ..Self::ZERO
+        }
+    }
+
+    pub const fn weeks(weeks: u32) -> Self {
+        Self {
+            weeks,
+            ..Self::ZERO
+        }
+    }
+
+    pub const fn days(days: u32) -> Self {
+        Self { days, ..Self::ZERO }
+    }
+
+    pub const fn hours(hours: u32) -> Self {
+        Self {
+            hours,
+            ..Self::ZERO
+        }
+    }
+
+    pub const fn minutes(minutes: u32) -> Self {
+        Self {
+            minutes,
+            ..Self::ZERO
+        }
+    }
+
+    pub const fn seconds(seconds: u32) -> Self {
+        Self {
+            seconds,
+            ..Self::ZERO
+        }
+    }
+
+    const ZERO: Self = Self {
+        years: 0,
+        months: 0,
+        weeks: 0,
+        days: 0,
+        hours: 0,
+        minutes: 0,
+        seconds: 0,
+    };
+
+    /// Whether this is the `P9999Y` sentinel for intervals that continue indefinitely
+    pub fn is_infinite(&self) -> bool {
+        *self == Self::P9999Y
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::PT0S
+    }
+
+    /// The months, days, and seconds this duration consists of.
+    /// Only these can be converted into each other without knowing the point in time.
+    fn totals(&self) -> (u64, u64, u64) {
+        (
+            u64::from(self.years) * 12 + u64::from(self.months),
+            u64::from(self.weeks) * 7 + u64::from(self.days),
+            u64::from(self.hours) * 3600 + u64::from(self.minutes) * 60 + u64::from(self.seconds),
+        )
+    }
+
+    /// Carries seconds into minutes, minutes into hours, and months into years, e.g., `PT90M` becomes `PT1H30M`.
+    /// Days are not carried into weeks, as ISO 8601 does not allow combining weeks with other components.
+    pub fn normalized(&self) -> Self {
+        let (months, _, seconds) = self.totals();
+        let saturate = |value: u64| u32::try_from(value).unwrap_or(u32::MAX);
+
+        Self {
+            years: saturate(months / 12),
+            months: (months % 12) as u32,
+            weeks: self.weeks,
+            days: self.days,
+            hours: saturate(seconds / 3600),
+            minutes: (seconds % 3600 / 60) as u32,
+            seconds: (seconds % 60) as u32,
+        }
+    }
+
+    /// Adds the duration to `at`, in the time zone of `at`.
+    ///
+    /// Years, months, weeks, and days are added to the local date, keeping the local time,
+    /// such that `P1D` is 23 hours if the clocks are set forward in between.
+    /// Hours, minutes, and seconds are added as elapsed time afterward.
+    /// If the local time does not exist on the resulting date, it is moved forward by the length of the gap;
+    /// if it is ambiguous, the earlier one is used.
+    ///
+    /// Returns `None` for the infinite duration `P9999Y`, or if the result is out of range.
+    ///
+    /// NOTE: does not consider leap seconds!
+    pub fn checked_add<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        if self.is_infinite() {
+            return None;
+        }
+
+        let (months, days, seconds) = self.totals();
+        let date = if months == 0 && days == 0 {
+            at.clone()
+        } else {
+            let local = at
+                .naive_local()
+                .checked_add_months(Months::new(u32::try_from(months).ok()?))?
+                .checked_add_days(Days::new(days))?;
+            resolve_local(&at.timezone(), local)?
+        };
+
+        date.checked_add_signed(TimeDelta::try_seconds(i64::try_from(seconds).ok()?)?)
+    }
+
+    /// Because iso8601 durations can include months and years, they don't independently have a
+    /// fixed duration. Their real duration (in real units like seconds) can only be determined
+    /// when a starting time is given.
+    ///
+    /// Saturates at [`TimeDelta::MAX`] for the infinite duration `P9999Y`, see [`Self::checked_add`].
+    ///
+    /// NOTE: does not consider leap seconds!
+    pub fn to_chrono_at_datetime<Tz: TimeZone>(&self, at: DateTime<Tz>) -> chrono::Duration {
+        self.checked_add(&at)
+            .map_or(TimeDelta::MAX, |end| end.signed_duration_since(at))
+    }
+}
+
+/// Maps a local time onto the time zone, see [`Duration::checked_add`]
+pub(crate) fn resolve_local<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(datetime) => Some(datetime),
+        LocalResult::Ambiguous(earliest, _) => Some(earliest),
+        LocalResult::None => {
+            // the offset before the gap applies, which moves the time forward by the length of the gap
+            let before = tz
+                .offset_from_local_datetime(&local.checked_sub_days(Days::new(1))?)
+                .earliest()?;
+            Some(tz.from_utc_datetime(&(local - before.fix())))
+        }
+    }
+}
+
+impl PartialEq for Duration {
+    fn eq(&self, other: &Self) -> bool {
+        self.totals() == other.totals()
+    }
+}
+
+impl Eq for Duration {}
+
+impl Hash for Duration {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.totals().hash(state)
+    }
+}
+
+impl PartialOrd for Duration {
+    /// A duration is shorter than another if none of its months, days, and seconds are more than the other's.
+    /// Then, adding it to any point in time results in an earlier or equal point in time.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let (months, days, seconds) = self.totals();
+        let (other_months, other_days, other_seconds) = other.totals();
+
+        let orderings = [
+            months.cmp(&other_months),
+            days.cmp(&other_days),
+            seconds.cmp(&other_seconds),
+        ];
+        let comparable = orderings.iter().all(|ordering| ordering.is_le())
+            || orderings.iter().all(|ordering| ordering.is_ge());
+        comparable.then(|| orderings.into_iter().fold(Ordering::Equal, Ordering::then))
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseDurationError {
+    #[error("duration must start with 'P'")]
+    MissingPrefix,
+    #[error("duration must contain at least one component")]
+    Empty,
+    #[error("'T' must be followed by at least one of the hours, minutes, or seconds")]
+    EmptyTime,
+    #[error("value without designator")]
+    MissingDesignator,
+    #[error("unexpected character '{0}'")]
+    UnexpectedCharacter(char),
+    #[error("only the last component may have a fraction, which must convert exactly into the lower components")]
+    Fraction,
+    #[error("value out of range")]
+    OutOfRange,
+}
+
+/// The fractional part of a component, i.e., `numerator / 10^digits`
+#[derive(Debug, Clone, Copy)]
+struct Fraction {
+    /// The index of the component in the order years, months, weeks, days, hours, minutes, seconds
+    position: usize,
+    numerator: u64,
+    digits: u32,
+}
+
+/// Parses the fractional part of the component that ends `part`, e.g., `5H` after `PT1.`.
+fn parse_fraction(part: &str) -> Result<(u64, u32, char), ParseDurationError> {
+    let end = part
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or(ParseDurationError::MissingDesignator)?;
+    let mut designator = part[end..].chars();
+    let (Some(designator), "") = (designator.next(), designator.as_str()) else {
+        return Err(ParseDurationError::Fraction);
+    };
+
+    let digits = part[..end].trim_end_matches('0');
+    if end == 0 || digits.len() > 9 {
+        return Err(ParseDurationError::Fraction);
+    }
+    let numerator = if digits.is_empty() {
+        0
+    } else {
+        digits.parse().map_err(|_| ParseDurationError::Fraction)?
+    };
+
+    Ok((numerator, digits.len() as u32, designator))
+}
+
+/// Parses the components of the date or time part, which must occur in the order of `designators`.
+/// Returns the number of components found and the fraction of the last one, if any.
+fn parse_components(
+    mut part: &str,
+    designators: &mut [(char, &mut u32)],
+) -> Result<(usize, Option<Fraction>), ParseDurationError> {
+    let mut next = 0;
+    let mut count = 0;
+    let mut fraction = None;
+
+    while !part.is_empty() {
+        let digits = part
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or(ParseDurationError::MissingDesignator)?;
+        let mut designator = part[digits..].chars().next().unwrap_or_default();
+        let mut length = digits + designator.len_utf8();
+        if matches!(designator, '.' | ',') {
+            if digits == 0 {
+                return Err(ParseDurationError::Fraction);
+            }
+            let (numerator, fraction_digits, fraction_designator) =
+                parse_fraction(&part[length..])?;
+            designator = fraction_designator;
+            length = part.len();
+            fraction = Some(Fraction {
+                position: 0,
+                numerator,
+                digits: fraction_digits,
+            });
+        }
+        if digits == 0 {
+            return Err(ParseDurationError::UnexpectedCharacter(designator));
+        }
+
+        let position = designators[next..]
+            .iter()
+            .position(|(d, _)| *d == designator)
+            .ok_or(ParseDurationError::UnexpectedCharacter(designator))?
+            + next;
+        *designators[position].1 = part[..digits]
+            .parse()
+            .map_err(|_| ParseDurationError::OutOfRange)?;
+        if let Some(fraction) = &mut fraction {
+            fraction.position = position;
+        }
+
+        next = position + 1;
+        count += 1;
+        part = &part[length..];
+    }
+
+    Ok((count, fraction))
+}
+
+impl FromStr for Duration {
+    type Err = ParseDurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .strip_prefix('P')
+            .ok_or(ParseDurationError::MissingPrefix)?;
+        let (date, time) = match rest.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (rest, None),
+        };
+
+        let mut duration = Self::ZERO;
+        let (date_components, date_fraction) = parse_components(
+            date,
+            &mut [
+                ('Y', &mut duration.years),
+                ('M', &mut duration.months),
+                ('W', &mut duration.weeks),
+                ('D', &mut duration.days),
+            ],
+        )?;
+        let (time_components, time_fraction) = match time {
+            Some(time) => match parse_components(
+                time,
+                &mut [
+                    ('H', &mut duration.hours),
+                    ('M', &mut duration.minutes),
+                    ('S', &mut duration.seconds),
+                ],
+            )? {
+                (0, _) => return Err(ParseDurationError::EmptyTime),
+                (count, fraction) => (
+                    count,
+                    fraction.map(|f| Fraction {
+                        position: f.position + 4,
+                        ..f
+                    }),
+                ),
+            },
+            None => (0, None),
+        };
+
+        if date_components + time_components == 0 {
+            return Err(ParseDurationError::Empty);
+        }
+
+        match (date_fraction, time_components, time_fraction) {
+            (Some(_), 1.., _) => return Err(ParseDurationError::Fraction),
+            (Some(fraction), _, _) | (_, _, Some(fraction)) => duration.carry(fraction)?,
+            (None, _, None) => {}
+        }
+
+        Ok(duration)
+    }
+}
+
+impl Duration {
+    /// Adds a fraction of a component to the lower components, e.g., `PT1.5H` as `PT1H30M`.
+    /// Months are not converted to days, so the fraction must amount to whole months or seconds.
+    fn carry(&mut self, fraction: Fraction) -> Result<(), ParseDurationError> {
+        // converts each component to the next lower one, if that is exact
+        const FACTORS: [Option<u64>; 6] = [Some(12), None, Some(7), Some(24), Some(60), Some(60)];
+
+        let denominator = 10u64.pow(fraction.digits);
+        let mut numerator = fraction.numerator;
+        let mut components = [
+            &mut self.years,
+            &mut self.months,
+            &mut self.weeks,
+            &mut self.days,
+            &mut self.hours,
+            &mut self.minutes,
+            &mut self.seconds,
+        ];
+        let lower = FACTORS[fraction.position..]
+            .iter()
+            .map_while(|factor| *factor)
+            .zip(components[fraction.position + 1..].iter_mut());
+        for (factor, component) in lower {
+            numerator *= factor;
+            **component = u32::try_from(numerator / denominator)
+                .ok()
+                .and_then(|whole| component.checked_add(whole))
+                .ok_or(ParseDurationError::OutOfRange)?;
+            numerator %= denominator;
+        }
+
+        match numerator {
+            0 => Ok(()),
+            _ => Err(ParseDurationError::Fraction),
+        }
+    }
+}
+
+impl Display for Duration {
+    /// The compact form without zero components, e.g., `PT1H` instead of `P0Y0M0DT1H0M0S`.
+    /// As ISO 8601 does not allow combining weeks with other components,
+    /// weeks are written as days unless they are the only component, e.g., `P1W1D` as `P8D`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return f.write_str("PT0S");
+        }
+        if *self == Self::weeks(self.weeks) {
+            return write!(f, "P{}W", self.weeks);
+        }
+
+        f.write_char('P')?;
+        let days = u64::from(self.weeks) * 7 + u64::from(self.days);
+        for (value, designator) in [
+            (self.years.into(), 'Y'),
+            (self.months.into(), 'M'),
+            (days, 'D'),
+        ] {
+            if value > 0 {
+                write!(f, "{value}{designator}")?;
+            }
+        }
+
+        if self.hours > 0 || self.minutes > 0 || self.seconds > 0 {
+            f.write_char('T')?;
+            for (value, designator) in [(self.hours, 'H'), (self.minutes, 'M'), (self.seconds, 'S')]
+            {
+                if value > 0 {
+                    write!(f, "{value}{designator}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(|err: ParseDurationError| {
+            serde::de::Error::invalid_value(Unexpected::Str(&raw), &err.to_string().as_str())
+        })
+    }
+}
+
+impl Serialize for Duration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.to_string().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, FixedOffset, NaiveDate, NaiveTime, Utc};
+    use quickcheck::{Arbitrary, Gen, TestResult};
+
+    impl Arbitrary for Duration {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let mut component = || u32::from(u16::arbitrary(g));
+            Self {
+                years: component(),
+                months: component(),
+                weeks: component(),
+                days: component(),
+                hours: component(),
+                minutes: component(),
+                seconds: component(),
+            }
+        }
+    }
+
+    /// Central European Time, switching to summer time from the last Sunday of March
+    /// until the last Sunday of October, at 01:00 UTC
+    #[derive(Clone, Copy, Debug)]
+    struct Cet;
+
+    const WINTER: i32 = 3600;
+    const SUMMER: i32 = 2 * 3600;
+
+    impl Cet {
+        fn last_sunday(year: i32, month: u32) -> NaiveDateTime {
+            let last = NaiveDate::from_ymd_opt(year, month, 31).unwrap();
+            let sunday = last - Days::new(last.weekday().num_days_from_sunday().into());
+            sunday.and_time(NaiveTime::from_hms_opt(1, 0, 0).unwrap())
+        }
+    }
+
+    impl TimeZone for Cet {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Cet
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            // the earlier point in time first
+            let candidates = [SUMMER, WINTER]
+                .map(|secs| FixedOffset::east_opt(secs).unwrap())
+                .into_iter()
+                .filter(|offset| self.offset_from_utc_datetime(&(*local - *offset)) == *offset)
+                .collect::<Vec<_>>();
+            match candidates[..] {
+                [offset] => LocalResult::Single(offset),
+                [earlier, later] => LocalResult::Ambiguous(earlier, later),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let summer = Self::last_sunday(utc.year(), 3) <= *utc
+                && *utc < Self::last_sunday(utc.year(), 10);
+            FixedOffset::east_opt(if summer { SUMMER } else { WINTER }).unwrap()
+        }
+    }
+
+    fn cet(datetime: &str) -> DateTime<Cet> {
+        Cet.from_local_datetime(&datetime.parse().unwrap())
+            .earliest()
+            .unwrap()
+    }
+
+    fn duration(s: &str) -> Duration {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        assert_eq!(Duration::PT1H.to_string(), "PT1H");
+        assert_eq!(Duration::PT0S.to_string(), "PT0S");
+        assert_eq!(Duration::P9999Y.to_string(), "P9999Y");
+        assert_eq!(duration("P0Y0M0DT1H0M0S").to_string(), "PT1H");
+        assert_eq!(duration("P2W").to_string(), "P2W");
+        assert_eq!(duration("PT0M").to_string(), "PT0S");
+
+        let all = duration("P1Y2M3W4DT5H6M7S");
+        assert_eq!((all.years, all.months, all.weeks, all.days), (1, 2, 3, 4));
+        assert_eq!((all.hours, all.minutes, all.seconds), (5, 6, 7));
+        assert_eq!(all.to_string(), "P1Y2M25DT5H6M7S");
+        assert_eq!(duration("P1W1D").to_string(), "P8D");
+        assert_eq!(duration("P1WT1H").to_string(), "P7DT1H");
+
+        assert_eq!(duration("PT90M").normalized().to_string(), "PT1H30M");
+        assert_eq!(duration("P14M").normalized().to_string(), "P1Y2M");
+    }
+
+    #[test]
+    fn parse_errors() {
+        use ParseDurationError::*;
+
+        let error = |s: &str| s.parse::<Duration>().unwrap_err();
+        assert_eq!(error("1H"), MissingPrefix);
+        assert_eq!(error("P"), Empty);
+        assert_eq!(error("PT"), EmptyTime);
+        assert_eq!(error("P1DT"), EmptyTime);
+        assert_eq!(error("PT1"), MissingDesignator);
+        assert_eq!(error("PT1.5S"), Fraction);
+        assert_eq!(error("P1.5M"), Fraction);
+        assert_eq!(error("P1.5DT1H"), Fraction);
+        assert_eq!(error("PT1.5H30M"), Fraction);
+        assert_eq!(error("PT.5H"), Fraction);
+        assert_eq!(error("PT1.H"), Fraction);
+        assert_eq!(error("PT0.0000000001H"), Fraction);
+        assert_eq!(error("P1H"), UnexpectedCharacter('H'));
+        assert_eq!(error("P1D1Y"), UnexpectedCharacter('Y'));
+        assert_eq!(error("PT1H1H"), UnexpectedCharacter('H'));
+        assert_eq!(error("P-1D"), UnexpectedCharacter('-'));
+        assert_eq!(error("PT99999999999S"), OutOfRange);
+
+        assert!(serde_json::from_str::<Duration>(r#""PT1.5S""#).is_err());
+    }
+
+    #[test]
+    fn fractions_as_lower_components() {
+        let components = |d: Duration| {
+            (
+                d.years, d.months, d.weeks, d.days, d.hours, d.minutes, d.seconds,
+            )
+        };
+        assert_eq!(components(duration("PT1.5H")), (0, 0, 0, 0, 1, 30, 0));
+        assert_eq!(components(duration("PT0,5H")), (0, 0, 0, 0, 0, 30, 0));
+        assert_eq!(components(duration("PT1.25M")), (0, 0, 0, 0, 0, 1, 15));
+        assert_eq!(components(duration("PT0.0025H")), (0, 0, 0, 0, 0, 0, 9));
+        assert_eq!(components(duration("PT2.50H")), (0, 0, 0, 0, 2, 30, 0));
+        assert_eq!(components(duration("P0.5Y")), (0, 6, 0, 0, 0, 0, 0));
+        assert_eq!(components(duration("P1.5D")), (0, 0, 0, 1, 12, 0, 0));
+        assert_eq!(components(duration("P0.5W")), (0, 0, 0, 3, 12, 0, 0));
+        assert_eq!(components(duration("P1DT0.5H")), (0, 0, 0, 1, 0, 30, 0));
+        assert_eq!(duration("PT1.5H").to_string(), "PT1H30M");
+
+        assert_eq!(
+            serde_json::from_str::<Duration>(r#""PT0.5H""#).unwrap(),
+            Duration::minutes(30)
+        );
+    }
+
+    #[test]
+    fn calendar_equality_and_ordering() {
+        assert_eq!(duration("PT60M"), Duration::PT1H);
+        assert_eq!(duration("P12M"), duration("P1Y"));
+        assert_eq!(duration("P1W"), duration("P7D"));
+        assert_ne!(duration("P1D"), duration("PT24H"));
+
+        assert!(Duration::PT1H < duration("PT2H"));
+        assert!(duration("P1D") < duration("P1DT1S"));
+        assert!(duration("P1Y") > duration("P11M"));
+        assert_eq!(duration("P1M").partial_cmp(&duration("P30D")), None);
+        assert_eq!(duration("P1D").partial_cmp(&duration("PT24H")), None);
+    }
+
+    #[test]
+    fn add_months_at_end_of_month() {
+        let at = "2024-01-31T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            duration("P1M").checked_add(&at).unwrap().to_rfc3339(),
+            "2024-02-29T12:00:00+00:00"
+        );
+        assert_eq!(
+            duration("P1Y1M").checked_add(&at).unwrap().to_rfc3339(),
+            "2025-02-28T12:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn add_across_daylight_saving_time() {
+        // the clocks are set forward from 02:00 to 03:00 on 2024-03-31
+        let at = cet("2024-03-30T12:00:00");
+        let day = duration("P1D").checked_add(&at).unwrap();
+        assert_eq!(day.to_rfc3339(), "2024-03-31T12:00:00+02:00");
+        assert_eq!(day - at, TimeDelta::hours(23));
+        assert_eq!(
+            duration("PT24H").checked_add(&at).unwrap().to_rfc3339(),
+            "2024-03-31T13:00:00+02:00"
+        );
+        assert_eq!(
+            duration("P1D").to_chrono_at_datetime(at),
+            TimeDelta::hours(23)
+        );
+
+        // 02:30 does not exist on that day
+        assert_eq!(
+            duration("P1D")
+                .checked_add(&cet("2024-03-30T02:30:00"))
+                .unwrap()
+                .to_rfc3339(),
+            "2024-03-31T03:30:00+02:00"
+        );
+
+        // the clocks are set back from 03:00 to 02:00 on 2024-10-27, such that 02:30 occurs twice
+        let at = cet("2024-10-26T02:30:00");
+        let day = duration("P1D").checked_add(&at).unwrap();
+        assert_eq!(day.to_rfc3339(), "2024-10-27T02:30:00+02:00");
+        assert_eq!(
+            duration("PT1H").checked_add(&day).unwrap().to_rfc3339(),
+            "2024-10-27T02:30:00+01:00"
+        );
+        assert_eq!(
+            duration("P1W")
+                .checked_add(&cet("2024-10-21T12:00:00"))
+                .unwrap()
+                - cet("2024-10-21T12:00:00"),
+            TimeDelta::hours(7 * 24 + 1)
+        );
+    }
+
+    #[test]
+    fn infinite() {
+        assert!(Duration::P9999Y.is_infinite());
+        assert!(duration("P9999Y").is_infinite());
+        assert!(!duration("P9998Y").is_infinite());
+        assert_eq!(Duration::P9999Y.checked_add(&Utc::now()), None);
+        assert_eq!(
+            Duration::P9999Y.to_chrono_at_datetime(Utc::now()),
+            TimeDelta::MAX
+        );
+    }
+
+    #[test]
+    fn to_string_from_str_roundtrip() {
+        fn test(input: Duration) -> bool {
+            let roundtrip = input.to_string().parse::<Duration>().unwrap();
+            let serde_roundtrip =
+                serde_json::from_str::<Duration>(&serde_json::to_string(&input).unwrap()).unwrap();
+
+            // weeks may come back as days
+            roundtrip == input
+                && (roundtrip.years, roundtrip.months) == (input.years, input.months)
+                && (roundtrip.hours, roundtrip.minutes, roundtrip.seconds)
+                    == (input.hours, input.minutes, input.seconds)
+                && serde_roundtrip == input
+        }
+
+        quickcheck::quickcheck(test as fn(_) -> bool);
+    }
+
+    #[test]
+    fn compact_format() {
+        fn test(input: Duration) -> bool {
+            let formatted = input.to_string();
+            // no zero components, e.g., `0M`
+            let compact = formatted
+                .split(|c: char| c.is_ascii_alphabetic())
+                .all(|value| value != "0");
+            (input.is_zero() && formatted == "PT0S") || compact
+        }
+
+        quickcheck::quickcheck(test as fn(_) -> bool);
+    }
+
+    #[test]
+    fn normalized_is_equal() {
+        fn test(input: Duration) -> bool {
+            input.normalized() == input
+        }
+
+        quickcheck::quickcheck(test as fn(_) -> bool);
+    }
+
+    #[test]
+    fn longer_durations_end_later() {
+        fn test(shorter: Duration, extra: Duration, at: u32) -> TestResult {
+            let longer = Duration {
+                years: shorter.years + extra.years,
+                months: shorter.months + extra.months,
+                weeks: shorter.weeks + extra.weeks,
+                days: shorter.days + extra.days,
+                hours: shorter.hours + extra.hours,
+                minutes: shorter.minutes + extra.minutes,
+                seconds: shorter.seconds + extra.seconds,
+            };
+            let at = Cet.timestamp_opt(at.into(), 0).unwrap();
+            let (Some(shorter_end), Some(longer_end)) =
+                (shorter.checked_add(&at), longer.checked_add(&at))
+            else {
+                return TestResult::discard();
+            };
+
+            TestResult::from_bool(
+                shorter <= longer && shorter_end <= longer_end && at <= shorter_end,
+            )
+        }
+
+        quickcheck::quickcheck(test as fn(_, _, _) -> TestResult);
+    }
+
+    #[test]
+    fn time_components_are_elapsed_time() {
+        fn test(hours: u16, minutes: u16, seconds: u16, at: u32) -> bool {
+            let duration = Duration {
+                hours: hours.into(),
+                minutes: minutes.into(),
+                seconds: seconds.into(),
+                ..Default::default()
+            };
+            let at = Cet.timestamp_opt(at.into(), 0).unwrap();
+            let elapsed = i64::from(hours) * 3600 + i64::from(minutes) * 60 + i64::from(seconds);
+
+            duration.to_chrono_at_datetime(at) == TimeDelta::seconds(elapsed)
+        }
+
+        quickcheck::quickcheck(test as fn(_, _, _, _) -> bool);
+    }
+}
@@ -0,0 +1,71 @@
+//! Types used for the enrollment endpoints
+//!
+//! A VEN is enrolled in a program via `/programs/{programID}/vens`.
+//! Once at least one VEN is enrolled in a program,
+//! only the enrolled VENs can see the program and its events.
+//! The programs a VEN is enrolled in are listed at `/vens/{venID}/programs`.
+//! These endpoints are an extension of this implementation and not part of the OpenADR 3.0 specification.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{program::ProgramId, ven::VenId};
+
+/// The enrollment of a VEN in a program
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Enrollment {
+    /// URL safe VTN assigned object ID of the program.
+    #[serde(rename = "programID")]
+    pub program_id: ProgramId,
+    /// Short name to uniquely identify the program.
+    pub program_name: String,
+    /// URL safe VTN assigned object ID of the VEN.
+    #[serde(rename = "venID")]
+    pub ven_id: VenId,
+    /// User generated identifier of the VEN.
+    pub ven_name: String,
+    /// datetime in ISO 8601 format
+    #[serde(with = "crate::serde_rfc3339")]
+    pub created_date_time: DateTime<Utc>,
+}
+
+/// Request to enroll a VEN in a program
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Validate)]
+pub struct NewEnrollment {
+    /// URL safe VTN assigned object ID of the VEN to enroll.
+    #[serde(rename = "venID")]
+    pub ven_id: VenId,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde() {
+        let example = r#"{"programID":"program-1","programName":"program-1-name","venID":"ven-1","venName":"ven-1-name","createdDateTime":"2024-07-25T08:31:10.776+00:00"}"#;
+
+        let expected = Enrollment {
+            program_id: "program-1".parse().unwrap(),
+            program_name: "program-1-name".to_string(),
+            ven_id: "ven-1".parse().unwrap(),
+            ven_name: "ven-1-name".to_string(),
+            created_date_time: "2024-07-25T08:31:10.776Z".parse().unwrap(),
+        };
+
+        assert_eq!(
+            serde_json::from_str::<Enrollment>(example).unwrap(),
+            expected
+        );
+        assert_eq!(serde_json::to_string(&expected).unwrap(), example);
+
+        assert_eq!(
+            serde_json::from_str::<NewEnrollment>(r#"{"venID":"ven-1"}"#).unwrap(),
+            NewEnrollment {
+                ven_id: "ven-1".parse().unwrap()
+            }
+        );
+    }
+}
@@ -1,8 +1,15 @@
 //! Types used for the `event/` endpoint
+//!
+//! The [`state`](Event::state) and [`superseded_by`](Event::superseded_by) of an event
+//! are an extension of this implementation and not part of the OpenADR 3.0 specification.
 
 use crate::{
-    interval::IntervalPeriod, program::ProgramId, report::ReportDescriptor, target::TargetMap,
-    values_map::Value, Identifier, IdentifierError, Unit,
+    interval::{interval_spans, IntervalPeriod},
+    program::{ProgramId, ProgramTimeZone},
+    report::ReportDescriptor,
+    target::TargetMap,
+    values_map::Value,
+    Identifier, IdentifierError, Unit,
 };
 use chrono::{DateTime, Utc};
 use iso_currency::Currency;
@@ -27,11 +34,41 @@ pub struct Event {
     /// datetime in ISO 8601 format
     #[serde(with = "crate::serde_rfc3339")]
     pub modification_date_time: DateTime<Utc>,
+    /// Lifecycle state of the event, assigned by the VTN.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<EventState>,
+    /// The event replacing this one if it is [`EventState::Superseded`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<EventId>,
     #[serde(flatten)]
     #[validate(nested)]
     pub content: EventContent,
 }
 
+/// Lifecycle state of an [`Event`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EventState {
+    /// The first interval did not start yet
+    Scheduled,
+    /// An interval started and the last interval did not end yet,
+    /// or the intervals have no start
+    Active,
+    /// The last interval ended
+    Completed,
+    /// The event got cancelled and must be disregarded
+    Cancelled,
+    /// The event got cancelled and replaced by the event [`Event::superseded_by`]
+    Superseded,
+}
+
+impl EventState {
+    /// Whether the event got cancelled, possibly replaced by another event
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Self::Cancelled | Self::Superseded)
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
 #[serde(rename_all = "camelCase", tag = "objectType", rename = "EVENT")]
@@ -106,6 +143,42 @@ impl EventContent {
         self.intervals = intervals;
         self
     }
+
+    /// Start of the first and end of the last interval,
+    /// where the durations are evaluated in the given time zone of the program.
+    ///
+    /// Returns `None` if no interval has a start.
+    /// The end is `None` if an interval lasts indefinitely.
+    pub fn time_span(
+        &self,
+        time_zone: &ProgramTimeZone,
+    ) -> Option<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+        self.interval_spans(time_zone)
+            .into_iter()
+            .map(|(_, start, end)| (start, end))
+            .reduce(|(first, last), (start, end)| {
+                (first.min(start), last.zip(end).map(|(a, b)| a.max(b)))
+            })
+    }
+
+    /// The id, start, and end of each interval that has a start,
+    /// where the durations are evaluated in the given time zone of the program.
+    ///
+    /// Intervals without their own period follow the previous one, using the duration of the event's
+    /// [`interval_period`](Self::interval_period).
+    /// The end is `None` if the interval lasts indefinitely.
+    pub fn interval_spans(
+        &self,
+        time_zone: &ProgramTimeZone,
+    ) -> Vec<(i32, DateTime<Utc>, Option<DateTime<Utc>>)> {
+        interval_spans(
+            self.interval_period.as_ref(),
+            self.intervals
+                .iter()
+                .map(|interval| (interval.id, interval.interval_period.as_ref())),
+            time_zone,
+        )
+    }
 }
 
 /// URL safe VTN assigned object ID
@@ -449,6 +522,8 @@ mod tests {
             id: EventId("object-999-foo".parse().unwrap()),
             created_date_time: "2023-06-15T09:30:00Z".parse().unwrap(),
             modification_date_time: "2023-06-15T09:30:00Z".parse().unwrap(),
+            state: None,
+            superseded_by: None,
             content: EventContent {
                 program_id: ProgramId("object-999".parse().unwrap()),
                 event_name: Some("price event 11-18-2022".into()),
@@ -482,6 +557,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn state() {
+        let event = r#"{
+            "id": "event-2",
+            "createdDateTime": "2023-06-15T09:30:00Z",
+            "modificationDateTime": "2023-06-15T09:30:00Z",
+            "state": "SUPERSEDED",
+            "supersededBy": "event-3",
+            "objectType": "EVENT",
+            "programID": "program-1",
+            "intervals": []
+        }"#;
+
+        let event: Event = serde_json::from_str(event).unwrap();
+        assert_eq!(event.state, Some(EventState::Superseded));
+        assert!(event.state.unwrap().is_cancelled());
+        assert_eq!(event.superseded_by, Some("event-3".parse().unwrap()));
+
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["state"], "SUPERSEDED");
+        assert_eq!(value["supersededBy"], "event-3");
+    }
+
+    #[test]
+    fn time_span() {
+        let utc = ProgramTimeZone::default();
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let interval = |id, interval_period| EventInterval {
+            id,
+            interval_period,
+            payloads: vec![],
+        };
+
+        // intervals without a period follow each other
+        let event = EventContent::new(
+            "program-1".parse().unwrap(),
+            vec![interval(0, None), interval(1, None)],
+        )
+        .with_interval_period(IntervalPeriod {
+            start: at("2024-03-01T10:00:00Z"),
+            duration: Some(Duration::PT1H),
+            randomize_start: None,
+        });
+        assert_eq!(
+            event.time_span(&utc),
+            Some((at("2024-03-01T10:00:00Z"), Some(at("2024-03-01T12:00:00Z"))))
+        );
+        assert_eq!(
+            event.interval_spans(&utc),
+            vec![
+                (
+                    0,
+                    at("2024-03-01T10:00:00Z"),
+                    Some(at("2024-03-01T11:00:00Z"))
+                ),
+                (
+                    1,
+                    at("2024-03-01T11:00:00Z"),
+                    Some(at("2024-03-01T12:00:00Z"))
+                ),
+            ]
+        );
+
+        // an own period takes precedence
+        let event = event.with_intervals(vec![
+            interval(0, None),
+            interval(
+                1,
+                Some(IntervalPeriod {
+                    start: at("2024-03-01T08:00:00Z"),
+                    duration: Some(Duration::PT1H),
+                    randomize_start: None,
+                }),
+            ),
+        ]);
+        assert_eq!(
+            event.time_span(&utc),
+            Some((at("2024-03-01T08:00:00Z"), Some(at("2024-03-01T11:00:00Z"))))
+        );
+
+        // no duration lasts indefinitely
+        let mut indefinite = event.clone();
+        indefinite.interval_period.as_mut().unwrap().duration = None;
+        assert_eq!(
+            indefinite.time_span(&utc),
+            Some((at("2024-03-01T08:00:00Z"), None))
+        );
+
+        // days are evaluated in the time zone, here across the start of daylight saving time
+        let amsterdam = ProgramTimeZone::Named(chrono_tz::Europe::Amsterdam);
+        let daily = EventContent::new("program-1".parse().unwrap(), vec![interval(0, None)])
+            .with_interval_period(IntervalPeriod {
+                start: at("2024-03-30T11:00:00Z"),
+                duration: Some("P1D".parse().unwrap()),
+                randomize_start: None,
+            });
+        assert_eq!(
+            daily.time_span(&amsterdam),
+            Some((at("2024-03-30T11:00:00Z"), Some(at("2024-03-31T10:00:00Z"))))
+        );
+
+        let no_start = EventContent::new("program-1".parse().unwrap(), vec![interval(0, None)]);
+        assert_eq!(no_start.time_span(&utc), None);
+    }
+
     #[test]
     fn test_currency() {
         // deserialize
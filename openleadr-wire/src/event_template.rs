@@ -0,0 +1,439 @@
+//! Types used for the `/event-templates` endpoints
+//!
+//! An event template describes an event that recurs on a schedule,
+//! e.g., the same time-of-use prices every weekday.
+//! The VTN creates the events of the upcoming occurrences ahead of time.
+//! These endpoints are an extension of this implementation and not part of the OpenADR 3.0 specification.
+
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    event::{EventContent, EventInterval, EventPayloadDescriptor, Priority},
+    interval::IntervalPeriod,
+    program::{ProgramId, ProgramTimeZone},
+    report::ReportDescriptor,
+    target::TargetMap,
+    Duration, Identifier, IdentifierError,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct EventTemplate {
+    /// URL safe VTN assigned object ID.
+    pub id: EventTemplateId,
+    /// datetime in ISO 8601 format
+    #[serde(with = "crate::serde_rfc3339")]
+    pub created_date_time: DateTime<Utc>,
+    /// datetime in ISO 8601 format
+    #[serde(with = "crate::serde_rfc3339")]
+    pub modification_date_time: DateTime<Utc>,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub content: EventTemplateContent,
+}
+
+/// The schedule and the content of the events created from a template.
+///
+/// Each event gets an `intervalPeriod` starting at its occurrence,
+/// with the [`interval_duration`](Self::interval_duration) and [`randomize_start`](Self::randomize_start)
+/// of the template. Therefore, the intervals must not have their own `intervalPeriod`.
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct EventTemplateContent {
+    /// URL safe VTN assigned object ID of the program the events belong to.
+    #[serde(rename = "programID")]
+    pub program_id: ProgramId,
+    /// User defined string for use in debugging or User Interface.
+    pub template_name: Option<String>,
+    #[validate(custom(function = "validate_recurrence"))]
+    pub recurrence: Recurrence,
+    /// The duration of each interval of the events.
+    #[validate(custom(function = "validate_interval_duration"))]
+    pub interval_duration: Duration,
+    /// Indicates a randomization time that may be applied to the start of the events.
+    pub randomize_start: Option<Duration>,
+    /// User defined string of the events for use in debugging or User Interface.
+    pub event_name: Option<String>,
+    /// Relative priority of the events. A lower number is a higher priority.
+    pub priority: Priority,
+    /// A list of valuesMap objects.
+    pub targets: Option<TargetMap>,
+    /// A list of reportDescriptor objects. Used to request reports from VEN.
+    pub report_descriptors: Option<Vec<ReportDescriptor>>,
+    /// A list of payloadDescriptor objects.
+    pub payload_descriptors: Option<Vec<EventPayloadDescriptor>>,
+    /// A list of interval objects, following each other.
+    #[validate(
+        length(min = 1),
+        nested,
+        custom(function = "validate_template_intervals")
+    )]
+    pub intervals: Vec<EventInterval>,
+}
+
+impl EventTemplateContent {
+    /// The starts of the occurrences within `from..until`,
+    /// where the start time of the [`Recurrence`] is evaluated in the given time zone of the program.
+    pub fn occurrences(
+        &self,
+        time_zone: &ProgramTimeZone,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Vec<DateTime<Utc>> {
+        // the local date can differ by up to a day from the date in UTC
+        let first = from
+            .date_naive()
+            .pred_opt()
+            .unwrap_or(NaiveDate::MIN)
+            .max(self.recurrence.start_date);
+        let last = until.date_naive().succ_opt().unwrap_or(NaiveDate::MAX);
+
+        first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .filter(|date| self.recurrence.occurs_on(*date))
+            .filter_map(|date| time_zone.from_local(&date.and_time(self.recurrence.start_time)))
+            .filter(|start| from <= *start && *start < until)
+            .collect()
+    }
+
+    /// The event of the occurrence starting at `start`
+    pub fn event_at(&self, start: DateTime<Utc>) -> EventContent {
+        EventContent {
+            program_id: self.program_id.clone(),
+            event_name: self.event_name.clone(),
+            priority: self.priority,
+            targets: self.targets.clone(),
+            report_descriptors: self.report_descriptors.clone(),
+            payload_descriptors: self.payload_descriptors.clone(),
+            interval_period: Some(IntervalPeriod {
+                start,
+                duration: Some(self.interval_duration),
+                randomize_start: self.randomize_start,
+            }),
+            intervals: self.intervals.clone(),
+        }
+    }
+}
+
+/// When the events of a template occur, similar to a `RRULE` of iCalendar (RFC 5545)
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// The days of the week the events occur on, required for [`Frequency::Weekly`] only.
+    pub by_day: Option<Vec<Weekday>>,
+    /// Local time of day the events start, in the time zone of the program.
+    pub start_time: NaiveTime,
+    /// The first day the events may occur on.
+    pub start_date: NaiveDate,
+    /// The last day the events may occur on. The events recur indefinitely if absent.
+    pub until: Option<NaiveDate>,
+    /// Days the events do not occur on, e.g., public holidays.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exceptions: Vec<NaiveDate>,
+}
+
+impl Recurrence {
+    /// Whether an event occurs on the given local date
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        if date < self.start_date
+            || self.until.is_some_and(|until| date > until)
+            || self.exceptions.contains(&date)
+        {
+            return false;
+        }
+
+        match self.frequency {
+            Frequency::Daily => true,
+            Frequency::Weekdays => date.weekday().number_from_monday() <= 5,
+            Frequency::Weekly => self
+                .by_day
+                .iter()
+                .flatten()
+                .any(|day| chrono::Weekday::from(*day) == date.weekday()),
+        }
+    }
+}
+
+fn validate_recurrence(recurrence: &Recurrence) -> Result<(), ValidationError> {
+    let error = |message: &'static str| {
+        Err(ValidationError::new("recurrence").with_message(message.into()))
+    };
+
+    match (&recurrence.frequency, &recurrence.by_day) {
+        (Frequency::Weekly, Some(by_day)) if !by_day.is_empty() => {}
+        (Frequency::Weekly, _) => return error("a weekly recurrence requires byDay"),
+        (_, Some(_)) => return error("byDay is only allowed for a weekly recurrence"),
+        (_, None) => {}
+    }
+    if recurrence
+        .until
+        .is_some_and(|until| until < recurrence.start_date)
+    {
+        return error("until must not be before startDate");
+    }
+
+    Ok(())
+}
+
+fn validate_interval_duration(duration: &Duration) -> Result<(), ValidationError> {
+    if duration.is_zero() || duration.is_infinite() {
+        return Err(ValidationError::new("interval_duration")
+            .with_message("the interval duration must be finite and greater than zero".into()));
+    }
+    Ok(())
+}
+
+fn validate_template_intervals(intervals: &[EventInterval]) -> Result<(), ValidationError> {
+    if intervals
+        .iter()
+        .any(|interval| interval.interval_period.is_some())
+    {
+        return Err(ValidationError::new("intervals").with_message(
+            "the intervals of a template must not have an intervalPeriod, it is set per occurrence"
+                .into(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Frequency {
+    /// Every day
+    Daily,
+    /// Monday to Friday
+    Weekdays,
+    /// The days of the week listed in [`Recurrence::by_day`]
+    Weekly,
+}
+
+/// Day of the week, abbreviated like in iCalendar (RFC 5545)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weekday {
+    #[serde(rename = "MO")]
+    Monday,
+    #[serde(rename = "TU")]
+    Tuesday,
+    #[serde(rename = "WE")]
+    Wednesday,
+    #[serde(rename = "TH")]
+    Thursday,
+    #[serde(rename = "FR")]
+    Friday,
+    #[serde(rename = "SA")]
+    Saturday,
+    #[serde(rename = "SU")]
+    Sunday,
+}
+
+impl From<Weekday> for chrono::Weekday {
+    fn from(value: Weekday) -> Self {
+        match value {
+            Weekday::Monday => Self::Mon,
+            Weekday::Tuesday => Self::Tue,
+            Weekday::Wednesday => Self::Wed,
+            Weekday::Thursday => Self::Thu,
+            Weekday::Friday => Self::Fri,
+            Weekday::Saturday => Self::Sat,
+            Weekday::Sunday => Self::Sun,
+        }
+    }
+}
+
+/// URL safe VTN assigned object ID
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Hash, Eq)]
+pub struct EventTemplateId(pub(crate) Identifier);
+
+impl Display for EventTemplateId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl EventTemplateId {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl FromStr for EventTemplateId {
+    type Err = IdentifierError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{event::EventValuesMap, values_map::Value};
+
+    fn template(recurrence: &str) -> EventTemplateContent {
+        serde_json::from_str(&format!(
+            r#"{{
+                "programID": "program-1",
+                "templateName": "time of use",
+                "recurrence": {recurrence},
+                "intervalDuration": "PT1H",
+                "intervals": [
+                    {{"id": 0, "payloads": [{{"type": "PRICE", "values": [0.25]}}]}},
+                    {{"id": 1, "payloads": [{{"type": "PRICE", "values": [0.31]}}]}}
+                ]
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn serde() {
+        let template = template(
+            r#"{"frequency": "WEEKLY", "byDay": ["MO", "TH"], "startTime": "17:00:00", "startDate": "2024-03-01", "exceptions": ["2024-03-28"]}"#,
+        );
+        assert_eq!(template.recurrence.frequency, Frequency::Weekly);
+        assert_eq!(
+            template.recurrence.by_day,
+            Some(vec![Weekday::Monday, Weekday::Thursday])
+        );
+        assert_eq!(
+            template.recurrence.start_time,
+            NaiveTime::from_hms_opt(17, 0, 0).unwrap()
+        );
+        assert_eq!(template.priority, Priority::UNSPECIFIED);
+        assert!(template.validate().is_ok());
+
+        let value = serde_json::to_value(&template).unwrap();
+        assert_eq!(value["recurrence"]["byDay"][1], "TH");
+        assert_eq!(value["recurrence"]["exceptions"][0], "2024-03-28");
+        assert_eq!(
+            serde_json::from_value::<EventTemplateContent>(value).unwrap(),
+            template
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let weekly_without_days = template(
+            r#"{"frequency": "WEEKLY", "startTime": "17:00:00", "startDate": "2024-03-01"}"#,
+        );
+        assert!(weekly_without_days.validate().is_err());
+
+        let daily_with_days = template(
+            r#"{"frequency": "DAILY", "byDay": ["MO"], "startTime": "17:00:00", "startDate": "2024-03-01"}"#,
+        );
+        assert!(daily_with_days.validate().is_err());
+
+        let until_before_start = template(
+            r#"{"frequency": "DAILY", "startTime": "17:00:00", "startDate": "2024-03-01", "until": "2024-02-01"}"#,
+        );
+        assert!(until_before_start.validate().is_err());
+
+        let mut zero_duration = template(
+            r#"{"frequency": "DAILY", "startTime": "17:00:00", "startDate": "2024-03-01"}"#,
+        );
+        zero_duration.interval_duration = Duration::PT0S;
+        assert!(zero_duration.validate().is_err());
+
+        let mut interval_period = template(
+            r#"{"frequency": "DAILY", "startTime": "17:00:00", "startDate": "2024-03-01"}"#,
+        );
+        interval_period.intervals[0].interval_period =
+            Some(IntervalPeriod::new(utc("2024-03-01T17:00:00Z")));
+        assert!(interval_period.validate().is_err());
+    }
+
+    #[test]
+    fn weekdays_with_exceptions() {
+        // 2024-03-29 is Good Friday
+        let template = template(
+            r#"{"frequency": "WEEKDAYS", "startTime": "07:00:00", "startDate": "2024-03-27", "until": "2024-04-02", "exceptions": ["2024-03-29", "2024-04-01"]}"#,
+        );
+
+        assert_eq!(
+            template.occurrences(
+                &ProgramTimeZone::default(),
+                utc("2024-03-01T00:00:00Z"),
+                utc("2024-05-01T00:00:00Z")
+            ),
+            vec![
+                utc("2024-03-27T07:00:00Z"),
+                utc("2024-03-28T07:00:00Z"),
+                utc("2024-04-02T07:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn occurrences_in_time_zone() {
+        let template = template(
+            r#"{"frequency": "DAILY", "startTime": "00:00:00", "startDate": "2024-03-01"}"#,
+        );
+        let amsterdam = ProgramTimeZone::Named(chrono_tz::Europe::Amsterdam);
+
+        // midnight in Amsterdam, before and after the clocks are set forward on 2024-03-31
+        assert_eq!(
+            template.occurrences(
+                &amsterdam,
+                utc("2024-03-30T00:00:00Z"),
+                utc("2024-04-01T23:00:00Z")
+            ),
+            vec![
+                utc("2024-03-30T23:00:00Z"),
+                utc("2024-03-31T22:00:00Z"),
+                utc("2024-04-01T22:00:00Z"),
+            ]
+        );
+
+        // `from` is inclusive, `until` exclusive
+        assert_eq!(
+            template.occurrences(
+                &amsterdam,
+                utc("2024-03-30T23:00:00Z"),
+                utc("2024-03-31T22:00:00Z")
+            ),
+            vec![utc("2024-03-30T23:00:00Z")]
+        );
+    }
+
+    #[test]
+    fn event_at() {
+        let template = template(
+            r#"{"frequency": "DAILY", "startTime": "17:00:00", "startDate": "2024-03-01"}"#,
+        );
+        let event = template.event_at(utc("2024-03-01T17:00:00Z"));
+
+        assert_eq!(event.program_id, template.program_id);
+        assert_eq!(
+            event.interval_period,
+            Some(IntervalPeriod {
+                start: utc("2024-03-01T17:00:00Z"),
+                duration: Some(Duration::PT1H),
+                randomize_start: None,
+            })
+        );
+        assert_eq!(
+            event.intervals[1].payloads,
+            vec![EventValuesMap {
+                value_type: crate::event::EventType::Price,
+                values: vec![Value::Number(0.31)],
+            }]
+        );
+        assert!(event.validate().is_ok());
+    }
+}
@@ -1,6 +1,6 @@
 //! Descriptions of temporal periods
 
-use crate::{values_map::ValuesMap, Duration};
+use crate::{program::ProgramTimeZone, values_map::ValuesMap, Duration};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -53,3 +53,39 @@ impl IntervalPeriod {
         }
     }
 }
+
+/// The id, start, and end of each interval that has a start,
+/// where the durations are evaluated in the given time zone of the program.
+///
+/// Intervals without their own period follow the previous one, using the duration of the `default_period`.
+/// The end is `None` if the interval lasts indefinitely.
+pub fn interval_spans<'a>(
+    default_period: Option<&IntervalPeriod>,
+    intervals: impl IntoIterator<Item = (i32, Option<&'a IntervalPeriod>)>,
+    time_zone: &ProgramTimeZone,
+) -> Vec<(i32, DateTime<Utc>, Option<DateTime<Utc>>)> {
+    let mut current_start = default_period.map(|period| period.start);
+    let mut spans = Vec::new();
+
+    for (id, own_period) in intervals {
+        let Some(period) = own_period.or(default_period) else {
+            continue;
+        };
+        let start = match own_period {
+            Some(period) => period.start,
+            None => match current_start {
+                Some(start) => start,
+                // follows an interval lasting indefinitely
+                None => continue,
+            },
+        };
+        let end = period
+            .duration
+            .and_then(|duration| time_zone.checked_add(&start, &duration));
+        current_start = end;
+
+        spans.push((id, start, end));
+    }
+
+    spans
+}
@@ -6,18 +6,26 @@
 
 use std::fmt::Display;
 
+pub use duration::Duration;
 pub use event::Event;
 pub use program::Program;
 pub use report::Report;
 use serde::{de::Unexpected, Deserialize, Deserializer, Serialize, Serializer};
 pub use ven::Ven;
 
+pub mod baseline;
+pub mod batch;
+pub mod duration;
+pub mod enrollment;
 pub mod event;
+pub mod event_template;
 pub mod interval;
 pub mod oauth;
+pub mod opt;
 pub mod problem;
 pub mod program;
 pub mod report;
+pub mod report_summary;
 pub mod resource;
 pub mod target;
 pub mod values_map;
@@ -80,10 +88,11 @@ impl<'de> Deserialize<'de> for Identifier {
     where
         D: Deserializer<'de>,
     {
-        let borrowed_str = <&str as Deserialize>::deserialize(deserializer)?;
+        // not borrowed, as borrowing fails for deserializers that do not own the input, e.g., `serde_json::Value`
+        let string = <String as Deserialize>::deserialize(deserializer)?;
 
-        borrowed_str.parse::<Identifier>().map_err(|e| {
-            serde::de::Error::invalid_value(Unexpected::Str(borrowed_str), &e.to_string().as_str())
+        string.parse::<Identifier>().map_err(|e| {
+            serde::de::Error::invalid_value(Unexpected::Str(&string), &e.to_string().as_str())
         })
     }
 }
@@ -130,116 +139,6 @@ impl Display for Identifier {
     }
 }
 
-/// An ISO 8601 formatted duration
-#[derive(Clone, Debug, PartialEq)]
-pub struct Duration(iso8601_duration::Duration);
-
-impl<'de> Deserialize<'de> for Duration {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let raw = String::deserialize(deserializer)?;
-        let duration = raw
-            .parse::<iso8601_duration::Duration>()
-            .map_err(|_| "iso8601_duration::ParseDurationError")
-            .map_err(serde::de::Error::custom)?;
-
-        Ok(Self(duration))
-    }
-}
-
-impl Serialize for Duration {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        self.to_string().serialize(serializer)
-    }
-}
-
-impl Duration {
-    /// Because iso8601 durations can include months and years, they don't independently have a
-    /// fixed duration. Their real duration (in real units like seconds) can only be determined
-    /// when a starting time is given.
-    ///
-    /// NOTE: does not consider leap seconds!
-    pub fn to_chrono_at_datetime<Tz: chrono::TimeZone>(
-        &self,
-        at: chrono::DateTime<Tz>,
-    ) -> chrono::Duration {
-        self.0.to_chrono_at_datetime(at)
-    }
-
-    /// One (1) hour
-    pub const PT1H: Self = Self(iso8601_duration::Duration {
-        year: 0.0,
-        month: 0.0,
-        day: 0.0,
-        hour: 1.0,
-        minute: 0.0,
-        second: 0.0,
-    });
-
-    /// Indicates that an event's intervals continue indefinitely into the future until the event is
-    /// deleted or modified. This effectively represents an infinite duration.
-    pub const P999Y: Self = Self(iso8601_duration::Duration {
-        year: 9999.0,
-        month: 0.0,
-        day: 0.0,
-        hour: 0.0,
-        minute: 0.0,
-        second: 0.0,
-    });
-
-    pub const PT0S: Self = Self(iso8601_duration::Duration {
-        year: 0.0,
-        month: 0.0,
-        day: 0.0,
-        hour: 0.0,
-        minute: 0.0,
-        second: 0.0,
-    });
-
-    pub const fn hours(hour: f32) -> Self {
-        Self(iso8601_duration::Duration {
-            year: 0.0,
-            month: 0.0,
-            day: 0.0,
-            hour,
-            minute: 0.0,
-            second: 0.0,
-        })
-    }
-}
-
-impl std::str::FromStr for Duration {
-    type Err = iso8601_duration::ParseDurationError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let duration = s.parse::<iso8601_duration::Duration>()?;
-        Ok(Self(duration))
-    }
-}
-
-impl Display for Duration {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let iso8601_duration::Duration {
-            year,
-            month,
-            day,
-            hour,
-            minute,
-            second,
-        } = self.0;
-
-        f.write_fmt(format_args!(
-            "P{}Y{}M{}DT{}H{}M{}S",
-            year, month, day, hour, minute, second
-        ))
-    }
-}
-
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OperatingState {
@@ -419,40 +318,16 @@ mod tests {
         );
     }
 
-    impl quickcheck::Arbitrary for super::Duration {
-        fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-            // the iso8601_duration library uses an f32 to store the values, which starts losing
-            // precision at 24-bit integers.
-            super::Duration(iso8601_duration::Duration {
-                year: (<u32 as quickcheck::Arbitrary>::arbitrary(g) & 0x00FF_FFFF) as f32,
-                month: (<u32 as quickcheck::Arbitrary>::arbitrary(g) & 0x00FF_FFFF) as f32,
-                day: (<u32 as quickcheck::Arbitrary>::arbitrary(g) & 0x00FF_FFFF) as f32,
-                hour: (<u32 as quickcheck::Arbitrary>::arbitrary(g) & 0x00FF_FFFF) as f32,
-                minute: (<u32 as quickcheck::Arbitrary>::arbitrary(g) & 0x00FF_FFFF) as f32,
-                second: (<u32 as quickcheck::Arbitrary>::arbitrary(g) & 0x00FF_FFFF) as f32,
-            })
-        }
-    }
-
-    #[test]
-    fn duration_to_string_from_str_roundtrip() {
-        quickcheck::quickcheck(test as fn(_) -> bool);
-
-        fn test(input: super::Duration) -> bool {
-            let roundtrip = input.to_string().parse::<super::Duration>().unwrap();
-
-            assert_eq!(input.0, roundtrip.0);
-
-            input.0 == roundtrip.0
-        }
-    }
-
     #[test]
     fn deserialize_identifier() {
         assert_eq!(
             serde_json::from_str::<Identifier>(r#""example-999""#).unwrap(),
             Identifier("example-999".to_string())
         );
+        assert_eq!(
+            serde_json::from_value::<Identifier>(serde_json::json!("example-999")).unwrap(),
+            Identifier("example-999".to_string())
+        );
         assert!(serde_json::from_str::<Identifier>(r#""þingvellir-999""#)
             .unwrap_err()
             .to_string()
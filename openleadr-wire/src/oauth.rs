@@ -1,13 +1,15 @@
+use std::{fmt::Display, str::FromStr};
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum OAuthErrorType {
     OAuthNotEnabled,
     InvalidRequest,
     InvalidClient,
-    // InvalidGrant,
-    // UnauthorizedClient,
+    InvalidGrant,
+    UnauthorizedClient,
     UnsupportedGrantType,
-    // InvalidScope,
+    InvalidScope,
     ServerError,
 }
 
@@ -39,3 +41,87 @@ impl OAuthError {
         self
     }
 }
+
+/// OAuth scopes defined by the OpenADR 3.0 specification.
+///
+/// A token is sent in the space separated `scope` parameter and claim, e.g., `read_all write_reports`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Scope {
+    /// Read all objects the client is authorized to read
+    ReadAll,
+    WritePrograms,
+    WriteEvents,
+    WriteReports,
+    WriteSubscriptions,
+    /// Write VENs and their resources
+    WriteVens,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 6] = [
+        Scope::ReadAll,
+        Scope::WritePrograms,
+        Scope::WriteEvents,
+        Scope::WriteReports,
+        Scope::WriteSubscriptions,
+        Scope::WriteVens,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ReadAll => "read_all",
+            Scope::WritePrograms => "write_programs",
+            Scope::WriteEvents => "write_events",
+            Scope::WriteReports => "write_reports",
+            Scope::WriteSubscriptions => "write_subscriptions",
+            Scope::WriteVens => "write_vens",
+        }
+    }
+
+    /// Formats the scopes as the value of a `scope` parameter or claim
+    pub fn join(scopes: &[Scope]) -> String {
+        scopes
+            .iter()
+            .map(Scope::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown scope: {0}")]
+pub struct UnknownScope(pub String);
+
+impl FromStr for Scope {
+    type Err = UnknownScope;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| UnknownScope(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_scope() {
+        for scope in Scope::ALL {
+            assert_eq!(scope.to_string().parse::<Scope>().unwrap(), scope);
+        }
+        assert!("write_everything".parse::<Scope>().is_err());
+        assert_eq!(
+            Scope::join(&[Scope::ReadAll, Scope::WriteReports]),
+            "read_all write_reports"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Types used for the `/events/{eventID}/opts` endpoints
+//!
+//! A VEN opts in or out of an event, or of some of its intervals, for itself or for one of its resources.
+//! The business owning the program sees the opt statuses of all VENs.
+//! The events of a program with [`binding_events`](crate::program::ProgramContent::binding_events)
+//! cannot be opted out of.
+//! These endpoints are an extension of this implementation and not part of the OpenADR 3.0 specification.
+
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use validator::{Validate, ValidationError};
+
+use crate::{event::EventId, resource::ResourceId, ven::VenId, Identifier, IdentifierError};
+
+/// The opt status of a VEN, or one of its resources, for an event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct Opt {
+    /// URL safe VTN assigned object ID.
+    pub id: OptId,
+    /// datetime in ISO 8601 format
+    #[serde(with = "crate::serde_rfc3339")]
+    pub created_date_time: DateTime<Utc>,
+    /// datetime in ISO 8601 format
+    #[serde(with = "crate::serde_rfc3339")]
+    pub modification_date_time: DateTime<Utc>,
+    /// URL safe VTN assigned object ID of the event.
+    #[serde(rename = "eventID")]
+    pub event_id: EventId,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub content: OptContent,
+}
+
+/// Setting an opt status replaces the previous one of the same VEN and resource for the event
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct OptContent {
+    /// URL safe VTN assigned object ID of the VEN.
+    #[serde(rename = "venID")]
+    pub ven_id: VenId,
+    /// URL safe VTN assigned object ID of a resource of the VEN.
+    /// If not set, the status applies to the VEN as a whole.
+    #[serde(rename = "resourceID")]
+    pub resource_id: Option<ResourceId>,
+    pub opt_type: OptType,
+    /// The intervals the status applies to. If not set, it applies to all intervals of the event.
+    #[validate(custom(function = "validate_interval_range"))]
+    pub interval_range: Option<IntervalRange>,
+    /// User defined string explaining the status, e.g., for use in a User Interface.
+    pub reason: Option<String>,
+}
+
+impl OptContent {
+    /// Opting out of all intervals of an event
+    pub fn opt_out(ven_id: VenId) -> Self {
+        Self {
+            ven_id,
+            resource_id: None,
+            opt_type: OptType::OptOut,
+            interval_range: None,
+            reason: None,
+        }
+    }
+
+    pub fn with_resource_id(mut self, resource_id: ResourceId) -> Self {
+        self.resource_id = Some(resource_id);
+        self
+    }
+
+    pub fn with_interval_range(mut self, interval_range: IntervalRange) -> Self {
+        self.interval_range = Some(interval_range);
+        self
+    }
+
+    pub fn with_reason(mut self, reason: impl ToString) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OptType {
+    /// The VEN participates in the event
+    OptIn,
+    /// The VEN does not participate in the event
+    OptOut,
+}
+
+/// The intervals whose [`id`](crate::event::EventInterval::id) is within `first..=last`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntervalRange {
+    pub first: i32,
+    pub last: i32,
+}
+
+impl IntervalRange {
+    pub fn contains(&self, interval_id: i32) -> bool {
+        (self.first..=self.last).contains(&interval_id)
+    }
+}
+
+fn validate_interval_range(range: &IntervalRange) -> Result<(), ValidationError> {
+    if range.first <= range.last {
+        Ok(())
+    } else {
+        Err(ValidationError::new("interval_range")
+            .with_message("first must not be greater than last".into()))
+    }
+}
+
+/// URL safe VTN assigned object ID
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Hash, Eq)]
+pub struct OptId(pub(crate) Identifier);
+
+impl Display for OptId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl OptId {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl FromStr for OptId {
+    type Err = IdentifierError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde() {
+        let example = r#"{"id":"opt-1","createdDateTime":"2024-07-25T08:31:10.776+00:00","modificationDateTime":"2024-07-25T08:31:10.776+00:00","eventID":"event-1","venID":"ven-1","optType":"OPT_OUT","intervalRange":{"first":1,"last":3},"reason":"maintenance"}"#;
+
+        let expected = Opt {
+            id: "opt-1".parse().unwrap(),
+            created_date_time: "2024-07-25T08:31:10.776+00:00".parse().unwrap(),
+            modification_date_time: "2024-07-25T08:31:10.776+00:00".parse().unwrap(),
+            event_id: "event-1".parse().unwrap(),
+            content: OptContent::opt_out("ven-1".parse().unwrap())
+                .with_interval_range(IntervalRange { first: 1, last: 3 })
+                .with_reason("maintenance"),
+        };
+
+        assert_eq!(serde_json::from_str::<Opt>(example).unwrap(), expected);
+        assert_eq!(serde_json::to_string(&expected).unwrap(), example);
+    }
+
+    #[test]
+    fn interval_range() {
+        let range = IntervalRange { first: 1, last: 3 };
+        assert!(range.contains(1) && range.contains(3));
+        assert!(!range.contains(0) && !range.contains(4));
+
+        let content = OptContent::opt_out("ven-1".parse().unwrap());
+        assert!(content.validate().is_ok());
+        assert!(content
+            .with_interval_range(IntervalRange { first: 3, last: 1 })
+            .validate()
+            .is_err());
+    }
+}
@@ -1,14 +1,22 @@
 //! Types used for the `program/` endpoint
+//!
+//! The [`time_zone`](ProgramContent::time_zone) of a program
+//! is an extension of this implementation and not part of the OpenADR 3.0 specification.
 
 use crate::{
-    event::EventPayloadDescriptor, interval::IntervalPeriod, report::ReportPayloadDescriptor,
-    target::TargetMap, Duration, IdentifierError,
+    duration::{resolve_local, ParseDurationError},
+    event::EventPayloadDescriptor,
+    interval::IntervalPeriod,
+    report::ReportPayloadDescriptor,
+    target::TargetMap,
+    Duration, IdentifierError,
 };
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, Utc};
+use chrono_tz::Tz;
+use serde::{de::Unexpected, Deserialize, Deserializer, Serialize, Serializer};
 use serde_with::skip_serializing_none;
 use std::{fmt::Display, str::FromStr};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use super::Identifier;
 
@@ -62,8 +70,15 @@ pub struct ProgramContent {
     /// duration in ISO 8601 format
     ///
     /// Number of hours different from UTC for the standard time applicable to the program.
+    /// Negative west of UTC, e.g., `-PT5H`.
     // TODO: aaaaaah why???
-    pub time_zone_offset: Option<Duration>,
+    #[validate(custom(function = "validate_time_zone_offset"))]
+    pub time_zone_offset: Option<TimeZoneOffset>,
+    /// IANA time zone name, e.g., `Europe/Amsterdam`.
+    ///
+    /// Unlike the [`time_zone_offset`](Self::time_zone_offset), it includes daylight saving time,
+    /// and takes precedence over the offset if both are present.
+    pub time_zone: Option<Tz>,
     pub interval_period: Option<IntervalPeriod>,
     /// A list of programDescriptions
     #[validate(nested)]
@@ -89,6 +104,7 @@ impl ProgramContent {
             country: Default::default(),
             principal_subdivision: Default::default(),
             time_zone_offset: Default::default(),
+            time_zone: Default::default(),
             interval_period: Default::default(),
             program_descriptions: Default::default(),
             binding_events: Default::default(),
@@ -97,6 +113,187 @@ impl ProgramContent {
             targets: Default::default(),
         }
     }
+
+    /// The time zone days, weeks, months, and years of this program's durations are evaluated in.
+    ///
+    /// That is the [`time_zone`](Self::time_zone) if present,
+    /// otherwise the [`time_zone_offset`](Self::time_zone_offset), or UTC if neither is present.
+    pub fn effective_time_zone(&self) -> ProgramTimeZone {
+        ProgramTimeZone::new(self.time_zone, self.time_zone_offset.as_ref())
+    }
+}
+
+/// The time zone of a [`Program`], see [`ProgramContent::effective_time_zone`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgramTimeZone {
+    /// Follows the daylight saving time rules of the IANA time zone
+    Named(Tz),
+    /// A fixed offset from UTC
+    Fixed(FixedOffset),
+}
+
+impl Default for ProgramTimeZone {
+    fn default() -> Self {
+        Self::Fixed(Utc.fix())
+    }
+}
+
+impl ProgramTimeZone {
+    /// The [`time_zone`](ProgramContent::time_zone) if present,
+    /// otherwise the [`time_zone_offset`](ProgramContent::time_zone_offset), or UTC if neither is present
+    pub fn new(time_zone: Option<Tz>, time_zone_offset: Option<&TimeZoneOffset>) -> Self {
+        match (time_zone, time_zone_offset) {
+            (Some(tz), _) => Self::Named(tz),
+            (None, Some(offset)) => fixed_offset(offset).map(Self::Fixed).unwrap_or_default(),
+            (None, None) => Self::default(),
+        }
+    }
+
+    /// The point in time of the local date and time in this time zone.
+    ///
+    /// If the local time occurs twice, as the clocks are set back, the earlier one is used.
+    /// If it does not exist, as the clocks are set forward, it is moved forward by the length of the gap.
+    pub fn from_local(&self, local: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Named(tz) => resolve_local(tz, *local).map(|datetime| datetime.to_utc()),
+            Self::Fixed(offset) => resolve_local(offset, *local).map(|datetime| datetime.to_utc()),
+        }
+    }
+
+    /// The local date and time of the point in time in this time zone
+    pub fn to_local(&self, datetime: &DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Self::Named(tz) => datetime.with_timezone(tz).naive_local(),
+            Self::Fixed(offset) => datetime.with_timezone(offset).naive_local(),
+        }
+    }
+
+    /// Adds the `duration` to the `datetime`, see [`Duration::checked_add`].
+    ///
+    /// For example, `P1D` starting at noon the day before the clocks are set forward
+    /// ends at noon the next day in the local time of this time zone, which is only 23 hours later.
+    pub fn checked_add(
+        &self,
+        datetime: &DateTime<Utc>,
+        duration: &Duration,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Named(tz) => duration
+                .checked_add(&datetime.with_timezone(tz))
+                .map(|end| end.to_utc()),
+            Self::Fixed(offset) => duration
+                .checked_add(&datetime.with_timezone(offset))
+                .map(|end| end.to_utc()),
+        }
+    }
+
+    /// The elapsed time of the `duration` starting at `datetime`.
+    /// Saturates at [`chrono::TimeDelta::MAX`] if the end is not representable, e.g., for [`Duration::P9999Y`].
+    pub fn to_chrono_at_datetime(
+        &self,
+        datetime: &DateTime<Utc>,
+        duration: &Duration,
+    ) -> chrono::TimeDelta {
+        self.checked_add(datetime, duration)
+            .map(|end| end - datetime)
+            .unwrap_or(chrono::TimeDelta::MAX)
+    }
+}
+
+/// The [`ProgramContent::time_zone_offset`]: an ISO 8601 duration,
+/// preceded by a `-` west of UTC, e.g., `PT1H` or `-PT5H30M`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TimeZoneOffset {
+    pub negative: bool,
+    pub duration: Duration,
+}
+
+impl TimeZoneOffset {
+    /// The offset east of UTC
+    pub const fn east(duration: Duration) -> Self {
+        Self {
+            negative: false,
+            duration,
+        }
+    }
+
+    /// The offset west of UTC
+    pub const fn west(duration: Duration) -> Self {
+        Self {
+            negative: true,
+            duration,
+        }
+    }
+}
+
+impl From<Duration> for TimeZoneOffset {
+    fn from(duration: Duration) -> Self {
+        Self::east(duration)
+    }
+}
+
+impl FromStr for TimeZoneOffset {
+    type Err = ParseDurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('-') {
+            Some(duration) => Ok(Self::west(duration.parse()?)),
+            None => Ok(Self::east(s.strip_prefix('+').unwrap_or(s).parse()?)),
+        }
+    }
+}
+
+impl Display for TimeZoneOffset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.negative && !self.duration.is_zero() {
+            write!(f, "-{}", self.duration)
+        } else {
+            write!(f, "{}", self.duration)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeZoneOffset {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(|err: ParseDurationError| {
+            serde::de::Error::invalid_value(Unexpected::Str(&raw), &err.to_string().as_str())
+        })
+    }
+}
+
+impl Serialize for TimeZoneOffset {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.to_string().serialize(serializer)
+    }
+}
+
+/// The [`ProgramContent::time_zone_offset`] as [`FixedOffset`], if it is less than a day
+/// and does not contain calendar components
+fn fixed_offset(offset: &TimeZoneOffset) -> Option<FixedOffset> {
+    let TimeZoneOffset { negative, duration } = offset;
+    if duration.years != 0 || duration.months != 0 || duration.weeks != 0 || duration.days != 0 {
+        return None;
+    }
+    let seconds = i64::from(duration.hours) * 3600
+        + i64::from(duration.minutes) * 60
+        + i64::from(duration.seconds);
+    let seconds = if *negative { -seconds } else { seconds };
+    FixedOffset::east_opt(seconds.try_into().ok()?)
+}
+
+fn validate_time_zone_offset(offset: &TimeZoneOffset) -> Result<(), ValidationError> {
+    match fixed_offset(offset) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("time_zone_offset")
+            .with_message("the time zone offset must be less than a day, e.g., PT1H".into())),
+    }
 }
 
 // example: object-999
@@ -189,7 +386,8 @@ mod test {
                 program_type: Some("PRICING_TARIFF".into()),
                 country: Some("US".into()),
                 principal_subdivision: Some("CO".into()),
-                time_zone_offset: Some(Duration::PT1H),
+                time_zone_offset: Some(Duration::PT1H.into()),
+                time_zone: None,
                 interval_period: Some(IntervalPeriod {
                     start: "2023-06-15T09:30:00Z".parse().unwrap(),
                     duration: Some(Duration::PT1H),
@@ -221,6 +419,7 @@ mod test {
                 country: None,
                 principal_subdivision: None,
                 time_zone_offset: None,
+                time_zone: None,
                 interval_period: None,
                 program_descriptions: None,
                 binding_events: None,
@@ -230,4 +429,86 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn time_zone() {
+        let example =
+            r#"{"programName":"test","timeZoneOffset":"PT1H","timeZone":"Europe/Amsterdam"}"#;
+        let program = serde_json::from_str::<ProgramContent>(example).unwrap();
+        assert_eq!(program.time_zone, Some(chrono_tz::Europe::Amsterdam));
+        assert_eq!(
+            program.effective_time_zone(),
+            ProgramTimeZone::Named(chrono_tz::Europe::Amsterdam)
+        );
+        assert_eq!(
+            serde_json::to_value(&program).unwrap()["timeZone"],
+            "Europe/Amsterdam"
+        );
+
+        let example = r#"{"programName":"test","timeZone":"Europe/Atlantis"}"#;
+        assert!(serde_json::from_str::<ProgramContent>(example).is_err());
+
+        let mut program = ProgramContent::new("test");
+        assert_eq!(program.effective_time_zone(), ProgramTimeZone::default());
+        program.time_zone_offset = Some(Duration::PT1H.into());
+        assert_eq!(
+            program.effective_time_zone(),
+            ProgramTimeZone::Fixed(FixedOffset::east_opt(3600).unwrap())
+        );
+        assert!(program.validate().is_ok());
+
+        // west of UTC
+        let example = r#"{"programName":"test","timeZoneOffset":"-PT5H30M"}"#;
+        let program = serde_json::from_str::<ProgramContent>(example).unwrap();
+        assert_eq!(
+            program.time_zone_offset,
+            Some(TimeZoneOffset::west("PT5H30M".parse().unwrap()))
+        );
+        assert_eq!(
+            program.effective_time_zone(),
+            ProgramTimeZone::Fixed(FixedOffset::west_opt(5 * 3600 + 30 * 60).unwrap())
+        );
+        assert!(program.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(&program).unwrap()["timeZoneOffset"],
+            "-PT5H30M"
+        );
+        assert_eq!("+PT1H".parse(), Ok(TimeZoneOffset::east(Duration::PT1H)));
+        assert!("-P".parse::<TimeZoneOffset>().is_err());
+
+        let mut program = ProgramContent::new("test");
+
+        program.time_zone_offset = Some("-P1D".parse().unwrap());
+        assert!(program.validate().is_err());
+        assert_eq!(program.effective_time_zone(), ProgramTimeZone::default());
+    }
+
+    #[test]
+    fn checked_add_in_time_zone() {
+        // the clocks in Amsterdam are set forward on 2024-03-31
+        let start = "2024-03-30T11:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let day = "P1D".parse::<Duration>().unwrap();
+
+        let amsterdam = ProgramTimeZone::Named(chrono_tz::Europe::Amsterdam);
+        assert_eq!(
+            amsterdam.checked_add(&start, &day).unwrap(),
+            "2024-03-31T10:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            amsterdam.to_chrono_at_datetime(&start, &day),
+            chrono::TimeDelta::hours(23)
+        );
+
+        // standard time only
+        let fixed = ProgramTimeZone::Fixed(FixedOffset::east_opt(3600).unwrap());
+        assert_eq!(
+            fixed.to_chrono_at_datetime(&start, &day),
+            chrono::TimeDelta::hours(24)
+        );
+
+        assert_eq!(
+            amsterdam.to_chrono_at_datetime(&start, &Duration::P9999Y),
+            chrono::TimeDelta::MAX
+        );
+    }
 }
@@ -2,8 +2,8 @@
 
 use crate::{
     event::EventId,
-    interval::{Interval, IntervalPeriod},
-    program::ProgramId,
+    interval::{interval_spans, Interval, IntervalPeriod},
+    program::{ProgramId, ProgramTimeZone},
     target::TargetMap,
     values_map::Value,
     Identifier, IdentifierError, Unit,
@@ -119,6 +119,22 @@ pub struct ReportResource {
     pub intervals: Vec<Interval>,
 }
 
+impl ReportResource {
+    /// The id, start, and end of each interval that has a start, see [`interval_spans`]
+    pub fn interval_spans(
+        &self,
+        time_zone: &ProgramTimeZone,
+    ) -> Vec<(i32, DateTime<Utc>, Option<DateTime<Utc>>)> {
+        interval_spans(
+            self.interval_period.as_ref(),
+            self.intervals
+                .iter()
+                .map(|interval| (interval.id, interval.interval_period.as_ref())),
+            time_zone,
+        )
+    }
+}
+
 /// An object that may be used to request a report from a VEN. See OpenADR REST User Guide for
 /// detailed description of how configure a report request.
 // TODO: replace "-1 means" with proper enum
@@ -516,7 +532,7 @@ impl ReadingType {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ResourceName {
     AggregatedReport,
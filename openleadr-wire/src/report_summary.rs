@@ -0,0 +1,376 @@
+//! Types used for the `/events/{eventID}/report-summary` endpoint
+//!
+//! The summary aggregates the `USAGE`, `DEMAND`, and `BASELINE` values of all reports of an event
+//! per interval, across all reporting VENs and resources.
+//! This endpoint is an extension of this implementation and not part of the OpenADR 3.0 specification.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+use crate::{
+    event::EventId,
+    program::{ProgramId, ProgramTimeZone},
+    values_map::Value,
+    ven::VenId,
+    Event, Report,
+};
+
+/// Aggregated reports of an event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSummary {
+    /// URL safe VTN assigned object ID of the event.
+    #[serde(rename = "eventID")]
+    pub event_id: EventId,
+    /// URL safe VTN assigned object ID of the program of the event.
+    #[serde(rename = "programID")]
+    pub program_id: ProgramId,
+    /// Number of reports of the event
+    pub report_count: usize,
+    /// The VENs that created at least one report of the event
+    #[serde(rename = "reportingVenIDs")]
+    pub reporting_ven_ids: Vec<VenId>,
+    /// The VENs enrolled in the program that did not report on the event
+    #[serde(rename = "silentVenIDs")]
+    pub silent_ven_ids: Vec<VenId>,
+    /// The intervals of the event and the reports, ordered by id
+    pub intervals: Vec<IntervalSummary>,
+}
+
+/// Aggregated values of the reports for the intervals with the same id
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntervalSummary {
+    /// The id of the interval of the event and the reports
+    pub id: i32,
+    /// Start of the interval of the event with this id, if any
+    pub start: Option<DateTime<Utc>>,
+    /// End of the interval of the event with this id, if it does not last indefinitely
+    pub end: Option<DateTime<Utc>>,
+    pub usage: Option<Aggregate>,
+    pub demand: Option<Aggregate>,
+    pub baseline: Option<Aggregate>,
+    /// Total usage minus total baseline of the resources reporting both for the interval,
+    /// negative if the usage was reduced
+    pub usage_delta_to_baseline: Option<f64>,
+}
+
+/// Total and average of the values of one type reported for an interval
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Aggregate {
+    pub total: f64,
+    pub average: f64,
+    /// Number of reported values, i.e., of resources reporting the type for the interval
+    pub count: usize,
+}
+
+impl Aggregate {
+    fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let total: f64 = values.iter().sum();
+        Some(Self {
+            total,
+            average: total / values.len() as f64,
+            count: values.len(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Samples {
+    usage: Vec<f64>,
+    demand: Vec<f64>,
+    baseline: Vec<f64>,
+    /// Usage minus baseline of each resource reporting both
+    deltas: Vec<f64>,
+}
+
+impl ReportSummary {
+    /// Aggregates the reports of the event.
+    ///
+    /// Each report comes with the VEN that created it, if known.
+    /// `enrolled_vens` are the VENs expected to report, those without a report are silent.
+    /// Per interval and resource, the first value of each `USAGE`, `DEMAND`, and `BASELINE` payload
+    /// is taken into account if it is a number.
+    /// The delta of usage to baseline only covers the resources that reported both,
+    /// such that resources without a baseline do not count as additional usage.
+    pub fn new<'a>(
+        event: &Event,
+        time_zone: &ProgramTimeZone,
+        reports: impl IntoIterator<Item = (Option<&'a VenId>, &'a Report)>,
+        enrolled_vens: &[VenId],
+    ) -> Self {
+        let mut report_count = 0;
+        let mut reporting_ven_ids: Vec<VenId> = Vec::new();
+        let mut samples: BTreeMap<i32, Samples> = BTreeMap::new();
+
+        let spans: BTreeMap<i32, (DateTime<Utc>, Option<DateTime<Utc>>)> = event
+            .content
+            .interval_spans(time_zone)
+            .into_iter()
+            .map(|(id, start, end)| (id, (start, end)))
+            .collect();
+        for interval in &event.content.intervals {
+            samples.entry(interval.id).or_default();
+        }
+
+        for (ven_id, report) in reports {
+            report_count += 1;
+            if let Some(ven_id) = ven_id.filter(|id| !reporting_ven_ids.contains(id)) {
+                reporting_ven_ids.push(ven_id.clone());
+            }
+
+            for resource in &report.content.resources {
+                // usage and baseline of the resource per interval id
+                let mut pairs: BTreeMap<i32, (Option<f64>, Option<f64>)> = BTreeMap::new();
+                for interval in &resource.intervals {
+                    let entry = samples.entry(interval.id).or_default();
+                    let pair = pairs.entry(interval.id).or_default();
+                    for payload in &interval.payloads {
+                        let Some(value) = payload.values.first().and_then(Value::as_number) else {
+                            continue;
+                        };
+                        // the serialized names of `ReportType::Usage`, `Demand`, and `Baseline`
+                        match payload.value_type.0.as_str() {
+                            "USAGE" => {
+                                entry.usage.push(value);
+                                *pair.0.get_or_insert(0.0) += value;
+                            }
+                            "DEMAND" => entry.demand.push(value),
+                            "BASELINE" => {
+                                entry.baseline.push(value);
+                                *pair.1.get_or_insert(0.0) += value;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                for (id, pair) in pairs {
+                    if let (Some(usage), Some(baseline)) = pair {
+                        samples.entry(id).or_default().deltas.push(usage - baseline);
+                    }
+                }
+            }
+        }
+
+        let intervals = samples
+            .into_iter()
+            .map(|(id, samples)| {
+                let span = spans.get(&id);
+                IntervalSummary {
+                    id,
+                    start: span.map(|(start, _)| *start),
+                    end: span.and_then(|(_, end)| *end),
+                    usage: Aggregate::of(&samples.usage),
+                    demand: Aggregate::of(&samples.demand),
+                    baseline: Aggregate::of(&samples.baseline),
+                    usage_delta_to_baseline: Aggregate::of(&samples.deltas)
+                        .map(|deltas| deltas.total),
+                }
+            })
+            .collect();
+
+        reporting_ven_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        let silent_ven_ids = enrolled_vens
+            .iter()
+            .filter(|ven_id| !reporting_ven_ids.contains(ven_id))
+            .cloned()
+            .collect();
+
+        Self {
+            event_id: event.id.clone(),
+            program_id: event.content.program_id.clone(),
+            report_count,
+            reporting_ven_ids,
+            silent_ven_ids,
+            intervals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        duration::Duration,
+        event::{EventContent, EventInterval},
+        interval::{Interval, IntervalPeriod},
+        report::{ReportContent, ReportResource, ResourceName},
+        values_map::{ValueType, ValuesMap},
+    };
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn event() -> Event {
+        let interval = |id| EventInterval {
+            id,
+            interval_period: None,
+            payloads: vec![],
+        };
+        Event {
+            id: "event-1".parse().unwrap(),
+            created_date_time: at("2024-03-01T00:00:00Z"),
+            modification_date_time: at("2024-03-01T00:00:00Z"),
+            state: None,
+            superseded_by: None,
+            content: EventContent::new(
+                "program-1".parse().unwrap(),
+                vec![interval(0), interval(1)],
+            )
+            .with_interval_period(IntervalPeriod {
+                start: at("2024-03-01T10:00:00Z"),
+                duration: Some(Duration::PT1H),
+                randomize_start: None,
+            }),
+        }
+    }
+
+    /// Interval id, payload type, and value
+    type Sample<'a> = (i32, &'a str, Value);
+
+    fn report(resources: Vec<(&str, Vec<Sample>)>) -> Report {
+        Report {
+            id: "report-1".parse().unwrap(),
+            created_date_time: at("2024-03-01T12:00:00Z"),
+            modification_date_time: at("2024-03-01T12:00:00Z"),
+            content: ReportContent {
+                program_id: "program-1".parse().unwrap(),
+                event_id: "event-1".parse().unwrap(),
+                client_name: "client".to_string(),
+                report_name: None,
+                payload_descriptors: None,
+                resources: resources
+                    .into_iter()
+                    .map(|(name, intervals)| ReportResource {
+                        resource_name: ResourceName::Private(name.to_string()),
+                        interval_period: None,
+                        intervals: intervals
+                            .into_iter()
+                            .map(|(id, value_type, value)| {
+                                Interval::new(
+                                    id,
+                                    vec![ValuesMap {
+                                        value_type: ValueType(value_type.to_string()),
+                                        values: vec![value],
+                                    }],
+                                )
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn aggregates_per_interval() {
+        let ven_1: VenId = "ven-1".parse().unwrap();
+        let ven_2: VenId = "ven-2".parse().unwrap();
+        let ven_3: VenId = "ven-3".parse().unwrap();
+        let report_1 = report(vec![
+            (
+                "resource-1",
+                vec![
+                    (0, "USAGE", Value::Number(2.0)),
+                    (0, "BASELINE", Value::Number(5.0)),
+                ],
+            ),
+            ("resource-2", vec![(0, "USAGE", Value::Integer(4))]),
+        ]);
+        let report_2 = report(vec![(
+            "resource-3",
+            vec![
+                (1, "DEMAND", Value::Number(1.5)),
+                (1, "USAGE", Value::String("n/a".to_string())),
+                // not an interval of the event
+                (7, "USAGE", Value::Number(1.0)),
+            ],
+        )]);
+
+        let summary = ReportSummary::new(
+            &event(),
+            &ProgramTimeZone::default(),
+            [(Some(&ven_1), &report_1), (None, &report_2)],
+            &[ven_1.clone(), ven_2.clone(), ven_3.clone()],
+        );
+
+        assert_eq!(summary.report_count, 2);
+        assert_eq!(summary.reporting_ven_ids, vec![ven_1]);
+        assert_eq!(summary.silent_ven_ids, vec![ven_2, ven_3]);
+        assert_eq!(
+            summary.intervals,
+            vec![
+                IntervalSummary {
+                    id: 0,
+                    start: Some(at("2024-03-01T10:00:00Z")),
+                    end: Some(at("2024-03-01T11:00:00Z")),
+                    usage: Some(Aggregate {
+                        total: 6.0,
+                        average: 3.0,
+                        count: 2
+                    }),
+                    demand: None,
+                    baseline: Some(Aggregate {
+                        total: 5.0,
+                        average: 5.0,
+                        count: 1
+                    }),
+                    // resource-2 reported no baseline
+                    usage_delta_to_baseline: Some(-3.0),
+                },
+                IntervalSummary {
+                    id: 1,
+                    start: Some(at("2024-03-01T11:00:00Z")),
+                    end: Some(at("2024-03-01T12:00:00Z")),
+                    usage: None,
+                    demand: Some(Aggregate {
+                        total: 1.5,
+                        average: 1.5,
+                        count: 1
+                    }),
+                    baseline: None,
+                    usage_delta_to_baseline: None,
+                },
+                IntervalSummary {
+                    id: 7,
+                    start: None,
+                    end: None,
+                    usage: Some(Aggregate {
+                        total: 1.0,
+                        average: 1.0,
+                        count: 1
+                    }),
+                    demand: None,
+                    baseline: None,
+                    usage_delta_to_baseline: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn without_reports() {
+        let summary = ReportSummary::new(&event(), &ProgramTimeZone::default(), [], &[]);
+
+        assert_eq!(summary.report_count, 0);
+        assert!(summary.reporting_ven_ids.is_empty());
+        assert_eq!(summary.intervals.len(), 2);
+        assert!(summary
+            .intervals
+            .iter()
+            .all(|interval| interval.usage.is_none()));
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["eventID"], "event-1");
+        assert_eq!(json["intervals"][0]["start"], "2024-03-01T10:00:00Z");
+    }
+}
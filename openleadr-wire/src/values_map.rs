@@ -28,6 +28,17 @@ pub enum Value {
     String(String),
 }
 
+impl Value {
+    /// The value as floating point number, if it is numeric
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Integer(value) => Some(*value as f64),
+            Self::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {